@@ -1,8 +1,14 @@
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use hyper::{HeaderMap, Method, Uri};
 use rift_http_proxy::config::{FaultConfig, LatencyFault, MatchConfig, PathMatch, Rule};
-use rift_http_proxy::matcher::{find_matching_rule, CompiledRule};
-use rift_http_proxy::predicate::{PathMatcher, PredicateOptions, RequestPredicate, StringMatcher};
+use rift_http_proxy::imposter::{stub_matches, Predicate, PredicateRequest};
+use rift_http_proxy::matcher::{
+    find_matching_rule, find_matching_rule_indexed, CompiledRule, RulePathSet,
+};
+use rift_http_proxy::predicate::{
+    extract_json_path, extract_json_path_streaming, CachedValue, PathMatcher, PredicateOptions,
+    RequestPredicate, StringMatcher,
+};
 use rift_http_proxy::rule_index::RuleIndex;
 
 fn create_test_rule(id: usize, path: &str, is_regex: bool) -> Rule {
@@ -22,7 +28,15 @@ fn create_test_rule(id: usize, path: &str, is_regex: bool) -> Rule {
             headers: vec![],
             header_predicates: vec![],
             query: vec![],
+            trailers: vec![],
             body: None,
+            body_size: None,
+            content_type: None,
+            hmac: None,
+            user_agent_family: None,
+            auth: None,
+            ip: None,
+            is_retry: None,
             case_sensitive: true,
         },
         fault: FaultConfig {
@@ -33,8 +47,19 @@ fn create_test_rule(id: usize, path: &str, is_regex: bool) -> Rule {
             }),
             error: None,
             tcp_fault: None,
+            drip: None,
+            chunked: None,
+            rate_limit: None,
+            mock: None,
+            responses: vec![],
         },
         upstream: None,
+        once: false,
+        action: Default::default(),
+        description: None,
+        metadata: Default::default(),
+        enabled: true,
+        active_window: None,
     }
 }
 
@@ -178,6 +203,56 @@ fn bench_regex_matching(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_rule_path_set(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rule_path_set");
+
+    // Half prefix rules, half regex rules, as in a large mixed config
+    let rules: Vec<CompiledRule> = (0..500)
+        .map(|i| {
+            let rule = if i % 2 == 0 {
+                create_test_rule(i, &format!("/api/v1/endpoint{i}/"), false)
+            } else {
+                create_test_rule(i, &format!("/api/v\\d+/endpoint{i}"), true)
+            };
+            CompiledRule::compile(rule).unwrap()
+        })
+        .collect();
+    let paths = RulePathSet::new(&rules).unwrap();
+    let method = Method::GET;
+    let headers = HeaderMap::new();
+
+    for (name, uri) in [
+        ("match_last", "http://localhost/api/v1/endpoint499"),
+        ("match_none", "http://localhost/not/found"),
+    ] {
+        let uri: Uri = uri.parse().unwrap();
+        group.throughput(Throughput::Elements(1));
+        group.bench_function(BenchmarkId::new("linear", name), |b| {
+            b.iter(|| {
+                find_matching_rule(
+                    black_box(&rules),
+                    black_box(&method),
+                    black_box(&uri),
+                    black_box(&headers),
+                )
+            });
+        });
+        group.bench_function(BenchmarkId::new("regex_set", name), |b| {
+            b.iter(|| {
+                find_matching_rule_indexed(
+                    black_box(&rules),
+                    black_box(&paths),
+                    black_box(&method),
+                    black_box(&uri),
+                    black_box(&headers),
+                )
+            });
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_single_rule_evaluation(c: &mut Criterion) {
     let mut group = c.benchmark_group("single_rule_eval");
 
@@ -196,18 +271,52 @@ fn bench_single_rule_evaluation(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_precompiled_rule(c: &mut Criterion) {
+    let mut group = c.benchmark_group("precompiled_rule");
+
+    // Regex path and header predicate, case-insensitive, as loaded from config
+    let mut rule = create_test_rule(0, r"/api/v\d+/users/\d+", true);
+    rule.match_config.header_predicates =
+        serde_yaml::from_str("- name: Authorization\n  matches: '^Bearer [A-Za-z0-9._-]+$'\n")
+            .unwrap();
+    rule.match_config.case_sensitive = false;
+    let compiled = CompiledRule::compile(rule.clone()).unwrap();
+
+    let uri: Uri = "http://localhost/api/v2/users/42".parse().unwrap();
+    let method = Method::GET;
+    let mut headers = HeaderMap::new();
+    headers.insert("authorization", "Bearer abc.def".parse().unwrap());
+
+    group.throughput(Throughput::Elements(1));
+    // What matching would cost if rules were compiled per request
+    group.bench_function("compile_per_request", |b| {
+        b.iter(|| {
+            CompiledRule::compile(black_box(rule.clone()))
+                .unwrap()
+                .matches(black_box(&method), black_box(&uri), black_box(&headers))
+        });
+    });
+    // What the proxy does: compiled once at load, only matched per request
+    group.bench_function("compiled_at_load", |b| {
+        b.iter(|| compiled.matches(black_box(&method), black_box(&uri), black_box(&headers)));
+    });
+
+    group.finish();
+}
+
 // =============================================================================
 // RuleIndex Benchmarks (Optimized matching with Radix Trie + Aho-Corasick)
 // =============================================================================
 
 fn create_predicate_exact(path: &str, method: Option<&str>) -> RequestPredicate {
     RequestPredicate {
-        method: method.map(|m| StringMatcher::Equals(m.to_string())),
+        method: method.map(|m| StringMatcher::Equals(m.to_string()).into()),
         path: Some(PathMatcher::Exact {
             exact: path.to_string(),
         }),
         headers: vec![],
         query: vec![],
+        trailers: vec![],
         body: None,
         options: PredicateOptions::default(),
     }
@@ -215,12 +324,13 @@ fn create_predicate_exact(path: &str, method: Option<&str>) -> RequestPredicate
 
 fn create_predicate_prefix(path: &str, method: Option<&str>) -> RequestPredicate {
     RequestPredicate {
-        method: method.map(|m| StringMatcher::Equals(m.to_string())),
+        method: method.map(|m| StringMatcher::Equals(m.to_string()).into()),
         path: Some(PathMatcher::Prefix {
             prefix: path.to_string(),
         }),
         headers: vec![],
         query: vec![],
+        trailers: vec![],
         body: None,
         options: PredicateOptions::default(),
     }
@@ -234,6 +344,7 @@ fn create_predicate_contains(pattern: &str) -> RequestPredicate {
         }),
         headers: vec![],
         query: vec![],
+        trailers: vec![],
         body: None,
         options: PredicateOptions::default(),
     }
@@ -423,14 +534,105 @@ fn bench_linear_vs_indexed(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_json_path_extraction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("json_path_extraction");
+
+    // ~1MB body with the target field near the start
+    let items: Vec<String> = (0..10_000)
+        .map(|i| format!(r#"{{"id": {i}, "name": "item-{i}", "tags": ["a", "b", "c"]}}"#))
+        .collect();
+    let body = format!(
+        r#"{{"status": "ok", "meta": {{"version": 3}}, "items": [{}]}}"#,
+        items.join(",")
+    );
+    group.throughput(Throughput::Bytes(body.len() as u64));
+
+    group.bench_function("full_parse_shallow", |b| {
+        b.iter(|| extract_json_path(black_box(&body), black_box("$.meta.version")));
+    });
+    group.bench_function("streaming_shallow", |b| {
+        b.iter(|| extract_json_path_streaming(black_box(&body), black_box("$.meta.version")));
+    });
+
+    group.finish();
+}
+
+fn bench_case_insensitive_contains(c: &mut Criterion) {
+    let mut group = c.benchmark_group("case_insensitive_contains");
+
+    let user_agent =
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) \
+         Chrome/120.0.0.0 Safari/537.36";
+    let regex = regex::Regex::new(&format!("(?i){}", regex::escape("safari"))).unwrap();
+    let cached = CachedValue::new("Safari");
+
+    group.bench_function("regex", |b| {
+        b.iter(|| regex.is_match(black_box(user_agent)));
+    });
+    group.bench_function("cached_value", |b| {
+        b.iter(|| cached.contained_in(black_box(user_agent), false));
+    });
+
+    group.finish();
+}
+
+fn bench_stub_predicates(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stub_predicates");
+
+    // Stubs telling tenants apart by query parameter; the request matches
+    // the last one
+    let stubs: Vec<Vec<Predicate>> = (0..20)
+        .map(|i| {
+            serde_json::from_value(serde_json::json!([
+                {"equals": {"method": "GET", "path": "/api/orders"}},
+                {"equals": {"query": {"tenant": format!("tenant-{i}")}}},
+                {"exists": {"headers": {"authorization": true}}},
+            ]))
+            .unwrap()
+        })
+        .collect();
+    let query = Some("tenant=tenant-19&page=2&sort=created%20desc");
+    let headers = std::collections::HashMap::from([
+        ("authorization".to_string(), "Bearer abc".to_string()),
+        ("accept".to_string(), "application/json".to_string()),
+    ]);
+
+    group.throughput(Throughput::Elements(1));
+    // Parsing the request again for every stub
+    group.bench_function("parse_per_stub", |b| {
+        b.iter(|| {
+            stubs.iter().position(|predicates| {
+                let request = PredicateRequest::new("GET", "/api/orders", query, &headers);
+                stub_matches(predicates, black_box(&request))
+            })
+        });
+    });
+    // What imposters do: parse once, share across stubs
+    group.bench_function("parsed_once", |b| {
+        b.iter(|| {
+            let request = PredicateRequest::new("GET", "/api/orders", query, &headers);
+            stubs
+                .iter()
+                .position(|predicates| stub_matches(predicates, black_box(&request)))
+        });
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_rule_matching,
     bench_regex_matching,
+    bench_rule_path_set,
     bench_single_rule_evaluation,
+    bench_precompiled_rule,
     bench_rule_index_exact,
     bench_rule_index_prefix,
     bench_rule_index_contains,
-    bench_linear_vs_indexed
+    bench_linear_vs_indexed,
+    bench_json_path_extraction,
+    bench_case_insensitive_contains,
+    bench_stub_predicates
 );
 criterion_main!(benches);
@@ -2,25 +2,71 @@ use crate::extensions::flow_state::FlowStore;
 use anyhow::Result;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, Weak};
 use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+/// Source of the current time, replaceable so tests can advance it
+type Clock = Arc<dyn Fn() -> SystemTime + Send + Sync>;
+
+type Entries = HashMap<String, (Value, Option<SystemTime>)>;
 
 /// In-memory implementation of FlowStore
 ///
 /// This implementation stores flow state in a HashMap with automatic TTL expiration.
+/// Every write stamps the entry with an expiry `default_ttl` from now; expired
+/// entries read as absent, and are dropped on their next write or by the
+/// background sweep (see [`InMemoryFlowStore::start_sweeper`]).
 /// Useful for testing, development, and single-instance deployments.
 pub struct InMemoryFlowStore {
-    #[allow(clippy::type_complexity)]
-    data: Arc<RwLock<HashMap<String, (Value, Option<SystemTime>)>>>,
+    data: Arc<RwLock<Entries>>,
     default_ttl: Duration,
+    clock: Clock,
 }
 
 impl InMemoryFlowStore {
     pub fn new(default_ttl_seconds: u64) -> Self {
+        Self::with_clock(default_ttl_seconds, Arc::new(SystemTime::now))
+    }
+
+    fn with_clock(default_ttl_seconds: u64, clock: Clock) -> Self {
         Self {
             data: Arc::new(RwLock::new(HashMap::new())),
             default_ttl: Duration::from_secs(default_ttl_seconds),
+            clock,
+        }
+    }
+
+    /// Drop expired entries every `interval` on a background thread.
+    ///
+    /// The thread exits once the store has been dropped.
+    pub fn start_sweeper(&self, interval: Duration) {
+        let data = Arc::downgrade(&self.data);
+        let clock = Arc::clone(&self.clock);
+        std::thread::Builder::new()
+            .name("flow-store-sweeper".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(interval);
+                if Self::sweep(&data, &clock).is_none() {
+                    break;
+                }
+            })
+            .expect("Failed to spawn flow store sweeper thread");
+    }
+
+    /// Remove all expired entries, returning how many were dropped, or
+    /// `None` if the store is gone
+    fn sweep(data: &Weak<RwLock<Entries>>, clock: &Clock) -> Option<usize> {
+        let data = data.upgrade()?;
+        let now = clock();
+        let mut data = data.write().unwrap();
+        let before = data.len();
+        data.retain(|_, (_, expiry)| expiry.is_none_or(|exp| now <= exp));
+        let removed = before - data.len();
+        if removed > 0 {
+            debug!("Swept {} expired flow state entries", removed);
         }
+        Some(removed)
     }
 
     fn make_key(&self, flow_id: &str, key: &str) -> String {
@@ -29,7 +75,7 @@ impl InMemoryFlowStore {
 
     fn is_expired(&self, expiry: &Option<SystemTime>) -> bool {
         if let Some(exp) = expiry {
-            SystemTime::now() > *exp
+            (self.clock)() > *exp
         } else {
             false
         }
@@ -40,7 +86,7 @@ impl InMemoryFlowStore {
     ///
     /// Note: `data` parameter must be a mutable reference acquired from self.data.lock()
     fn cleanup_on_write(
-        data: &mut HashMap<String, (Value, Option<SystemTime>)>,
+        data: &mut Entries,
         key: &str,
         is_expired_fn: impl Fn(&Option<SystemTime>) -> bool,
     ) {
@@ -66,7 +112,7 @@ impl FlowStore for InMemoryFlowStore {
 
     fn set(&self, flow_id: &str, key: &str, value: Value) -> Result<()> {
         let key_str = self.make_key(flow_id, key);
-        let expiry = SystemTime::now() + self.default_ttl;
+        let expiry = (self.clock)() + self.default_ttl;
         let mut data = self.data.write().unwrap();
 
         // Opportunistically clean up this specific key if expired
@@ -96,7 +142,7 @@ impl FlowStore for InMemoryFlowStore {
 
     fn increment(&self, flow_id: &str, key: &str) -> Result<i64> {
         let key_str = self.make_key(flow_id, key);
-        let expiry = SystemTime::now() + self.default_ttl;
+        let expiry = (self.clock)() + self.default_ttl;
         let mut data = self.data.write().unwrap();
 
         // Opportunistically clean up this specific key if expired
@@ -113,7 +159,7 @@ impl FlowStore for InMemoryFlowStore {
 
     fn set_ttl(&self, flow_id: &str, ttl_seconds: i64) -> Result<()> {
         let prefix = format!("flow:{flow_id}:");
-        let new_expiry = SystemTime::now() + Duration::from_secs(ttl_seconds as u64);
+        let new_expiry = (self.clock)() + Duration::from_secs(ttl_seconds as u64);
         let mut data = self.data.write().unwrap();
 
         for (key, (_, expiry)) in data.iter_mut() {
@@ -130,6 +176,52 @@ impl FlowStore for InMemoryFlowStore {
 mod tests {
     use super::*;
     use serde_json::json;
+    use std::sync::Mutex;
+
+    /// Store whose clock only moves when the returned handle is advanced
+    fn store_with_manual_clock(ttl_seconds: u64) -> (InMemoryFlowStore, Arc<Mutex<SystemTime>>) {
+        let now = Arc::new(Mutex::new(SystemTime::UNIX_EPOCH));
+        let clock_now = Arc::clone(&now);
+        let store = InMemoryFlowStore::with_clock(
+            ttl_seconds,
+            Arc::new(move || *clock_now.lock().unwrap()),
+        );
+        (store, now)
+    }
+
+    #[test]
+    fn test_increment_restarts_after_ttl() {
+        let (store, now) = store_with_manual_clock(300);
+
+        assert_eq!(store.increment("flow1", "attempts").unwrap(), 1);
+        assert_eq!(store.increment("flow1", "attempts").unwrap(), 2);
+
+        // Each write extends the expiry, so the counter survives while used
+        *now.lock().unwrap() += Duration::from_secs(299);
+        assert_eq!(store.increment("flow1", "attempts").unwrap(), 3);
+
+        *now.lock().unwrap() += Duration::from_secs(301);
+        assert_eq!(store.get("flow1", "attempts").unwrap(), None);
+        assert_eq!(store.increment("flow1", "attempts").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_sweep_drops_expired_entries() {
+        let (store, now) = store_with_manual_clock(10);
+        store.set("old", "key", json!(1)).unwrap();
+        *now.lock().unwrap() += Duration::from_secs(5);
+        store.set("new", "key", json!(2)).unwrap();
+
+        *now.lock().unwrap() += Duration::from_secs(6);
+        let data = Arc::downgrade(&store.data);
+        let clock = Arc::clone(&store.clock);
+        assert_eq!(InMemoryFlowStore::sweep(&data, &clock), Some(1));
+        assert_eq!(store.data.read().unwrap().len(), 1);
+        assert_eq!(store.get("new", "key").unwrap(), Some(json!(2)));
+
+        drop(store);
+        assert_eq!(InMemoryFlowStore::sweep(&data, &clock), None);
+    }
 
     #[test]
     fn test_inmemory_get_set() {
@@ -288,4 +380,34 @@ mod tests {
             "Concurrent increments lost updates: expected {expected}, got {final_value:?}"
         );
     }
+
+    #[test]
+    fn test_concurrent_increment_returns_each_value_once() {
+        use std::thread;
+
+        let store = Arc::new(InMemoryFlowStore::new(300));
+        let barrier = Arc::new(std::sync::Barrier::new(16));
+
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let store = Arc::clone(&store);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    (0..250)
+                        .map(|_| store.increment("race", "attempts").unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        // Every call saw its own post-increment value: no two callers got the
+        // same count, and none was skipped
+        let mut values: Vec<i64> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+        values.sort_unstable();
+        assert_eq!(values, (1..=4000).collect::<Vec<_>>());
+    }
 }
@@ -12,7 +12,7 @@ use std::sync::Mutex;
 /// # Compatibility
 ///
 /// - Redis 6.x, 7.x: Fully supported
-/// - Valkey: Likely compatible but not officially supported
+/// - Valkey: Supported through the same client (`backend: valkey`)
 ///
 /// Simple connection manager for Redis
 struct RedisConnectionManager {
@@ -184,15 +184,16 @@ impl FlowStore for RedisFlowStore {
             .context("Failed to get Redis connection from pool")?;
         let mut conn_guard = conn.lock().unwrap();
 
-        // INCR returns the new value
-        let new_value: i64 = conn_guard.incr(&key_str, 1).context("Redis INCR failed")?;
-
-        // Set TTL on the key (INCR doesn't reset TTL)
-        let _: () = redis::cmd("EXPIRE")
-            .arg(&key_str)
-            .arg(self.default_ttl_seconds)
+        // INCR is atomic and returns the new value; EXPIRE refreshes the TTL
+        // (INCR doesn't). MULTI keeps a counter from ever existing without
+        // an expiry.
+        let (new_value,): (i64,) = redis::pipe()
+            .atomic()
+            .incr(&key_str, 1)
+            .expire(&key_str, self.default_ttl_seconds)
+            .ignore()
             .query(&mut *conn_guard)
-            .context("Redis EXPIRE failed")?;
+            .context("Redis INCR failed")?;
 
         Ok(new_value)
     }
@@ -231,6 +232,7 @@ pub(crate) fn health_check(pool: &r2d2::Pool<RedisConnectionManager>) -> Result<
 mod tests {
     use super::*;
     use serde_json::json;
+    use std::sync::Arc;
 
     // Helper to check if Redis is available
     fn redis_available() -> bool {
@@ -283,6 +285,40 @@ mod tests {
         store.delete("flow1", "counter").unwrap();
     }
 
+    #[test]
+    #[ignore] // Only run when Redis is available
+    fn test_redis_concurrent_increment() {
+        if !redis_available() {
+            eprintln!("Skipping test: Redis not available");
+            return;
+        }
+
+        let store = Arc::new(
+            RedisFlowStore::new("redis://localhost:6379", 8, "test:".to_string(), 300).unwrap(),
+        );
+        store.delete("race", "counter").unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let store = Arc::clone(&store);
+                std::thread::spawn(move || {
+                    (0..50)
+                        .map(|_| store.increment("race", "counter").unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut values: Vec<i64> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+        values.sort_unstable();
+        assert_eq!(values, (1..=400).collect::<Vec<_>>());
+
+        // Cleanup
+        store.delete("race", "counter").unwrap();
+    }
+
     #[test]
     #[ignore] // Only run when Redis is available
     fn test_redis_ttl() {
@@ -23,7 +23,8 @@ pub struct CopyBehavior {
 pub enum CopySource {
     /// Simple field: "path", "body", "method"
     Simple(String),
-    /// Nested field: {"query": "name"} or {"headers": "Content-Type"}
+    /// Nested field: {"query": "name"}, {"headers": "Content-Type"} or
+    /// {"pathParams": "id"}
     Nested(HashMap<String, String>),
 }
 
@@ -48,6 +49,8 @@ impl CopySource {
                         .iter()
                         .find(|(k, _)| k.to_lowercase() == lower_name)
                         .map(|(_, v)| v.clone())
+                } else if let Some(param_name) = map.get("pathParams") {
+                    request.path_params.get(param_name).cloned()
                 } else {
                     None
                 }
@@ -103,6 +106,7 @@ mod tests {
             query: HashMap::new(),
             headers: HashMap::new(),
             body: Some("test body".to_string()),
+            path_params: HashMap::new(),
         };
 
         let source = CopySource::Simple("path".to_string());
@@ -129,6 +133,7 @@ mod tests {
             query,
             headers,
             body: None,
+            path_params: HashMap::new(),
         };
 
         let mut map = HashMap::new();
@@ -143,6 +148,15 @@ mod tests {
             source.extract(&request),
             Some("application/json".to_string())
         );
+
+        let request = RequestContext {
+            path_params: HashMap::from([("id".to_string(), "42".to_string())]),
+            ..request
+        };
+        let mut map = HashMap::new();
+        map.insert("pathParams".to_string(), "id".to_string());
+        let source = CopySource::Nested(map);
+        assert_eq!(source.extract(&request), Some("42".to_string()));
     }
 
     #[test]
@@ -156,6 +170,7 @@ mod tests {
             query,
             headers: HashMap::new(),
             body: None,
+            path_params: HashMap::new(),
         };
 
         let behaviors = vec![
@@ -185,4 +200,66 @@ mod tests {
         let result = apply_copy_behaviors(body, &mut headers, &behaviors, &request);
         assert_eq!(result, r#"{"userId": "123", "greeting": "Hello, Alice!"}"#);
     }
+
+    #[test]
+    fn test_apply_copy_behaviors_header_source() {
+        let mut headers = HashMap::new();
+        headers.insert("X-Request-Id".to_string(), "req-42".to_string());
+
+        let request = RequestContext {
+            method: "GET".to_string(),
+            path: "/orders".to_string(),
+            query: HashMap::new(),
+            headers,
+            body: None,
+            path_params: HashMap::new(),
+        };
+
+        let behaviors: Vec<CopyBehavior> = serde_json::from_str(
+            r#"[{"from": {"headers": "x-request-id"}, "into": "${REQUEST_ID}", "using": {"method": "regex", "selector": ".+"}}]"#,
+        )
+        .unwrap();
+
+        let mut response_headers = HashMap::new();
+        response_headers.insert("X-Request-Id".to_string(), "${REQUEST_ID}".to_string());
+
+        let result = apply_copy_behaviors(
+            r#"{"requestId": "${REQUEST_ID}"}"#,
+            &mut response_headers,
+            &behaviors,
+            &request,
+        );
+        assert_eq!(result, r#"{"requestId": "req-42"}"#);
+        assert_eq!(response_headers.get("X-Request-Id").unwrap(), "req-42");
+    }
+
+    #[test]
+    fn test_apply_copy_behaviors_jsonpath_body_source() {
+        let request = RequestContext {
+            method: "POST".to_string(),
+            path: "/orders".to_string(),
+            query: HashMap::new(),
+            headers: HashMap::new(),
+            body: Some(r#"{"order": {"id": 1001, "customer": "Bob"}}"#.to_string()),
+            path_params: HashMap::new(),
+        };
+
+        let behaviors: Vec<CopyBehavior> = serde_json::from_str(
+            r#"[
+                {"from": "body", "into": "${ORDER_ID}", "using": {"method": "jsonpath", "selector": "$.order.id"}},
+                {"from": "body", "into": "${MISSING}", "using": {"method": "jsonpath", "selector": "$.order.missing"}}
+            ]"#,
+        )
+        .unwrap();
+
+        let mut headers = HashMap::new();
+        let result = apply_copy_behaviors(
+            r#"{"id": ${ORDER_ID}, "note": "${MISSING}"}"#,
+            &mut headers,
+            &behaviors,
+            &request,
+        );
+        // Unresolved selectors are replaced with an empty string
+        assert_eq!(result, r#"{"id": 1001, "note": ""}"#);
+    }
 }
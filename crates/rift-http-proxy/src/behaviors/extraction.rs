@@ -2,6 +2,7 @@
 
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Method for extracting values from source
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -85,13 +86,29 @@ pub fn extract_jsonpath(json_str: &str, path: &str) -> Option<String> {
 /// Extract value using XPath
 /// Used by copy behaviors and predicate xpath parameter
 pub fn extract_xpath(xml_str: &str, path: &str) -> Option<String> {
+    extract_xpath_with_namespaces(xml_str, path, &HashMap::new())
+}
+
+/// Extract value using XPath, resolving prefixes in `path` through
+/// `namespaces` (prefix -> URI), as in the predicate `xpath.ns` parameter
+pub fn extract_xpath_with_namespaces(
+    xml_str: &str,
+    path: &str,
+    namespaces: &HashMap<String, String>,
+) -> Option<String> {
     use sxd_document::parser;
-    use sxd_xpath::{evaluate_xpath, Value};
+    use sxd_xpath::{Context, Factory, Value};
 
     let package = parser::parse(xml_str).ok()?;
     let document = package.as_document();
 
-    match evaluate_xpath(&document, path) {
+    let xpath = Factory::new().build(path).ok()??;
+    let mut context = Context::new();
+    for (prefix, uri) in namespaces {
+        context.set_namespace(prefix, uri);
+    }
+
+    match xpath.evaluate(&context, document.root()) {
         Ok(Value::String(s)) => Some(s),
         Ok(Value::Number(n)) => Some(n.to_string()),
         Ok(Value::Boolean(b)) => Some(b.to_string()),
@@ -141,4 +158,17 @@ mod tests {
         let json = r#"{"items": ["first", "second"]}"#;
         assert_eq!(method.extract(json), Some("first".to_string()));
     }
+
+    #[test]
+    fn test_extract_xpath_with_namespaces() {
+        let xml = r#"<o:order xmlns:o="urn:orders"><o:id>42</o:id></o:order>"#;
+        let namespaces = HashMap::from([("x".to_string(), "urn:orders".to_string())]);
+        assert_eq!(
+            extract_xpath_with_namespaces(xml, "//x:id", &namespaces),
+            Some("42".to_string())
+        );
+        // Prefixes resolve by URI, not by the document's own prefix
+        let ns = HashMap::from([("x".to_string(), "urn:other".to_string())]);
+        assert_eq!(extract_xpath_with_namespaces(xml, "//x:id", &ns), None);
+    }
 }
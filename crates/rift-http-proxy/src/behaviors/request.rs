@@ -25,6 +25,8 @@ pub struct RequestContext {
     pub query: HashMap<String, String>,
     pub headers: HashMap<String, String>,
     pub body: Option<String>,
+    /// Variables captured by the matched rule's path template
+    pub path_params: HashMap<String, String>,
 }
 
 impl RequestContext {
@@ -60,6 +62,13 @@ impl RequestContext {
             query: query_map,
             headers: header_map,
             body: body.map(|s| s.to_string()),
+            path_params: HashMap::new(),
         }
     }
+
+    /// Attach path template captures
+    pub fn with_path_params(mut self, path_params: HashMap<String, String>) -> Self {
+        self.path_params = path_params;
+        self
+    }
 }
@@ -144,6 +144,7 @@ mod tests {
             query: HashMap::new(),
             headers: HashMap::new(),
             body: Some(r#"{"test": "data"}"#.to_string()),
+            path_params: HashMap::new(),
         };
 
         // Simple echo command that outputs a fixed string
@@ -163,6 +164,7 @@ mod tests {
             query: HashMap::new(),
             headers: HashMap::new(),
             body: None,
+            path_params: HashMap::new(),
         };
 
         // Command that outputs the MB_REQUEST env var (which contains JSON)
@@ -185,6 +187,7 @@ mod tests {
             query: HashMap::new(),
             headers: HashMap::new(),
             body: None,
+            path_params: HashMap::new(),
         };
 
         let mut headers = HashMap::new();
@@ -210,6 +213,7 @@ mod tests {
             query: HashMap::new(),
             headers: HashMap::new(),
             body: None,
+            path_params: HashMap::new(),
         };
 
         let mut headers = HashMap::new();
@@ -234,6 +238,7 @@ mod tests {
             query: HashMap::new(),
             headers: HashMap::new(),
             body: Some(r#"{"name": "Alice"}"#.to_string()),
+            path_params: HashMap::new(),
         };
 
         let mut headers = HashMap::new();
@@ -258,6 +263,7 @@ mod tests {
             query: HashMap::new(),
             headers: HashMap::new(),
             body: None,
+            path_params: HashMap::new(),
         };
 
         let mut headers = HashMap::new();
@@ -281,6 +287,7 @@ mod tests {
             query: HashMap::new(),
             headers: HashMap::new(),
             body: None,
+            path_params: HashMap::new(),
         };
 
         let mut headers = HashMap::new();
@@ -0,0 +1,68 @@
+//! CORS configuration.
+
+use serde::{Deserialize, Serialize};
+
+/// Cross-origin access for browser clients.
+///
+/// Preflight `OPTIONS` requests are answered by Rift without reaching the
+/// upstream; other responses to an allowed origin get
+/// `Access-Control-Allow-Origin`.
+///
+/// ```yaml
+/// cors:
+///   allowed_origins: ["https://app.example.com"]
+///   allowed_headers: [Content-Type, Authorization]
+///   max_age_secs: 600
+///   allow_credentials: true
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CorsConfig {
+    /// Origins allowed to make requests; `*` allows any origin
+    pub allowed_origins: Vec<String>,
+    /// Methods allowed in preflight requests
+    #[serde(default = "default_allowed_methods")]
+    pub allowed_methods: Vec<String>,
+    /// Request headers allowed in preflight requests. If empty, the headers
+    /// a preflight asks for are allowed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_headers: Vec<String>,
+    /// How long browsers may cache a preflight response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_secs: Option<u64>,
+    /// Allow cookies and other credentials on cross-origin requests
+    #[serde(default)]
+    pub allow_credentials: bool,
+}
+
+fn default_allowed_methods() -> Vec<String> {
+    ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE"]
+        .map(String::from)
+        .to_vec()
+}
+
+impl CorsConfig {
+    /// Whether requests from `origin` are allowed
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.allowed_origins.is_empty() {
+            return Err("allowed_origins must not be empty".to_string());
+        }
+        // Browsers reject credentialed responses allowing any origin
+        if self.allow_credentials && self.allowed_origins.iter().any(|o| o == "*") {
+            return Err("allow_credentials can't be combined with allowed origin '*'".to_string());
+        }
+        if let Some(method) = self
+            .allowed_methods
+            .iter()
+            .find(|m| m.parse::<hyper::Method>().is_err())
+        {
+            return Err(format!("invalid method '{method}' in allowed_methods"));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+//! `!include` directives for YAML config files.
+//!
+//! A node tagged `!include other.yaml` is replaced by the contents of that
+//! file, resolved relative to the file holding the tag. An include that is
+//! an item of a sequence and names a file holding a sequence is spliced into
+//! it, so shared rule lists combine with local rules:
+//!
+//! ```yaml
+//! rules:
+//!   - !include shared/rules.yaml
+//!   - id: local-rule
+//!     # ...
+//! ```
+//!
+//! Included files may include others; a file that ends up including itself
+//! is an error.
+
+use anyhow::Context;
+use serde_yaml::Value;
+use std::path::{Path, PathBuf};
+
+const INCLUDE_TAG: &str = "!include";
+
+/// Parse `contents` of the config file at `path`, expanding includes.
+///
+/// Returns `None` when there is nothing to expand, so plain YAML keeps
+/// deserializing straight from text (and keeps line numbers in errors).
+pub(super) fn expand_includes(path: &Path, contents: &str) -> anyhow::Result<Option<Value>> {
+    let Ok(value) = serde_yaml::from_str::<Value>(contents) else {
+        return Ok(None);
+    };
+    if !has_includes(&value) {
+        return Ok(None);
+    }
+    let path = canonicalize(path)?;
+    let mut stack = vec![path.clone()];
+    expand(value, parent_dir(&path), &mut stack).map(Some)
+}
+
+fn has_includes(value: &Value) -> bool {
+    match value {
+        Value::Tagged(tagged) => tagged.tag == INCLUDE_TAG || has_includes(&tagged.value),
+        Value::Sequence(items) => items.iter().any(has_includes),
+        Value::Mapping(map) => map.values().any(has_includes),
+        _ => false,
+    }
+}
+
+fn is_include(value: &Value) -> bool {
+    matches!(value, Value::Tagged(tagged) if tagged.tag == INCLUDE_TAG)
+}
+
+/// Expand includes in `value`, taken from a file in `dir`. `stack` holds
+/// the files being expanded, outermost first.
+fn expand(value: Value, dir: &Path, stack: &mut Vec<PathBuf>) -> anyhow::Result<Value> {
+    Ok(match value {
+        Value::Tagged(tagged) if tagged.tag == INCLUDE_TAG => include(&tagged.value, dir, stack)?,
+        Value::Tagged(mut tagged) => {
+            tagged.value = expand(tagged.value, dir, stack)?;
+            Value::Tagged(tagged)
+        }
+        Value::Sequence(items) => {
+            let mut expanded = Vec::with_capacity(items.len());
+            for item in items {
+                if !is_include(&item) {
+                    expanded.push(expand(item, dir, stack)?);
+                    continue;
+                }
+                match expand(item, dir, stack)? {
+                    Value::Sequence(included) => expanded.extend(included),
+                    other => expanded.push(other),
+                }
+            }
+            Value::Sequence(expanded)
+        }
+        Value::Mapping(map) => Value::Mapping(
+            map.into_iter()
+                .map(|(key, value)| Ok((key, expand(value, dir, stack)?)))
+                .collect::<anyhow::Result<_>>()?,
+        ),
+        other => other,
+    })
+}
+
+fn include(target: &Value, dir: &Path, stack: &mut Vec<PathBuf>) -> anyhow::Result<Value> {
+    let Value::String(target) = target else {
+        anyhow::bail!("{INCLUDE_TAG} expects a file path, got {target:?}");
+    };
+    let path = canonicalize(&dir.join(target))?;
+    if stack.contains(&path) {
+        let chain: Vec<_> = stack
+            .iter()
+            .chain([&path])
+            .map(|path| path.display().to_string())
+            .collect();
+        anyhow::bail!("Config include cycle: {}", chain.join(" -> "));
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read included config file {}", path.display()))?;
+    let value: Value = serde_yaml::from_str(&contents)
+        .with_context(|| format!("Failed to parse included config file {}", path.display()))?;
+    stack.push(path.clone());
+    let expanded = expand(value, parent_dir(&path), stack);
+    stack.pop();
+    expanded
+}
+
+fn canonicalize(path: &Path) -> anyhow::Result<PathBuf> {
+    path.canonicalize()
+        .with_context(|| format!("Failed to read config file {}", path.display()))
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Config;
+
+    const MAIN: &str = r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 8000
+rules:
+  - !include shared/rules.yaml
+  - id: local
+    match:
+      path:
+        exact: /local
+    fault:
+      error: !include shared/error.yaml
+"#;
+
+    const SHARED_RULES: &str = r#"
+- id: shared-a
+  match:
+    path:
+      prefix: /a
+  fault:
+    error: !include error.yaml
+- id: shared-b
+  match:
+    path:
+      prefix: /b
+  fault:
+    latency:
+      probability: 1.0
+      min_ms: 10
+      max_ms: 20
+"#;
+
+    const SHARED_ERROR: &str = "probability: 1.0\nstatus: 503\n";
+
+    fn write(dir: &std::path::Path, name: &str, contents: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_config_includes_shared_rules_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = write(dir.path(), "rift.yaml", MAIN);
+        write(dir.path(), "shared/rules.yaml", SHARED_RULES);
+        write(dir.path(), "shared/error.yaml", SHARED_ERROR);
+
+        let config = Config::from_file(&main).unwrap();
+        let ids: Vec<_> = config.rules.iter().map(|rule| rule.id.as_str()).collect();
+        assert_eq!(ids, ["shared-a", "shared-b", "local"]);
+        // Nested includes resolve relative to the including file
+        let status = |i: usize| config.rules[i].fault.error.as_ref().unwrap().status;
+        assert_eq!(status(0), 503);
+        assert_eq!(status(2), 503);
+        assert!(config.rules[1].fault.latency.is_some());
+    }
+
+    #[test]
+    fn test_include_cycle_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = write(dir.path(), "rift.yaml", MAIN);
+        write(dir.path(), "shared/rules.yaml", SHARED_RULES);
+        write(
+            dir.path(),
+            "shared/error.yaml",
+            "!include ../shared/error.yaml",
+        );
+
+        let error = format!("{:#}", Config::from_file(&main).unwrap_err());
+        assert!(error.contains("include cycle"), "{error}");
+        assert!(error.contains("error.yaml -> "), "{error}");
+    }
+
+    #[test]
+    fn test_missing_include_names_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = write(dir.path(), "rift.yaml", MAIN);
+
+        let error = format!("{:#}", Config::from_file(&main).unwrap_err());
+        assert!(error.contains("rules.yaml"), "{error}");
+    }
+}
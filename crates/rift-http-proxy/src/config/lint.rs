@@ -0,0 +1,425 @@
+//! Warnings for fault rules that can't take effect.
+//!
+//! Two kinds of mistakes are caught: a rule whose predicates can never all
+//! hold (a lowercase method, a header required to both be absent and equal
+//! a value, two different values for one query parameter), and a rule that
+//! an earlier rule always matches first. Only obvious cases are reported;
+//! a rule without warnings may still be unreachable.
+
+use super::{Config, MatchConfig, PathMatch, Rule};
+use crate::predicate::{FieldMatcher, MultiValue, StringMatcher};
+use hyper::Method;
+
+impl Config {
+    /// Describe rules that can never match, each naming its rule id
+    pub fn lint(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (i, rule) in self.rules.iter().enumerate() {
+            for problem in unsatisfiable(&rule.match_config) {
+                warnings.push(format!("Rule '{}' can never match: {problem}", rule.id));
+            }
+            if let Some(earlier) = self.rules[..i].iter().find(|e| shadows(e, rule)) {
+                warnings.push(format!(
+                    "Rule '{}' is unreachable: rule '{}' before it matches every request it does",
+                    rule.id, earlier.id
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+/// What a predicate requires of one field
+#[derive(Debug)]
+enum Requirement<'a> {
+    Present(bool),
+    Equals {
+        value: &'a str,
+        case_sensitive: bool,
+        scope: Scope,
+    },
+}
+
+/// Which instances of a repeatable field a requirement applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    First,
+    Every,
+    Some,
+}
+
+impl Scope {
+    /// Whether differing values required in the two scopes contradict:
+    /// a field repeated with different values can satisfy the rest
+    fn exclusive(self, other: Scope) -> bool {
+        matches!(
+            (self, other),
+            (Scope::Every, _) | (_, Scope::Every) | (Scope::First, Scope::First)
+        )
+    }
+}
+
+fn unsatisfiable(config: &MatchConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for method in &config.methods {
+        let upper = method.to_ascii_uppercase();
+        if *method != upper && upper.parse::<Method>().is_ok_and(is_standard) {
+            problems.push(format!(
+                "method '{method}' is case-sensitive and never equals {upper}"
+            ));
+        }
+    }
+
+    match &config.path {
+        PathMatch::Exact { exact: path } | PathMatch::Prefix { prefix: path }
+            if !path.starts_with('/') =>
+        {
+            problems.push(format!("path '{path}' doesn't start with '/'"));
+        }
+        _ => {}
+    }
+
+    // Legacy headers compare against the first value only
+    let headers = config
+        .headers
+        .iter()
+        .map(|h| {
+            let requirement = Requirement::Equals {
+                value: &h.value,
+                case_sensitive: true,
+                scope: Scope::First,
+            };
+            (h.name.to_ascii_lowercase(), requirement)
+        })
+        .chain(config.header_predicates.iter().filter_map(|m| {
+            let scope = match m {
+                FieldMatcher::Or {
+                    values: MultiValue::All,
+                    ..
+                }
+                | FieldMatcher::Full {
+                    values: MultiValue::All,
+                    ..
+                } => Scope::Every,
+                _ => Scope::Some,
+            };
+            Some((m.name().to_ascii_lowercase(), requirement(m, scope)?))
+        }));
+    problems.extend(conflicts("header", headers.collect()));
+
+    // Query parameters carry a single value each
+    let query = config
+        .query
+        .iter()
+        .filter_map(|m| Some((m.name().to_string(), requirement(m, Scope::Every)?)));
+    problems.extend(conflicts("query parameter", query.collect()));
+
+    for matcher in config.header_predicates.iter().chain(&config.query) {
+        if let FieldMatcher::Or {
+            name, or, options, ..
+        } = matcher
+        {
+            if or.is_empty() && !options.not {
+                problems.push(format!("'{name}' has an empty 'or'"));
+            }
+        }
+    }
+
+    problems
+}
+
+fn is_standard(method: Method) -> bool {
+    [
+        Method::GET,
+        Method::POST,
+        Method::PUT,
+        Method::DELETE,
+        Method::HEAD,
+        Method::OPTIONS,
+        Method::CONNECT,
+        Method::PATCH,
+        Method::TRACE,
+    ]
+    .contains(&method)
+}
+
+/// The requirement a field matcher makes, if it is simple enough to compare
+fn requirement(matcher: &FieldMatcher, scope: Scope) -> Option<Requirement<'_>> {
+    match matcher {
+        FieldMatcher::Simple { value, .. } => Some(Requirement::Equals {
+            value,
+            case_sensitive: true,
+            scope,
+        }),
+        FieldMatcher::Full {
+            matcher, options, ..
+        } if options.except.is_none() => match (matcher, options.not) {
+            (StringMatcher::Exists(exists), not) => Some(Requirement::Present(*exists != not)),
+            (StringMatcher::Equals(value), false) => Some(Requirement::Equals {
+                value,
+                case_sensitive: options.case_sensitive,
+                scope,
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Pairs of requirements on the same field that can't both hold
+fn conflicts(kind: &str, requirements: Vec<(String, Requirement<'_>)>) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (i, (name, a)) in requirements.iter().enumerate() {
+        for (_, b) in requirements[i + 1..].iter().filter(|(n, _)| n == name) {
+            let problem = match (a, b) {
+                (Requirement::Present(x), Requirement::Present(y)) if x != y => {
+                    format!("{kind} '{name}' must be both present and absent")
+                }
+                (Requirement::Present(false), Requirement::Equals { value, .. })
+                | (Requirement::Equals { value, .. }, Requirement::Present(false)) => {
+                    format!("{kind} '{name}' must be absent and equal '{value}'")
+                }
+                (
+                    Requirement::Equals {
+                        value: x,
+                        case_sensitive: x_case,
+                        scope: x_scope,
+                    },
+                    Requirement::Equals {
+                        value: y,
+                        case_sensitive: y_case,
+                        scope: y_scope,
+                    },
+                ) if x_scope.exclusive(*y_scope)
+                    && if *x_case && *y_case {
+                        x != y
+                    } else {
+                        !x.eq_ignore_ascii_case(y)
+                    } =>
+                {
+                    format!("{kind} '{name}' must equal both '{x}' and '{y}'")
+                }
+                _ => continue,
+            };
+            problems.push(problem);
+        }
+    }
+    problems
+}
+
+/// Whether `earlier` matches every request `later` does, so `later` is never
+/// reached. Only rules constrained by nothing but method and path count.
+fn shadows(earlier: &Rule, later: &Rule) -> bool {
+    let e = &earlier.match_config;
+    let l = &later.match_config;
+    let only_method_and_path = e.headers.is_empty()
+        && e.header_predicates.is_empty()
+        && e.query.is_empty()
+        && e.trailers.is_empty()
+        && e.body.is_none()
+        && e.body_size.is_none()
+        && e.content_type.is_none()
+        && e.hmac.is_none()
+        && e.auth.is_none()
+        && e.user_agent_family.is_none()
+        && e.ip.is_none()
+        && e.is_retry.is_none();
+    let upstream_covered = earlier.upstream.is_none() || earlier.upstream == later.upstream;
+    let methods_covered = e.methods.is_empty()
+        || (!l.methods.is_empty() && l.methods.iter().all(|m| e.methods.contains(m)));
+
+    // Access rules are all checked before any fault rule
+    let checked_first = earlier.action.is_fault() || !later.action.is_fault();
+
+    !earlier.once
+        && checked_first
+        && only_method_and_path
+        && upstream_covered
+        && methods_covered
+        && path_covered(&e.path, e.case_sensitive, &l.path, l.case_sensitive)
+}
+
+fn path_covered(
+    earlier: &PathMatch,
+    earlier_case_sensitive: bool,
+    later: &PathMatch,
+    later_case_sensitive: bool,
+) -> bool {
+    if matches!(earlier, PathMatch::Any) {
+        return true;
+    }
+    // A case-insensitive later rule matches paths a sensitive one doesn't
+    if earlier_case_sensitive && !later_case_sensitive {
+        return false;
+    }
+    let fold = |s: &str| {
+        if earlier_case_sensitive {
+            s.to_string()
+        } else {
+            s.to_lowercase()
+        }
+    };
+    match (earlier, later) {
+        (PathMatch::Exact { exact: e }, PathMatch::Exact { exact: l }) => fold(e) == fold(l),
+        (PathMatch::Prefix { prefix: e }, PathMatch::Exact { exact: l })
+        | (PathMatch::Prefix { prefix: e }, PathMatch::Prefix { prefix: l }) => {
+            fold(l).starts_with(&fold(e))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Config;
+
+    fn lint(rules: &str) -> Vec<String> {
+        let yaml = format!(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\nrules:\n{rules}"
+        );
+        let config: Config = serde_yaml::from_str(&yaml).unwrap();
+        config.lint()
+    }
+
+    #[test]
+    fn test_lint_flags_method_that_never_matches() {
+        let warnings = lint(
+            r#"
+  - id: lowercase-method
+    match:
+      methods: [GET, post]
+    fault:
+      error: {probability: 1.0, status: 500}
+"#,
+        );
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert!(warnings[0].contains("'lowercase-method'"), "{warnings:?}");
+        assert!(warnings[0].contains("method 'post'"), "{warnings:?}");
+    }
+
+    #[test]
+    fn test_lint_flags_contradictory_fields() {
+        let warnings = lint(
+            r#"
+  - id: contradictions
+    match:
+      path:
+        prefix: api
+      headers:
+        - {name: X-Env, value: prod}
+      headerPredicates:
+        - {name: x-env, equals: staging, values: all}
+        - {name: X-Trace, exists: true}
+        - {name: X-Trace, exists: false}
+      query:
+        - {name: page, value: "1"}
+        - {name: page, equals: "2"}
+    fault:
+      error: {probability: 1.0, status: 500}
+"#,
+        );
+        let expected = [
+            "path 'api'",
+            "header 'x-env' must equal both 'prod' and 'staging'",
+            "header 'x-trace' must be both present and absent",
+            "query parameter 'page' must equal both '1' and '2'",
+        ];
+        assert_eq!(warnings.len(), expected.len(), "{warnings:?}");
+        for (warning, expected) in warnings.iter().zip(expected) {
+            assert!(warning.starts_with("Rule 'contradictions'"), "{warning}");
+            assert!(warning.contains(expected), "{warning}");
+        }
+    }
+
+    #[test]
+    fn test_lint_allows_satisfiable_repeated_headers() {
+        // Two `accept` headers can each satisfy one of the predicates
+        let warnings = lint(
+            r#"
+  - id: two-accepts
+    match:
+      headerPredicates:
+        - {name: Accept, equals: text/html}
+        - {name: Accept, equals: application/json}
+    fault:
+      error: {probability: 1.0, status: 500}
+"#,
+        );
+        assert!(warnings.is_empty(), "{warnings:?}");
+    }
+
+    #[test]
+    fn test_lint_flags_shadowed_rules() {
+        let warnings = lint(
+            r#"
+  - id: all-api
+    match:
+      path:
+        prefix: /api
+    fault:
+      latency: {probability: 0.5, min_ms: 10, max_ms: 20}
+  - id: api-users
+    match:
+      methods: [GET]
+      path:
+        exact: /api/users
+    fault:
+      error: {probability: 1.0, status: 500}
+  - id: other-path
+    match:
+      path:
+        prefix: /admin
+    fault:
+      error: {probability: 1.0, status: 500}
+  - id: get-only
+    match:
+      methods: [GET]
+    fault:
+      error: {probability: 1.0, status: 500}
+  - id: post-admin
+    match:
+      methods: [POST]
+      path:
+        prefix: /admin/users
+    fault:
+      error: {probability: 1.0, status: 500}
+"#,
+        );
+        assert_eq!(
+            warnings,
+            [
+                "Rule 'api-users' is unreachable: rule 'all-api' before it matches every \
+                 request it does",
+                "Rule 'post-admin' is unreachable: rule 'other-path' before it matches every \
+                 request it does",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_ignores_once_and_constrained_rules() {
+        let warnings = lint(
+            r#"
+  - id: first-only
+    once: true
+    match:
+      path:
+        prefix: /
+    fault:
+      error: {probability: 1.0, status: 500}
+  - id: beta-only
+    match:
+      headerPredicates:
+        - {name: X-Beta, exists: true}
+    fault:
+      error: {probability: 1.0, status: 500}
+  - id: everything
+    match: {}
+    fault:
+      error: {probability: 1.0, status: 503}
+"#,
+        );
+        assert!(warnings.is_empty(), "{warnings:?}");
+    }
+}
@@ -2,6 +2,7 @@
 
 use super::protocol::Protocol;
 use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
 
 /// TLS configuration for HTTPS listener
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -14,6 +15,10 @@ pub struct TlsConfig {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ListenConfig {
+    /// IP address of the interface to listen on; `0.0.0.0` listens on all
+    /// IPv4 interfaces, `::` on all interfaces
+    #[serde(default = "default_bind")]
+    pub bind: String,
     pub port: u16,
     /// Number of worker threads (0 = auto-detect CPU count)
     #[serde(default)]
@@ -24,10 +29,40 @@ pub struct ListenConfig {
     /// TLS configuration (required when protocol is https)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tls: Option<TlsConfig>,
+    /// Also accept cleartext HTTP/2 from clients that start with the HTTP/2
+    /// preface (h2c with prior knowledge). HTTPS listeners always offer
+    /// HTTP/2 through ALPN.
+    #[serde(default)]
+    pub h2c: bool,
+}
+
+fn default_bind() -> String {
+    "0.0.0.0".to_string()
+}
+
+impl ListenConfig {
+    /// Address to listen on, from `bind` and `port`
+    pub fn socket_addr(&self) -> Result<SocketAddr, String> {
+        // IPv6 addresses may be written in URL brackets, e.g. `[::1]`
+        let ip = self
+            .bind
+            .strip_prefix('[')
+            .and_then(|ip| ip.strip_suffix(']'))
+            .unwrap_or(&self.bind);
+        let ip: IpAddr = ip.parse().map_err(|_| {
+            format!(
+                "invalid listen.bind address '{}': expected an IP address such as \
+                 127.0.0.1 or ::1",
+                self.bind
+            )
+        })?;
+        Ok(SocketAddr::new(ip, self.port))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MetricsConfig {
+    /// Port serving `/metrics` and the `/__rift/` admin endpoints
     #[serde(default = "default_metrics_port")]
     pub port: u16,
 }
@@ -1,18 +1,26 @@
 //! Configuration types for Rift proxy.
 
+mod cors;
+mod include;
+mod lint;
 mod listen;
 mod protocol;
 mod recording;
+mod request_id;
+mod response_cache;
 mod routing;
 mod rules;
 mod scripting;
 mod upstream;
 
+use anyhow::Context;
 use std::path::Path;
+use tracing::warn;
 
 use serde::{Deserialize, Serialize};
 
 // Re-export all types for library consumers
+pub use cors::CorsConfig;
 #[allow(unused_imports)]
 pub use listen::{ListenConfig, MetricsConfig, TlsConfig};
 pub use protocol::{DeploymentMode, Protocol};
@@ -20,18 +28,29 @@ pub use protocol::{DeploymentMode, Protocol};
 pub use recording::{
     PredicateGenerator, PredicateGeneratorMatches, RecordingConfig, RecordingPersistence,
 };
+pub use request_id::RequestIdConfig;
+pub use response_cache::ResponseCacheConfig;
 #[allow(unused_imports)]
-pub use routing::{HeaderMatch, HostMatch, Route, RouteMatch};
+pub use routing::{
+    HeaderMatch, HostMatch, PathReplace, PathRewrite, ResponseHeaders, Route, RouteMatch,
+};
 #[allow(unused_imports)]
 pub use rules::{
-    ErrorFault, FaultConfig, LatencyFault, MatchConfig, PathMatch, Rule, ScriptRule, TcpFault,
+    ActiveWindow, BodyOverflow, ChunkedFault, ChunkedFaultMode, DenyAction, DripFault, ErrorFault,
+    FaultConfig, GrpcFault, LatencyFault, MatchConfig, MockResponse, PathMatch, RateLimitFault,
+    RateLimitKey, RegexConfig, Rule, RuleAction, ScriptRule, SequenceResponse, TcpFault,
+    WindowTime,
 };
 #[allow(unused_imports)]
 pub use scripting::{
     DecisionCacheConfigFile, FlowStateConfig, RedisConfig, ScriptEngineConfig, ScriptPoolConfigFile,
 };
 #[allow(unused_imports)]
-pub use upstream::{ConnectionPoolConfig, HealthCheckConfig, Upstream, UpstreamConfig};
+pub use upstream::{
+    AffinityKey, ConnectionPoolConfig, ForwardHeaders, HealthCheckConfig, RetryCondition,
+    RetryConfig, RetryOn, Upstream, UpstreamConfig, UpstreamGroup, UpstreamPoolConfig,
+    UpstreamTlsConfig,
+};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
@@ -72,6 +91,10 @@ pub struct Config {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub upstreams: Vec<Upstream>,
 
+    /// Weighted groups of upstreams; a route's `upstream` may name a group
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub upstream_groups: Vec<UpstreamGroup>,
+
     /// Routing rules for reverse proxy mode (required when 'upstreams' is used)
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub routing: Vec<Route>,
@@ -93,16 +116,73 @@ pub struct Config {
     /// Recording configuration for proxy record/replay (Mountebank-compatible)
     #[serde(default)]
     pub recording: RecordingConfig,
+    /// Seed for fault injection randomness, for reproducible runs.
+    /// If omitted, faults are sampled from OS entropy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Most request body bytes buffered for body and HMAC matchers.
+    /// Larger bodies are still forwarded in full; see `body_overflow`.
+    /// Also bounds the request described to `POST /__rift/match`.
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// Handling of bodies larger than `max_body_bytes`
+    #[serde(default)]
+    pub body_overflow: BodyOverflow,
+    /// Size limits for regexes in rule and route patterns
+    #[serde(default)]
+    pub regex: RegexConfig,
+    /// Headers put on every response sent to clients, proxied or faulted
+    #[serde(default, skip_serializing_if = "ResponseHeaders::is_empty")]
+    pub response_headers: ResponseHeaders,
+    /// CORS handling for browser clients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cors: Option<CorsConfig>,
+    /// Request ids taken from or added to each request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<RequestIdConfig>,
+    /// Cache of upstream responses to GET requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_cache: Option<ResponseCacheConfig>,
+}
+
+fn default_max_body_bytes() -> usize {
+    1024 * 1024
 }
 
 impl Config {
+    /// Read and validate a config file.
+    ///
+    /// Files ending in `.json` are parsed as JSON, everything else as YAML,
+    /// where `!include other.yaml` pulls in other files.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, anyhow::Error> {
-        let contents = std::fs::read_to_string(path)?;
-        let config: Config = serde_yaml::from_str(&contents)?;
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+        let config: Config = if is_json {
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse {} as JSON config", path.display()))?
+        } else if let Some(value) = include::expand_includes(path, &contents)? {
+            serde_yaml::from_value(value)
+                .with_context(|| format!("Failed to parse {} as YAML config", path.display()))?
+        } else {
+            serde_yaml::from_str(&contents)
+                .with_context(|| format!("Failed to parse {} as YAML config", path.display()))?
+        };
         config.validate()?;
         Ok(config)
     }
 
+    /// Read and validate YAML config from a reader (e.g. stdin)
+    pub fn from_reader<R: std::io::Read>(mut reader: R) -> Result<Self, anyhow::Error> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        contents.parse()
+    }
+
     /// Validate configuration
     pub fn validate(&self) -> Result<(), anyhow::Error> {
         // Validate listener configuration
@@ -113,23 +193,36 @@ impl Config {
             );
         }
 
+        self.listen.socket_addr().map_err(anyhow::Error::msg)?;
+
         // Validate listener protocol is supported
         if !self.listen.protocol.is_supported() {
             anyhow::bail!(
-                "Unsupported listener protocol: '{}'. Currently supported: http, https",
+                "Unsupported listener protocol: '{}'. Currently supported: http, https, websocket",
                 self.listen.protocol.as_str()
             );
         }
 
+        self.validate_deployment_mode()?;
+
         // Validate upstream configuration (sidecar mode)
         if let Some(ref upstream) = self.upstream {
             let protocol = upstream.get_protocol();
             if !protocol.is_supported() {
                 anyhow::bail!(
-                    "Unsupported upstream protocol: '{}'. Currently supported: http, https",
+                    "Unsupported upstream protocol: '{}'. Currently supported: http, https, websocket",
                     protocol.as_str()
                 );
             }
+            if let Some(retry) = &upstream.retry {
+                retry
+                    .validate()
+                    .map_err(|e| anyhow::anyhow!("Invalid upstream retry: {e}"))?;
+            }
+            upstream
+                .tls
+                .validate()
+                .map_err(|e| anyhow::anyhow!("Invalid upstream TLS: {e}"))?;
         }
 
         // Validate all upstreams (reverse proxy mode)
@@ -137,12 +230,244 @@ impl Config {
             upstream.validate().map_err(|e| anyhow::anyhow!(e))?;
         }
 
+        // A zero timeout would fail every request
+        if self.connection_pool.request_timeout_secs == Some(0) {
+            anyhow::bail!("connection_pool.request_timeout_secs must be at least 1");
+        }
+        if let Some(route) = self
+            .routing
+            .iter()
+            .find(|r| r.request_timeout_secs == Some(0))
+        {
+            anyhow::bail!(
+                "Route '{}' has request_timeout_secs 0; it must be at least 1",
+                route.name
+            );
+        }
+
+        if let Some(cors) = &self.cors {
+            cors.validate()
+                .map_err(|e| anyhow::anyhow!("Invalid cors config: {e}"))?;
+        }
+        if let Some(request_id) = &self.request_id {
+            request_id
+                .validate()
+                .map_err(|e| anyhow::anyhow!("Invalid request_id config: {e}"))?;
+        }
+        if let Some(response_cache) = &self.response_cache {
+            response_cache
+                .validate()
+                .map_err(|e| anyhow::anyhow!("Invalid response_cache config: {e}"))?;
+        }
+        self.response_headers
+            .validate()
+            .map_err(|e| anyhow::anyhow!("Invalid response_headers: {e}"))?;
+        for route in &self.routing {
+            route.response_headers.validate().map_err(|e| {
+                anyhow::anyhow!("Route '{}' has invalid response_headers: {e}", route.name)
+            })?;
+        }
+
+        for group in &self.upstream_groups {
+            if group.upstreams.is_empty() {
+                anyhow::bail!("Upstream group '{}' has no members", group.name);
+            }
+            if self.upstreams.iter().any(|u| u.name == group.name) {
+                anyhow::bail!(
+                    "Upstream group '{}' has the same name as an upstream",
+                    group.name
+                );
+            }
+            if let Some(missing) = group
+                .upstreams
+                .iter()
+                .find(|member| !self.upstreams.iter().any(|u| &u.name == *member))
+            {
+                anyhow::bail!(
+                    "Upstream group '{}' references unknown upstream '{}'",
+                    group.name,
+                    missing
+                );
+            }
+        }
+
+        if self.regex.size_limit == 0 || self.regex.dfa_size_limit == 0 {
+            anyhow::bail!("regex.size_limit and regex.dfa_size_limit must be at least 1");
+        }
+
+        // Compiling the matchers reports bad patterns with their rule and
+        // field, including patterns over the regex size limits
+        self.regex.apply();
+        for rule in self
+            .rules
+            .iter()
+            .cloned()
+            .chain(self.script_rules.iter().map(ScriptRule::match_rule))
+        {
+            crate::extensions::matcher::CompiledRule::compile(rule)?;
+        }
+
+        for rule in &self.rules {
+            rule.fault
+                .validate()
+                .map_err(|e| anyhow::anyhow!("Rule '{}': {}", rule.id, e))?;
+            if let Some(window) = &rule.active_window {
+                window
+                    .validate()
+                    .map_err(|e| anyhow::anyhow!("Rule '{}': {}", rule.id, e))?;
+            }
+            if !rule.action.is_fault() {
+                if rule.once {
+                    anyhow::bail!("Rule '{}': once applies only to fault rules", rule.id);
+                }
+                if rule.fault.has_fault() {
+                    warn!(
+                        "Rule '{}' is an access rule; its fault is never injected",
+                        rule.id
+                    );
+                }
+            }
+            if rule.fault.latency.is_some() && rule.fault.error.is_some() {
+                warn!(
+                    "Rule '{}' configures both latency and error faults; only one is \
+                     injected per request, error taking precedence",
+                    rule.id
+                );
+            }
+        }
+        for warning in self.lint() {
+            warn!("{warning}");
+        }
+
+        if let Some(flow_state) = &self.flow_state {
+            match flow_state.backend.as_str() {
+                "inmemory" => {}
+                "redis" | "valkey" => {
+                    if flow_state.redis.is_none() {
+                        anyhow::bail!(
+                            "flow_state backend '{}' requires 'flow_state.redis'",
+                            flow_state.backend
+                        );
+                    }
+                }
+                other => anyhow::bail!(
+                    "Unknown flow_state backend '{other}' (expected inmemory, redis or valkey)"
+                ),
+            }
+        }
+
+        // Retry detection tracks idempotency keys in the flow store
+        if self.flow_state.is_none() {
+            let retry_rule = self
+                .rules
+                .iter()
+                .map(|r| (&r.id, &r.match_config))
+                .chain(self.script_rules.iter().map(|r| (&r.id, &r.match_config)))
+                .find(|(_, m)| m.is_retry.is_some());
+            if let Some((id, _)) = retry_rule {
+                anyhow::bail!(
+                    "Rule '{id}' uses is_retry, which requires flow_state to be configured"
+                );
+            }
+        }
+
         // Validate script rules if present
         self.validate_script_rules()?;
 
         Ok(())
     }
 
+    /// Check that the upstream fields agree with the deployment mode and
+    /// that every route targets a declared upstream or group
+    fn validate_deployment_mode(&self) -> Result<(), anyhow::Error> {
+        match self.deployment_mode() {
+            DeploymentMode::Sidecar => {
+                if self.upstream.is_none() {
+                    anyhow::bail!("Sidecar mode requires 'upstream'");
+                }
+                if !self.upstreams.is_empty() {
+                    anyhow::bail!(
+                        "'upstreams' is not allowed in sidecar mode; use 'upstream' for the single target"
+                    );
+                }
+                if !self.routing.is_empty() {
+                    anyhow::bail!("'routing' is not allowed in sidecar mode");
+                }
+            }
+            DeploymentMode::ReverseProxy => {
+                if self.upstream.is_some() {
+                    anyhow::bail!(
+                        "'upstream' is not allowed in reverse-proxy mode; declare targets in 'upstreams'"
+                    );
+                }
+                if self.upstreams.is_empty() {
+                    anyhow::bail!("Reverse-proxy mode requires 'upstreams'");
+                }
+                if self.routing.is_empty() {
+                    anyhow::bail!("Reverse-proxy mode requires 'routing'");
+                }
+            }
+        }
+
+        for route in &self.routing {
+            let known = self.upstreams.iter().any(|u| u.name == route.upstream)
+                || self
+                    .upstream_groups
+                    .iter()
+                    .any(|g| g.name == route.upstream);
+            if !known {
+                anyhow::bail!(
+                    "Route '{}' references unknown upstream '{}' in 'routing[].upstream'",
+                    route.name,
+                    route.upstream
+                );
+            }
+        }
+
+        // Rule filters compare against the selected upstream, never a group
+        // name. Sidecar mode has no names and applies every rule.
+        if self.deployment_mode() == DeploymentMode::ReverseProxy {
+            let filters = self
+                .rules
+                .iter()
+                .map(|r| ("rules", &r.id, &r.upstream))
+                .chain(
+                    self.script_rules
+                        .iter()
+                        .map(|r| ("script_rules", &r.id, &r.upstream)),
+                );
+            for (field, id, upstream) in filters {
+                let Some(name) = upstream else { continue };
+                if !self.upstreams.iter().any(|u| &u.name == name) {
+                    anyhow::bail!(
+                        "Rule '{id}' references unknown upstream '{name}' in '{field}[].upstream'"
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The explicit `mode`, or the one implied by the upstream fields:
+    /// reverse proxy if `upstreams` or `routing` are set, sidecar otherwise
+    pub fn deployment_mode(&self) -> DeploymentMode {
+        self.mode
+            .unwrap_or(if self.upstreams.is_empty() && self.routing.is_empty() {
+                DeploymentMode::Sidecar
+            } else {
+                DeploymentMode::ReverseProxy
+            })
+    }
+
+    /// The config as Rift sees it: defaults filled in by parsing and the
+    /// deployment mode made explicit
+    pub fn normalized(&self) -> Config {
+        Config {
+            mode: Some(self.deployment_mode()),
+            ..self.clone()
+        }
+    }
+
     /// Validate all script rules based on the configured script engine
     fn validate_script_rules(&self) -> Result<(), anyhow::Error> {
         if self.script_rules.is_empty() {
@@ -156,6 +481,17 @@ impl Config {
             .unwrap_or("rhai");
 
         for script_rule in &self.script_rules {
+            if let Some(window) = &script_rule.active_window {
+                window
+                    .validate()
+                    .map_err(|e| anyhow::anyhow!("Script rule '{}': {}", script_rule.id, e))?;
+            }
+            for field in &script_rule.cache_key_fields {
+                field
+                    .parse::<crate::scripting::CacheKeyField>()
+                    .map_err(|e| anyhow::anyhow!("Script rule '{}': {}", script_rule.id, e))?;
+            }
+
             match engine_type {
                 "rhai" => {
                     use crate::scripting::{RhaiValidator, ScriptValidator};
@@ -215,6 +551,17 @@ impl Config {
     }
 }
 
+/// Parse and validate inline YAML config
+impl std::str::FromStr for Config {
+    type Err = anyhow::Error;
+
+    fn from_str(yaml: &str) -> Result<Self, Self::Err> {
+        let config: Config = serde_yaml::from_str(yaml)?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -602,6 +949,344 @@ rules: []
         assert_eq!(config.recording.mode, ProxyMode::ProxyAlways);
     }
 
+    fn config_with_fault(fault: &str) -> String {
+        format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 8000
+rules:
+  - id: "bad-fault"
+    match:
+      path:
+        prefix: "/api"
+    fault:
+{fault}
+"#
+        )
+    }
+
+    #[test]
+    fn test_validate_fault_bounds() {
+        let inverted = config_with_fault(
+            "      latency:\n        probability: 0.5\n        min_ms: 500\n        max_ms: 100",
+        );
+        let error = inverted.parse::<Config>().unwrap_err().to_string();
+        assert!(error.contains("bad-fault"), "{error}");
+        assert!(
+            error.contains("min_ms (500) must not exceed max_ms (100)"),
+            "{error}"
+        );
+
+        let out_of_range =
+            config_with_fault("      error:\n        probability: 1.5\n        status: 503");
+        let error = out_of_range.parse::<Config>().unwrap_err().to_string();
+        assert!(
+            error.contains("error probability must be between 0.0 and 1.0"),
+            "{error}"
+        );
+
+        let negative = config_with_fault(
+            "      latency:\n        probability: -0.1\n        min_ms: 1\n        max_ms: 2",
+        );
+        assert!(negative.parse::<Config>().is_err());
+    }
+
+    #[test]
+    fn test_validate_allows_latency_and_error_together() {
+        // Both may be configured; error takes precedence at injection time
+        let both = config_with_fault(
+            "      latency:\n        probability: 1.0\n        min_ms: 10\n        max_ms: 10\n      \
+             error:\n        probability: 0.5\n        status: 503",
+        );
+        let config: Config = both.parse().unwrap();
+        assert!(config.rules[0].fault.latency.is_some());
+        assert!(config.rules[0].fault.error.is_some());
+    }
+
+    #[test]
+    fn test_rate_limit_config() {
+        let rate_limit = |key: &str| {
+            let yaml = config_with_fault(&format!(
+                "      rate_limit:\n        requests: 5\n        per_secs: 10\n{key}"
+            ));
+            yaml.parse::<Config>()
+                .map(|config| config.rules[0].fault.rate_limit.clone().unwrap())
+        };
+        assert_eq!(rate_limit("").unwrap().key, RateLimitKey::Ip);
+        assert_eq!(
+            rate_limit("        key: path").unwrap().key,
+            RateLimitKey::Path
+        );
+        assert_eq!(
+            rate_limit("        key: { header: x-api-key }")
+                .unwrap()
+                .key,
+            RateLimitKey::Header("x-api-key".to_string())
+        );
+
+        let zero =
+            config_with_fault("      rate_limit:\n        requests: 0\n        per_secs: 10");
+        let error = zero.parse::<Config>().unwrap_err().to_string();
+        assert!(error.contains("at least 1"), "{error}");
+    }
+
+    #[test]
+    fn test_upstream_groups_config() {
+        let yaml = r#"
+listen:
+  port: 8080
+upstreams:
+  - name: api-1
+    url: "http://api-1:8000"
+    weight: 3
+  - name: api-2
+    url: "http://api-2:8000"
+upstream_groups:
+  - name: api
+    upstreams: [api-1, api-2]
+routing:
+  - name: "api"
+    match:
+      path_prefix: "/api"
+    upstream: api
+"#;
+        let config: Config = yaml.parse().unwrap();
+        assert_eq!(config.upstreams[0].weight, 3);
+        assert_eq!(config.upstreams[1].weight, 1);
+        assert_eq!(config.upstream_groups[0].upstreams, ["api-1", "api-2"]);
+
+        let unknown = yaml.replace("[api-1, api-2]", "[api-1, api-3]");
+        let error = unknown.parse::<Config>().unwrap_err().to_string();
+        assert!(error.contains("unknown upstream 'api-3'"), "{error}");
+    }
+
+    #[test]
+    fn test_upstream_group_affinity_config() {
+        let group = |affinity: &str| {
+            let yaml = format!("name: api\nupstreams: [api-1]\naffinity: {affinity}\n");
+            serde_yaml::from_str::<UpstreamGroup>(&yaml)
+                .unwrap()
+                .affinity
+        };
+        assert_eq!(
+            group("{ cookie: session_id }"),
+            Some(AffinityKey::Cookie("session_id".to_string()))
+        );
+        assert_eq!(
+            group("{ header: x-user-id }"),
+            Some(AffinityKey::Header("x-user-id".to_string()))
+        );
+        assert_eq!(group("client_ip"), Some(AffinityKey::ClientIp));
+    }
+
+    #[test]
+    fn test_json_and_yaml_files_load_same_config() {
+        let yaml = r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 8000
+rules:
+  - id: "api-errors"
+    match:
+      methods: ["GET"]
+      path:
+        prefix: "/api"
+    fault:
+      error:
+        probability: 0.5
+        status: 503
+"#;
+        let json = r#"{
+  "listen": {"port": 8080},
+  "upstream": {"host": "127.0.0.1", "port": 8000},
+  "rules": [{
+    "id": "api-errors",
+    "match": {"methods": ["GET"], "path": {"prefix": "/api"}},
+    "fault": {"error": {"probability": 0.5, "status": 503}}
+  }]
+}"#;
+
+        let dir = tempfile::tempdir().unwrap();
+        let yaml_path = dir.path().join("rift.yaml");
+        let json_path = dir.path().join("rift.json");
+        std::fs::write(&yaml_path, yaml).unwrap();
+        std::fs::write(&json_path, json).unwrap();
+
+        let from_yaml = Config::from_file(&yaml_path).unwrap();
+        let from_json = Config::from_file(&json_path).unwrap();
+        assert_eq!(
+            serde_json::to_value(&from_yaml).unwrap(),
+            serde_json::to_value(&from_json).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_config_parse_error_names_file_and_parser() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.json");
+        std::fs::write(&path, "{\"listen\": ").unwrap();
+
+        let error = format!("{:#}", Config::from_file(&path).unwrap_err());
+        assert!(error.contains("broken.json"), "{error}");
+        assert!(error.contains("as JSON config"), "{error}");
+    }
+
+    #[test]
+    fn test_enormous_pattern_rejected_at_load() {
+        let yaml = r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 8000
+rules:
+  - id: huge
+    match:
+      headerPredicates:
+        - name: X-Id
+          matches: '\w{1000}{1000}'
+    fault:
+      error:
+        probability: 1.0
+        status: 500
+"#;
+        let error = yaml.parse::<Config>().unwrap_err().to_string();
+        assert!(error.contains("Rule 'huge'"), "{error}");
+        assert!(error.contains(r"'\w{1000}{1000}'"), "{error}");
+        assert!(error.contains("regex size limit"), "{error}");
+    }
+
+    #[test]
+    fn test_regex_limits_section() {
+        let base = "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\n";
+        let config: Config = base.parse().unwrap();
+        assert_eq!(config.regex, RegexConfig::default());
+        assert_eq!(config.regex.size_limit, 10 * (1 << 20));
+
+        let config: Config =
+            serde_yaml::from_str(&format!("{base}regex:\n  size_limit: 4096\n")).unwrap();
+        assert_eq!(config.regex.size_limit, 4096);
+        assert_eq!(config.regex.dfa_size_limit, 2 * (1 << 20));
+
+        let zero = format!("{base}regex:\n  dfa_size_limit: 0\n");
+        assert!(zero.parse::<Config>().is_err());
+    }
+
+    #[test]
+    fn test_config_sources_share_validation() {
+        let valid = r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 8000
+rules: []
+"#;
+        let from_str: Config = valid.parse().unwrap();
+        let from_reader = Config::from_reader(valid.as_bytes()).unwrap();
+        assert_eq!(from_str.listen.port, 8080);
+        assert_eq!(from_reader.listen.port, 8080);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rift.yaml");
+        let invalid = valid.replace("port: 8080", "port: 8080\n  protocol: https");
+        std::fs::write(&path, &invalid).unwrap();
+
+        // HTTPS without TLS settings is rejected the same way from every source
+        let errors = [
+            invalid.parse::<Config>().unwrap_err(),
+            Config::from_reader(invalid.as_bytes()).unwrap_err(),
+            Config::from_file(&path).unwrap_err(),
+        ];
+        for error in errors {
+            assert!(error.to_string().contains("TLS configuration is required"));
+        }
+    }
+
+    #[test]
+    fn test_rule_metadata_round_trip() {
+        let yaml = r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 8000
+rules:
+  - id: "slow-search"
+    description: "Slow down search for timeout testing"
+    metadata:
+      owner: "search-team"
+      ticket: 1234
+      tags: ["latency"]
+    match:
+      path:
+        prefix: "/search"
+    fault:
+      latency:
+        probability: 1.0
+        min_ms: 100
+        max_ms: 200
+  - id: "plain"
+    match:
+      path:
+        exact: "/plain"
+    fault: {}
+"#;
+
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let reparsed: Config =
+            serde_yaml::from_str(&serde_yaml::to_string(&config).unwrap()).unwrap();
+
+        let rule = &reparsed.rules[0];
+        assert_eq!(
+            rule.description.as_deref(),
+            Some("Slow down search for timeout testing")
+        );
+        assert_eq!(rule.metadata["owner"], "search-team");
+        assert_eq!(rule.metadata["ticket"], 1234);
+        assert_eq!(rule.metadata["tags"][0], "latency");
+
+        // Rules without metadata don't grow empty fields when serialized
+        let plain = serde_yaml::to_string(&reparsed.rules[1]).unwrap();
+        assert!(!plain.contains("metadata"));
+        assert!(!plain.contains("description"));
+    }
+
+    #[test]
+    fn test_recording_signature_uses_predicate_generator_fields() {
+        let headers = vec![("x-tenant".to_string(), "acme".to_string())];
+
+        // No generators: method, path and query all count
+        let recording = RecordingConfig::default();
+        let sig = recording.signature("get", "/users", Some("page=2"), &headers);
+        assert_eq!(sig.method, "GET");
+        assert_eq!(sig.path, "/users");
+        assert_eq!(sig.query.as_deref(), Some("page=2"));
+
+        let yaml = r#"
+mode: proxyOnce
+predicateGenerators:
+  - matches:
+      path: true
+      headers: [x-tenant]
+"#;
+        let recording: RecordingConfig = serde_yaml::from_str(yaml).unwrap();
+        let sig = recording.signature("GET", "/users", Some("page=2"), &headers);
+        assert_eq!(sig.method, "");
+        assert_eq!(sig.path, "/users");
+        assert_eq!(sig.query, None);
+        assert_eq!(sig.headers, headers);
+        assert_eq!(
+            sig,
+            recording.signature("POST", "/users", Some("page=3"), &headers)
+        );
+    }
+
     #[test]
     fn test_parse_recording_config_default_transparent() {
         let yaml = r#"
@@ -634,4 +1319,513 @@ rules: []
         let config: Config = serde_yaml::from_str(yaml).unwrap();
         assert_eq!(config.recording.mode, ProxyMode::ProxyTransparent);
     }
+
+    #[test]
+    fn test_parse_script_rule_cache_key_fields() {
+        let yaml = r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 8000
+script_rules:
+  - id: "flow-keyed"
+    script: |
+      fn should_inject(request, flow_store) { #{ inject: false } }
+    cache_key_fields: [method, path, "header:x-flow-id"]
+"#;
+
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            config.script_rules[0].cache_key_fields,
+            vec!["method", "path", "header:x-flow-id"]
+        );
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_cache_key_field() {
+        let yaml = r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 8000
+script_rules:
+  - id: "bad-key"
+    script: |
+      fn should_inject(request, flow_store) { #{ inject: false } }
+    cache_key_fields: ["cookie:session"]
+"#;
+
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("bad-key"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_is_retry_requires_flow_state() {
+        let yaml = r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 8000
+rules:
+  - id: "retry-conflict"
+    match:
+      is_retry:
+        window_seconds: 60
+    fault:
+      error:
+        probability: 1.0
+        status: 409
+"#;
+
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        let retry = config.rules[0].match_config.is_retry.as_ref().unwrap();
+        assert_eq!(retry.header, "Idempotency-Key");
+        assert_eq!(retry.window_seconds, 60);
+
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("retry-conflict"), "unexpected error: {err}");
+
+        config.flow_state = Some(FlowStateConfig::default());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_rule_action_forms() {
+        let yaml = r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 8000
+rules:
+  - id: "health-allowed"
+    action: allow
+    match:
+      path:
+        exact: /health
+  - id: "blocked"
+    action: deny
+    match:
+      ip:
+        cidrs: ["10.0.0.0/8"]
+  - id: "unauthorized"
+    action:
+      deny:
+        status: 401
+        body: "login required"
+    match:
+      path:
+        prefix: /admin
+  - id: "slow"
+    match: {}
+    fault:
+      latency:
+        probability: 1.0
+        min_ms: 10
+        max_ms: 10
+"#;
+
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.rules[0].action, RuleAction::Allow);
+        let RuleAction::Deny(deny) = &config.rules[1].action else {
+            panic!("expected deny: {:?}", config.rules[1].action);
+        };
+        assert_eq!(deny.status, 403);
+        let RuleAction::Deny(deny) = &config.rules[2].action else {
+            panic!("expected deny: {:?}", config.rules[2].action);
+        };
+        assert_eq!((deny.status, deny.body.as_str()), (401, "login required"));
+        assert!(config.rules[3].action.is_fault());
+        assert!(config.validate().is_ok());
+
+        // The short forms survive a round trip
+        let yaml = serde_yaml::to_string(&config.rules[..2]).unwrap();
+        assert!(yaml.contains("action: allow"), "{yaml}");
+        assert!(yaml.contains("action: deny"), "{yaml}");
+        let reparsed: Vec<Rule> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(reparsed[1].action, config.rules[1].action);
+
+        config.rules[1].once = true;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("blocked"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_cors_validation() {
+        let base = "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\n";
+        let parse =
+            |cors: &str| -> Config { serde_yaml::from_str(&format!("{base}{cors}")).unwrap() };
+
+        let config = parse("cors:\n  allowed_origins: ['*']\n");
+        assert!(config.validate().is_ok());
+        assert_eq!(config.cors.unwrap().allowed_methods.len(), 6);
+
+        for (cors, expected) in [
+            (
+                "cors:\n  allowed_origins: ['*']\n  allow_credentials: true\n",
+                "allow_credentials",
+            ),
+            ("cors:\n  allowed_origins: []\n", "allowed_origins"),
+            (
+                "cors:\n  allowed_origins: ['*']\n  allowed_methods: ['BAD METHOD']\n",
+                "BAD METHOD",
+            ),
+        ] {
+            let err = parse(cors).validate().unwrap_err().to_string();
+            assert!(err.contains(expected), "unexpected error: {err}");
+        }
+    }
+
+    #[test]
+    fn test_request_id_config() {
+        let base = "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\n";
+        let parse = |request_id: &str| -> Config {
+            serde_yaml::from_str(&format!("{base}{request_id}")).unwrap()
+        };
+
+        let config = parse("request_id: {}\n");
+        assert!(config.validate().is_ok());
+        assert_eq!(config.request_id.unwrap().header, "X-Request-Id");
+        assert!(parse("").request_id.is_none());
+
+        let err = parse("request_id:\n  header: 'bad header'\n")
+            .validate()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid header name 'bad header'"), "{err}");
+    }
+
+    #[test]
+    fn test_response_cache_config() {
+        let base = "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\n";
+        let parse = |response_cache: &str| -> Config {
+            serde_yaml::from_str(&format!("{base}{response_cache}")).unwrap()
+        };
+
+        let config = parse("response_cache: {}\n");
+        assert!(config.validate().is_ok());
+        let cache = config.response_cache.unwrap();
+        assert_eq!((cache.max_size, cache.ttl_seconds), (1000, 60));
+        assert!(parse("").response_cache.is_none());
+
+        for (response_cache, expected) in [
+            (
+                "response_cache: {max_size: 0}\n",
+                "max_size must be at least 1",
+            ),
+            (
+                "response_cache: {ttl_seconds: 0}\n",
+                "ttl_seconds must be at least 1",
+            ),
+            (
+                "response_cache: {vary_headers: ['bad header']}\n",
+                "invalid header name 'bad header'",
+            ),
+        ] {
+            let err = parse(response_cache).validate().unwrap_err().to_string();
+            assert!(err.contains(expected), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_listen_bind_address() {
+        let parse = |listen: &str| -> Config {
+            serde_yaml::from_str(&format!(
+                "listen:\n  port: 8080\n{listen}upstream:\n  host: 127.0.0.1\n  port: 8000\n"
+            ))
+            .unwrap()
+        };
+
+        let config = parse("");
+        assert_eq!(
+            config.listen.socket_addr().unwrap().to_string(),
+            "0.0.0.0:8080"
+        );
+        for (bind, expected) in [
+            ("127.0.0.1", "127.0.0.1:8080"),
+            ("::1", "[::1]:8080"),
+            ("'[::1]'", "[::1]:8080"),
+        ] {
+            let config = parse(&format!("  bind: {bind}\n"));
+            assert_eq!(config.listen.socket_addr().unwrap().to_string(), expected);
+        }
+
+        let err = parse("  bind: localhost:80\n")
+            .validate()
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("invalid listen.bind address 'localhost:80'"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn test_flow_state_backend_validation() {
+        let base = "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\n";
+
+        let valkey: Config = serde_yaml::from_str(&format!(
+            "{base}flow_state:\n  backend: valkey\n  redis:\n    url: redis://valkey:6379\n"
+        ))
+        .unwrap();
+        assert!(valkey.validate().is_ok());
+
+        let missing: Config =
+            serde_yaml::from_str(&format!("{base}flow_state:\n  backend: valkey\n")).unwrap();
+        let err = missing.validate().unwrap_err().to_string();
+        assert!(err.contains("flow_state.redis"), "unexpected error: {err}");
+
+        let unknown: Config =
+            serde_yaml::from_str(&format!("{base}flow_state:\n  backend: memcached\n")).unwrap();
+        let err = unknown.validate().unwrap_err().to_string();
+        assert!(err.contains("memcached"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn test_normalized_config_makes_defaults_explicit() {
+        let sidecar: Config = "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\n"
+            .parse()
+            .unwrap();
+        assert_eq!(sidecar.mode, None);
+        assert_eq!(sidecar.deployment_mode(), DeploymentMode::Sidecar);
+
+        let yaml = serde_yaml::to_string(&sidecar.normalized()).unwrap();
+        assert!(yaml.contains("mode: sidecar"));
+        assert!(yaml.contains("max_body_bytes: 1048576"));
+        let reparsed: Config = yaml.parse().unwrap();
+        assert_eq!(reparsed.mode, Some(DeploymentMode::Sidecar));
+
+        let reverse: Config = r#"
+listen:
+  port: 8080
+upstreams:
+  - name: api
+    url: "http://127.0.0.1:8000"
+routing:
+  - name: all
+    match:
+      path_prefix: /
+    upstream: api
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(reverse.deployment_mode(), DeploymentMode::ReverseProxy);
+    }
+
+    fn deployment_error(yaml: &str) -> String {
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap_err().to_string()
+    }
+
+    const SIDECAR_UPSTREAM: &str = "upstream:\n  host: 127.0.0.1\n  port: 8000\n";
+    const UPSTREAMS: &str = "upstreams:\n  - name: api\n    url: \"http://127.0.0.1:8000\"\n";
+    const ROUTING: &str =
+        "routing:\n  - name: all\n    match:\n      path_prefix: /\n    upstream: api\n";
+
+    #[test]
+    fn test_sidecar_mode_requires_upstream() {
+        let err = deployment_error("listen:\n  port: 8080\n");
+        assert!(err.contains("requires 'upstream'"), "{err}");
+    }
+
+    #[test]
+    fn test_sidecar_mode_forbids_upstreams_and_routing() {
+        let err = deployment_error(&format!(
+            "mode: sidecar\nlisten:\n  port: 8080\n{SIDECAR_UPSTREAM}{UPSTREAMS}"
+        ));
+        assert!(
+            err.contains("'upstreams' is not allowed in sidecar mode"),
+            "{err}"
+        );
+
+        let err = deployment_error(&format!(
+            "mode: sidecar\nlisten:\n  port: 8080\n{SIDECAR_UPSTREAM}{ROUTING}"
+        ));
+        assert!(
+            err.contains("'routing' is not allowed in sidecar mode"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_reverse_proxy_mode_forbids_upstream() {
+        // Inferred from `upstreams`
+        let err = deployment_error(&format!(
+            "listen:\n  port: 8080\n{SIDECAR_UPSTREAM}{UPSTREAMS}{ROUTING}"
+        ));
+        assert!(
+            err.contains("'upstream' is not allowed in reverse-proxy mode"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_reverse_proxy_mode_requires_upstreams_and_routing() {
+        let err = deployment_error(&format!("listen:\n  port: 8080\n{UPSTREAMS}"));
+        assert!(err.contains("requires 'routing'"), "{err}");
+
+        let err = deployment_error("mode: reverse-proxy\nlisten:\n  port: 8080\n");
+        assert!(err.contains("requires 'upstreams'"), "{err}");
+    }
+
+    #[test]
+    fn test_route_to_unknown_upstream_is_rejected() {
+        let err = deployment_error(&format!(
+            "listen:\n  port: 8080\n{UPSTREAMS}{}",
+            ROUTING.replace("upstream: api", "upstream: apii")
+        ));
+        assert!(
+            err.contains("Route 'all' references unknown upstream 'apii'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_rule_filter_to_unknown_upstream_is_rejected() {
+        let config_with_filter = |upstream: &str| {
+            format!(
+                r#"
+listen:
+  port: 8080
+{UPSTREAMS}{ROUTING}rules:
+  - id: slow-api
+    upstream: {upstream}
+    match:
+      path:
+        prefix: /
+    fault:
+      latency:
+        probability: 1.0
+        min_ms: 1
+        max_ms: 2
+"#
+            )
+        };
+        let config: Config = serde_yaml::from_str(&config_with_filter("api")).unwrap();
+        config.validate().unwrap();
+
+        let err = deployment_error(&config_with_filter("backend"));
+        assert!(
+            err.contains(
+                "Rule 'slow-api' references unknown upstream 'backend' in 'rules[].upstream'"
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_upstream_retry_config() {
+        let config: Config = format!(
+            "listen:\n  port: 8080\n{UPSTREAMS}    retry:\n      retry_on: [connect_error, 503]\n{ROUTING}"
+        )
+        .parse()
+        .unwrap();
+        config.validate().unwrap();
+        let retry = config.upstreams[0].retry.as_ref().unwrap();
+        assert_eq!(retry.max_attempts, 3);
+        assert!(retry.only_idempotent);
+        assert!(retry.retries_connect_errors());
+        assert!(retry.retries_status(503));
+        assert!(!retry.retries_status(502));
+        assert_eq!(retry.backoff(3), std::time::Duration::from_millis(400));
+
+        let err = deployment_error(&format!(
+            "listen:\n  port: 8080\n{SIDECAR_UPSTREAM}  retry:\n    max_attempts: 0\n"
+        ));
+        assert!(err.contains("max_attempts must be at least 1"), "{err}");
+    }
+
+    #[test]
+    fn test_upstream_tls_config() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let cert_path = dir.path().join("cert.pem");
+        let key_path = dir.path().join("key.pem");
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+        let (cert_path, key_path) = (cert_path.display(), key_path.display());
+
+        let config: Config = format!(
+            "listen:\n  port: 8080\n{UPSTREAMS}    client_cert_path: {cert_path}\n    \
+             client_key_path: {key_path}\n    ca_path: {cert_path}\n{ROUTING}"
+        )
+        .parse()
+        .unwrap();
+        config.validate().unwrap();
+        let tls = &config.upstreams[0].tls;
+        assert_eq!(tls.ca_path.as_deref(), Some(cert_path.to_string().as_str()));
+
+        let err = deployment_error(&format!(
+            "listen:\n  port: 8080\n{SIDECAR_UPSTREAM}  client_cert_path: {cert_path}\n"
+        ));
+        assert!(
+            err.contains("client_cert_path requires client_key_path"),
+            "{err}"
+        );
+
+        let err = deployment_error(&format!(
+            "listen:\n  port: 8080\n{SIDECAR_UPSTREAM}  ca_path: /nonexistent/ca.pem\n"
+        ));
+        assert!(err.contains("Invalid upstream TLS"), "{err}");
+        assert!(err.contains("/nonexistent/ca.pem"), "{err}");
+
+        let err = deployment_error(&format!(
+            "listen:\n  port: 8080\n{UPSTREAMS}    ca_path: {key_path}\n{ROUTING}"
+        ));
+        assert!(err.contains("Invalid TLS for upstream 'api'"), "{err}");
+    }
+
+    #[test]
+    fn test_rule_active_window() {
+        let rule = |window: &str| -> Rule {
+            serde_yaml::from_str(&format!(
+                "id: scheduled\nmatch: {{}}\nactive_window: {window}\nfault: {{}}"
+            ))
+            .unwrap()
+        };
+        let at = |time: &str| time.parse::<chrono::DateTime<chrono::Utc>>().unwrap();
+
+        let maintenance =
+            rule(r#"{from: "2026-03-01T02:00:00Z", to: "2026-03-01T04:00:00+00:00"}"#);
+        assert!(!maintenance.in_active_window(at("2026-03-01T01:59:59Z")));
+        assert!(maintenance.in_active_window(at("2026-03-01T02:00:00Z")));
+        assert!(maintenance.in_active_window(at("2026-03-01T03:59:59Z")));
+        assert!(!maintenance.in_active_window(at("2026-03-01T04:00:00Z")));
+
+        // A daily window spanning midnight
+        let nightly = rule(r#"{from: "22:00", to: "02:00"}"#);
+        assert!(!nightly.in_active_window(at("2026-03-01T21:59:59Z")));
+        assert!(nightly.in_active_window(at("2026-03-01T22:00:00Z")));
+        assert!(nightly.in_active_window(at("2026-03-02T01:59:59Z")));
+        assert!(!nightly.in_active_window(at("2026-03-02T02:00:00Z")));
+
+        let disabled: Rule =
+            serde_yaml::from_str("id: off\nmatch: {}\nenabled: false\nfault: {}").unwrap();
+        assert!(!disabled.enabled);
+        assert!(maintenance.enabled);
+
+        for (window, expected) in [
+            (r#"{from: "04:00", to: "04:00"}"#, "same time of day"),
+            (
+                r#"{from: "2026-03-01T04:00:00Z", to: "2026-03-01T02:00:00Z"}"#,
+                "before it starts",
+            ),
+            (r#"{from: "2026-03-01T02:00:00Z", to: "04:00"}"#, "mixes"),
+        ] {
+            let err = rule(window).active_window.unwrap().validate().unwrap_err();
+            assert!(err.contains(expected), "{window}: {err}");
+        }
+        let err = serde_yaml::from_str::<Rule>(
+            "id: bad\nmatch: {}\nactive_window: {from: tomorrow, to: \"04:00\"}",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("invalid time 'tomorrow'"), "{err}");
+    }
 }
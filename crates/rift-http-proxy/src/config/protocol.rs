@@ -15,7 +15,7 @@ pub enum Protocol {
     /// TCP protocol (for future support)
     #[serde(rename = "tcp")]
     Tcp,
-    /// WebSocket protocol (for future support)
+    /// WebSocket protocol (HTTP with upgrade pass-through)
     #[serde(rename = "websocket")]
     WebSocket,
     /// DynamoDB protocol (for future support - Mountebank compatibility)
@@ -26,7 +26,7 @@ pub enum Protocol {
 impl Protocol {
     /// Check if protocol is currently supported
     pub fn is_supported(&self) -> bool {
-        matches!(self, Protocol::Http | Protocol::Https)
+        matches!(self, Protocol::Http | Protocol::Https | Protocol::WebSocket)
     }
 
     /// Get protocol name as string
@@ -46,7 +46,7 @@ impl Protocol {
             "http" => Ok(Protocol::Http),
             "https" => Ok(Protocol::Https),
             "tcp" => Ok(Protocol::Tcp),
-            "ws" | "websocket" => Ok(Protocol::WebSocket),
+            "ws" | "wss" | "websocket" => Ok(Protocol::WebSocket),
             "dynamodb" => Ok(Protocol::DynamoDB),
             _ => Err(format!("Unsupported protocol scheme: {scheme}")),
         }
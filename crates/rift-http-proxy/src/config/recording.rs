@@ -1,6 +1,6 @@
 //! Recording configuration for proxy record/replay.
 
-use crate::recording::ProxyMode;
+use crate::recording::{ProxyMode, RequestSignature};
 use serde::{Deserialize, Serialize};
 
 /// Recording configuration for proxy record/replay (Mountebank-compatible)
@@ -24,6 +24,36 @@ pub struct RecordingConfig {
     pub persistence: Option<RecordingPersistence>,
 }
 
+impl RecordingConfig {
+    /// Build the recording signature for a request.
+    ///
+    /// Without predicate generators every request field is significant.
+    /// Otherwise only the fields enabled by at least one generator are
+    /// included, so requests differing in other fields share a recording.
+    /// `headers` should already be filtered to the generators' headers.
+    pub fn signature(
+        &self,
+        method: &str,
+        path: &str,
+        query: Option<&str>,
+        headers: &[(String, String)],
+    ) -> RequestSignature {
+        if self.predicate_generators.is_empty() {
+            return RequestSignature::new(method, path, query, headers);
+        }
+
+        let any = |f: fn(&PredicateGeneratorMatches) -> bool| {
+            self.predicate_generators.iter().any(|pg| f(&pg.matches))
+        };
+        RequestSignature::new(
+            if any(|m| m.method) { method } else { "" },
+            if any(|m| m.path) { path } else { "" },
+            query.filter(|_| any(|m| m.query)),
+            headers,
+        )
+    }
+}
+
 /// Predicate generator for auto-generating stubs from recorded requests
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -64,6 +94,9 @@ pub struct RecordingPersistence {
     /// Redis URL for Redis-based persistence
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub redis_url: Option<String>,
+    /// Backend to fall back to when this one fails to save or load
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<Box<RecordingPersistence>>,
 }
 
 fn default_persistence_type() -> String {
@@ -0,0 +1,41 @@
+//! Request id configuration.
+
+use serde::{Deserialize, Serialize};
+
+/// Tag each request with an id for tracing it across services.
+///
+/// A request's id is taken from `header`, or generated as a UUID if the
+/// request has none. It is forwarded upstream, echoed in the response and
+/// recorded in every log line for the request. Scripts, templates and copy
+/// behaviors see it as a request header.
+///
+/// ```yaml
+/// request_id:
+///   header: X-Correlation-Id
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RequestIdConfig {
+    /// Header carrying the id
+    #[serde(default = "default_header")]
+    pub header: String,
+}
+
+fn default_header() -> String {
+    "X-Request-Id".to_string()
+}
+
+impl Default for RequestIdConfig {
+    fn default() -> Self {
+        Self {
+            header: default_header(),
+        }
+    }
+}
+
+impl RequestIdConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        hyper::header::HeaderName::try_from(self.header.as_str())
+            .map(|_| ())
+            .map_err(|_| format!("invalid header name '{}'", self.header))
+    }
+}
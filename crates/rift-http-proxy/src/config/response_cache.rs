@@ -0,0 +1,84 @@
+//! Response cache configuration.
+
+use serde::{Deserialize, Serialize};
+
+/// Cache upstream responses to GET requests.
+///
+/// Responses are keyed by method, path, query, the request's
+/// `Authorization` and `Cookie`, and the values of `vary_headers`. Only
+/// successful responses to requests forwarded without a fault are cached,
+/// and only when their `Content-Length` is at most `max_body_bytes`; other
+/// responses stream through unbuffered. Responses marked
+/// `Cache-Control: no-store` or `private`, setting cookies, carrying
+/// `text/event-stream`, or with a `Vary` naming a header outside the key
+/// are never cached. A response to a request with `Authorization` is only
+/// cached if marked `public`, `s-maxage` or `must-revalidate`. The least
+/// recently used entry is evicted once `max_size` is reached.
+///
+/// ```yaml
+/// response_cache:
+///   max_size: 1000
+///   ttl_seconds: 30
+///   max_body_bytes: 65536
+///   vary_headers: [Accept, Accept-Encoding]
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ResponseCacheConfig {
+    /// Most responses held at once
+    #[serde(default = "default_max_size")]
+    pub max_size: usize,
+    /// How long a cached response is served
+    #[serde(default = "default_ttl_seconds")]
+    pub ttl_seconds: u64,
+    /// Largest response body cached
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: u64,
+    /// Request headers whose values are part of the cache key
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vary_headers: Vec<String>,
+}
+
+fn default_max_size() -> usize {
+    1000
+}
+
+fn default_ttl_seconds() -> u64 {
+    60
+}
+
+fn default_max_body_bytes() -> u64 {
+    1024 * 1024
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_size: default_max_size(),
+            ttl_seconds: default_ttl_seconds(),
+            max_body_bytes: default_max_body_bytes(),
+            vary_headers: Vec::new(),
+        }
+    }
+}
+
+impl ResponseCacheConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_size == 0 {
+            return Err("max_size must be at least 1".to_string());
+        }
+        if self.ttl_seconds == 0 {
+            return Err("ttl_seconds must be at least 1".to_string());
+        }
+        if self.max_body_bytes == 0 {
+            return Err("max_body_bytes must be at least 1".to_string());
+        }
+        if let Some(bad) = self
+            .vary_headers
+            .iter()
+            .find(|name| hyper::header::HeaderName::try_from(name.as_str()).is_err())
+        {
+            return Err(format!("invalid header name '{bad}'"));
+        }
+        Ok(())
+    }
+}
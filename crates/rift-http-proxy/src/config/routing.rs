@@ -1,6 +1,8 @@
 //! Routing configuration for reverse proxy mode.
 
+use super::upstream::ForwardHeaders;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Route {
@@ -8,6 +10,83 @@ pub struct Route {
     #[serde(rename = "match")]
     pub match_config: RouteMatch,
     pub upstream: String, // upstream name
+    /// Path rewrite applied before forwarding to the upstream
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewrite: Option<PathRewrite>,
+    /// Header changes applied to requests forwarded by this route
+    #[serde(flatten)]
+    pub forward_headers: ForwardHeaders,
+    /// Overrides `connection_pool.request_timeout_secs` for this route
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout_secs: Option<u64>,
+    /// Response headers for this route; a header named here replaces the
+    /// global `response_headers` entry for it
+    #[serde(default, skip_serializing_if = "ResponseHeaders::is_empty")]
+    pub response_headers: ResponseHeaders,
+}
+
+/// Headers put on responses sent to clients, whether proxied or faulted.
+///
+/// ```yaml
+/// response_headers:
+///   set:
+///     Strict-Transport-Security: max-age=31536000
+///   add:
+///     Access-Control-Allow-Origin: "*"
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct ResponseHeaders {
+    /// Headers set only if the response doesn't already have them
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub add: HashMap<String, String>,
+    /// Headers replacing any existing values
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub set: HashMap<String, String>,
+}
+
+impl ResponseHeaders {
+    pub fn is_empty(&self) -> bool {
+        self.add.is_empty() && self.set.is_empty()
+    }
+
+    /// Whether `name` is configured, ignoring case
+    pub fn contains(&self, name: &str) -> bool {
+        self.add
+            .keys()
+            .chain(self.set.keys())
+            .any(|configured| configured.eq_ignore_ascii_case(name))
+    }
+
+    /// Check that every name and value is a valid HTTP header
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in self.add.iter().chain(&self.set) {
+            if hyper::header::HeaderName::try_from(name.as_str()).is_err() {
+                return Err(format!("invalid header name '{name}'"));
+            }
+            if hyper::header::HeaderValue::from_str(value).is_err() {
+                return Err(format!("invalid value for header '{name}'"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Path rewrite for a route. `strip_prefix` is applied before `replace`.
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct PathRewrite {
+    /// Remove this leading path segment(s), e.g. "/api" turns "/api/users" into "/users"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strip_prefix: Option<String>,
+    /// Regex replacement on the path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replace: Option<PathReplace>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PathReplace {
+    pub from_regex: String,
+    /// Replacement, may reference capture groups (`$1`, `${name}`)
+    pub to: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -1,19 +1,233 @@
 //! Fault injection rules configuration.
 
-use crate::behaviors::ResponseBehaviors;
-use crate::predicate::{BodyMatcher, HeaderMatcher, QueryMatcher};
+use crate::behaviors::{HasRepeatBehavior, ResponseBehaviors};
+use crate::extensions::retry::RetryMatcher;
+use crate::predicate::{
+    AuthMatcher, BodyMatcher, BodySizeMatcher, HeaderMatcher, HmacMatcher, IpMatcher, QueryMatcher,
+    TrailerMatcher, UserAgentFamily,
+};
+use chrono::{DateTime, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Rule {
     pub id: String,
     #[serde(rename = "match")]
     pub match_config: MatchConfig,
+    /// Access rules (`allow`, `deny`) need no fault
+    #[serde(default)]
     pub fault: FaultConfig,
+    /// What a match does: inject `fault` (default), or allow or deny access
+    #[serde(default, skip_serializing_if = "RuleAction::is_fault")]
+    pub action: RuleAction,
     // Optional: scope fault to specific upstream (v3 multi-upstream mode)
     // If None, applies to all upstreams
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub upstream: Option<String>,
+    /// Inject the fault at most once, then stop matching
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub once: bool,
+    /// Human-readable description, for documentation only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Free-form tooling metadata (tags, owner, ...); never affects matching
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// A disabled rule is skipped as if it weren't configured. Toggle it
+    /// at runtime through `/__rift/rules/{id}/enable` and `/disable` on the
+    /// metrics port, or by editing the config file with hot reload on.
+    #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+    /// Only match within this window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_window: Option<ActiveWindow>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+impl Rule {
+    /// Whether `now` falls in the rule's active window, if it has one
+    pub fn in_active_window(&self, now: DateTime<Utc>) -> bool {
+        self.active_window
+            .as_ref()
+            .is_none_or(|window| window.contains(now))
+    }
+}
+
+/// When a rule may match: between two instants, or between two times of
+/// day (UTC) every day. `from` is inclusive and `to` exclusive; a daily
+/// window whose `to` is earlier than its `from` spans midnight.
+///
+/// ```yaml
+/// active_window: { from: "2026-03-01T02:00:00Z", to: "2026-03-01T04:00:00Z" }
+/// active_window: { from: "22:00", to: "02:00" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ActiveWindow {
+    pub from: WindowTime,
+    pub to: WindowTime,
+}
+
+impl ActiveWindow {
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.from, &self.to) {
+            (WindowTime::At(from), WindowTime::At(to)) if from >= to => {
+                Err(format!("active_window ends at {to}, before it starts"))
+            }
+            (WindowTime::Daily(from), WindowTime::Daily(to)) if from == to => {
+                Err("active_window starts and ends at the same time of day".to_string())
+            }
+            (WindowTime::At(_), WindowTime::Daily(_))
+            | (WindowTime::Daily(_), WindowTime::At(_)) => {
+                Err("active_window mixes a timestamp and a time of day".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        match (&self.from, &self.to) {
+            (WindowTime::At(from), WindowTime::At(to)) => *from <= now && now < *to,
+            (WindowTime::Daily(from), WindowTime::Daily(to)) => {
+                let time = now.time();
+                if from < to {
+                    *from <= time && time < *to
+                } else {
+                    *from <= time || time < *to
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A bound of an [`ActiveWindow`]: an RFC 3339 timestamp or an `HH:MM[:SS]`
+/// time of day in UTC
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum WindowTime {
+    At(DateTime<Utc>),
+    Daily(NaiveTime),
+}
+
+impl TryFrom<String> for WindowTime {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if let Ok(at) = DateTime::parse_from_rfc3339(&value) {
+            return Ok(WindowTime::At(at.with_timezone(&Utc)));
+        }
+        NaiveTime::parse_from_str(&value, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(&value, "%H:%M"))
+            .map(WindowTime::Daily)
+            .map_err(|_| format!("invalid time '{value}': expected an RFC 3339 timestamp or HH:MM"))
+    }
+}
+
+impl From<WindowTime> for String {
+    fn from(time: WindowTime) -> Self {
+        match time {
+            WindowTime::At(at) => at.to_rfc3339(),
+            WindowTime::Daily(time) => time.format("%H:%M:%S").to_string(),
+        }
+    }
+}
+
+/// What a matched rule does.
+///
+/// Access rules (`allow` and `deny`) are checked before script and fault
+/// rules, in config order, and the first that matches decides: `allow`
+/// forwards the request untouched, `deny` answers without forwarding it.
+///
+/// ```yaml
+/// action: allow
+/// action: deny                  # 403
+/// action: { deny: { status: 401, body: "login required" } }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(from = "RawRuleAction", into = "RawRuleAction")]
+pub enum RuleAction {
+    #[default]
+    Fault,
+    Allow,
+    Deny(DenyAction),
+}
+
+impl RuleAction {
+    pub fn is_fault(&self) -> bool {
+        *self == RuleAction::Fault
+    }
+}
+
+/// Response for a denied request
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DenyAction {
+    #[serde(default = "default_deny_status")]
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub body: String,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+}
+
+impl Default for DenyAction {
+    fn default() -> Self {
+        Self {
+            status: default_deny_status(),
+            body: String::new(),
+            headers: HashMap::new(),
+        }
+    }
+}
+
+fn default_deny_status() -> u16 {
+    403
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawRuleAction {
+    Deny { deny: DenyAction },
+    Named(NamedRuleAction),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum NamedRuleAction {
+    Fault,
+    Allow,
+    Deny,
+}
+
+impl From<RawRuleAction> for RuleAction {
+    fn from(raw: RawRuleAction) -> Self {
+        match raw {
+            RawRuleAction::Deny { deny } => RuleAction::Deny(deny),
+            RawRuleAction::Named(NamedRuleAction::Fault) => RuleAction::Fault,
+            RawRuleAction::Named(NamedRuleAction::Allow) => RuleAction::Allow,
+            RawRuleAction::Named(NamedRuleAction::Deny) => RuleAction::Deny(DenyAction::default()),
+        }
+    }
+}
+
+impl From<RuleAction> for RawRuleAction {
+    fn from(action: RuleAction) -> Self {
+        match action {
+            RuleAction::Fault => RawRuleAction::Named(NamedRuleAction::Fault),
+            RuleAction::Allow => RawRuleAction::Named(NamedRuleAction::Allow),
+            RuleAction::Deny(deny) if deny == DenyAction::default() => {
+                RawRuleAction::Named(NamedRuleAction::Deny)
+            }
+            RuleAction::Deny(deny) => RawRuleAction::Deny { deny },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -40,10 +254,53 @@ pub struct MatchConfig {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub query: Vec<QueryMatcher>,
 
+    /// Request trailer matching. Trailers arrive after the body, so a rule
+    /// using them buffers the whole request body, past `max_body_bytes`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trailers: Vec<TrailerMatcher>,
+
     /// Request body matching
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub body: Option<BodyMatcher>,
 
+    /// Request body size in bytes, e.g. `{ gt: 1048576 }`. Taken from the
+    /// body itself when it fits in `max_body_bytes`, else from
+    /// `Content-Length`.
+    #[serde(default, rename = "bodySize", skip_serializing_if = "Option::is_none")]
+    pub body_size: Option<BodySizeMatcher>,
+
+    /// Media type of the request, ignoring parameters such as `charset`;
+    /// `text/*` matches any subtype
+    #[serde(
+        default,
+        rename = "contentType",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub content_type: Option<String>,
+
+    /// HMAC signature header validation over the raw body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hmac: Option<HmacMatcher>,
+
+    /// Credentials in the `Authorization` header, e.g.
+    /// `{ basic: { username: admin, password: secret } }` or
+    /// `{ bearer: { startsWith: "test-" } }`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthMatcher>,
+
+    /// User-Agent family classification: bot, mobile or desktop
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent_family: Option<UserAgentFamily>,
+
+    /// Client address ranges, e.g. `{ cidrs: [10.0.0.0/8], not: true }`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<IpMatcher>,
+
+    /// Match only retries: requests whose idempotency key was seen before
+    /// (requires flow_state)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_retry: Option<RetryMatcher>,
+
     /// Case-sensitive matching (default: true)
     #[serde(default = "default_case_sensitive", rename = "caseSensitive")]
     pub case_sensitive: bool,
@@ -76,8 +333,21 @@ pub enum PathMatch {
         #[serde(rename = "endsWith")]
         ends_with: String,
     },
+    /// Path template capturing variables, e.g. `/users/{id:\d+}/orders/{orderId}`;
+    /// captures are available to templates and copy behaviors as path params
+    Template {
+        template: String,
+    },
 }
 
+/// Faults a rule may inject.
+///
+/// At most one fault is injected per request. When several are configured
+/// they are tried in order `tcp_fault`, `error`, `mock`, `drip`, `chunked`, `latency`: a TCP
+/// fault always wins, and each later fault only applies when the probability
+/// rolls before it miss. A `rate_limit` is checked before all of them:
+/// requests over the limit get a 429, the rest go on to the other faults.
+/// A rule with `responses` answers with the next of those instead.
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct FaultConfig {
     #[serde(default)]
@@ -87,6 +357,90 @@ pub struct FaultConfig {
     /// TCP-level fault (Mountebank-compatible)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tcp_fault: Option<TcpFault>,
+    /// Upstream response sent partially, then stalled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drip: Option<DripFault>,
+    /// Upstream response re-sent with broken chunked framing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunked: Option<ChunkedFault>,
+    /// Reject requests over a rate with 429 Too Many Requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimitFault>,
+    /// Canned response built from the request, returned without forwarding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mock: Option<MockResponse>,
+    /// Responses served in order, one per matched request, starting over
+    /// after the last
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub responses: Vec<SequenceResponse>,
+}
+
+impl FaultConfig {
+    /// Whether any fault is configured
+    pub fn has_fault(&self) -> bool {
+        self.latency.is_some()
+            || self.error.is_some()
+            || self.tcp_fault.is_some()
+            || self.drip.is_some()
+            || self.chunked.is_some()
+            || self.rate_limit.is_some()
+            || self.mock.is_some()
+            || !self.responses.is_empty()
+    }
+
+    /// Validate probabilities and latency bounds
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(latency) = &self.latency {
+            validate_probability("latency", latency.probability)?;
+            if latency.min_ms > latency.max_ms {
+                return Err(format!(
+                    "latency min_ms ({}) must not exceed max_ms ({})",
+                    latency.min_ms, latency.max_ms
+                ));
+            }
+        }
+        if let Some(error) = &self.error {
+            validate_probability("error", error.probability)?;
+        }
+        if let Some(drip) = &self.drip {
+            validate_probability("drip", drip.probability)?;
+        }
+        if let Some(chunked) = &self.chunked {
+            validate_probability("chunked", chunked.probability)?;
+        }
+        if let Some(mock) = &self.mock {
+            validate_probability("mock", mock.probability)?;
+        }
+        if let Some(rate_limit) = &self.rate_limit {
+            if rate_limit.requests == 0 || rate_limit.per_secs == 0 {
+                return Err("rate_limit requests and per_secs must be at least 1".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_probability(fault: &str, probability: f64) -> Result<(), String> {
+    if (0.0..=1.0).contains(&probability) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{fault} probability must be between 0.0 and 1.0, got {probability}"
+        ))
+    }
+}
+
+/// What to do when a request body is larger than `max_body_bytes` and a
+/// rule has a body matcher
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyOverflow {
+    /// Match against the buffered prefix; matchers needing the whole body
+    /// (equals, JSON, XPath, HMAC) do not match
+    #[default]
+    Skip,
+    /// Reject the request with 413 Payload Too Large
+    Reject,
 }
 
 /// TCP-level fault types (Mountebank-compatible)
@@ -106,6 +460,146 @@ pub struct LatencyFault {
     pub max_ms: u64,
 }
 
+/// Forward the request, then send only the first `initial_bytes` of the
+/// response body before stalling for `stall_ms`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DripFault {
+    pub probability: f64,
+    #[serde(default)]
+    pub initial_bytes: usize,
+    pub stall_ms: u64,
+    /// Send the rest of the body after the stall; if false the response is
+    /// left open and never completes
+    #[serde(default = "default_drip_finish")]
+    pub finish: bool,
+}
+
+fn default_drip_finish() -> bool {
+    true
+}
+
+/// Forward the request, then send the upstream response with
+/// `Transfer-Encoding: chunked` framing that breaks at the end, and close
+/// the connection.
+///
+/// Chunked encoding only exists in HTTP/1.1: requests made over HTTP/1.0
+/// are forwarded without the fault.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ChunkedFault {
+    pub probability: f64,
+    pub mode: ChunkedFaultMode,
+}
+
+/// How a [`ChunkedFault`] breaks the chunked body
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkedFaultMode {
+    /// Close the connection without the terminating `0\r\n\r\n` chunk
+    MissingTerminator,
+    /// End with a chunk whose size line isn't hexadecimal
+    BadChunkSize,
+}
+
+/// Response returned instead of forwarding the request.
+///
+/// `body` may refer to the request with `${path}`, `${query.<name>}`,
+/// `${header.<name>}` and `${body:<jsonpath>}`; references the request
+/// can't fill are left empty.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MockResponse {
+    #[serde(default = "default_mock_probability")]
+    pub probability: f64,
+    #[serde(default = "default_mock_status")]
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+fn default_mock_probability() -> f64 {
+    1.0
+}
+
+fn default_mock_status() -> u16 {
+    200
+}
+
+impl MockResponse {
+    /// Whether the body refers to the request body, which then has to be
+    /// buffered
+    pub fn uses_request_body(&self) -> bool {
+        self.body.contains("${body:")
+    }
+}
+
+/// Allow `requests` per `per_secs` for each value of `key`, answering the
+/// rest with 429 and a `Retry-After` header
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RateLimitFault {
+    pub requests: u32,
+    pub per_secs: u64,
+    #[serde(default)]
+    pub key: RateLimitKey,
+}
+
+/// Request dimension rate limits are counted per.
+///
+/// ```yaml
+/// key: ip
+/// key: method
+/// key: path
+/// key: { header: x-api-key }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(from = "RawRateLimitKey", into = "RawRateLimitKey")]
+pub enum RateLimitKey {
+    /// Client IP address
+    #[default]
+    Ip,
+    Method,
+    Path,
+    /// Value of the named header; requests without it share one counter
+    Header(String),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawRateLimitKey {
+    Header { header: String },
+    Named(NamedRateLimitKey),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum NamedRateLimitKey {
+    Ip,
+    Method,
+    Path,
+}
+
+impl From<RawRateLimitKey> for RateLimitKey {
+    fn from(raw: RawRateLimitKey) -> Self {
+        match raw {
+            RawRateLimitKey::Header { header } => RateLimitKey::Header(header),
+            RawRateLimitKey::Named(NamedRateLimitKey::Ip) => RateLimitKey::Ip,
+            RawRateLimitKey::Named(NamedRateLimitKey::Method) => RateLimitKey::Method,
+            RawRateLimitKey::Named(NamedRateLimitKey::Path) => RateLimitKey::Path,
+        }
+    }
+}
+
+impl From<RateLimitKey> for RawRateLimitKey {
+    fn from(key: RateLimitKey) -> Self {
+        match key {
+            RateLimitKey::Header(header) => RawRateLimitKey::Header { header },
+            RateLimitKey::Ip => RawRateLimitKey::Named(NamedRateLimitKey::Ip),
+            RateLimitKey::Method => RawRateLimitKey::Named(NamedRateLimitKey::Method),
+            RateLimitKey::Path => RawRateLimitKey::Named(NamedRateLimitKey::Path),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ErrorFault {
     pub probability: f64,
@@ -118,6 +612,37 @@ pub struct ErrorFault {
     /// Mountebank-compatible response behaviors (wait, repeat, copy, lookup)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub behaviors: Option<ResponseBehaviors>,
+    /// gRPC status returned instead of `status` when the request is gRPC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grpc: Option<GrpcFault>,
+}
+
+/// One response of a rule's `responses` sequence. `behaviors.repeat`
+/// serves it that many times before moving on to the next.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SequenceResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub behaviors: Option<ResponseBehaviors>,
+}
+
+impl HasRepeatBehavior for SequenceResponse {
+    fn get_repeat(&self) -> Option<u32> {
+        self.behaviors.as_ref().and_then(|b| b.repeat)
+    }
+}
+
+/// gRPC-level error: HTTP 200 with `grpc-status` and `grpc-message` trailers
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GrpcFault {
+    /// gRPC status code, e.g. 14 (UNAVAILABLE)
+    pub status_code: u32,
+    #[serde(default)]
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -130,4 +655,75 @@ pub struct ScriptRule {
     // If None, applies to all upstreams
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub upstream: Option<String>,
+    /// Request fields the decision cache key is built from, e.g.
+    /// `[method, path, "header:x-flow-id"]`. Empty means all of method,
+    /// path, headers and body.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cache_key_fields: Vec<String>,
+    /// Human-readable description, for documentation only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Free-form tooling metadata (tags, owner, ...); never affects matching
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// A disabled script rule is skipped, as for [`Rule::enabled`]
+    #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+    /// Only match within this window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_window: Option<ActiveWindow>,
+}
+
+impl ScriptRule {
+    /// The script rule's request matcher, as a rule without a fault
+    pub fn match_rule(&self) -> Rule {
+        Rule {
+            id: self.id.clone(),
+            match_config: self.match_config.clone(),
+            fault: Default::default(),
+            upstream: self.upstream.clone(),
+            once: false,
+            action: Default::default(),
+            description: self.description.clone(),
+            metadata: self.metadata.clone(),
+            enabled: self.enabled,
+            active_window: self.active_window.clone(),
+        }
+    }
+}
+
+/// Limits on regexes compiled from rule and route patterns. A pattern
+/// whose compiled form is larger fails to load.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RegexConfig {
+    /// Largest compiled regex program, in bytes
+    #[serde(default = "default_regex_size_limit")]
+    pub size_limit: usize,
+    /// Largest lazy DFA cache per regex, in bytes
+    #[serde(default = "default_regex_dfa_size_limit")]
+    pub dfa_size_limit: usize,
+}
+
+impl Default for RegexConfig {
+    fn default() -> Self {
+        Self {
+            size_limit: default_regex_size_limit(),
+            dfa_size_limit: default_regex_dfa_size_limit(),
+        }
+    }
+}
+
+fn default_regex_size_limit() -> usize {
+    crate::predicate::DEFAULT_SIZE_LIMIT
+}
+
+fn default_regex_dfa_size_limit() -> usize {
+    crate::predicate::DEFAULT_DFA_SIZE_LIMIT
+}
+
+impl RegexConfig {
+    /// Make these the limits for regexes compiled from now on
+    pub fn apply(&self) {
+        crate::predicate::set_regex_limits(self.size_limit, self.dfa_size_limit);
+    }
 }
@@ -77,6 +77,10 @@ pub struct ScriptPoolConfigFile {
     /// Timeout in milliseconds for script execution
     #[serde(default = "default_script_pool_timeout_ms")]
     pub timeout_ms: u64,
+    /// Respond 503 when a script times out or the queue is full, instead of
+    /// forwarding the request unfaulted
+    #[serde(default)]
+    pub fail_closed: bool,
 }
 
 fn default_script_pool_workers() -> usize {
@@ -97,6 +101,7 @@ impl Default for ScriptPoolConfigFile {
             workers: default_script_pool_workers(),
             queue_size: default_script_pool_queue_size(),
             timeout_ms: default_script_pool_timeout_ms(),
+            fail_closed: false,
         }
     }
 }
@@ -113,6 +118,10 @@ pub struct DecisionCacheConfigFile {
     /// TTL for cache entries in seconds (0 = no expiration)
     #[serde(default = "default_decision_cache_ttl_seconds")]
     pub ttl_seconds: u64,
+    /// Interval in seconds between sweeps of expired entries
+    /// (defaults to min(ttl_seconds / 2, 60))
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cleanup_interval_seconds: Option<u64>,
 }
 
 fn default_decision_cache_enabled() -> bool {
@@ -133,6 +142,7 @@ impl Default for DecisionCacheConfigFile {
             enabled: default_decision_cache_enabled(),
             max_size: default_decision_cache_max_size(),
             ttl_seconds: default_decision_cache_ttl_seconds(),
+            cleanup_interval_seconds: None,
         }
     }
 }
@@ -2,6 +2,8 @@
 
 use super::protocol::Protocol;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct UpstreamConfig {
@@ -17,6 +19,78 @@ pub struct UpstreamConfig {
     /// Skip TLS certificate verification (for self-signed certs in dev/test)
     #[serde(default)]
     pub tls_skip_verify: bool,
+    /// Client certificate and trusted CAs for HTTPS connections
+    #[serde(flatten)]
+    pub tls: UpstreamTlsConfig,
+    /// Header changes applied to forwarded requests
+    #[serde(flatten)]
+    pub forward_headers: ForwardHeaders,
+    /// Retry failed requests to this upstream
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryConfig>,
+}
+
+/// Request header changes applied before forwarding to an upstream.
+///
+/// Client headers are copied first, then `X-Forwarded-*` headers are
+/// populated, then `remove_headers`, `set_headers` and `add_headers` are
+/// applied in that order.
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct ForwardHeaders {
+    /// Headers appended alongside any existing values
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub add_headers: HashMap<String, String>,
+    /// Headers replacing any existing values
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub set_headers: HashMap<String, String>,
+    /// Headers removed from the forwarded request
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove_headers: Vec<String>,
+}
+
+/// TLS settings for HTTPS connections to an upstream, for upstreams that
+/// require mutual TLS or use a private CA. Files are PEM.
+///
+/// ```yaml
+/// client_cert_path: /etc/rift/client.pem
+/// client_key_path: /etc/rift/client-key.pem
+/// ca_path: /etc/rift/upstream-ca.pem
+/// ```
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct UpstreamTlsConfig {
+    /// Certificate chain presented to the upstream
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert_path: Option<String>,
+    /// Private key of the client certificate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key_path: Option<String>,
+    /// CA certificates trusted for the upstream's certificate, in place of
+    /// the system roots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_path: Option<String>,
+}
+
+impl UpstreamTlsConfig {
+    /// Whether any setting differs from the default TLS client
+    pub fn is_configured(&self) -> bool {
+        self.client_cert_path.is_some() || self.client_key_path.is_some() || self.ca_path.is_some()
+    }
+
+    /// Check that the certificate and key come together and that every
+    /// file loads
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.client_cert_path, &self.client_key_path) {
+            (Some(_), None) => return Err("client_cert_path requires client_key_path".to_string()),
+            (None, Some(_)) => return Err("client_key_path requires client_cert_path".to_string()),
+            _ => {}
+        }
+        if !self.is_configured() {
+            return Ok(());
+        }
+        crate::proxy::create_client_tls_config(self, false)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
 }
 
 impl UpstreamConfig {
@@ -46,6 +120,84 @@ pub struct Upstream {
     /// Skip TLS certificate verification (for self-signed certs in dev/test)
     #[serde(default)]
     pub tls_skip_verify: bool,
+    /// Client certificate and trusted CAs for HTTPS connections
+    #[serde(flatten)]
+    pub tls: UpstreamTlsConfig,
+    /// Relative share of traffic when part of an upstream group (0 = never selected)
+    #[serde(default = "default_upstream_weight")]
+    pub weight: u32,
+    /// Retry failed requests to this upstream
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryConfig>,
+    /// Pool settings for this upstream, overriding `connection_pool`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_pool: Option<UpstreamPoolConfig>,
+}
+
+fn default_upstream_weight() -> u32 {
+    1
+}
+
+/// Named set of upstreams a route can target; requests are spread across
+/// members by weight.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UpstreamGroup {
+    pub name: String,
+    /// Member upstream names
+    pub upstreams: Vec<String>,
+    /// Pin requests sharing this key to one member while it is healthy;
+    /// requests without the key are spread by weight as usual
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub affinity: Option<AffinityKey>,
+}
+
+/// Request value that sticky sessions are keyed on.
+///
+/// ```yaml
+/// affinity: { cookie: session_id }
+/// affinity: { header: x-user-id }
+/// affinity: client_ip
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "RawAffinityKey", into = "RawAffinityKey")]
+pub enum AffinityKey {
+    Cookie(String),
+    Header(String),
+    ClientIp,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawAffinityKey {
+    Cookie { cookie: String },
+    Header { header: String },
+    Named(NamedAffinityKey),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum NamedAffinityKey {
+    ClientIp,
+}
+
+impl From<RawAffinityKey> for AffinityKey {
+    fn from(raw: RawAffinityKey) -> Self {
+        match raw {
+            RawAffinityKey::Cookie { cookie } => AffinityKey::Cookie(cookie),
+            RawAffinityKey::Header { header } => AffinityKey::Header(header),
+            RawAffinityKey::Named(NamedAffinityKey::ClientIp) => AffinityKey::ClientIp,
+        }
+    }
+}
+
+impl From<AffinityKey> for RawAffinityKey {
+    fn from(key: AffinityKey) -> Self {
+        match key {
+            AffinityKey::Cookie(cookie) => RawAffinityKey::Cookie { cookie },
+            AffinityKey::Header(header) => RawAffinityKey::Header { header },
+            AffinityKey::ClientIp => RawAffinityKey::Named(NamedAffinityKey::ClientIp),
+        }
+    }
 }
 
 impl Upstream {
@@ -67,15 +219,121 @@ impl Upstream {
         let protocol = self.get_protocol()?;
         if !protocol.is_supported() {
             return Err(format!(
-                "Unsupported protocol '{}' for upstream '{}'. Currently supported: http, https",
+                "Unsupported protocol '{}' for upstream '{}'. Currently supported: http, https, websocket",
                 protocol.as_str(),
                 self.name
             ));
         }
+        if let Some(retry) = &self.retry {
+            retry
+                .validate()
+                .map_err(|e| format!("Invalid retry for upstream '{}': {e}", self.name))?;
+        }
+        self.tls
+            .validate()
+            .map_err(|e| format!("Invalid TLS for upstream '{}': {e}", self.name))?;
         Ok(())
     }
 }
 
+/// Retries of failed upstream requests.
+///
+/// The request body is buffered so it can be sent again. Rules are applied
+/// once per client request; only the forwarding step is repeated.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RetryConfig {
+    /// Total attempts, including the first
+    #[serde(default = "default_retry_max_attempts")]
+    pub max_attempts: u32,
+    /// Outcomes that trigger another attempt
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<RetryOn>,
+    /// Wait before the first retry, doubled for each one after it
+    #[serde(default = "default_retry_backoff_ms")]
+    pub backoff_ms: u64,
+    /// Only retry GET, HEAD, OPTIONS, TRACE, PUT and DELETE requests
+    #[serde(default = "default_retry_only_idempotent")]
+    pub only_idempotent: bool,
+}
+
+/// Upstream outcome that triggers a retry: a status code such as `503`,
+/// or `connect_error`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum RetryOn {
+    Status(u16),
+    Condition(RetryCondition),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryCondition {
+    /// No connection to the upstream could be established
+    ConnectError,
+}
+
+impl RetryConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_attempts == 0 {
+            return Err("max_attempts must be at least 1".to_string());
+        }
+        if let Some(status) = self.retry_on.iter().find_map(|on| match on {
+            RetryOn::Status(status) if !(100..=599).contains(status) => Some(status),
+            _ => None,
+        }) {
+            return Err(format!("invalid status code {status} in retry_on"));
+        }
+        Ok(())
+    }
+
+    /// Whether a request with this method may be sent more than once
+    pub fn allows_method(&self, method: &str) -> bool {
+        !self.only_idempotent
+            || matches!(
+                method,
+                "GET" | "HEAD" | "OPTIONS" | "TRACE" | "PUT" | "DELETE"
+            )
+    }
+
+    pub fn retries_status(&self, status: u16) -> bool {
+        self.retry_on.contains(&RetryOn::Status(status))
+    }
+
+    pub fn retries_connect_errors(&self) -> bool {
+        self.retry_on
+            .contains(&RetryOn::Condition(RetryCondition::ConnectError))
+    }
+
+    /// Wait before retry number `retry` (1 for the first retry)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u64
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+        Duration::from_millis(self.backoff_ms.saturating_mul(factor))
+    }
+}
+
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_on() -> Vec<RetryOn> {
+    vec![
+        RetryOn::Condition(RetryCondition::ConnectError),
+        RetryOn::Status(502),
+        RetryOn::Status(503),
+        RetryOn::Status(504),
+    ]
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    100
+}
+
+fn default_retry_only_idempotent() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct HealthCheckConfig {
     #[serde(default = "default_health_path")]
@@ -135,6 +393,10 @@ pub struct ConnectionPoolConfig {
 
     #[serde(default = "default_connect_timeout")]
     pub connect_timeout_secs: u64,
+
+    /// Longest wait for an upstream's response head (default: no limit)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout_secs: Option<u64>,
 }
 
 impl Default for ConnectionPoolConfig {
@@ -144,6 +406,29 @@ impl Default for ConnectionPoolConfig {
             idle_timeout_secs: default_pool_idle_timeout(),
             keepalive_timeout_secs: default_keepalive_timeout(),
             connect_timeout_secs: default_connect_timeout(),
+            request_timeout_secs: None,
+        }
+    }
+}
+
+/// Connection pool settings one upstream overrides; the others come from
+/// the top-level `connection_pool`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct UpstreamPoolConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_idle_per_host: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_secs: Option<u64>,
+}
+
+impl UpstreamPoolConfig {
+    /// `base` with these overrides applied
+    pub fn apply(&self, base: &ConnectionPoolConfig) -> ConnectionPoolConfig {
+        ConnectionPoolConfig {
+            max_idle_per_host: self.max_idle_per_host.unwrap_or(base.max_idle_per_host),
+            idle_timeout_secs: self.idle_timeout_secs.unwrap_or(base.idle_timeout_secs),
+            ..base.clone()
         }
     }
 }
@@ -1,11 +1,21 @@
 use crate::behaviors::ResponseBehaviors;
-use crate::config::{FaultConfig, TcpFault};
-use http_body_util::Full;
-use hyper::body::Bytes;
+use crate::config::{ChunkedFault, DripFault, FaultConfig, GrpcFault, MockResponse, TcpFault};
+use futures::stream;
+use http_body_util::{Full, StreamBody};
+use hyper::body::{Bytes, Frame};
+use hyper::header::{HeaderMap, HeaderValue, CONTENT_TYPE, TRAILER};
 use hyper::{Response, StatusCode};
-use rand::Rng;
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
 use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
+use tracing::warn;
+
+/// Set once an inverted latency range has been reported
+static INVERTED_LATENCY_WARNED: AtomicBool = AtomicBool::new(false);
 
 #[derive(Debug, Clone)]
 pub enum FaultDecision {
@@ -27,11 +37,73 @@ pub enum FaultDecision {
         fault_type: TcpFault,
         rule_id: String,
     },
+    Drip {
+        drip: DripFault,
+        rule_id: String,
+    },
+    Chunked {
+        chunked: ChunkedFault,
+        rule_id: String,
+    },
+    /// Canned response, rendered from the request by the caller
+    Mock {
+        mock: MockResponse,
+        rule_id: String,
+    },
+}
+
+/// Random source for fault decisions.
+///
+/// Seeded sources yield the same sequence of decisions on every run; unseeded
+/// ones use the thread-local entropy RNG.
+#[derive(Default)]
+pub struct FaultRng {
+    seeded: Option<Mutex<StdRng>>,
+}
+
+impl FaultRng {
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seeded: seed.map(|seed| Mutex::new(StdRng::seed_from_u64(seed))),
+        }
+    }
+
+    /// Decide which fault (if any) to inject using this random source
+    pub fn decide(&self, fault_config: &FaultConfig, rule_id: &str) -> FaultDecision {
+        match &self.seeded {
+            Some(rng) => decide_fault_with_rng(fault_config, rule_id, &mut *rng.lock()),
+            None => decide_fault(fault_config, rule_id),
+        }
+    }
+
+    /// Run `f` with this random source
+    pub fn with_rng<T>(&self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+        match &self.seeded {
+            Some(rng) => f(&mut *rng.lock()),
+            None => f(&mut rand::thread_rng()),
+        }
+    }
+
+    /// Sample a float in `[0, 1)` from this random source
+    pub fn next_f64(&self) -> f64 {
+        match &self.seeded {
+            Some(rng) => rng.lock().gen(),
+            None => rand::thread_rng().gen(),
+        }
+    }
 }
 
+/// Decide which fault (if any) to inject, sampling each fault's probability
 pub fn decide_fault(fault_config: &FaultConfig, rule_id: &str) -> FaultDecision {
-    let mut rng = rand::thread_rng();
+    decide_fault_with_rng(fault_config, rule_id, &mut rand::thread_rng())
+}
 
+/// [`decide_fault`] with an explicit random source
+pub fn decide_fault_with_rng(
+    fault_config: &FaultConfig,
+    rule_id: &str,
+    rng: &mut impl Rng,
+) -> FaultDecision {
     // Check TCP fault first (highest priority - immediate connection failure)
     if let Some(tcp_fault) = &fault_config.tcp_fault {
         return FaultDecision::TcpFault {
@@ -42,7 +114,7 @@ pub fn decide_fault(fault_config: &FaultConfig, rule_id: &str) -> FaultDecision
 
     // Check error fault (higher priority than latency)
     if let Some(error_fault) = &fault_config.error {
-        if should_inject(error_fault.probability, &mut rng) {
+        if should_inject(error_fault.probability, rng) {
             return FaultDecision::Error {
                 status: error_fault.status,
                 body: error_fault.body.clone(),
@@ -53,10 +125,49 @@ pub fn decide_fault(fault_config: &FaultConfig, rule_id: &str) -> FaultDecision
         }
     }
 
+    if let Some(mock) = &fault_config.mock {
+        if should_inject(mock.probability, rng) {
+            return FaultDecision::Mock {
+                mock: mock.clone(),
+                rule_id: rule_id.to_string(),
+            };
+        }
+    }
+
+    if let Some(drip) = &fault_config.drip {
+        if should_inject(drip.probability, rng) {
+            return FaultDecision::Drip {
+                drip: drip.clone(),
+                rule_id: rule_id.to_string(),
+            };
+        }
+    }
+
+    if let Some(chunked) = &fault_config.chunked {
+        if should_inject(chunked.probability, rng) {
+            return FaultDecision::Chunked {
+                chunked: chunked.clone(),
+                rule_id: rule_id.to_string(),
+            };
+        }
+    }
+
     // Check latency fault
     if let Some(latency_fault) = &fault_config.latency {
-        if should_inject(latency_fault.probability, &mut rng) {
-            let duration_ms = rng.gen_range(latency_fault.min_ms..=latency_fault.max_ms);
+        if should_inject(latency_fault.probability, rng) {
+            let (min_ms, max_ms) = (latency_fault.min_ms, latency_fault.max_ms);
+            // Validation rejects inverted bounds, but never panic on a live request
+            let duration_ms = if min_ms <= max_ms {
+                rng.gen_range(min_ms..=max_ms)
+            } else {
+                if !INVERTED_LATENCY_WARNED.swap(true, Ordering::Relaxed) {
+                    warn!(
+                        "Rule '{}' has latency min_ms ({}) > max_ms ({}); using max_ms",
+                        rule_id, min_ms, max_ms
+                    );
+                }
+                max_ms
+            };
             return FaultDecision::Latency {
                 duration_ms,
                 rule_id: rule_id.to_string(),
@@ -67,6 +178,70 @@ pub fn decide_fault(fault_config: &FaultConfig, rule_id: &str) -> FaultDecision
     FaultDecision::None
 }
 
+/// The fault `fault_config` injects when its probability fires, and that
+/// probability, without drawing from any random source.
+///
+/// Faults are tried in [`decide_fault`]'s order, skipping those that never
+/// fire. A latency fault takes its shortest delay. Returns
+/// `(FaultDecision::None, 0.0)` if no fault can fire.
+pub fn configured_fault(fault_config: &FaultConfig, rule_id: &str) -> (FaultDecision, f64) {
+    let rule_id = rule_id.to_string();
+    let fires = |probability: f64| probability > 0.0;
+    if let Some(tcp_fault) = &fault_config.tcp_fault {
+        let decision = FaultDecision::TcpFault {
+            fault_type: *tcp_fault,
+            rule_id,
+        };
+        return (decision, 1.0);
+    }
+    let (decision, probability) =
+        if let Some(error) = fault_config.error.as_ref().filter(|e| fires(e.probability)) {
+            let decision = FaultDecision::Error {
+                status: error.status,
+                body: error.body.clone(),
+                rule_id,
+                headers: error.headers.clone(),
+                behaviors: error.behaviors.clone(),
+            };
+            (decision, error.probability)
+        } else if let Some(mock) = fault_config.mock.as_ref().filter(|m| fires(m.probability)) {
+            let decision = FaultDecision::Mock {
+                mock: mock.clone(),
+                rule_id,
+            };
+            (decision, mock.probability)
+        } else if let Some(drip) = fault_config.drip.as_ref().filter(|d| fires(d.probability)) {
+            let decision = FaultDecision::Drip {
+                drip: drip.clone(),
+                rule_id,
+            };
+            (decision, drip.probability)
+        } else if let Some(chunked) = fault_config
+            .chunked
+            .as_ref()
+            .filter(|c| fires(c.probability))
+        {
+            let decision = FaultDecision::Chunked {
+                chunked: chunked.clone(),
+                rule_id,
+            };
+            (decision, chunked.probability)
+        } else if let Some(latency) = fault_config
+            .latency
+            .as_ref()
+            .filter(|l| fires(l.probability))
+        {
+            let decision = FaultDecision::Latency {
+                duration_ms: latency.min_ms.min(latency.max_ms),
+                rule_id,
+            };
+            (decision, latency.probability)
+        } else {
+            return (FaultDecision::None, 0.0);
+        };
+    (decision, probability.min(1.0))
+}
+
 fn should_inject(probability: f64, rng: &mut impl Rng) -> bool {
     rng.gen::<f64>() < probability
 }
@@ -139,10 +314,67 @@ pub fn create_error_response(
     Ok(response)
 }
 
+/// Whether a request is gRPC, judged by its content type
+/// (`application/grpc`, `application/grpc+proto`, ...)
+pub fn is_grpc_request(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(str::trim)
+        .is_some_and(|essence| {
+            essence
+                .strip_prefix("application/grpc")
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('+'))
+        })
+}
+
+/// Body of a gRPC error response: no messages, only the status trailers
+pub type GrpcErrorBody =
+    StreamBody<stream::Once<std::future::Ready<Result<Frame<Bytes>, Infallible>>>>;
+
+/// Build a gRPC error response: HTTP 200 with the status in trailers.
+///
+/// HTTP/1.1 clients only receive the trailers if they sent `TE: trailers`,
+/// as gRPC clients do.
+pub fn create_grpc_error_response(fault: &GrpcFault) -> Response<GrpcErrorBody> {
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", HeaderValue::from(fault.status_code));
+    if !fault.message.is_empty() {
+        // Percent-encoding leaves only visible ASCII, which is always valid
+        if let Ok(message) = HeaderValue::from_str(&encode_grpc_message(&fault.message)) {
+            trailers.insert("grpc-message", message);
+        }
+    }
+    let body = StreamBody::new(stream::once(std::future::ready(Ok(Frame::trailers(
+        trailers,
+    )))));
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/grpc")
+        .header(TRAILER, "grpc-status, grpc-message")
+        .body(body)
+        .unwrap()
+}
+
+/// Percent-encode a `grpc-message` as the gRPC HTTP/2 spec requires:
+/// everything outside printable ASCII, and `%` itself
+fn encode_grpc_message(message: &str) -> String {
+    let mut encoded = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        if (0x20..=0x7e).contains(&byte) && byte != b'%' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{ErrorFault, LatencyFault};
+    use crate::config::{ChunkedFaultMode, ErrorFault, LatencyFault};
 
     #[test]
     fn test_should_inject_always() {
@@ -189,6 +421,122 @@ mod tests {
         );
     }
 
+    /// Fraction of `iterations` seeded decisions that inject a fault
+    fn observed_rate(fault_config: &FaultConfig, iterations: u32) -> f64 {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let injected = (0..iterations)
+            .filter(|_| {
+                !matches!(
+                    decide_fault_with_rng(fault_config, "rate", &mut rng),
+                    FaultDecision::None
+                )
+            })
+            .count();
+        injected as f64 / iterations as f64
+    }
+
+    #[test]
+    fn test_decide_fault_respects_probability() {
+        for probability in [0.0, 0.1, 0.5, 0.9, 1.0] {
+            let error_only = FaultConfig {
+                latency: None,
+                error: Some(ErrorFault {
+                    probability,
+                    status: 503,
+                    body: String::new(),
+                    headers: HashMap::new(),
+                    behaviors: None,
+                    grpc: None,
+                }),
+                tcp_fault: None,
+                drip: None,
+                chunked: None,
+                rate_limit: None,
+                mock: None,
+                responses: vec![],
+            };
+            let latency_only = FaultConfig {
+                latency: Some(LatencyFault {
+                    probability,
+                    min_ms: 1,
+                    max_ms: 5,
+                }),
+                error: None,
+                tcp_fault: None,
+                drip: None,
+                chunked: None,
+                rate_limit: None,
+                mock: None,
+                responses: vec![],
+            };
+
+            for config in [error_only, latency_only] {
+                let rate = observed_rate(&config, 20_000);
+                assert!(
+                    (rate - probability).abs() < 0.02,
+                    "Expected ~{probability}, got {rate}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_seeded_fault_rng_is_reproducible() {
+        let fault_config = FaultConfig {
+            latency: None,
+            error: Some(ErrorFault {
+                probability: 0.5,
+                status: 503,
+                body: String::new(),
+                headers: HashMap::new(),
+                behaviors: None,
+                grpc: None,
+            }),
+            tcp_fault: None,
+            drip: None,
+            chunked: None,
+            rate_limit: None,
+            mock: None,
+            responses: vec![],
+        };
+        let sequence = |rng: &FaultRng| -> Vec<bool> {
+            (0..64)
+                .map(|_| !matches!(rng.decide(&fault_config, "seeded"), FaultDecision::None))
+                .collect()
+        };
+
+        let first = sequence(&FaultRng::new(Some(7)));
+        assert_eq!(first, sequence(&FaultRng::new(Some(7))));
+        assert_ne!(first, sequence(&FaultRng::new(Some(8))));
+        assert!(first.contains(&true) && first.contains(&false));
+    }
+
+    #[test]
+    fn test_decide_fault_inverted_latency_bounds() {
+        let fault_config = FaultConfig {
+            latency: Some(LatencyFault {
+                probability: 1.0,
+                min_ms: 500,
+                max_ms: 100,
+            }),
+            error: None,
+            tcp_fault: None,
+            drip: None,
+            chunked: None,
+            rate_limit: None,
+            mock: None,
+            responses: vec![],
+        };
+
+        for _ in 0..10 {
+            match decide_fault(&fault_config, "inverted") {
+                FaultDecision::Latency { duration_ms, .. } => assert_eq!(duration_ms, 100),
+                other => panic!("Expected latency fault, got {other:?}"),
+            }
+        }
+    }
+
     #[test]
     fn test_decide_fault_with_error() {
         let fault_config = FaultConfig {
@@ -199,8 +547,14 @@ mod tests {
                 body: "error".to_string(),
                 headers: HashMap::new(),
                 behaviors: None,
+                grpc: None,
             }),
             tcp_fault: None,
+            drip: None,
+            chunked: None,
+            rate_limit: None,
+            mock: None,
+            responses: vec![],
         };
 
         let decision = decide_fault(&fault_config, "test-rule");
@@ -231,6 +585,11 @@ mod tests {
             }),
             error: None,
             tcp_fault: None,
+            drip: None,
+            chunked: None,
+            rate_limit: None,
+            mock: None,
+            responses: vec![],
         };
 
         let decision = decide_fault(&fault_config, "test-rule");
@@ -246,6 +605,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_drip_takes_precedence_over_latency() {
+        let fault_config: FaultConfig = serde_yaml::from_str(
+            "latency:\n  probability: 1.0\n  min_ms: 1\n  max_ms: 2\n\
+             drip:\n  probability: 1.0\n  initial_bytes: 16\n  stall_ms: 500\n",
+        )
+        .unwrap();
+
+        match decide_fault(&fault_config, "test-rule") {
+            FaultDecision::Drip { drip, rule_id } => {
+                assert_eq!(drip.initial_bytes, 16);
+                assert_eq!(drip.stall_ms, 500);
+                assert!(drip.finish);
+                assert_eq!(rule_id, "test-rule");
+            }
+            other => panic!("Expected Drip decision, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_chunked_fault_decision() {
+        let fault_config: FaultConfig = serde_yaml::from_str(
+            "latency:\n  probability: 1.0\n  min_ms: 1\n  max_ms: 2\n\
+             chunked:\n  probability: 1.0\n  mode: bad_chunk_size\n",
+        )
+        .unwrap();
+
+        match decide_fault(&fault_config, "test-rule") {
+            FaultDecision::Chunked { chunked, rule_id } => {
+                assert_eq!(chunked.mode, ChunkedFaultMode::BadChunkSize);
+                assert_eq!(rule_id, "test-rule");
+            }
+            other => panic!("Expected Chunked decision, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_create_error_response() {
         let response =
@@ -286,4 +681,39 @@ mod tests {
         // Content-Length should be set
         assert!(response.headers().get("content-length").is_some());
     }
+
+    #[test]
+    fn test_is_grpc_request() {
+        let with_type = |content_type: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, content_type.parse().unwrap());
+            headers
+        };
+        assert!(is_grpc_request(&with_type("application/grpc")));
+        assert!(is_grpc_request(&with_type("application/grpc+proto")));
+        assert!(is_grpc_request(&with_type(
+            "application/grpc; charset=utf-8"
+        )));
+        assert!(!is_grpc_request(&with_type("application/grpc-web")));
+        assert!(!is_grpc_request(&with_type("application/json")));
+        assert!(!is_grpc_request(&HeaderMap::new()));
+    }
+
+    #[tokio::test]
+    async fn test_grpc_error_response_sets_trailers() {
+        use http_body_util::BodyExt;
+
+        let response = create_grpc_error_response(&GrpcFault {
+            status_code: 14,
+            message: "upstream 100% down".to_string(),
+        });
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/grpc");
+
+        let collected = response.into_body().collect().await.unwrap();
+        let trailers = collected.trailers().unwrap().clone();
+        assert_eq!(trailers["grpc-status"], "14");
+        assert_eq!(trailers["grpc-message"], "upstream 100%25 down");
+        assert!(collected.to_bytes().is_empty());
+    }
 }
@@ -85,13 +85,18 @@ pub fn create_flow_store(config: &crate::config::FlowStateConfig) -> Result<Arc<
         "inmemory" => {
             use crate::backends::InMemoryFlowStore;
             tracing::info!("Using InMemory FlowStore (ttl={}s)", config.ttl_seconds);
-            Ok(Arc::new(InMemoryFlowStore::new(config.ttl_seconds as u64)))
+            let ttl_seconds = config.ttl_seconds as u64;
+            let store = InMemoryFlowStore::new(ttl_seconds);
+            // Reads already ignore expired entries; the sweep only bounds
+            // memory held by flows that are never touched again
+            store.start_sweeper(std::time::Duration::from_secs(ttl_seconds.clamp(1, 60)));
+            Ok(Arc::new(store))
         }
-        "redis" => {
-            let redis_config = config
-                .redis
-                .as_ref()
-                .ok_or_else(|| anyhow!("Redis backend selected but no redis config provided"))?;
+        // Valkey speaks the Redis protocol, so both use the same client
+        backend @ ("redis" | "valkey") => {
+            let redis_config = config.redis.as_ref().ok_or_else(|| {
+                anyhow!("{backend} backend selected but no redis config provided")
+            })?;
 
             #[cfg(feature = "redis-backend")]
             {
@@ -106,7 +111,8 @@ pub fn create_flow_store(config: &crate::config::FlowStateConfig) -> Result<Arc<
                 .context("Failed to create Redis backend")?;
 
                 tracing::info!(
-                    "Using redis FlowStore (url={}, ttl={}s)",
+                    "Using {} FlowStore (url={}, ttl={}s)",
+                    backend,
                     redis_config.url,
                     config.ttl_seconds
                 );
@@ -116,8 +122,9 @@ pub fn create_flow_store(config: &crate::config::FlowStateConfig) -> Result<Arc<
 
             #[cfg(not(feature = "redis-backend"))]
             {
+                let _ = redis_config;
                 Err(anyhow!(
-                    "Redis backend not available. Compile with --features redis-backend"
+                    "{backend} backend not available. Compile with --features redis-backend"
                 ))
             }
         }
@@ -248,6 +255,50 @@ mod tests {
         assert!(err_msg.contains("redis config"));
     }
 
+    #[test]
+    fn test_create_flow_store_valkey_without_config() {
+        use crate::config::FlowStateConfig;
+        let config = FlowStateConfig {
+            backend: "valkey".to_string(),
+            ttl_seconds: 300,
+            redis: None,
+        };
+        let err_msg = create_flow_store(&config).err().unwrap().to_string();
+        assert!(
+            err_msg.contains("valkey backend"),
+            "unexpected error: {err_msg}"
+        );
+    }
+
+    #[cfg(feature = "redis-backend")]
+    #[test]
+    #[ignore] // Only run when a Valkey (or Redis) server is on localhost:6379
+    fn test_create_flow_store_valkey() {
+        use crate::config::{FlowStateConfig, RedisConfig};
+        let config = FlowStateConfig {
+            backend: "valkey".to_string(),
+            ttl_seconds: 300,
+            redis: Some(RedisConfig {
+                url: "redis://localhost:6379".to_string(),
+                pool_size: 2,
+                key_prefix: "test:valkey:".to_string(),
+            }),
+        };
+        let store = create_flow_store(&config).unwrap();
+
+        store.delete("flow", "count").unwrap();
+        assert_eq!(store.increment("flow", "count").unwrap(), 1);
+        assert_eq!(store.increment("flow", "count").unwrap(), 2);
+        store.set("flow", "state", json!({"step": 2})).unwrap();
+        assert_eq!(
+            store.get("flow", "state").unwrap(),
+            Some(json!({"step": 2}))
+        );
+
+        store.delete("flow", "count").unwrap();
+        store.delete("flow", "state").unwrap();
+    }
+
     // ============================================
     // Tests for FlowStore trait object behavior
     // ============================================
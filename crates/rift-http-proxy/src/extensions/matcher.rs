@@ -1,16 +1,38 @@
-use crate::config::{HeaderMatch, PathMatch, Rule};
+use crate::behaviors::{HasRepeatBehavior, RuleCycler};
+use crate::config::{HeaderMatch, MockResponse, PathMatch, Rule};
+use crate::extensions::fault::{configured_fault, FaultDecision, FaultRng};
+use crate::extensions::rate_limit::RateLimiter;
+use crate::extensions::retry::RetryTracker;
 use crate::predicate::{
-    compile_header_matcher, compile_query_matcher, parse_query_string, CompiledBodyMatcher,
-    CompiledFieldMatcher,
+    build_regex, compile_header_matcher, compile_query_matcher, parse_query_string, AuthMatcher,
+    BodySize, BodySizeMatcher, CachedValue, CompiledAuthMatcher, CompiledBodyMatcher,
+    CompiledContentType, CompiledFieldMatcher, CompiledHmacMatcher, CompiledIpMatcher,
+    FieldMatcher, PathTemplate, PredicateCompileError, StringMatcher, UserAgentFamily,
 };
 use hyper::{HeaderMap, Method, Uri};
-use regex::Regex;
+use regex::{Regex, RegexSet, SetMatches};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 pub struct CompiledRule {
     pub id: String,
     pub match_config: CompiledMatch,
     pub rule: Arc<Rule>,
+    /// Set once a `once` rule has injected its fault
+    fired: AtomicBool,
+    /// Request counters for the rule's `rate_limit` fault
+    rate_limiter: Option<RateLimiter>,
+    /// Position in the rule's `responses` sequence
+    sequence: RuleCycler,
+    /// Requests this rule has matched since it was compiled
+    match_count: AtomicU64,
+    /// When the rule last matched, in Unix milliseconds; 0 if never
+    last_matched_ms: AtomicI64,
+    /// The rule's `enabled` flag, as overridden through the admin API
+    enabled: AtomicBool,
 }
 
 pub struct CompiledMatch {
@@ -21,54 +43,129 @@ pub struct CompiledMatch {
     header_predicates: Vec<CompiledFieldMatcher>,
     /// Query parameter matchers
     query_matchers: Vec<CompiledFieldMatcher>,
+    /// Request trailer matchers
+    trailer_matchers: Vec<CompiledFieldMatcher>,
     /// Body matcher
     body_matcher: Option<CompiledBodyMatcher>,
+    /// Body size bounds
+    body_size: Option<BodySizeMatcher>,
+    /// Request media type
+    content_type: Option<CompiledContentType>,
+    /// HMAC signature matcher
+    hmac_matcher: Option<CompiledHmacMatcher>,
+    /// Authorization header credentials
+    auth_matcher: Option<CompiledAuthMatcher>,
+    /// User-Agent family
+    user_agent_family: Option<UserAgentFamily>,
+    /// Client address ranges
+    ip_matcher: Option<CompiledIpMatcher>,
     /// Case-sensitive matching
     case_sensitive: bool,
 }
 
+/// A request body as the matchers see it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatchBody<'a> {
+    /// The body as received; HMAC signatures are checked over these bytes
+    pub raw: Option<&'a [u8]>,
+    /// True if `raw` stops short of the whole body
+    pub truncated: bool,
+    /// The body as text for body matchers, decoded if it was compressed
+    pub text: Option<&'a str>,
+    /// True if `text` stops short of the whole body
+    pub text_truncated: bool,
+    /// Trailers sent after the body, if any
+    pub trailers: Option<&'a HeaderMap>,
+}
+
+impl<'a> MatchBody<'a> {
+    /// An uncompressed, complete body
+    pub fn text(body: Option<&'a str>) -> Self {
+        Self {
+            raw: body.map(str::as_bytes),
+            truncated: false,
+            text: body,
+            text_truncated: false,
+            trailers: None,
+        }
+    }
+}
+
+// Patterns are lowercased once here rather than on every request
 enum PathMatcher {
     Any,
     Exact(String),
-    Prefix(String),
+    Prefix(CachedValue),
     Regex(Regex),
-    Contains(String),
-    EndsWith(String),
+    Contains(CachedValue),
+    EndsWith(CachedValue),
+    Template(PathTemplate),
 }
 
 impl CompiledRule {
+    /// Compile a rule's matcher; a bad pattern is reported with the rule
+    /// id and the field it is in.
     pub fn compile(rule: Rule) -> Result<Self, anyhow::Error> {
+        let id = rule.id.as_str();
+        let invalid =
+            |field: String| move |e: regex::Error| PredicateCompileError::new(field, e).in_rule(id);
+
         let methods: Vec<Method> = rule
             .match_config
             .methods
             .iter()
-            .map(|m| m.parse())
+            .map(|m| {
+                m.parse()
+                    .map_err(|_| anyhow::anyhow!("Rule '{id}': invalid method '{m}'"))
+            })
             .collect::<Result<Vec<_>, _>>()?;
 
         let path_matcher = match &rule.match_config.path {
             PathMatch::Any => PathMatcher::Any,
             PathMatch::Exact { exact } => PathMatcher::Exact(exact.clone()),
-            PathMatch::Prefix { prefix } => PathMatcher::Prefix(prefix.clone()),
-            PathMatch::Regex { regex } => PathMatcher::Regex(Regex::new(regex)?),
-            PathMatch::Contains { contains } => PathMatcher::Contains(contains.clone()),
-            PathMatch::EndsWith { ends_with } => PathMatcher::EndsWith(ends_with.clone()),
+            PathMatch::Prefix { prefix } => PathMatcher::Prefix(CachedValue::new(prefix)),
+            PathMatch::Regex { regex } => {
+                PathMatcher::Regex(build_regex(regex).map_err(invalid("path".into()))?)
+            }
+            PathMatch::Contains { contains } => PathMatcher::Contains(CachedValue::new(contains)),
+            PathMatch::EndsWith { ends_with } => PathMatcher::EndsWith(CachedValue::new(ends_with)),
+            PathMatch::Template { template } => PathMatcher::Template(
+                PathTemplate::compile(template, rule.match_config.case_sensitive)
+                    .map_err(|e| anyhow::anyhow!("Rule '{id}': {e}"))?,
+            ),
         };
 
         // Compile enhanced header predicates
-        let header_predicates: Result<Vec<_>, _> = rule
+        let header_predicates = rule
             .match_config
             .header_predicates
             .iter()
-            .map(compile_header_matcher)
-            .collect();
+            .map(|header| {
+                compile_header_matcher(header)
+                    .map_err(invalid(format!("header '{}'", header.name())))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         // Compile query matchers
-        let query_matchers: Result<Vec<_>, _> = rule
+        let query_matchers = rule
             .match_config
             .query
             .iter()
-            .map(compile_query_matcher)
-            .collect();
+            .map(|param| {
+                compile_query_matcher(param).map_err(invalid(format!("query '{}'", param.name())))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Compile trailer matchers
+        let trailer_matchers = rule
+            .match_config
+            .trailers
+            .iter()
+            .map(|trailer| {
+                compile_header_matcher(trailer)
+                    .map_err(invalid(format!("trailer '{}'", trailer.name())))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         // Compile body matcher
         let body_matcher = rule
@@ -76,7 +173,41 @@ impl CompiledRule {
             .body
             .as_ref()
             .map(CompiledBodyMatcher::compile)
-            .transpose()?;
+            .transpose()
+            .map_err(invalid("body".into()))?;
+
+        let content_type = rule
+            .match_config
+            .content_type
+            .as_deref()
+            .map(CompiledContentType::compile)
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Rule '{id}': {e}"))?;
+
+        // Compile HMAC signature matcher
+        let hmac_matcher = rule
+            .match_config
+            .hmac
+            .as_ref()
+            .map(CompiledHmacMatcher::compile)
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Rule '{id}': {e}"))?;
+
+        let auth_matcher = rule
+            .match_config
+            .auth
+            .as_ref()
+            .map(CompiledAuthMatcher::compile)
+            .transpose()
+            .map_err(invalid("auth".into()))?;
+
+        let ip_matcher = rule
+            .match_config
+            .ip
+            .as_ref()
+            .map(CompiledIpMatcher::compile)
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Rule '{id}': {e}"))?;
 
         Ok(CompiledRule {
             id: rule.id.clone(),
@@ -84,15 +215,296 @@ impl CompiledRule {
                 methods,
                 path_matcher,
                 headers: rule.match_config.headers.clone(),
-                header_predicates: header_predicates?,
-                query_matchers: query_matchers?,
+                header_predicates,
+                query_matchers,
+                trailer_matchers,
                 body_matcher,
+                body_size: rule.match_config.body_size,
+                content_type,
+                hmac_matcher,
+                auth_matcher,
+                user_agent_family: rule.match_config.user_agent_family,
+                ip_matcher,
                 case_sensitive: rule.match_config.case_sensitive,
             },
+            rate_limiter: rule.fault.rate_limit.as_ref().map(RateLimiter::new),
+            enabled: AtomicBool::new(rule.enabled),
             rule: Arc::new(rule),
+            fired: AtomicBool::new(false),
+            sequence: RuleCycler::new(),
+            match_count: AtomicU64::new(0),
+            last_matched_ms: AtomicI64::new(0),
         })
     }
 
+    /// Decide the fault to inject for a matched request.
+    ///
+    /// A `once` rule injects at most one fault: the first request that gets
+    /// a fault disables the rule, even under concurrent requests.
+    pub fn decide_fault(&self, rng: &FaultRng) -> FaultDecision {
+        let decision = self
+            .next_sequence_response()
+            .unwrap_or_else(|| rng.decide(&self.rule.fault, &self.id));
+        if self.rule.once
+            && !matches!(decision, FaultDecision::None)
+            && self.fired.swap(true, Ordering::AcqRel)
+        {
+            return FaultDecision::None;
+        }
+        decision
+    }
+
+    /// The fault a matched request could get and the probability it does,
+    /// without any side effects or sampling: a `once` rule isn't consumed, a
+    /// sequence doesn't advance and no random source is drawn from. See
+    /// [`configured_fault`].
+    pub fn preview_fault(&self) -> (FaultDecision, f64) {
+        let responses = &self.rule.fault.responses;
+        if responses.is_empty() {
+            return configured_fault(&self.rule.fault, &self.id);
+        }
+        let index = self.sequence.peek_response_index(responses.len() as u32);
+        let response = &responses[index as usize];
+        let decision = FaultDecision::Error {
+            status: response.status,
+            body: response.body.clone(),
+            rule_id: self.id.clone(),
+            headers: response.headers.clone(),
+            behaviors: response.behaviors.clone(),
+        };
+        (decision, 1.0)
+    }
+
+    /// Take the next response of the rule's `responses` sequence, if it has
+    /// one. Concurrent requests each get their own step of the sequence.
+    fn next_sequence_response(&self) -> Option<FaultDecision> {
+        let responses = &self.rule.fault.responses;
+        if responses.is_empty() {
+            return None;
+        }
+        let index = self
+            .sequence
+            .get_response_index_advance(responses.len() as u32, |i| {
+                responses.get(i as usize).and_then(|r| r.get_repeat())
+            });
+        let response = &responses[index as usize];
+        Some(FaultDecision::Error {
+            status: response.status,
+            body: response.body.clone(),
+            rule_id: self.id.clone(),
+            headers: response.headers.clone(),
+            behaviors: response.behaviors.clone(),
+        })
+    }
+
+    /// Variables captured by the rule's path template; empty for other path
+    /// matchers
+    pub fn path_params(&self, path: &str) -> HashMap<String, String> {
+        match &self.match_config.path_matcher {
+            PathMatcher::Template(template) => template.captures(path).unwrap_or_default(),
+            _ => HashMap::new(),
+        }
+    }
+
+    /// Count a matched request against the rule's `rate_limit`, returning
+    /// the `Retry-After` delay if it is over the limit
+    pub fn check_rate_limit(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        client_ip: Option<IpAddr>,
+    ) -> Option<Duration> {
+        self.rate_limiter
+            .as_ref()?
+            .check(method, uri, headers, client_ip)
+    }
+
+    /// Check the `is_retry` condition, if configured.
+    ///
+    /// Kept separate from `matches` because it records the request's
+    /// idempotency key; call it only once the other conditions match.
+    pub fn matches_retry(&self, tracker: &mut RetryTracker) -> bool {
+        self.rule
+            .match_config
+            .is_retry
+            .as_ref()
+            .is_none_or(|matcher| tracker.is_retry(matcher))
+    }
+
+    /// Check the rule's client address ranges, if any
+    pub fn matches_client_ip(&self, client_ip: Option<IpAddr>) -> bool {
+        self.match_config
+            .ip_matcher
+            .as_ref()
+            .is_none_or(|matcher| matcher.matches(client_ip))
+    }
+
+    /// Whether the rule needs the request body: for a body, HMAC or trailer
+    /// matcher, or a mock response rendered from it
+    pub fn needs_body(&self) -> bool {
+        self.match_config.body_matcher.is_some()
+            || self.match_config.body_size.is_some()
+            || self.match_config.hmac_matcher.is_some()
+            || self.needs_trailers()
+            || self
+                .rule
+                .fault
+                .mock
+                .as_ref()
+                .is_some_and(MockResponse::uses_request_body)
+    }
+
+    /// Whether matching needs the request trailers, and so the whole body
+    pub fn needs_trailers(&self) -> bool {
+        !self.match_config.trailer_matchers.is_empty()
+    }
+
+    /// Count a request this rule was selected for
+    pub fn record_match(&self) {
+        self.match_count.fetch_add(1, Ordering::Relaxed);
+        self.last_matched_ms
+            .store(chrono::Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    /// Requests this rule has matched since it was compiled
+    pub fn match_count(&self) -> u64 {
+        self.match_count.load(Ordering::Relaxed)
+    }
+
+    /// When this rule last matched a request
+    pub fn last_matched(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self.last_matched_ms.load(Ordering::Relaxed) {
+            0 => None,
+            ms => chrono::DateTime::from_timestamp_millis(ms),
+        }
+    }
+
+    /// One-line description of what the rule matches, e.g.
+    /// `method POST and path starts with "/checkout"`.
+    ///
+    /// With `redact`, header and trailer values are replaced by
+    /// `<redacted>` so secrets in matchers aren't exposed.
+    pub fn summary(&self, redact: bool) -> String {
+        let config = &self.rule.match_config;
+        let quoted = |value: &str| format!("{value:?}");
+        let secret = |value: &str| {
+            if redact {
+                "<redacted>".to_string()
+            } else {
+                quoted(value)
+            }
+        };
+
+        let mut parts = Vec::new();
+        if !config.methods.is_empty() {
+            parts.push(format!("method {}", config.methods.join("|")));
+        }
+        match &config.path {
+            PathMatch::Any => {}
+            PathMatch::Exact { exact } => parts.push(format!("path = {}", quoted(exact))),
+            PathMatch::Prefix { prefix } => {
+                parts.push(format!("path starts with {}", quoted(prefix)))
+            }
+            PathMatch::Regex { regex } => parts.push(format!("path matches /{regex}/")),
+            PathMatch::Contains { contains } => {
+                parts.push(format!("path contains {}", quoted(contains)))
+            }
+            PathMatch::EndsWith { ends_with } => {
+                parts.push(format!("path ends with {}", quoted(ends_with)))
+            }
+            PathMatch::Template { template } => {
+                parts.push(format!("path template {}", quoted(template)))
+            }
+        }
+        for header in &config.headers {
+            parts.push(format!(
+                "header {} = {}",
+                header.name,
+                secret(&header.value)
+            ));
+        }
+        for matcher in &config.header_predicates {
+            parts.push(describe_field("header", matcher, &secret));
+        }
+        for matcher in &config.query {
+            parts.push(describe_field("query", matcher, &quoted));
+        }
+        for matcher in &config.trailers {
+            parts.push(describe_field("trailer", matcher, &secret));
+        }
+        if config.body.is_some() {
+            parts.push("body predicate".to_string());
+        }
+        if let Some(size) = &config.body_size {
+            parts.push(format!("body size {}", size.describe()));
+        }
+        if let Some(content_type) = &config.content_type {
+            parts.push(format!("content type {content_type}"));
+        }
+        if let Some(hmac) = &config.hmac {
+            parts.push(format!(
+                "{} signature in header {}",
+                hmac.algorithm, hmac.header
+            ));
+        }
+        match &config.auth {
+            Some(AuthMatcher::Basic { basic }) => {
+                parts.push(format!("basic auth as {}", secret(&basic.username)))
+            }
+            Some(AuthMatcher::Bearer { bearer }) => parts.push(format!(
+                "bearer token {}",
+                describe_string(&bearer.token, &secret)
+            )),
+            None => {}
+        }
+        if let Some(family) = config.user_agent_family {
+            parts.push(format!("user agent {family:?}").to_lowercase());
+        }
+        if let Some(ip) = &config.ip {
+            let op = if ip.not { "not in" } else { "in" };
+            parts.push(format!("client ip {op} {}", ip.cidrs.join("|")));
+        }
+        if let Some(retry) = &config.is_retry {
+            parts.push(format!("retry of header {}", retry.header));
+        }
+
+        let mut summary = if parts.is_empty() {
+            "any request".to_string()
+        } else {
+            parts.join(" and ")
+        };
+        if !config.case_sensitive {
+            summary.push_str(" (case-insensitive)");
+        }
+        summary
+    }
+
+    /// Whether the rule allows or denies access rather than injecting a fault
+    pub fn is_access_rule(&self) -> bool {
+        !self.rule.action.is_fault()
+    }
+
+    /// Whether this is a `once` rule that has already fired
+    pub fn is_exhausted(&self) -> bool {
+        self.rule.once && self.fired.load(Ordering::Acquire)
+    }
+
+    /// Whether the rule can match now: enabled, inside its active window
+    /// and not exhausted
+    pub fn is_active(&self) -> bool {
+        self.is_enabled() && !self.is_exhausted() && self.rule.in_active_window(chrono::Utc::now())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Override the rule's `enabled` flag until the rules are recompiled
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
     pub fn matches(&self, method: &Method, uri: &Uri, headers: &HeaderMap) -> bool {
         self.matches_with_body(method, uri, headers, None)
     }
@@ -104,6 +516,20 @@ impl CompiledRule {
         uri: &Uri,
         headers: &HeaderMap,
         body: Option<&str>,
+    ) -> bool {
+        self.matches_with_body_prefix(method, uri, headers, MatchBody::text(body))
+    }
+
+    /// Match with a request body that may be truncated to `max_body_bytes`.
+    ///
+    /// See [`CompiledBodyMatcher::matches_buffer`] for how a truncated body
+    /// is matched. HMAC signatures never verify over a truncated body.
+    pub fn matches_with_body_prefix(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: MatchBody<'_>,
     ) -> bool {
         let case_sensitive = self.match_config.case_sensitive;
 
@@ -127,12 +553,7 @@ impl CompiledRule {
                 }
             }
             PathMatcher::Prefix(prefix) => {
-                let matches = if case_sensitive {
-                    path.starts_with(prefix)
-                } else {
-                    path.to_lowercase().starts_with(&prefix.to_lowercase())
-                };
-                if !matches {
+                if !prefix.starts(path, case_sensitive) {
                     return false;
                 }
             }
@@ -142,22 +563,17 @@ impl CompiledRule {
                 }
             }
             PathMatcher::Contains(pattern) => {
-                let matches = if case_sensitive {
-                    path.contains(pattern)
-                } else {
-                    path.to_lowercase().contains(&pattern.to_lowercase())
-                };
-                if !matches {
+                if !pattern.contained_in(path, case_sensitive) {
                     return false;
                 }
             }
             PathMatcher::EndsWith(suffix) => {
-                let matches = if case_sensitive {
-                    path.ends_with(suffix)
-                } else {
-                    path.to_lowercase().ends_with(&suffix.to_lowercase())
-                };
-                if !matches {
+                if !suffix.ends(path, case_sensitive) {
+                    return false;
+                }
+            }
+            PathMatcher::Template(template) => {
+                if !template.is_match(path) {
                     return false;
                 }
             }
@@ -182,10 +598,7 @@ impl CompiledRule {
 
         // Match enhanced header predicates
         for header_pred in &self.match_config.header_predicates {
-            let value = headers
-                .get(header_pred.name.as_str())
-                .and_then(|v| v.to_str().ok());
-            if !header_pred.matches(value) {
+            if !header_pred.matches_headers(Some(headers)) {
                 return false;
             }
         }
@@ -201,11 +614,18 @@ impl CompiledRule {
             }
         }
 
+        // Match trailers
+        for trailer_matcher in &self.match_config.trailer_matchers {
+            if !trailer_matcher.matches_headers(body.trailers) {
+                return false;
+            }
+        }
+
         // Match body (if provided and body matcher configured)
         if let Some(ref body_matcher) = self.match_config.body_matcher {
-            match body {
+            match body.text {
                 Some(body_str) => {
-                    if !body_matcher.matches(body_str, case_sensitive) {
+                    if !body_matcher.matches_buffer(body_str, body.text_truncated, case_sensitive) {
                         return false;
                     }
                 }
@@ -216,17 +636,163 @@ impl CompiledRule {
             }
         }
 
+        if let Some(size) = &self.match_config.body_size {
+            if !size.matches(body_size(headers, &body)) {
+                return false;
+            }
+        }
+
+        if let Some(content_type) = &self.match_config.content_type {
+            let header = headers
+                .get(hyper::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok());
+            if !content_type.matches(header) {
+                return false;
+            }
+        }
+
+        // Match HMAC signature over the raw body
+        if let Some(ref hmac_matcher) = self.match_config.hmac_matcher {
+            let signature = headers
+                .get(hmac_matcher.header.as_str())
+                .and_then(|v| v.to_str().ok());
+            let body_bytes = body.raw.unwrap_or_default();
+            if body.truncated || !hmac_matcher.matches(body_bytes, signature) {
+                return false;
+            }
+        }
+
+        // Match Authorization credentials
+        if let Some(ref auth_matcher) = self.match_config.auth_matcher {
+            let authorization = headers
+                .get(hyper::header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok());
+            if !auth_matcher.matches(authorization, case_sensitive) {
+                return false;
+            }
+        }
+
+        // Match User-Agent family
+        if let Some(family) = self.match_config.user_agent_family {
+            let user_agent = headers
+                .get(hyper::header::USER_AGENT)
+                .and_then(|v| v.to_str().ok());
+            if !family.matches(user_agent) {
+                return false;
+            }
+        }
+
         true
     }
 }
 
+/// Size of the request body: its length if it was read whole, otherwise
+/// `Content-Length`, otherwise more than the prefix that was read
+fn body_size(headers: &HeaderMap, body: &MatchBody<'_>) -> BodySize {
+    let content_length = headers
+        .get(hyper::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    match (body.raw, content_length) {
+        (Some(raw), _) if !body.truncated => BodySize::Exact(raw.len() as u64),
+        (_, Some(length)) => BodySize::Exact(length),
+        (Some(raw), None) => BodySize::AtLeast(raw.len() as u64 + 1),
+        (None, None) => BodySize::Unknown,
+    }
+}
+
+/// Describe a header, query or trailer matcher, rendering values with
+/// `value`
+fn describe_field(kind: &str, matcher: &FieldMatcher, value: &dyn Fn(&str) -> String) -> String {
+    let describe = |matcher: &StringMatcher| describe_string(matcher, value);
+    match matcher {
+        FieldMatcher::Simple { name, value: v } => format!("{kind} {name} = {}", value(v)),
+        FieldMatcher::Full { name, matcher, .. } => format!("{kind} {name} {}", describe(matcher)),
+        FieldMatcher::Or { name, or, .. } => {
+            let alternatives: Vec<String> = or.iter().map(describe).collect();
+            format!("{kind} {name} ({})", alternatives.join(" or "))
+        }
+    }
+}
+
+/// Describe a string predicate, rendering its operand with `value`
+fn describe_string(matcher: &StringMatcher, value: &dyn Fn(&str) -> String) -> String {
+    match matcher {
+        StringMatcher::Equals(v) => format!("= {}", value(v)),
+        StringMatcher::Contains(v) => format!("contains {}", value(v)),
+        StringMatcher::StartsWith(v) => format!("starts with {}", value(v)),
+        StringMatcher::EndsWith(v) => format!("ends with {}", value(v)),
+        StringMatcher::Matches(v) => format!("matches {}", value(v)),
+        StringMatcher::Exists(true) => "exists".to_string(),
+        StringMatcher::Exists(false) => "is absent".to_string(),
+    }
+}
+
+/// First-stage path filter over a whole rule list.
+///
+/// Every rule's path matcher is compiled into a single `RegexSet`, so one
+/// pass over the request path yields the candidate rules. Candidates are
+/// then confirmed with the full per-rule matcher.
+pub struct RulePathSet {
+    set: RegexSet,
+}
+
+impl RulePathSet {
+    /// Build the set; pattern `i` corresponds to `rules[i]`
+    pub fn new(rules: &[CompiledRule]) -> Result<Self, regex::Error> {
+        let patterns = rules.iter().map(|rule| {
+            let case_sensitive = rule.match_config.case_sensitive;
+            let literal = |pattern: &str, prefix: &str, suffix: &str| {
+                let flags = if case_sensitive { "" } else { "(?i)" };
+                format!("{flags}{prefix}{}{suffix}", regex::escape(pattern))
+            };
+            match &rule.match_config.path_matcher {
+                PathMatcher::Any => String::new(),
+                PathMatcher::Exact(exact) => literal(exact, "^", "$"),
+                PathMatcher::Prefix(prefix) => literal(&prefix.value, "^", ""),
+                PathMatcher::Contains(pattern) => literal(&pattern.value, "", ""),
+                PathMatcher::EndsWith(suffix) => literal(&suffix.value, "", "$"),
+                // Regex paths ignore `case_sensitive` in the per-rule matcher too
+                PathMatcher::Regex(regex) => regex.as_str().to_string(),
+                PathMatcher::Template(template) => template.as_str().to_string(),
+            }
+        });
+        Ok(Self {
+            set: RegexSet::new(patterns)?,
+        })
+    }
+
+    /// Rules whose path matcher accepts `path`
+    pub fn candidates(&self, path: &str) -> SetMatches {
+        self.set.matches(path)
+    }
+}
+
+/// Like [`find_matching_rule`], but only evaluates rules whose path matches
+/// according to `paths`, which must be built from the same `rules`.
+pub fn find_matching_rule_indexed<'a>(
+    rules: &'a [CompiledRule],
+    paths: &RulePathSet,
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Option<&'a CompiledRule> {
+    paths
+        .candidates(uri.path())
+        .into_iter()
+        .map(|idx| &rules[idx])
+        .find(|rule| rule.is_active() && rule.matches(method, uri, headers))
+}
+
 pub fn find_matching_rule<'a>(
     rules: &'a [CompiledRule],
     method: &Method,
     uri: &Uri,
     headers: &HeaderMap,
 ) -> Option<&'a CompiledRule> {
-    rules.iter().find(|rule| rule.matches(method, uri, headers))
+    rules
+        .iter()
+        .find(|rule| rule.is_active() && rule.matches(method, uri, headers))
 }
 
 #[cfg(test)]
@@ -243,7 +809,15 @@ mod tests {
                 headers: vec![],
                 header_predicates: vec![],
                 query: vec![],
+                trailers: vec![],
                 body: None,
+                body_size: None,
+                content_type: None,
+                hmac: None,
+                user_agent_family: None,
+                auth: None,
+                ip: None,
+                is_retry: None,
                 case_sensitive: true,
             },
             fault: FaultConfig {
@@ -254,8 +828,19 @@ mod tests {
                 }),
                 error: None,
                 tcp_fault: None,
+                drip: None,
+                chunked: None,
+                rate_limit: None,
+                mock: None,
+                responses: vec![],
             },
             upstream: None, // No upstream filter for tests
+            once: false,
+            action: Default::default(),
+            description: None,
+            metadata: Default::default(),
+            enabled: true,
+            active_window: None,
         }
     }
 
@@ -494,6 +1079,31 @@ mod tests {
         assert!(!compiled.matches(&Method::GET, &uri, &headers3));
     }
 
+    #[test]
+    fn test_header_predicate_checks_repeated_headers() {
+        let mut rule = create_test_rule("test", vec![], PathMatch::Any);
+        rule.match_config.header_predicates =
+            vec![
+                serde_json::from_str(r#"{"name": "Accept", "equals": "application/json"}"#)
+                    .unwrap(),
+            ];
+        let any = CompiledRule::compile(rule.clone()).unwrap();
+        rule.match_config.header_predicates = vec![serde_json::from_str(
+            r#"{"name": "Accept", "equals": "application/json", "values": "all"}"#,
+        )
+        .unwrap()];
+        let all = CompiledRule::compile(rule).unwrap();
+
+        let uri = "http://localhost/test".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.append("accept", "text/html".parse().unwrap());
+        headers.append("accept", "application/json".parse().unwrap());
+
+        // Only the second of the two values matches
+        assert!(any.matches(&Method::GET, &uri, &headers));
+        assert!(!all.matches(&Method::GET, &uri, &headers));
+    }
+
     #[test]
     fn test_path_contains_matching() {
         let rule = create_test_rule(
@@ -587,6 +1197,198 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_compiled_rule_with_hmac_matcher() {
+        use crate::predicate::HmacMatcher;
+
+        let mut rule = create_test_rule("test", vec![], PathMatch::Any);
+        rule.match_config.hmac = Some(HmacMatcher {
+            header: "X-Signature".to_string(),
+            algorithm: "sha256".to_string(),
+            secret: "key".to_string(),
+        });
+        let compiled = CompiledRule::compile(rule).unwrap();
+
+        let uri = "http://localhost/webhook".parse().unwrap();
+        let body = "The quick brown fox jumps over the lazy dog";
+        // Well-known HMAC-SHA256 test vector for key "key"
+        let signature = "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8";
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-signature", signature.parse().unwrap());
+        assert!(compiled.matches_with_body(&Method::POST, &uri, &headers, Some(body)));
+        assert!(!compiled.matches_with_body(&Method::POST, &uri, &headers, Some("tampered")));
+
+        // Missing signature header
+        let empty = HeaderMap::new();
+        assert!(!compiled.matches_with_body(&Method::POST, &uri, &empty, Some(body)));
+
+        // A signature can't be verified over a truncated body
+        assert!(!compiled.matches_with_body_prefix(
+            &Method::POST,
+            &uri,
+            &headers,
+            MatchBody {
+                truncated: true,
+                ..MatchBody::text(Some(body))
+            }
+        ));
+    }
+
+    #[test]
+    fn test_compiled_rule_with_user_agent_family() {
+        let yaml = r#"
+user_agent_family: mobile
+"#;
+        let mut rule = create_test_rule("test", vec![], PathMatch::Any);
+        rule.match_config = serde_yaml::from_str(yaml).unwrap();
+        let compiled = CompiledRule::compile(rule).unwrap();
+        let uri = "http://localhost/home".parse().unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            hyper::header::USER_AGENT,
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) Mobile/15E148"
+                .parse()
+                .unwrap(),
+        );
+        assert!(compiled.matches(&Method::GET, &uri, &headers));
+
+        headers.insert(
+            hyper::header::USER_AGENT,
+            "Mozilla/5.0 (compatible; Googlebot/2.1)".parse().unwrap(),
+        );
+        assert!(!compiled.matches(&Method::GET, &uri, &headers));
+        assert!(!compiled.matches(&Method::GET, &uri, &HeaderMap::new()));
+    }
+
+    #[test]
+    fn test_compiled_rule_with_ip_ranges() {
+        let yaml = r#"
+ip:
+  cidrs: ["192.168.0.0/16", "2001:db8::/32"]
+"#;
+        let mut rule = create_test_rule("lan", vec![], PathMatch::Any);
+        rule.match_config = serde_yaml::from_str(yaml).unwrap();
+        let compiled = CompiledRule::compile(rule.clone()).unwrap();
+        let ip = |s: &str| Some(s.parse::<IpAddr>().unwrap());
+
+        assert!(compiled.matches_client_ip(ip("192.168.1.1")));
+        assert!(compiled.matches_client_ip(ip("2001:db8::7")));
+        assert!(!compiled.matches_client_ip(ip("8.8.8.8")));
+        assert_eq!(
+            compiled.summary(false),
+            "client ip in 192.168.0.0/16|2001:db8::/32"
+        );
+
+        rule.match_config.ip.as_mut().unwrap().cidrs = vec!["192.168.0.0/40".to_string()];
+        let error = CompiledRule::compile(rule).err().unwrap().to_string();
+        assert!(error.contains("Rule 'lan'"), "{error}");
+        assert!(error.contains("192.168.0.0/40"), "{error}");
+    }
+
+    #[test]
+    fn test_compiled_rule_with_bearer_auth() {
+        let yaml = r#"
+auth:
+  bearer:
+    startsWith: test-
+"#;
+        let mut rule = create_test_rule("test-tokens", vec![], PathMatch::Any);
+        rule.match_config = serde_yaml::from_str(yaml).unwrap();
+        let compiled = CompiledRule::compile(rule).unwrap();
+        let uri: Uri = "/api".parse().unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            hyper::header::AUTHORIZATION,
+            "Bearer test-123".parse().unwrap(),
+        );
+        assert!(compiled.matches(&Method::GET, &uri, &headers));
+        headers.insert(
+            hyper::header::AUTHORIZATION,
+            "Bearer live-123".parse().unwrap(),
+        );
+        assert!(!compiled.matches(&Method::GET, &uri, &headers));
+        assert!(!compiled.matches(&Method::GET, &uri, &HeaderMap::new()));
+
+        assert_eq!(
+            compiled.summary(true),
+            "bearer token starts with <redacted>"
+        );
+    }
+
+    #[test]
+    fn test_once_rule_injects_exactly_once_across_threads() {
+        let mut rule = create_test_rule("one-shot", vec![], PathMatch::Any);
+        rule.once = true;
+        rule.fault.latency.as_mut().unwrap().probability = 1.0;
+        let compiled = Arc::new(CompiledRule::compile(rule).unwrap());
+
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let compiled = Arc::clone(&compiled);
+                std::thread::spawn(move || {
+                    (0..10)
+                        .filter(|_| {
+                            !matches!(
+                                compiled.decide_fault(&FaultRng::default()),
+                                FaultDecision::None
+                            )
+                        })
+                        .count()
+                })
+            })
+            .collect();
+        let injected: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+
+        assert_eq!(injected, 1);
+        assert!(compiled.is_exhausted());
+
+        // An exhausted rule no longer matches, so later rules can apply
+        let uri: Uri = "http://localhost/api".parse().unwrap();
+        let rules = [Arc::try_unwrap(compiled).ok().unwrap()];
+        assert!(find_matching_rule(&rules, &Method::GET, &uri, &HeaderMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_once_rule_not_consumed_without_fault() {
+        let mut rule = create_test_rule("one-shot", vec![], PathMatch::Any);
+        rule.once = true;
+        rule.fault.latency.as_mut().unwrap().probability = 0.0;
+        let compiled = CompiledRule::compile(rule).unwrap();
+
+        assert!(matches!(
+            compiled.decide_fault(&FaultRng::default()),
+            FaultDecision::None
+        ));
+        assert!(!compiled.is_exhausted());
+    }
+
+    #[test]
+    fn test_response_sequence_repeats_and_wraps() {
+        let mut rule = create_test_rule("flaky", vec![], PathMatch::Any);
+        let response = |status: u16, repeat: Option<u32>| crate::config::SequenceResponse {
+            status,
+            body: String::new(),
+            headers: Default::default(),
+            behaviors: repeat.map(|repeat| crate::behaviors::ResponseBehaviors {
+                repeat: Some(repeat),
+                ..Default::default()
+            }),
+        };
+        rule.fault.responses = vec![response(503, Some(2)), response(200, None)];
+        let compiled = CompiledRule::compile(rule).unwrap();
+
+        let statuses: Vec<u16> = (0..6)
+            .map(|_| match compiled.decide_fault(&FaultRng::default()) {
+                FaultDecision::Error { status, .. } => status,
+                other => panic!("expected a sequence response, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(statuses, [503, 503, 200, 503, 503, 200]);
+    }
+
     #[test]
     fn test_invalid_regex_compilation() {
         let rule = create_test_rule(
@@ -596,8 +1398,22 @@ mod tests {
                 regex: "[invalid(regex".to_string(), // Invalid regex
             },
         );
-        let result = CompiledRule::compile(rule);
-        assert!(result.is_err());
+        let err = CompiledRule::compile(rule).err().unwrap().to_string();
+        assert!(
+            err.starts_with("Rule 'test': invalid pattern in path"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn test_invalid_header_pattern_names_header() {
+        let mut rule = create_test_rule("strict-auth", vec![], PathMatch::Any);
+        rule.match_config.header_predicates =
+            serde_yaml::from_str("- name: Authorization\n  matches: 'Bearer (['\n").unwrap();
+        let err = CompiledRule::compile(rule).err().unwrap();
+        let compile_error = err.downcast_ref::<PredicateCompileError>().unwrap();
+        assert_eq!(compile_error.rule.as_deref(), Some("strict-auth"));
+        assert_eq!(compile_error.field, "header 'Authorization'");
     }
 
     #[test]
@@ -727,4 +1543,70 @@ mod tests {
         let uri5 = "http://localhost/other/api".parse().unwrap();
         assert!(!compiled.matches(&Method::GET, &uri5, &headers));
     }
+
+    #[test]
+    fn test_rule_path_set_agrees_with_linear_scan() {
+        let mut lenient = create_test_rule(
+            "lenient",
+            vec![],
+            PathMatch::Exact {
+                exact: "/Health".to_string(),
+            },
+        );
+        lenient.match_config.case_sensitive = false;
+        let rules: Vec<CompiledRule> = [
+            create_test_rule(
+                "exact",
+                vec!["POST"],
+                PathMatch::Exact {
+                    exact: "/api/orders".to_string(),
+                },
+            ),
+            create_test_rule(
+                "prefix",
+                vec![],
+                PathMatch::Prefix {
+                    prefix: "/api/".to_string(),
+                },
+            ),
+            create_test_rule(
+                "regex",
+                vec![],
+                PathMatch::Regex {
+                    regex: r"^/items/\d+$".to_string(),
+                },
+            ),
+            create_test_rule(
+                "suffix",
+                vec![],
+                PathMatch::EndsWith {
+                    ends_with: ".json".to_string(),
+                },
+            ),
+            lenient,
+            create_test_rule("any", vec!["DELETE"], PathMatch::Any),
+        ]
+        .into_iter()
+        .map(|rule| CompiledRule::compile(rule).unwrap())
+        .collect();
+        let paths = RulePathSet::new(&rules).unwrap();
+        let headers = HeaderMap::new();
+
+        for (method, path) in [
+            (Method::POST, "/api/orders"),
+            (Method::GET, "/api/orders"),
+            (Method::GET, "/items/42"),
+            (Method::GET, "/items/abc"),
+            (Method::GET, "/data.json"),
+            (Method::GET, "/health"),
+            (Method::DELETE, "/anything"),
+            (Method::GET, "/nothing"),
+        ] {
+            let uri: Uri = format!("http://localhost{path}").parse().unwrap();
+            let linear = find_matching_rule(&rules, &method, &uri, &headers).map(|r| &r.id);
+            let indexed =
+                find_matching_rule_indexed(&rules, &paths, &method, &uri, &headers).map(|r| &r.id);
+            assert_eq!(linear, indexed, "{method} {path}");
+        }
+    }
 }
@@ -5,8 +5,8 @@
 //! Tracks fault injection activity, script execution, and proxy performance.
 use lazy_static::lazy_static;
 use prometheus::{
-    register_counter_vec, register_gauge_vec, register_histogram_vec, CounterVec, Encoder,
-    GaugeVec, HistogramVec, TextEncoder,
+    register_counter_vec, register_gauge, register_gauge_vec, register_histogram_vec, CounterVec,
+    Encoder, Gauge, GaugeVec, HistogramVec, TextEncoder,
 };
 
 lazy_static! {
@@ -92,6 +92,82 @@ lazy_static! {
         &["rule_id", "error_type"]  // error_type: syntax|runtime|flow_state
     )
     .unwrap();
+
+    /// Decision cache lookups for script rules
+    pub static ref DECISION_CACHE_LOOKUPS_TOTAL: CounterVec = register_counter_vec!(
+        "rift_decision_cache_lookups_total",
+        "Total number of script decision cache lookups",
+        &["result"]  // result: hit|miss
+    )
+    .unwrap();
+
+    /// Decision cache hit rate (0.0 to 1.0)
+    pub static ref DECISION_CACHE_HIT_RATE: Gauge = register_gauge!(
+        "rift_decision_cache_hit_rate",
+        "Ratio of script decision cache hits to total lookups"
+    )
+    .unwrap();
+
+    /// Requests matched by each fault or script rule
+    pub static ref RULE_MATCHES_TOTAL: CounterVec = register_counter_vec!(
+        "rift_rule_matches_total",
+        "Total number of requests matched by each rule",
+        &["rule"]
+    )
+    .unwrap();
+
+    /// Requests routed by each route
+    pub static ref ROUTE_MATCHES_TOTAL: CounterVec = register_counter_vec!(
+        "rift_route_matches_total",
+        "Total number of requests routed by each route",
+        &["route"]
+    )
+    .unwrap();
+
+    /// Open upstream connections waiting in the pool
+    pub static ref UPSTREAM_POOL_IDLE: GaugeVec = register_gauge_vec!(
+        "rift_upstream_pool_idle",
+        "Number of idle pooled connections to each upstream host",
+        &["host"]
+    )
+    .unwrap();
+
+    /// Upstream requests holding (or waiting for) a connection
+    pub static ref UPSTREAM_POOL_ACTIVE: GaugeVec = register_gauge_vec!(
+        "rift_upstream_pool_active",
+        "Number of in-flight requests to each upstream host",
+        &["host"]
+    )
+    .unwrap();
+
+    /// Decision cache entry count
+    pub static ref DECISION_CACHE_SIZE: Gauge = register_gauge!(
+        "rift_decision_cache_size",
+        "Number of entries in the script decision cache"
+    )
+    .unwrap();
+
+    /// Response cache lookups for forwarded GET requests
+    pub static ref RESPONSE_CACHE_LOOKUPS_TOTAL: CounterVec = register_counter_vec!(
+        "rift_response_cache_lookups_total",
+        "Total number of upstream response cache lookups",
+        &["result"]  // result: hit|miss
+    )
+    .unwrap();
+
+    /// Response cache hit rate (0.0 to 1.0)
+    pub static ref RESPONSE_CACHE_HIT_RATE: Gauge = register_gauge!(
+        "rift_response_cache_hit_rate",
+        "Ratio of upstream response cache hits to total lookups"
+    )
+    .unwrap();
+
+    /// Response cache entry count
+    pub static ref RESPONSE_CACHE_SIZE: Gauge = register_gauge!(
+        "rift_response_cache_size",
+        "Number of entries in the upstream response cache"
+    )
+    .unwrap();
 }
 
 /// Collect and return all metrics in Prometheus text format
@@ -182,6 +258,16 @@ pub fn record_upstream_duration(method: &str, status: u16, duration_ms: f64) {
         .observe(duration_ms);
 }
 
+/// Helper to set the upstream connection pool gauges for one host
+pub fn set_upstream_pool(host: &str, idle: i64, active: i64) {
+    UPSTREAM_POOL_IDLE
+        .with_label_values(&[host])
+        .set(idle as f64);
+    UPSTREAM_POOL_ACTIVE
+        .with_label_values(&[host])
+        .set(active as f64);
+}
+
 /// Helper to record script error
 pub fn record_script_error(rule_id: &str, error_type: &str) {
     SCRIPT_ERRORS_TOTAL
@@ -189,6 +275,50 @@ pub fn record_script_error(rule_id: &str, error_type: &str) {
         .inc();
 }
 
+/// Helper to record a decision cache lookup and refresh the cache gauges
+pub fn record_decision_cache_lookup(hit: bool, hit_rate: f64, size: usize) {
+    let result = if hit { "hit" } else { "miss" };
+    DECISION_CACHE_LOOKUPS_TOTAL
+        .with_label_values(&[result])
+        .inc();
+    DECISION_CACHE_HIT_RATE.set(hit_rate);
+    DECISION_CACHE_SIZE.set(size as f64);
+}
+
+/// Helper to record a response cache lookup and refresh the cache gauges
+pub fn record_response_cache_lookup(hit: bool, hit_rate: f64, size: usize) {
+    let result = if hit { "hit" } else { "miss" };
+    RESPONSE_CACHE_LOOKUPS_TOTAL
+        .with_label_values(&[result])
+        .inc();
+    RESPONSE_CACHE_HIT_RATE.set(hit_rate);
+    RESPONSE_CACHE_SIZE.set(size as f64);
+}
+
+/// Helper to record a request matching a rule
+pub fn record_rule_match(rule_id: &str) {
+    RULE_MATCHES_TOTAL.with_label_values(&[rule_id]).inc();
+}
+
+/// Helper to record a route being selected
+pub fn record_route_match(route: &str) {
+    ROUTE_MATCHES_TOTAL.with_label_values(&[route]).inc();
+}
+
+/// Export zero-valued match counters for the given rules and routes, so
+/// ones that never fire still show up in `/metrics`
+pub fn init_match_counters<'a>(
+    rule_ids: impl IntoIterator<Item = &'a str>,
+    routes: impl IntoIterator<Item = &'a str>,
+) {
+    for rule_id in rule_ids {
+        RULE_MATCHES_TOTAL.with_label_values(&[rule_id]);
+    }
+    for route in routes {
+        ROUTE_MATCHES_TOTAL.with_label_values(&[route]);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,6 +361,17 @@ mod tests {
         assert!(metrics.contains("rift_active_flows"));
     }
 
+    #[test]
+    fn test_decision_cache_metrics() {
+        record_decision_cache_lookup(false, 0.0, 1);
+        record_decision_cache_lookup(true, 0.5, 1);
+
+        let metrics = collect_metrics();
+        assert!(metrics.contains("rift_decision_cache_lookups_total"));
+        assert!(metrics.contains("rift_decision_cache_hit_rate"));
+        assert!(metrics.contains("rift_decision_cache_size"));
+    }
+
     // ============================================
     // Additional tests for expanded coverage
     // ============================================
@@ -452,4 +593,28 @@ mod tests {
         let metrics = collect_metrics();
         assert!(metrics.contains("rift_script_execution_duration_ms"));
     }
+
+    #[test]
+    fn test_rule_and_route_match_counters() {
+        init_match_counters(["never-matching-rule"], ["idle-route"]);
+        let metrics = collect_metrics();
+        assert!(metrics.contains(r#"rift_rule_matches_total{rule="never-matching-rule"} 0"#));
+        assert!(metrics.contains(r#"rift_route_matches_total{route="idle-route"} 0"#));
+
+        let before = RULE_MATCHES_TOTAL
+            .with_label_values(&["metrics-test-rule"])
+            .get();
+        record_rule_match("metrics-test-rule");
+        record_rule_match("metrics-test-rule");
+        record_route_match("metrics-test-route");
+        assert_eq!(
+            RULE_MATCHES_TOTAL
+                .with_label_values(&["metrics-test-rule"])
+                .get(),
+            before + 2.0
+        );
+        assert!(
+            collect_metrics().contains(r#"rift_route_matches_total{route="metrics-test-route"}"#)
+        );
+    }
 }
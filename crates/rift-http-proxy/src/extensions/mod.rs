@@ -12,11 +12,15 @@
 //! - **Stub Analysis** (`stub_analysis`): Conflict detection and overlap warnings
 //! - **Template** (`template`): Response body templating with request data
 //! - **Routing** (`routing`): Multi-upstream routing for reverse proxy mode
+//! - **Retry Detection** (`retry`): Idempotency-key based retry matching
+//! - **Rate Limiting** (`rate_limit`): Token-bucket counters for the rate-limit fault
 
 pub mod fault;
 pub mod flow_state;
 pub mod matcher;
 pub mod metrics;
+pub mod rate_limit;
+pub mod retry;
 pub mod routing;
 pub mod rule_index;
 pub mod stub_analysis;
@@ -24,7 +28,9 @@ pub mod template;
 
 // Re-export commonly used types for library consumers
 #[allow(unused_imports)]
-pub use fault::{create_error_response, decide_fault, FaultDecision};
+pub use fault::{
+    create_error_response, decide_fault, decide_fault_with_rng, FaultDecision, FaultRng,
+};
 #[allow(unused_imports)]
 pub use flow_state::{create_flow_store, FlowStore, NoOpFlowStore};
 #[allow(unused_imports)]
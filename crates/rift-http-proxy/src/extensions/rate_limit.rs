@@ -0,0 +1,174 @@
+//! Token-bucket counters for the rate-limit fault.
+//!
+//! Each value of the configured key gets a bucket holding up to `requests`
+//! tokens, refilled evenly over `per_secs`. A request takes a token; with
+//! none left it is rejected, and told how long until the next token.
+
+use crate::config::{RateLimitFault, RateLimitKey};
+use hyper::{HeaderMap, Method, Uri};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// Buckets kept before full ones are dropped; a full bucket behaves the
+/// same as a missing one
+const MAX_BUCKETS: usize = 10_000;
+
+pub struct RateLimiter {
+    key: RateLimitKey,
+    capacity: f64,
+    /// Tokens regained per second
+    refill_rate: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(fault: &RateLimitFault) -> Self {
+        // Validation rejects zeroes, but never divide by zero on a live request
+        let capacity = f64::from(fault.requests.max(1));
+        Self {
+            key: fault.key.clone(),
+            capacity,
+            refill_rate: capacity / fault.per_secs.max(1) as f64,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request, returning how long the client should wait if it is
+    /// over the limit
+    pub fn check(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        client_ip: Option<IpAddr>,
+    ) -> Option<Duration> {
+        let key = match &self.key {
+            RateLimitKey::Ip => client_ip.map(|ip| ip.to_string()).unwrap_or_default(),
+            RateLimitKey::Method => method.to_string(),
+            RateLimitKey::Path => uri.path().to_string(),
+            RateLimitKey::Header(name) => headers
+                .get(name.as_str())
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string(),
+        };
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: String, now: Instant) -> Option<Duration> {
+        let mut buckets = self.buckets.lock();
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(&key) {
+            buckets.retain(|_, bucket| self.tokens_at(bucket, now) < self.capacity);
+        }
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+        });
+        bucket.tokens = self.tokens_at(bucket, now);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.refill_rate,
+            ))
+        }
+    }
+
+    fn tokens_at(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.refill_rate).min(self.capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(requests: u32, per_secs: u64) -> RateLimiter {
+        RateLimiter::new(&RateLimitFault {
+            requests,
+            per_secs,
+            key: RateLimitKey::Path,
+        })
+    }
+
+    #[test]
+    fn test_limit_triggers_after_requests() {
+        let limiter = limiter(3, 10);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at("/a".into(), now), None);
+        }
+        let retry_after = limiter.check_at("/a".into(), now).unwrap();
+        assert!(retry_after > Duration::from_secs(3), "{retry_after:?}");
+        assert!(retry_after <= Duration::from_secs(4), "{retry_after:?}");
+        // Other keys have their own budget
+        assert_eq!(limiter.check_at("/b".into(), now), None);
+    }
+
+    #[test]
+    fn test_limit_resets_after_window() {
+        let limiter = limiter(2, 1);
+        let start = Instant::now();
+        assert_eq!(limiter.check_at("/a".into(), start), None);
+        assert_eq!(limiter.check_at("/a".into(), start), None);
+        assert!(limiter.check_at("/a".into(), start).is_some());
+
+        // Half the window regains one request
+        let half = start + Duration::from_millis(500);
+        assert_eq!(limiter.check_at("/a".into(), half), None);
+        assert!(limiter.check_at("/a".into(), half).is_some());
+
+        // A whole idle window restores the full budget, and no more
+        let later = half + Duration::from_secs(5);
+        assert_eq!(limiter.check_at("/a".into(), later), None);
+        assert_eq!(limiter.check_at("/a".into(), later), None);
+        assert!(limiter.check_at("/a".into(), later).is_some());
+    }
+
+    #[test]
+    fn test_key_dimensions() {
+        let uri: Uri = "/orders".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", "alpha".parse().unwrap());
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let by_header = RateLimiter::new(&RateLimitFault {
+            requests: 1,
+            per_secs: 60,
+            key: RateLimitKey::Header("x-api-key".to_string()),
+        });
+        assert_eq!(
+            by_header.check(&Method::GET, &uri, &headers, Some(ip)),
+            None
+        );
+        assert!(by_header
+            .check(&Method::POST, &uri, &headers, None)
+            .is_some());
+        headers.insert("x-api-key", "beta".parse().unwrap());
+        assert_eq!(
+            by_header.check(&Method::GET, &uri, &headers, Some(ip)),
+            None
+        );
+
+        let by_method = RateLimiter::new(&RateLimitFault {
+            requests: 1,
+            per_secs: 60,
+            key: RateLimitKey::Method,
+        });
+        assert_eq!(by_method.check(&Method::GET, &uri, &headers, None), None);
+        assert_eq!(by_method.check(&Method::POST, &uri, &headers, None), None);
+        assert!(by_method
+            .check(&Method::GET, &uri, &headers, None)
+            .is_some());
+    }
+}
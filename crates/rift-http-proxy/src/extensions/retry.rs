@@ -0,0 +1,145 @@
+//! Retry detection via idempotency keys.
+//!
+//! A request is a retry when its idempotency key header has already been
+//! seen within the configured window. Sightings are tracked in the flow
+//! store, so detection works across instances when Redis is used.
+
+use crate::extensions::flow_state::FlowStore;
+use hyper::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Matcher for requests that repeat a previously seen idempotency key.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct RetryMatcher {
+    /// Header carrying the idempotency key
+    #[serde(default = "default_retry_header")]
+    pub header: String,
+    /// How long a key is remembered, in seconds
+    #[serde(default = "default_retry_window_seconds")]
+    pub window_seconds: i64,
+}
+
+fn default_retry_header() -> String {
+    "Idempotency-Key".to_string()
+}
+
+fn default_retry_window_seconds() -> i64 {
+    300
+}
+
+impl Default for RetryMatcher {
+    fn default() -> Self {
+        Self {
+            header: default_retry_header(),
+            window_seconds: default_retry_window_seconds(),
+        }
+    }
+}
+
+/// Per-request retry detection.
+///
+/// Each idempotency key is recorded at most once per request, so several
+/// rules using `is_retry` see a consistent answer.
+pub struct RetryTracker<'a> {
+    flow_store: &'a dyn FlowStore,
+    headers: &'a HeaderMap,
+    seen: HashMap<String, bool>,
+}
+
+impl<'a> RetryTracker<'a> {
+    pub fn new(flow_store: &'a dyn FlowStore, headers: &'a HeaderMap) -> Self {
+        Self {
+            flow_store,
+            headers,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Whether this request repeats an idempotency key seen before.
+    ///
+    /// Requests without the header are never retries.
+    pub fn is_retry(&mut self, matcher: &RetryMatcher) -> bool {
+        let header = matcher.header.to_lowercase();
+        if let Some(&is_retry) = self.seen.get(&header) {
+            return is_retry;
+        }
+
+        let Some(key) = self
+            .headers
+            .get(header.as_str())
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| !v.is_empty())
+        else {
+            return false;
+        };
+
+        let flow_id = format!("idempotency:{header}:{key}");
+        let is_retry = match self.flow_store.increment(&flow_id, "seen") {
+            Ok(count) => {
+                if let Err(e) = self.flow_store.set_ttl(&flow_id, matcher.window_seconds) {
+                    warn!("Failed to set idempotency key TTL: {}", e);
+                }
+                count > 1
+            }
+            Err(e) => {
+                warn!("Failed to track idempotency key: {}", e);
+                false
+            }
+        };
+
+        self.seen.insert(header, is_retry);
+        is_retry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::InMemoryFlowStore;
+
+    fn headers_with_key(key: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("idempotency-key", key.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_second_request_with_same_key_is_retry() {
+        let store = InMemoryFlowStore::new(300);
+        let matcher = RetryMatcher::default();
+
+        let first = headers_with_key("order-1");
+        assert!(!RetryTracker::new(&store, &first).is_retry(&matcher));
+
+        let second = headers_with_key("order-1");
+        assert!(RetryTracker::new(&store, &second).is_retry(&matcher));
+
+        let other = headers_with_key("order-2");
+        assert!(!RetryTracker::new(&store, &other).is_retry(&matcher));
+    }
+
+    #[test]
+    fn test_missing_key_is_not_retry() {
+        let store = InMemoryFlowStore::new(300);
+        let headers = HeaderMap::new();
+        let mut tracker = RetryTracker::new(&store, &headers);
+        assert!(!tracker.is_retry(&RetryMatcher::default()));
+        assert!(!tracker.is_retry(&RetryMatcher::default()));
+    }
+
+    #[test]
+    fn test_key_recorded_once_per_request() {
+        let store = InMemoryFlowStore::new(300);
+        let matcher = RetryMatcher::default();
+        let headers = headers_with_key("order-1");
+
+        // Several rules checking the same request agree it's a first attempt
+        let mut tracker = RetryTracker::new(&store, &headers);
+        assert!(!tracker.is_retry(&matcher));
+        assert!(!tracker.is_retry(&matcher));
+
+        assert!(RetryTracker::new(&store, &headers).is_retry(&matcher));
+    }
+}
@@ -1,10 +1,121 @@
-use crate::config::{HeaderMatch, HostMatch, Route};
+use crate::config::{
+    AffinityKey, ForwardHeaders, HeaderMatch, HostMatch, PathRewrite, ResponseHeaders, Route,
+    Upstream, UpstreamGroup,
+};
+use crate::predicate::build_regex;
+use hyper::header::COOKIE;
 use hyper::Request;
+use parking_lot::{Mutex, RwLock};
 use regex::Regex;
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::net::IpAddr;
+use std::time::Duration;
 
 /// Router matches incoming requests to upstream services
 pub struct Router {
     routes: Vec<CompiledRoute>,
+    groups: HashMap<String, CompiledGroup>,
+    unhealthy: RwLock<HashSet<String>>,
+}
+
+struct CompiledGroup {
+    members: WeightedRoundRobin,
+    affinity: Option<AffinityKey>,
+}
+
+impl CompiledGroup {
+    /// The request's affinity key value, if the group has one configured
+    /// and the request carries it
+    fn affinity_value<B>(&self, req: &Request<B>, client_ip: Option<IpAddr>) -> Option<String> {
+        match self.affinity.as_ref()? {
+            AffinityKey::Cookie(name) => req
+                .headers()
+                .get_all(COOKIE)
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(';'))
+                .find_map(|pair| {
+                    let (key, value) = pair.trim().split_once('=')?;
+                    (key == name).then(|| value.to_string())
+                }),
+            AffinityKey::Header(name) => req
+                .headers()
+                .get(name.as_str())
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+            AffinityKey::ClientIp => client_ip.map(|ip| ip.to_string()),
+        }
+    }
+}
+
+/// Smooth weighted round-robin over a fixed set of upstreams.
+///
+/// Over any window of `sum(weights)` selections each member is picked
+/// exactly `weight` times, interleaved rather than in bursts.
+pub struct WeightedRoundRobin {
+    members: Vec<(String, i64)>,
+    current: Mutex<Vec<i64>>,
+}
+
+impl WeightedRoundRobin {
+    pub fn new(members: Vec<(String, u32)>) -> Self {
+        let current = vec![0; members.len()];
+        Self {
+            members: members
+                .into_iter()
+                .map(|(name, weight)| (name, i64::from(weight)))
+                .collect(),
+            current: Mutex::new(current),
+        }
+    }
+
+    /// Pick the next member, considering only those `is_available` accepts.
+    /// Returns None if no available member has a positive weight.
+    pub fn select(&self, is_available: impl Fn(&str) -> bool) -> Option<&str> {
+        let mut current = self.current.lock();
+        let mut total = 0;
+        let mut best: Option<usize> = None;
+
+        for (idx, (name, weight)) in self.members.iter().enumerate() {
+            if *weight == 0 || !is_available(name) {
+                continue;
+            }
+            current[idx] += weight;
+            total += weight;
+            if best.is_none_or(|b| current[idx] > current[b]) {
+                best = Some(idx);
+            }
+        }
+
+        let best = best?;
+        current[best] -= total;
+        Some(&self.members[best].0)
+    }
+
+    /// Pick the member `key` is pinned to, considering only those
+    /// `is_available` accepts.
+    ///
+    /// Uses weighted rendezvous hashing: every key ranks the members in its
+    /// own fixed order, so a key keeps its member until that member becomes
+    /// unavailable, and only the keys of that member move elsewhere. Keys
+    /// spread across members in proportion to their weights.
+    pub fn select_for_key(&self, key: &str, is_available: impl Fn(&str) -> bool) -> Option<&str> {
+        self.members
+            .iter()
+            .filter(|(name, weight)| *weight > 0 && is_available(name))
+            .map(|(name, weight)| {
+                let mut hasher = DefaultHasher::new();
+                (key, name).hash(&mut hasher);
+                // Uniform in (0, 1), from the top 53 bits of the hash
+                let unit = ((hasher.finish() >> 11) as f64 + 0.5) / (1u64 << 53) as f64;
+                (name, -(*weight as f64) / unit.ln())
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(name, _)| name.as_str())
+    }
 }
 
 struct CompiledRoute {
@@ -15,6 +126,72 @@ struct CompiledRoute {
     path_exact: Option<String>,
     path_regex: Option<Regex>,
     headers: Vec<HeaderMatch>,
+    rewrite: Option<PathRewriter>,
+    forward_headers: ForwardHeaders,
+    request_timeout: Option<Duration>,
+    response_headers: ResponseHeaders,
+}
+
+/// Upstream, path rewrite and header changes selected for a request
+pub struct RouteTarget<'a> {
+    /// Name of the matched route
+    pub route: &'a str,
+    pub upstream: &'a str,
+    pub rewrite: Option<&'a PathRewriter>,
+    pub forward_headers: &'a ForwardHeaders,
+    /// Overrides `connection_pool.request_timeout_secs`
+    pub request_timeout: Option<Duration>,
+}
+
+/// Compiled route path rewrite
+pub struct PathRewriter {
+    strip_prefix: Option<String>,
+    replace: Option<(Regex, String)>,
+}
+
+impl PathRewriter {
+    fn compile(rewrite: PathRewrite, route_name: &str) -> Result<Self, String> {
+        let replace = rewrite
+            .replace
+            .map(|replace| {
+                build_regex(&replace.from_regex)
+                    .map(|regex| (regex, replace.to))
+                    .map_err(|e| format!("Invalid rewrite regex in route '{route_name}': {e}"))
+            })
+            .transpose()?;
+        Ok(Self {
+            strip_prefix: rewrite
+                .strip_prefix
+                .map(|prefix| prefix.trim_end_matches('/').to_string()),
+            replace,
+        })
+    }
+
+    /// Rewrite a request path, returning None if the path is unchanged.
+    ///
+    /// `strip_prefix` only removes whole segments: "/api" strips "/api/users"
+    /// and "/api" but not "/apix".
+    pub fn apply(&self, path: &str) -> Option<String> {
+        let mut rewritten = Cow::Borrowed(path);
+
+        if let Some(prefix) = &self.strip_prefix {
+            if let Some(rest) = rewritten.strip_prefix(prefix.as_str()) {
+                if rest.is_empty() {
+                    rewritten = Cow::Borrowed("/");
+                } else if rest.starts_with('/') {
+                    rewritten = Cow::Owned(rest.to_string());
+                }
+            }
+        }
+
+        if let Some((regex, to)) = &self.replace {
+            if let Cow::Owned(replaced) = regex.replace(&rewritten, to.as_str()) {
+                rewritten = Cow::Owned(replaced);
+            }
+        }
+
+        (rewritten != path).then(|| rewritten.into_owned())
+    }
 }
 
 enum CompiledHost {
@@ -31,19 +208,121 @@ impl Router {
             compiled.push(compile_route(route)?);
         }
 
-        Ok(Router { routes: compiled })
+        Ok(Router {
+            routes: compiled,
+            groups: HashMap::new(),
+            unhealthy: RwLock::new(HashSet::new()),
+        })
+    }
+
+    /// Register upstream groups that routes can target by name.
+    ///
+    /// Member weights are taken from `upstreams`.
+    pub fn with_upstream_groups(
+        mut self,
+        groups: &[UpstreamGroup],
+        upstreams: &[Upstream],
+    ) -> Result<Self, String> {
+        for group in groups {
+            let members = group
+                .upstreams
+                .iter()
+                .map(|member| {
+                    upstreams
+                        .iter()
+                        .find(|u| &u.name == member)
+                        .map(|u| (u.name.clone(), u.weight))
+                        .ok_or_else(|| {
+                            format!(
+                                "Upstream group '{}' references unknown upstream '{}'",
+                                group.name, member
+                            )
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            self.groups.insert(
+                group.name.clone(),
+                CompiledGroup {
+                    members: WeightedRoundRobin::new(members),
+                    affinity: group.affinity.clone(),
+                },
+            );
+        }
+        Ok(self)
+    }
+
+    /// Record health-check state for an upstream.
+    ///
+    /// Unhealthy upstreams are skipped when selecting from a group.
+    pub fn set_upstream_health(&self, name: &str, healthy: bool) {
+        let mut unhealthy = self.unhealthy.write();
+        if healthy {
+            unhealthy.remove(name);
+        } else {
+            unhealthy.insert(name.to_string());
+        }
+    }
+
+    /// Names of all routes, in match order
+    pub fn route_names(&self) -> impl Iterator<Item = &str> {
+        self.routes.iter().map(|route| route.name.as_str())
+    }
+
+    /// Name and target (upstream or group) of the first route matching the
+    /// request, without picking a group member
+    pub fn find_route<B>(&self, req: &Request<B>) -> Option<(&str, &str)> {
+        self.routes
+            .iter()
+            .find(|route| matches_route(req, route))
+            .map(|route| (route.name.as_str(), route.upstream.as_str()))
+    }
+
+    /// Response headers of the first route matching the request, if it
+    /// configures any
+    pub fn response_headers<B>(&self, req: &Request<B>) -> Option<&ResponseHeaders> {
+        self.routes
+            .iter()
+            .find(|route| matches_route(req, route))
+            .map(|route| &route.response_headers)
+            .filter(|headers| !headers.is_empty())
     }
 
     /// Match a request to an upstream service name
     /// Returns the upstream name if matched, None if no match
+    /// (or if every member of the matched group is unhealthy)
     pub fn match_request<B>(&self, req: &Request<B>) -> Option<&str> {
+        self.match_route(req, None).map(|target| target.upstream)
+    }
+
+    /// Match a request to an upstream and the route's path rewrite.
+    ///
+    /// `client_ip` is only used by groups with client IP affinity.
+    pub fn match_route<B>(
+        &self,
+        req: &Request<B>,
+        client_ip: Option<IpAddr>,
+    ) -> Option<RouteTarget<'_>> {
         // First-match-wins algorithm
-        for route in &self.routes {
-            if matches_route(req, route) {
-                return Some(&route.upstream);
+        let route = self.routes.iter().find(|route| matches_route(req, route))?;
+
+        let upstream = match self.groups.get(&route.upstream) {
+            Some(group) => {
+                let unhealthy = self.unhealthy.read();
+                let is_available = |name: &str| !unhealthy.contains(name);
+                match group.affinity_value(req, client_ip) {
+                    Some(key) => group.members.select_for_key(&key, is_available)?,
+                    None => group.members.select(is_available)?,
+                }
             }
-        }
-        None
+            None => &route.upstream,
+        };
+        Some(RouteTarget {
+            route: &route.name,
+            upstream,
+            rewrite: route.rewrite.as_ref(),
+            forward_headers: &route.forward_headers,
+            request_timeout: route.request_timeout,
+        })
     }
 }
 
@@ -54,14 +333,23 @@ fn compile_route(route: Route) -> Result<CompiledRoute, String> {
     });
 
     let path_regex = if let Some(pattern) = &route.match_config.path_regex {
-        let regex = Regex::new(pattern)
+        let regex = build_regex(pattern)
             .map_err(|e| format!("Invalid path regex in route '{}': {}", route.name, e))?;
         Some(regex)
     } else {
         None
     };
 
+    let rewrite = route
+        .rewrite
+        .map(|rewrite| PathRewriter::compile(rewrite, &route.name))
+        .transpose()?;
+
     Ok(CompiledRoute {
+        rewrite,
+        forward_headers: route.forward_headers,
+        request_timeout: route.request_timeout_secs.map(Duration::from_secs),
+        response_headers: route.response_headers,
         name: route.name,
         upstream: route.upstream,
         host,
@@ -148,6 +436,10 @@ mod tests {
                 ..Default::default()
             },
             upstream: "api-service".to_string(),
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
         }];
 
         let router = Router::new(routes).unwrap();
@@ -176,6 +468,10 @@ mod tests {
                 ..Default::default()
             },
             upstream: "health-service".to_string(),
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
         }];
 
         let router = Router::new(routes).unwrap();
@@ -204,6 +500,10 @@ mod tests {
                 ..Default::default()
             },
             upstream: "user-service".to_string(),
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
         }];
 
         let router = Router::new(routes).unwrap();
@@ -232,6 +532,10 @@ mod tests {
                 ..Default::default()
             },
             upstream: "api-service".to_string(),
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
         }];
 
         let router = Router::new(routes).unwrap();
@@ -262,6 +566,10 @@ mod tests {
                 ..Default::default()
             },
             upstream: "wildcard-service".to_string(),
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
         }];
 
         let router = Router::new(routes).unwrap();
@@ -293,6 +601,10 @@ mod tests {
                 ..Default::default()
             },
             upstream: "v2-service".to_string(),
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
         }];
 
         let router = Router::new(routes).unwrap();
@@ -324,6 +636,10 @@ mod tests {
                     ..Default::default()
                 },
                 upstream: "users-service".to_string(),
+                rewrite: None,
+                forward_headers: Default::default(),
+                request_timeout_secs: None,
+                response_headers: Default::default(),
             },
             Route {
                 name: "general".to_string(),
@@ -332,6 +648,10 @@ mod tests {
                     ..Default::default()
                 },
                 upstream: "api-service".to_string(),
+                rewrite: None,
+                forward_headers: Default::default(),
+                request_timeout_secs: None,
+                response_headers: Default::default(),
             },
         ];
 
@@ -360,6 +680,10 @@ mod tests {
                 ..Default::default()
             },
             upstream: "secure-v2-service".to_string(),
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
         }];
 
         let router = Router::new(routes).unwrap();
@@ -381,4 +705,305 @@ mod tests {
 
         assert_eq!(router.match_request(&req2), None);
     }
+
+    fn weighted(name: &str, weight: u32) -> Upstream {
+        Upstream {
+            name: name.to_string(),
+            url: format!("http://{name}:8000"),
+            health_check: None,
+            tls_skip_verify: false,
+            tls: Default::default(),
+            weight,
+            retry: None,
+            connection_pool: None,
+        }
+    }
+
+    fn group_router() -> Router {
+        affinity_router(None)
+    }
+
+    fn affinity_router(affinity: Option<AffinityKey>) -> Router {
+        let routes = vec![Route {
+            name: "api".to_string(),
+            match_config: RouteMatch {
+                path_prefix: Some("/api".to_string()),
+                ..Default::default()
+            },
+            upstream: "api-pool".to_string(),
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
+        }];
+        let groups = vec![UpstreamGroup {
+            name: "api-pool".to_string(),
+            upstreams: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            affinity,
+        }];
+        let upstreams = vec![weighted("a", 5), weighted("b", 3), weighted("c", 2)];
+        Router::new(routes)
+            .unwrap()
+            .with_upstream_groups(&groups, &upstreams)
+            .unwrap()
+    }
+
+    fn distribution(router: &Router, requests: usize) -> HashMap<String, usize> {
+        let req = Request::builder()
+            .uri("http://example.com/api/users")
+            .body(())
+            .unwrap();
+        let mut counts = HashMap::new();
+        for _ in 0..requests {
+            let name = router.match_request(&req).unwrap().to_string();
+            *counts.entry(name).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_weighted_round_robin_interleaves() {
+        let wrr = WeightedRoundRobin::new(vec![("a".to_string(), 2), ("b".to_string(), 1)]);
+        let picks: Vec<&str> = (0..6).map(|_| wrr.select(|_| true).unwrap()).collect();
+        assert_eq!(picks, ["a", "b", "a", "a", "b", "a"]);
+    }
+
+    #[test]
+    fn test_group_distribution_matches_weights() {
+        let counts = distribution(&group_router(), 10_000);
+        assert_eq!(counts["a"], 5_000);
+        assert_eq!(counts["b"], 3_000);
+        assert_eq!(counts["c"], 2_000);
+    }
+
+    #[test]
+    fn test_group_skips_unhealthy_upstreams() {
+        let router = group_router();
+        router.set_upstream_health("a", false);
+        let counts = distribution(&router, 1_000);
+        assert!(!counts.contains_key("a"));
+        assert_eq!(counts["b"], 600);
+        assert_eq!(counts["c"], 400);
+
+        router.set_upstream_health("b", false);
+        router.set_upstream_health("c", false);
+        let req = Request::builder()
+            .uri("http://example.com/api/users")
+            .body(())
+            .unwrap();
+        assert_eq!(router.match_request(&req), None);
+
+        router.set_upstream_health("a", true);
+        assert_eq!(router.match_request(&req), Some("a"));
+    }
+
+    fn keyed_request(header: &str, value: &str) -> Request<()> {
+        Request::builder()
+            .uri("http://example.com/api/users")
+            .header(header, value)
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_affinity_pins_key_to_one_upstream() {
+        let router = affinity_router(Some(AffinityKey::Header("x-user-id".to_string())));
+        let mut seen = HashSet::new();
+        for user in 0..100 {
+            let req = keyed_request("x-user-id", &format!("user-{user}"));
+            let first = router.match_request(&req).unwrap().to_string();
+            for _ in 0..20 {
+                assert_eq!(router.match_request(&req), Some(first.as_str()));
+            }
+            seen.insert(first);
+        }
+        // Different keys spread across the group
+        assert_eq!(seen.len(), 3);
+    }
+
+    #[test]
+    fn test_affinity_cookie_key() {
+        let router = affinity_router(Some(AffinityKey::Cookie("session_id".to_string())));
+        let req = keyed_request("cookie", "theme=dark; session_id=abc123");
+        let pinned = router.match_request(&req).unwrap().to_string();
+        // Only the configured cookie matters
+        let req = keyed_request("cookie", "session_id=abc123; theme=light");
+        for _ in 0..20 {
+            assert_eq!(router.match_request(&req), Some(pinned.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_affinity_client_ip_key() {
+        let router = affinity_router(Some(AffinityKey::ClientIp));
+        let req = Request::builder()
+            .uri("http://example.com/api/users")
+            .body(())
+            .unwrap();
+        let ip: IpAddr = "10.1.2.3".parse().unwrap();
+        let pinned = router.match_route(&req, Some(ip)).unwrap().upstream;
+        for _ in 0..20 {
+            assert_eq!(router.match_route(&req, Some(ip)).unwrap().upstream, pinned);
+        }
+    }
+
+    #[test]
+    fn test_affinity_without_key_uses_weights() {
+        let router = affinity_router(Some(AffinityKey::Header("x-user-id".to_string())));
+        let counts = distribution(&router, 10_000);
+        assert_eq!(counts["a"], 5_000);
+        assert_eq!(counts["b"], 3_000);
+        assert_eq!(counts["c"], 2_000);
+    }
+
+    #[test]
+    fn test_affinity_moves_off_unhealthy_upstream() {
+        let router = affinity_router(Some(AffinityKey::Header("x-user-id".to_string())));
+        let req = keyed_request("x-user-id", "user-1");
+        let pinned = router.match_request(&req).unwrap().to_string();
+
+        router.set_upstream_health(&pinned, false);
+        let fallback = router.match_request(&req).unwrap().to_string();
+        assert_ne!(fallback, pinned);
+        for _ in 0..20 {
+            assert_eq!(router.match_request(&req), Some(fallback.as_str()));
+        }
+
+        router.set_upstream_health(&pinned, true);
+        assert_eq!(router.match_request(&req), Some(pinned.as_str()));
+    }
+
+    #[test]
+    fn test_select_for_key_follows_weights() {
+        let wrr = WeightedRoundRobin::new(vec![("a".to_string(), 3), ("b".to_string(), 1)]);
+        let mut counts = HashMap::new();
+        for key in 0..4_000 {
+            let name = wrr.select_for_key(&key.to_string(), |_| true).unwrap();
+            *counts.entry(name).or_insert(0) += 1;
+        }
+        assert!((2_700..3_300).contains(&counts["a"]), "{counts:?}");
+    }
+
+    fn rewriter(strip_prefix: Option<&str>, replace: Option<(&str, &str)>) -> PathRewriter {
+        PathRewriter::compile(
+            PathRewrite {
+                strip_prefix: strip_prefix.map(str::to_string),
+                replace: replace.map(|(from_regex, to)| crate::config::PathReplace {
+                    from_regex: from_regex.to_string(),
+                    to: to.to_string(),
+                }),
+            },
+            "test",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rewrite_strip_prefix() {
+        let rewrite = rewriter(Some("/api/"), None);
+        assert_eq!(rewrite.apply("/api/users/1"), Some("/users/1".to_string()));
+        assert_eq!(rewrite.apply("/api"), Some("/".to_string()));
+        // Partial segments and other paths pass through unchanged
+        assert_eq!(rewrite.apply("/apix/users"), None);
+        assert_eq!(rewrite.apply("/health"), None);
+    }
+
+    #[test]
+    fn test_rewrite_regex_replace() {
+        let rewrite = rewriter(None, Some((r"^/v(\d+)/(.*)$", "/api/$2/v$1")));
+        assert_eq!(
+            rewrite.apply("/v2/orders"),
+            Some("/api/orders/v2".to_string())
+        );
+        assert_eq!(rewrite.apply("/orders"), None);
+
+        // strip_prefix runs first, then the replacement
+        let rewrite = rewriter(Some("/svc"), Some(("^/old/", "/new/")));
+        assert_eq!(
+            rewrite.apply("/svc/old/item"),
+            Some("/new/item".to_string())
+        );
+    }
+
+    #[test]
+    fn test_match_route_returns_rewrite() {
+        let routes = vec![Route {
+            name: "api".to_string(),
+            match_config: RouteMatch {
+                path_prefix: Some("/api".to_string()),
+                ..Default::default()
+            },
+            upstream: "api-service".to_string(),
+            rewrite: Some(PathRewrite {
+                strip_prefix: Some("/api".to_string()),
+                replace: None,
+            }),
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
+        }];
+        let router = Router::new(routes).unwrap();
+
+        let req = Request::builder()
+            .uri("http://example.com/api/users")
+            .body(())
+            .unwrap();
+        let target = router.match_route(&req, None).unwrap();
+        assert_eq!(target.upstream, "api-service");
+        assert_eq!(
+            target.rewrite.unwrap().apply(req.uri().path()),
+            Some("/users".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_rewrite_regex_rejected() {
+        let routes = vec![Route {
+            name: "bad".to_string(),
+            match_config: RouteMatch::default(),
+            upstream: "svc".to_string(),
+            rewrite: Some(PathRewrite {
+                strip_prefix: None,
+                replace: Some(crate::config::PathReplace {
+                    from_regex: "(".to_string(),
+                    to: String::new(),
+                }),
+            }),
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
+        }];
+        assert!(Router::new(routes).is_err());
+    }
+
+    #[test]
+    fn test_match_route_returns_timeout_override() {
+        let route = |name: &str, prefix: &str, timeout| Route {
+            name: name.to_string(),
+            match_config: RouteMatch {
+                path_prefix: Some(prefix.to_string()),
+                ..Default::default()
+            },
+            upstream: "svc".to_string(),
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: timeout,
+            response_headers: Default::default(),
+        };
+        let router = Router::new(vec![
+            route("reports", "/reports", Some(120)),
+            route("default", "/", None),
+        ])
+        .unwrap();
+
+        let target = |path: &str| {
+            let req = Request::builder().uri(path).body(()).unwrap();
+            router.match_route(&req, None).map(|t| t.request_timeout)
+        };
+        assert_eq!(
+            target("/reports/daily"),
+            Some(Some(Duration::from_secs(120)))
+        );
+        assert_eq!(target("/users"), Some(None));
+    }
 }
@@ -23,7 +23,9 @@ use matchit::Router;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use crate::predicate::{CompiledRequestPredicate, PathMatcher, RequestPredicate};
+use crate::predicate::{
+    CompiledRequestPredicate, MethodMatcher, PathMatcher, RequestPredicate, StringMatcher,
+};
 
 /// Index entry storing rule ID and compiled predicate.
 #[derive(Debug, Clone)]
@@ -162,7 +164,14 @@ impl RuleIndex {
             }
 
             // Index by method if specified
-            if let Some(crate::predicate::StringMatcher::Equals(method)) = &predicate.method {
+            let methods = match &predicate.method {
+                Some(MethodMatcher::Matcher(StringMatcher::Equals(method))) => {
+                    std::slice::from_ref(method)
+                }
+                Some(MethodMatcher::OneOf(methods)) => methods.as_slice(),
+                _ => &[],
+            };
+            for method in methods {
                 index
                     .method_index
                     .entry(method.to_uppercase())
@@ -234,17 +243,14 @@ impl RuleIndex {
             Some(PathMatcher::Contains { contains }) => PathCategory::Contains(contains.clone()),
             Some(PathMatcher::EndsWith { ends_with }) => PathCategory::EndsWith(ends_with.clone()),
             Some(PathMatcher::Regex { .. }) => PathCategory::Regex,
-            Some(PathMatcher::Full { matcher, .. }) => {
-                use crate::predicate::StringMatcher;
-                match matcher {
-                    StringMatcher::Equals(v) => PathCategory::Exact(v.clone()),
-                    StringMatcher::StartsWith(v) => PathCategory::Prefix(v.clone()),
-                    StringMatcher::Contains(v) => PathCategory::Contains(v.clone()),
-                    StringMatcher::EndsWith(v) => PathCategory::EndsWith(v.clone()),
-                    StringMatcher::Matches(_) => PathCategory::Regex,
-                    StringMatcher::Exists(_) => PathCategory::Any,
-                }
-            }
+            Some(PathMatcher::Full { matcher, .. }) => match matcher {
+                StringMatcher::Equals(v) => PathCategory::Exact(v.clone()),
+                StringMatcher::StartsWith(v) => PathCategory::Prefix(v.clone()),
+                StringMatcher::Contains(v) => PathCategory::Contains(v.clone()),
+                StringMatcher::EndsWith(v) => PathCategory::EndsWith(v.clone()),
+                StringMatcher::Matches(_) => PathCategory::Regex,
+                StringMatcher::Exists(_) => PathCategory::Any,
+            },
         }
     }
 
@@ -444,14 +450,15 @@ impl SharedRuleIndex {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::predicate::{PredicateOptions, StringMatcher};
+    use crate::predicate::PredicateOptions;
 
     fn make_predicate(path: Option<PathMatcher>, method: Option<&str>) -> RequestPredicate {
         RequestPredicate {
-            method: method.map(|m| StringMatcher::Equals(m.to_string())),
+            method: method.map(|m| StringMatcher::Equals(m.to_string()).into()),
             path,
             headers: vec![],
             query: vec![],
+            trailers: vec![],
             body: None,
             options: PredicateOptions::default(),
         }
@@ -691,6 +698,22 @@ mod tests {
         assert!(candidates.contains(&2)); // any_method
     }
 
+    #[test]
+    fn test_method_list_indexed_per_method() {
+        let mut predicate = make_predicate(
+            Some(PathMatcher::Exact {
+                exact: "/users".to_string(),
+            }),
+            None,
+        );
+        predicate.method = Some(MethodMatcher::OneOf(vec!["GET".into(), "post".into()]));
+        let index = RuleIndex::build(vec![("read_write".to_string(), predicate, 0)]).unwrap();
+
+        assert_eq!(index.find_candidates("/users", Some("GET")), vec![0]);
+        assert_eq!(index.find_candidates("/users", Some("POST")), vec![0]);
+        assert!(index.find_candidates("/users", Some("DELETE")).is_empty());
+    }
+
     #[test]
     fn test_index_stats() {
         let predicates = vec![
@@ -304,6 +304,12 @@ fn is_subset_predicates(a: &[Predicate], b: &[Predicate]) -> bool {
         return false;
     }
 
+    // Predicates the heuristic can't inspect (or, not, matches, ...) still
+    // constrain `b`, so dropping them would make `b` look more general
+    if !b.iter().all(|pred| is_field_constraint(&pred.operation)) {
+        return false;
+    }
+
     // Simple heuristic: check if predicates share the same fields but with different specificity
     // For example, if stub A matches path="/api/users" and stub B matches path="/api",
     // then B is more general and will shadow A for paths starting with /api.
@@ -326,6 +332,16 @@ fn is_subset_predicates(a: &[Predicate], b: &[Predicate]) -> bool {
     false
 }
 
+/// Whether the operation is one `extract_predicate_fields` understands
+fn is_field_constraint(operation: &PredicateOperation) -> bool {
+    matches!(
+        operation,
+        PredicateOperation::Equals(_)
+            | PredicateOperation::Contains(_)
+            | PredicateOperation::StartsWith(_)
+    )
+}
+
 /// Extract field paths from predicates for comparison
 fn extract_predicate_fields(predicates: &[Predicate]) -> HashMap<String, PredicateConstraint> {
     let mut fields = HashMap::new();
@@ -524,6 +540,25 @@ mod tests {
             .any(|w| w.warning_type == WarningType::PotentiallyShadowed));
     }
 
+    #[test]
+    fn test_or_predicate_not_ignored_for_shadowing() {
+        // The earlier stub also requires GET or PUT, so it can't be assumed
+        // to shadow a POST-capable stub
+        let stubs = vec![
+            stub_with_predicates(vec![
+                json!({"startsWith": {"path": "/api"}}),
+                json!({"or": [{"equals": {"method": "GET"}}, {"equals": {"method": "PUT"}}]}),
+            ]),
+            stub_with_predicates(vec![json!({"equals": {"path": "/api/users"}})]),
+        ];
+
+        let result = analyze_stubs(&stubs);
+        assert!(!result
+            .warnings
+            .iter()
+            .any(|w| w.warning_type == WarningType::PotentiallyShadowed));
+    }
+
     #[test]
     fn test_analyze_new_stub_duplicate_id() {
         let existing = vec![stub_with_id_and_predicates(
@@ -20,8 +20,29 @@
 //!     status: 200
 //!     body: '{"echo": "${request.query.message}", "path": "${request.path}"}'
 //! ```
+//!
+//! # Mock Response Variables
+//!
+//! Mock response bodies ([`render_mock_body`]) use a shorter syntax:
+//!
+//! - `${path}` - The request path
+//! - `${query.<name>}` - Query parameter value
+//! - `${header.<name>}` - Header value (case-insensitive)
+//! - `${body:<jsonpath>}` - Value extracted from a JSON request body
+//! - `${pathParams.<name>}` - Variable captured by the rule's path template
+//!
+//! # Generated Values
+//!
+//! Mock and error bodies ([`render_generated_values`]) may also contain
+//! values generated per response, drawn from the seedable fault RNG:
+//!
+//! - `${uuid}` - A random v4 UUID
+//! - `${now:iso8601}` - The current time; also `now:epoch` and `now:epoch_ms`
+//! - `${randomInt:<min>-<max>}` - A random integer in the inclusive range
 
-use crate::predicate::parse_query_string;
+use crate::extensions::fault::FaultRng;
+use crate::predicate::{extract_json_path, parse_query_string};
+use rand::Rng;
 use regex::Regex;
 use std::collections::HashMap;
 use std::sync::OnceLock;
@@ -36,6 +57,24 @@ fn get_template_regex() -> &'static Regex {
     })
 }
 
+/// Regex for mock response variables: ${path}, ${query.name}, ${body:$.id}, etc.
+static MOCK_TEMPLATE_REGEX: OnceLock<Regex> = OnceLock::new();
+
+fn get_mock_template_regex() -> &'static Regex {
+    MOCK_TEMPLATE_REGEX.get_or_init(|| {
+        Regex::new(r"\$\{(path|query\.[^}]+|header\.[^}]+|pathParams\.[^}]+|body:[^}]+)\}").unwrap()
+    })
+}
+
+/// Regex for generated values: ${uuid}, ${now:iso8601}, ${randomInt:1-100}
+static GENERATED_REGEX: OnceLock<Regex> = OnceLock::new();
+
+fn get_generated_regex() -> &'static Regex {
+    GENERATED_REGEX.get_or_init(|| {
+        Regex::new(r"\$\{(?:(uuid)|now:([a-z0-9_]+)|randomInt:(-?\d+)-(-?\d+))\}").unwrap()
+    })
+}
+
 /// Parsed request data for template substitution
 #[derive(Debug, Clone, Default)]
 pub struct RequestData {
@@ -139,6 +178,74 @@ pub fn process_template(template: &str, request_data: &RequestData) -> String {
         .to_string()
 }
 
+/// Render a mock response body, substituting mock variables with request
+/// data.
+///
+/// Variables the request can't fill (a missing header, a JSONPath that
+/// doesn't resolve) become empty; anything else in `${...}` is left as is.
+pub fn render_mock_body(template: &str, request_data: &RequestData) -> String {
+    get_mock_template_regex()
+        .replace_all(template, |caps: &regex::Captures| {
+            let var = &caps[1];
+            let value = if var == "path" {
+                Some(request_data.path.clone())
+            } else if let Some(name) = var.strip_prefix("query.") {
+                request_data.query.get(name).cloned()
+            } else if let Some(name) = var.strip_prefix("header.") {
+                request_data.headers.get(&name.to_lowercase()).cloned()
+            } else if let Some(name) = var.strip_prefix("pathParams.") {
+                request_data.path_params.get(name).cloned()
+            } else if let Some(path) = var.strip_prefix("body:") {
+                extract_json_path(&request_data.body, path)
+            } else {
+                None
+            };
+            value.unwrap_or_default()
+        })
+        .to_string()
+}
+
+/// Substitute generated values, drawing randomness from `rng`.
+///
+/// Tokens that can't be generated (an unknown time format, a range with
+/// `min` above `max`) are left as is.
+pub fn render_generated_values(template: &str, rng: &FaultRng) -> String {
+    if !template.contains("${") {
+        return template.to_string();
+    }
+    get_generated_regex()
+        .replace_all(template, |caps: &regex::Captures| {
+            let value = if caps.get(1).is_some() {
+                let mut bytes = [0u8; 16];
+                rng.with_rng(|r| r.fill_bytes(&mut bytes));
+                Some(
+                    uuid::Builder::from_random_bytes(bytes)
+                        .into_uuid()
+                        .to_string(),
+                )
+            } else if let Some(format) = caps.get(2) {
+                let now = chrono::Utc::now();
+                match format.as_str() {
+                    "iso8601" => Some(now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+                    "epoch" => Some(now.timestamp().to_string()),
+                    "epoch_ms" => Some(now.timestamp_millis().to_string()),
+                    _ => None,
+                }
+            } else {
+                let min = caps[3].parse::<i64>().ok();
+                let max = caps[4].parse::<i64>().ok();
+                match (min, max) {
+                    (Some(min), Some(max)) if min <= max => {
+                        Some(rng.with_rng(|r| r.gen_range(min..=max)).to_string())
+                    }
+                    _ => None,
+                }
+            };
+            value.unwrap_or_else(|| caps[0].to_string())
+        })
+        .to_string()
+}
+
 /// Check if a string contains template variables
 pub fn has_template_variables(s: &str) -> bool {
     get_template_regex().is_match(s)
@@ -279,6 +386,89 @@ mod tests {
         assert_eq!(result, r#"{"static": "value"}"#);
     }
 
+    #[test]
+    fn test_render_mock_body_sources() {
+        let data = create_test_request_data();
+        assert_eq!(render_mock_body("${path}", &data), "/users/123");
+        assert_eq!(render_mock_body("${query.name}", &data), "John");
+        assert_eq!(
+            render_mock_body("${header.X-Request-Id}", &data),
+            "req-12345"
+        );
+        assert_eq!(render_mock_body("${body:$.action}", &data), "test");
+        assert_eq!(
+            render_mock_body(r#"{"user": "${query.name}", "at": "${path}"}"#, &data),
+            r#"{"user": "John", "at": "/users/123"}"#
+        );
+    }
+
+    #[test]
+    fn test_render_mock_body_unresolved() {
+        let data = create_test_request_data();
+        // Unresolved references become empty
+        assert_eq!(render_mock_body("[${query.missing}]", &data), "[]");
+        assert_eq!(render_mock_body("[${header.x-missing}]", &data), "[]");
+        assert_eq!(render_mock_body("[${body:$.missing}]", &data), "[]");
+        // Non-JSON bodies resolve nothing
+        let mut plain = data.clone();
+        plain.body = "not json".to_string();
+        assert_eq!(render_mock_body("[${body:$.action}]", &plain), "[]");
+        // Unknown variables are kept literally
+        assert_eq!(
+            render_mock_body("${method} ${user}", &data),
+            "${method} ${user}"
+        );
+    }
+
+    #[test]
+    fn test_generated_uuid() {
+        let rng = FaultRng::new(None);
+        let rendered = render_generated_values("${uuid}", &rng);
+        let id = uuid::Uuid::parse_str(&rendered).unwrap();
+        assert_eq!(id.get_version_num(), 4);
+        assert_ne!(render_generated_values("${uuid}", &rng), rendered);
+    }
+
+    #[test]
+    fn test_generated_random_int() {
+        let rng = FaultRng::new(None);
+        for _ in 0..100 {
+            assert_eq!(render_generated_values("${randomInt:1-1}", &rng), "1");
+            let n: i64 = render_generated_values("${randomInt:-5-5}", &rng)
+                .parse()
+                .unwrap();
+            assert!((-5..=5).contains(&n));
+        }
+        // Inverted ranges are left alone
+        assert_eq!(
+            render_generated_values("${randomInt:9-1}", &rng),
+            "${randomInt:9-1}"
+        );
+    }
+
+    #[test]
+    fn test_generated_values_follow_seed() {
+        let template = "${uuid} ${randomInt:1-1000000}";
+        let first = render_generated_values(template, &FaultRng::new(Some(7)));
+        let second = render_generated_values(template, &FaultRng::new(Some(7)));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_generated_now() {
+        let rng = FaultRng::new(None);
+        let rendered = render_generated_values("${now:iso8601}", &rng);
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(&rendered).is_ok(),
+            "{rendered}"
+        );
+        let epoch: i64 = render_generated_values("${now:epoch}", &rng)
+            .parse()
+            .unwrap();
+        assert!(epoch > 1_600_000_000);
+        assert_eq!(render_generated_values("${now:unix}", &rng), "${now:unix}");
+    }
+
     #[test]
     fn test_has_template_variables() {
         assert!(has_template_variables("${request.path}"));
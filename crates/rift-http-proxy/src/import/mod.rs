@@ -0,0 +1,3 @@
+//! Importers generating Rift config from other API descriptions.
+
+pub mod openapi;
@@ -0,0 +1,537 @@
+//! Mock rules generated from an OpenAPI 3 document.
+//!
+//! Each operation becomes a rule matching its method and path, with path
+//! parameters as template captures, that answers with a mock response
+//! instead of forwarding. The response is the operation's lowest 2xx
+//! response, else its first one. Its body is the first example given for
+//! the response's media type (JSON preferred), or else a value built from
+//! its schema.
+//!
+//! Rules for fixed paths come before templated ones, so `/pets/mine` isn't
+//! shadowed by `/pets/{petId}`. A path from the first server URL, such as
+//! `/v1` in `https://api.example.com/v1`, prefixes every path.
+
+use crate::config::{FaultConfig, MatchConfig, MockResponse, PathMatch, Rule, RuleAction};
+use anyhow::{anyhow, bail};
+use serde_json::Value as Json;
+use serde_yaml::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Operation keys of a path item, in the order their rules are emitted
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Most `$ref`s followed to reach an object, so reference cycles end
+const MAX_REF_HOPS: usize = 8;
+
+/// Generate a mock rule for every operation of the OpenAPI 3 document
+/// `spec`, given as YAML or JSON
+pub fn from_openapi(spec: &str) -> anyhow::Result<Vec<Rule>> {
+    let doc: Value =
+        serde_yaml::from_str(spec).map_err(|e| anyhow!("Invalid OpenAPI document: {e}"))?;
+    let version = match doc.get("openapi") {
+        Some(Value::String(version)) => Some(version.clone()),
+        Some(Value::Number(version)) => Some(version.to_string()),
+        _ => None,
+    };
+    match version {
+        Some(version) if version.starts_with("3.") => {}
+        Some(version) => bail!("Unsupported OpenAPI version {version}, expected 3.x"),
+        None => bail!("Not an OpenAPI 3 document: missing 'openapi' version"),
+    }
+    let paths = doc
+        .get("paths")
+        .and_then(Value::as_mapping)
+        .ok_or_else(|| anyhow!("OpenAPI document has no 'paths'"))?;
+
+    let base_path = base_path(&doc);
+    let mut ids = HashSet::new();
+    let mut rules = Vec::new();
+    for (path, item) in paths {
+        let Some(path) = path.as_str() else {
+            continue;
+        };
+        let path = format!("{base_path}{path}");
+        for method in METHODS {
+            if let Some(operation) = item.get(method) {
+                rules.push(operation_rule(&doc, &path, method, operation, &mut ids));
+            }
+        }
+    }
+    // Stable, so rules keep the document's order otherwise
+    rules.sort_by_key(|rule| matches!(rule.match_config.path, PathMatch::Template { .. }));
+    Ok(rules)
+}
+
+/// Path of the first server URL, without a trailing slash. URLs with
+/// variables are skipped, as their path isn't known.
+fn base_path(doc: &Value) -> String {
+    let Some(url) = doc
+        .get("servers")
+        .and_then(|servers| servers.get(0))
+        .and_then(|server| server.get("url"))
+        .and_then(Value::as_str)
+    else {
+        return String::new();
+    };
+    if url.contains('{') {
+        return String::new();
+    }
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("", |start| &rest[start..]),
+        None => url,
+    };
+    path.trim_end_matches('/').to_string()
+}
+
+fn operation_rule(
+    doc: &Value,
+    path: &str,
+    method: &str,
+    operation: &Value,
+    ids: &mut HashSet<String>,
+) -> Rule {
+    let id = operation
+        .get("operationId")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| slug(method, path));
+    let id = unique_id(id, ids);
+
+    let (status, response) = pick_response(doc, operation.get("responses"));
+    let mut headers = HashMap::new();
+    let body = match response.and_then(|response| media(doc, response)) {
+        Some((media_type, media)) => {
+            headers.insert("Content-Type".to_string(), media_type.to_string());
+            media_body(doc, media_type, media)
+        }
+        None => String::new(),
+    };
+
+    let path = if path.contains('{') {
+        PathMatch::Template {
+            template: path.to_string(),
+        }
+    } else {
+        PathMatch::Exact {
+            exact: path.to_string(),
+        }
+    };
+    Rule {
+        id,
+        match_config: MatchConfig {
+            methods: vec![method.to_uppercase()],
+            path,
+            ..Default::default()
+        },
+        fault: FaultConfig {
+            mock: Some(MockResponse {
+                probability: 1.0,
+                status,
+                headers,
+                body,
+            }),
+            ..Default::default()
+        },
+        action: RuleAction::Fault,
+        upstream: None,
+        once: false,
+        description: operation
+            .get("summary")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        metadata: HashMap::new(),
+        enabled: true,
+        active_window: None,
+    }
+}
+
+/// Rule id for an operation without an `operationId`, e.g. `get-pets-petid`
+fn slug(method: &str, path: &str) -> String {
+    let mut slug = method.to_string();
+    for part in path.split(|c: char| !c.is_ascii_alphanumeric()) {
+        if !part.is_empty() {
+            slug.push('-');
+            slug.push_str(&part.to_ascii_lowercase());
+        }
+    }
+    slug
+}
+
+/// `id`, suffixed with a number if an earlier rule has it
+fn unique_id(id: String, ids: &mut HashSet<String>) -> String {
+    let mut unique = id.clone();
+    let mut n = 2;
+    while !ids.insert(unique.clone()) {
+        unique = format!("{id}-{n}");
+        n += 1;
+    }
+    unique
+}
+
+/// Status and response object to mock: the lowest 2xx response, else the
+/// first one, else `default` as a 200
+fn pick_response<'a>(doc: &'a Value, responses: Option<&'a Value>) -> (u16, Option<&'a Value>) {
+    let Some(responses) = responses.and_then(Value::as_mapping) else {
+        return (200, None);
+    };
+    let mut coded = Vec::new();
+    let mut default = None;
+    for (code, response) in responses {
+        let code = match code {
+            Value::Number(code) => code.as_u64().map(|code| code.to_string()),
+            Value::String(code) => Some(code.to_ascii_uppercase()),
+            _ => None,
+        };
+        let status = match code.as_deref() {
+            Some("DEFAULT") => {
+                default = Some(resolve(doc, response));
+                continue;
+            }
+            // Ranges such as `2XX` stand for their first status
+            Some(code) => code.replace('X', "0").parse::<u16>().ok(),
+            None => None,
+        };
+        if let Some(status) = status {
+            coded.push((status, resolve(doc, response)));
+        }
+    }
+    coded
+        .iter()
+        .filter(|(status, _)| (200..300).contains(status))
+        .min_by_key(|(status, _)| *status)
+        .or(coded.first())
+        .map(|&(status, response)| (status, Some(response)))
+        .unwrap_or((200, default))
+}
+
+/// Media type and media type object of a response, preferring JSON
+fn media<'a>(doc: &'a Value, response: &'a Value) -> Option<(&'a str, &'a Value)> {
+    let content = response.get("content")?.as_mapping()?;
+    let media_types: Vec<_> = content
+        .iter()
+        .filter_map(|(media_type, media)| Some((media_type.as_str()?, resolve(doc, media))))
+        .collect();
+    media_types
+        .iter()
+        .find(|(media_type, _)| is_json(media_type))
+        .or(media_types.first())
+        .copied()
+}
+
+fn is_json(media_type: &str) -> bool {
+    media_type == "application/json" || media_type.ends_with("+json")
+}
+
+/// Body for a media type object: its example, the first of its
+/// `examples`, or a value built from its schema
+fn media_body(doc: &Value, media_type: &str, media: &Value) -> String {
+    let example = media.get("example").or_else(|| {
+        media
+            .get("examples")
+            .and_then(Value::as_mapping)
+            .and_then(|examples| examples.values().next())
+            .and_then(|example| resolve(doc, example).get("value"))
+    });
+    let value = match example {
+        Some(example) => to_json(example),
+        None => match media.get("schema") {
+            Some(schema) => schema_value(doc, schema, &mut Vec::new()),
+            None => return String::new(),
+        },
+    };
+    match value {
+        Json::String(text) if !is_json(media_type) => text,
+        Json::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+/// Example value for a schema: its `example`, `default` or first `enum`
+/// value, else one built from its type. `expanding` holds the `$ref`s
+/// being built; a schema referring back to one of them is left null.
+fn schema_value(doc: &Value, schema: &Value, expanding: &mut Vec<String>) -> Json {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        if expanding.iter().any(|outer| outer == reference) {
+            return Json::Null;
+        }
+        expanding.push(reference.to_string());
+        let value = schema_value(doc, resolve(doc, schema), expanding);
+        expanding.pop();
+        return value;
+    }
+    if let Some(example) = schema.get("example").or_else(|| schema.get("default")) {
+        return to_json(example);
+    }
+    if let Some(first) = schema.get("enum").and_then(|values| values.get(0)) {
+        return to_json(first);
+    }
+    if let Some(all_of) = schema.get("allOf").and_then(Value::as_sequence) {
+        let mut merged = serde_json::Map::new();
+        for part in all_of {
+            if let Json::Object(fields) = schema_value(doc, part, expanding) {
+                merged.extend(fields);
+            }
+        }
+        return Json::Object(merged);
+    }
+    if let Some(first) = ["oneOf", "anyOf"]
+        .iter()
+        .find_map(|key| schema.get(key).and_then(|options| options.get(0)))
+    {
+        return schema_value(doc, first, expanding);
+    }
+
+    let schema_type = match schema.get("type") {
+        Some(Value::String(schema_type)) => Some(schema_type.as_str()),
+        // OpenAPI 3.1 type lists, such as `[string, "null"]`
+        Some(Value::Sequence(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|schema_type| *schema_type != "null"),
+        _ => None,
+    };
+    match schema_type {
+        Some("object") | None if schema.get("properties").is_some() => {
+            let properties = schema.get("properties").and_then(Value::as_mapping);
+            let fields = properties
+                .into_iter()
+                .flatten()
+                .filter_map(|(name, property)| {
+                    Some((
+                        name.as_str()?.to_string(),
+                        schema_value(doc, property, expanding),
+                    ))
+                });
+            Json::Object(fields.collect())
+        }
+        Some("object") => Json::Object(Default::default()),
+        Some("array") => match schema.get("items") {
+            Some(items) => Json::Array(vec![schema_value(doc, items, expanding)]),
+            None => Json::Array(Vec::new()),
+        },
+        Some("string") => Json::String(
+            match schema.get("format").and_then(Value::as_str) {
+                Some("date-time") => "2024-01-01T00:00:00Z",
+                Some("date") => "2024-01-01",
+                Some("uuid") => "00000000-0000-0000-0000-000000000000",
+                Some("email") => "user@example.com",
+                Some("uri" | "url") => "https://example.com",
+                _ => "string",
+            }
+            .to_string(),
+        ),
+        Some("integer") => Json::from(0),
+        Some("number") => Json::from(0.0),
+        Some("boolean") => Json::Bool(true),
+        _ => Json::Null,
+    }
+}
+
+/// Follow `$ref`s to objects in the same document
+fn resolve<'a>(doc: &'a Value, mut value: &'a Value) -> &'a Value {
+    for _ in 0..MAX_REF_HOPS {
+        let Some(pointer) = value
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| reference.strip_prefix("#/"))
+        else {
+            break;
+        };
+        let target = pointer.split('/').try_fold(doc, |node, segment| {
+            node.get(segment.replace("~1", "/").replace("~0", "~"))
+        });
+        match target {
+            Some(target) => value = target,
+            None => break,
+        }
+    }
+    value
+}
+
+/// A YAML value as JSON; values JSON can't hold, such as maps with
+/// non-string keys, become null
+fn to_json(value: &Value) -> Json {
+    serde_json::to_value(value).unwrap_or(Json::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extensions::matcher::CompiledRule;
+    use hyper::{HeaderMap, Method, Uri};
+
+    const SPEC: &str = r##"
+openapi: 3.0.3
+info: {title: Pets, version: "1.0"}
+servers:
+  - url: https://api.example.com/v1/
+paths:
+  /pets/{petId}:
+    get:
+      operationId: showPet
+      responses:
+        "404":
+          description: Not found
+        "200":
+          $ref: "#/components/responses/Pet"
+  /pets:
+    get:
+      operationId: listPets
+      summary: List all pets
+      responses:
+        200:
+          description: Pets
+          content:
+            application/json:
+              schema:
+                type: array
+                items: {$ref: "#/components/schemas/Pet"}
+    post:
+      responses:
+        "201":
+          description: Created
+          content:
+            application/json:
+              example: {id: 7, name: Rex}
+  /pets/mine:
+    get:
+      responses:
+        default:
+          description: Mine
+          content:
+            text/plain:
+              examples:
+                plain: {value: "all yours"}
+components:
+  responses:
+    Pet:
+      description: A pet
+      content:
+        application/xml:
+          example: "<pet/>"
+        application/json:
+          schema: {$ref: "#/components/schemas/Pet"}
+  schemas:
+    Pet:
+      type: object
+      properties:
+        id: {type: integer, format: int64}
+        name: {type: string, example: Fido}
+        status: {type: string, enum: [available, sold]}
+        born: {type: string, format: date}
+        parent: {$ref: "#/components/schemas/Pet"}
+"##;
+
+    fn mock(rule: &Rule) -> &MockResponse {
+        rule.fault.mock.as_ref().unwrap()
+    }
+
+    #[test]
+    fn test_rules_match_operations() {
+        let rules = from_openapi(SPEC).unwrap();
+        let summary: Vec<_> = rules
+            .iter()
+            .map(|rule| {
+                let path = match &rule.match_config.path {
+                    PathMatch::Exact { exact } => format!("exact {exact}"),
+                    PathMatch::Template { template } => format!("template {template}"),
+                    other => format!("{other:?}"),
+                };
+                (rule.id.as_str(), rule.match_config.methods.join(","), path)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("listPets", "GET".to_string(), "exact /v1/pets".to_string()),
+                (
+                    "post-v1-pets",
+                    "POST".to_string(),
+                    "exact /v1/pets".to_string()
+                ),
+                (
+                    "get-v1-pets-mine",
+                    "GET".to_string(),
+                    "exact /v1/pets/mine".to_string()
+                ),
+                (
+                    "showPet",
+                    "GET".to_string(),
+                    "template /v1/pets/{petId}".to_string()
+                ),
+            ]
+        );
+        assert_eq!(rules[0].description.as_deref(), Some("List all pets"));
+
+        let compiled: Vec<CompiledRule> = rules
+            .into_iter()
+            .map(|rule| CompiledRule::compile(rule).unwrap())
+            .collect();
+        let first_match = |method: Method, path: &str| {
+            let uri: Uri = path.parse().unwrap();
+            compiled
+                .iter()
+                .find(|rule| rule.matches(&method, &uri, &HeaderMap::new()))
+                .map(|rule| rule.id.as_str())
+        };
+        assert_eq!(
+            first_match(Method::GET, "/v1/pets/mine"),
+            Some("get-v1-pets-mine")
+        );
+        assert_eq!(first_match(Method::GET, "/v1/pets/42"), Some("showPet"));
+        assert_eq!(first_match(Method::DELETE, "/v1/pets/42"), None);
+        assert_eq!(first_match(Method::GET, "/pets"), None);
+    }
+
+    #[test]
+    fn test_mock_responses() {
+        let rules = from_openapi(SPEC).unwrap();
+        let by_id = |id: &str| mock(rules.iter().find(|rule| rule.id == id).unwrap());
+
+        // Built from the schema, leaving the recursive `parent` out
+        let list = by_id("listPets");
+        assert_eq!(list.status, 200);
+        assert_eq!(list.headers["Content-Type"], "application/json");
+        let pets: Json = serde_json::from_str(&list.body).unwrap();
+        assert_eq!(pets[0]["id"], 0);
+        assert_eq!(pets[0]["name"], "Fido");
+        assert_eq!(pets[0]["status"], "available");
+        assert_eq!(pets[0]["born"], "2024-01-01");
+        assert!(pets[0]["parent"].is_null());
+
+        let created = by_id("post-v1-pets");
+        assert_eq!(created.status, 201);
+        assert_eq!(created.body, r#"{"id":7,"name":"Rex"}"#);
+
+        // The 2xx response is preferred, and JSON over XML
+        let shown = by_id("showPet");
+        assert_eq!(shown.status, 200);
+        assert_eq!(shown.headers["Content-Type"], "application/json");
+
+        let mine = by_id("get-v1-pets-mine");
+        assert_eq!(mine.status, 200);
+        assert_eq!(mine.headers["Content-Type"], "text/plain");
+        assert_eq!(mine.body, "all yours");
+    }
+
+    #[test]
+    fn test_emitted_rules_load_back() {
+        let rules = from_openapi(SPEC).unwrap();
+        let yaml = serde_yaml::to_string(&rules).unwrap();
+        let loaded: Vec<Rule> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded.len(), rules.len());
+        assert_eq!(mock(&loaded[1]).body, mock(&rules[1]).body);
+    }
+
+    #[test]
+    fn test_rejects_other_documents() {
+        let err = from_openapi("swagger: \"2.0\"\npaths: {}\n").unwrap_err();
+        assert!(err.to_string().contains("missing 'openapi'"), "{err}");
+        let err = from_openapi("openapi: 2.0.0\npaths: {}\n").unwrap_err();
+        assert!(err.to_string().contains("expected 3.x"), "{err}");
+        let err = from_openapi("openapi: 3.1.0\ninfo: {title: t, version: v}\n").unwrap_err();
+        assert!(err.to_string().contains("no 'paths'"), "{err}");
+    }
+}
@@ -3,7 +3,7 @@
 //! This module contains the Imposter struct which represents a single
 //! running imposter instance with its configuration, stubs, and state.
 
-use super::predicates::stub_matches;
+use super::predicates::{stub_matches, PredicateRequest};
 use super::response::{
     create_response_preview, create_stub_from_proxy_response, execute_stub_response,
     execute_stub_response_with_rift, get_rift_script_config,
@@ -244,22 +244,16 @@ impl Imposter {
     ) -> Option<(StubState, usize)> {
         let stubs = self.stubs.read();
         let headers_map = Self::header_map_to_hashmap(headers);
-        // Parse form data if Content-Type is application/x-www-form-urlencoded
-        let form = Self::parse_form_data(headers, body);
+        // Parsed once and shared by every stub's predicates
+        let request = PredicateRequest::new(method, path, query, &headers_map)
+            .with_body(body.unwrap_or_default())
+            .with_client(request_from, client_ip)
+            // Parse form data if Content-Type is application/x-www-form-urlencoded
+            .with_form(Self::parse_form_data(headers, body));
 
         for (index, stub_state) in stubs.iter().enumerate() {
             let stub = &stub_state.stub;
-            if stub_matches(
-                &stub.predicates,
-                method,
-                path,
-                query,
-                &headers_map,
-                body,
-                request_from,
-                client_ip,
-                form.as_ref(),
-            ) {
+            if stub_matches(&stub.predicates, &request) {
                 // TODO(perf): It's unfortunate that we end up deep cloning the whole stub here
                 return Some((stub_state.clone(), index));
             }
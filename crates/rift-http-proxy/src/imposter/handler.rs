@@ -5,12 +5,13 @@
 
 use super::core::Imposter;
 use super::predicates::parse_query_string;
-use super::response::apply_js_or_rhai_decorate;
+use super::response::{apply_js_or_rhai_decorate, is_not_modified};
 use super::types::{DebugMatchResult, DebugRequest, DebugResponse, RecordedRequest, ResponseMode};
 use crate::admin_api::types::{build_response, build_response_with_headers};
 use crate::behaviors::{
     apply_copy_behaviors, header_to_title_case, RequestContext, ResponseBehaviors,
 };
+use crate::predicate::decode_body;
 #[cfg(feature = "javascript")]
 use crate::scripting::{execute_mountebank_inject, MountebankRequest};
 use crate::scripting::{FaultDecision, ScriptEngine, ScriptRequest};
@@ -27,6 +28,9 @@ use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, warn};
 
+/// Most bytes a compressed request body is decoded to for predicate matching
+const MAX_DECODED_BODY_BYTES: usize = 16 * 1024 * 1024;
+
 /// Handle a request to an imposter
 pub async fn handle_imposter_request(
     req: Request<Incoming>,
@@ -62,17 +66,26 @@ pub async fn handle_imposter_request(
     let query_str = uri.query().unwrap_or("").to_string();
 
     // Always collect request body - needed for recording, copy behaviors, and predicate matching
-    let body_string = match req.into_body().collect().await {
+    let content_encoding = headers_clone
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-encoding"))
+        .map(|(_, v)| v.clone());
+    let (body_string, decoded_body) = match req.into_body().collect().await {
         Ok(collected) => {
             let bytes = collected.to_bytes();
             if bytes.is_empty() {
-                None
+                (None, None)
             } else {
-                Some(String::from_utf8_lossy(&bytes).to_string())
+                // Predicates match a compressed body by its decoded payload
+                let decoded = content_encoding
+                    .and_then(|coding| decode_body(&coding, &bytes, MAX_DECODED_BODY_BYTES))
+                    .map(|decoded| String::from_utf8_lossy(&decoded.bytes).into_owned());
+                (Some(String::from_utf8_lossy(&bytes).to_string()), decoded)
             }
         }
-        Err(_) => None,
+        Err(_) => (None, None),
     };
+    let match_body = decoded_body.as_deref().or(body_string.as_deref());
 
     // Build HeaderMap from captured headers for request context
     let mut headers_for_context = hyper::HeaderMap::new();
@@ -127,7 +140,7 @@ pub async fn handle_imposter_request(
             &path,
             &query_str,
             &headers_clone,
-            &body_string,
+            match_body,
             &headers_for_context,
             client_addr,
         );
@@ -141,7 +154,7 @@ pub async fn handle_imposter_request(
         path_str,
         &headers_for_context,
         query_opt,
-        body_string.as_deref(),
+        match_body,
         Some(&request_from),
         Some(&client_ip),
     ) {
@@ -430,6 +443,28 @@ pub async fn handle_imposter_request(
                 }
             }
 
+            // Honor If-None-Match when the stub opts into conditional responses.
+            // Only a would-be 2xx response to GET or HEAD becomes a 304
+            // (RFC 9110 §13.1.2).
+            if rift_ext.as_ref().is_some_and(|rift| rift.conditional)
+                && matches!(method_str, "GET" | "HEAD")
+                && (200..300).contains(&status)
+                && is_not_modified(&headers, &headers_clone)
+            {
+                let mut response = Response::builder().status(StatusCode::NOT_MODIFIED);
+                for (k, v) in &headers {
+                    // A 304 carries validators and caching headers, but no content
+                    if !k.to_lowercase().starts_with("content-") {
+                        response = response.header(k, v);
+                    }
+                }
+                response = response.header("x-rift-imposter", "true");
+
+                return Ok(response.body(Full::new(Bytes::new())).unwrap_or_else(|_| {
+                    build_response(StatusCode::INTERNAL_SERVER_ERROR, "Response build error")
+                }));
+            }
+
             let mut response = Response::builder().status(status);
 
             for (k, v) in &headers {
@@ -518,7 +553,7 @@ fn handle_debug_request(
     path: &str,
     query_str: &str,
     headers_clone: &HashMap<String, String>,
-    body_string: &Option<String>,
+    body: Option<&str>,
     headers_for_context: &hyper::HeaderMap,
     client_addr: SocketAddr,
 ) -> Result<Response<Full<Bytes>>, Infallible> {
@@ -538,7 +573,7 @@ fn handle_debug_request(
             .filter(|(k, _)| !k.eq_ignore_ascii_case("x-rift-debug"))
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect(),
-        body: body_string.clone(),
+        body: body.map(str::to_string),
     };
 
     // Get imposter info
@@ -559,7 +594,7 @@ fn handle_debug_request(
             path,
             headers_for_context,
             query_opt,
-            body_string.as_deref(),
+            body,
             Some(&request_from),
             Some(&client_ip),
         ) {
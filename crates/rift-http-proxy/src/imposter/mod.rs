@@ -30,9 +30,9 @@ mod tests;
 #[allow(unused_imports)]
 pub use types::{
     DebugImposter, DebugMatchResult, DebugRequest, DebugResponse, DebugResponsePreview,
-    DebugStubInfo, ImposterConfig, ImposterError, IsResponse, MountebankStateMapping, PathRewrite,
-    Predicate, PredicateOperation, ProxyResponse, RecordedRequest, ResponseMode, RiftConfig,
-    RiftConnectionPoolConfig, RiftErrorFault, RiftFaultConfig, RiftFlowStateConfig,
+    DebugStubInfo, ImposterConfig, ImposterError, InjectScript, IsResponse, MountebankStateMapping,
+    PathRewrite, Predicate, PredicateOperation, ProxyResponse, RecordedRequest, ResponseMode,
+    RiftConfig, RiftConnectionPoolConfig, RiftErrorFault, RiftFaultConfig, RiftFlowStateConfig,
     RiftLatencyFault, RiftMetricsConfig, RiftProxyConfig, RiftRedisConfig, RiftResponseExtension,
     RiftScriptConfig, RiftScriptEngineConfig, RiftUpstreamConfig, Stub, StubResponse,
 };
@@ -46,7 +46,7 @@ pub use manager::ImposterManager;
 
 // Re-export predicate utilities (used in tests and for external consumers)
 #[allow(unused_imports)]
-pub use predicates::{parse_query_string, predicate_matches, stub_matches};
+pub use predicates::{parse_query_string, predicate_matches, stub_matches, PredicateRequest};
 
 // Re-export response utilities
 #[allow(unused_imports)]
@@ -1,48 +1,89 @@
 //! Predicate matching logic for Mountebank-compatible stub matching.
 //!
-//! Supports: equals, deepEquals, contains, startsWith, endsWith, matches, exists, not, or, and
-//! Also supports requestFrom, ip, and form fields.
+//! Supports: equals, deepEquals, contains, startsWith, endsWith, matches, exists, not, or, and,
+//! inject. Also supports requestFrom, ip, and form fields.
 
-use crate::behaviors::{extract_jsonpath, extract_xpath};
-use crate::imposter::types::{Predicate, PredicateOperation, PredicateSelector};
+use crate::behaviors::{extract_jsonpath, extract_xpath_with_namespaces};
+use crate::imposter::types::{InjectScript, Predicate, PredicateOperation, PredicateSelector};
+use rhai::{Dynamic, Engine, Map, Scope};
 use std::collections::HashMap;
+use std::sync::OnceLock;
 use tracing::warn;
 
-/// Check if a stub matches a request based on its predicates
-#[allow(clippy::too_many_arguments)]
-pub fn stub_matches(
-    predicates: &[Predicate],
-    method: &str,
-    path: &str,
-    query: Option<&str>,
-    headers: &HashMap<String, String>,
-    body: Option<&str>,
-    request_from: Option<&str>,
-    client_ip: Option<&str>,
-    form: Option<&HashMap<String, String>>,
-) -> bool {
-    // If no predicates, match everything
-    if predicates.is_empty() {
-        return true;
-    }
+/// Engine shared by all inject predicates
+static INJECT_ENGINE: OnceLock<Engine> = OnceLock::new();
+
+/// Operation budget for one inject predicate, so a runaway script can't
+/// stall the request
+const INJECT_MAX_OPERATIONS: u64 = 100_000;
+
+/// A request as stub predicates see it: the query string and form body are
+/// parsed once, rather than again for every predicate.
+#[derive(Debug, Clone)]
+pub struct PredicateRequest<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    /// URL-decoded query parameters
+    pub query: HashMap<String, String>,
+    pub headers: &'a HashMap<String, String>,
+    /// Empty if the request has no body
+    pub body: &'a str,
+    /// Client address with port, for `requestFrom`
+    pub request_from: Option<&'a str>,
+    /// Client IP address, for `ip`
+    pub client_ip: Option<&'a str>,
+    /// Fields of a form-urlencoded body
+    pub form: Option<HashMap<String, String>>,
+}
 
-    // All predicates must match (implicit AND)
-    for predicate in predicates {
-        if !predicate_matches(
-            predicate,
+impl<'a> PredicateRequest<'a> {
+    /// A request without a body or client address
+    pub fn new(
+        method: &'a str,
+        path: &'a str,
+        query: Option<&str>,
+        headers: &'a HashMap<String, String>,
+    ) -> Self {
+        Self {
             method,
             path,
-            query,
+            query: parse_query(query),
             headers,
-            body,
-            request_from,
-            client_ip,
-            form,
-        ) {
-            return false;
+            body: "",
+            request_from: None,
+            client_ip: None,
+            form: None,
         }
     }
-    true
+
+    pub fn with_body(mut self, body: &'a str) -> Self {
+        self.body = body;
+        self
+    }
+
+    /// Set the client address, as `ip:port` and as the bare IP
+    pub fn with_client(
+        mut self,
+        request_from: Option<&'a str>,
+        client_ip: Option<&'a str>,
+    ) -> Self {
+        self.request_from = request_from;
+        self.client_ip = client_ip;
+        self
+    }
+
+    pub fn with_form(mut self, form: Option<HashMap<String, String>>) -> Self {
+        self.form = form;
+        self
+    }
+}
+
+/// Check if a stub matches a request based on its predicates
+pub fn stub_matches(predicates: &[Predicate], request: &PredicateRequest<'_>) -> bool {
+    // If no predicates, match everything (implicit AND otherwise)
+    predicates
+        .iter()
+        .all(|predicate| predicate_matches(predicate, request))
 }
 
 /// Parse query string for predicate matching, URL-decoding both keys and values
@@ -51,20 +92,9 @@ pub fn parse_query(query: Option<&str>) -> HashMap<String, String> {
 }
 
 /// Check if a single predicate matches (Mountebank-compatible)
-/// Supports: equals, deepEquals, contains, startsWith, endsWith, matches, exists, not, or, and
-/// Also supports requestFrom, ip, and form fields
-#[allow(clippy::too_many_arguments)]
-pub fn predicate_matches(
-    predicate: &Predicate,
-    method: &str,
-    path: &str,
-    query: Option<&str>,
-    headers: &HashMap<String, String>,
-    body: Option<&str>,
-    request_from: Option<&str>,
-    client_ip: Option<&str>,
-    form: Option<&HashMap<String, String>>,
-) -> bool {
+/// Supports: equals, deepEquals, contains, startsWith, endsWith, matches, exists, not, or, and,
+/// inject. Also supports requestFrom, ip, and form fields
+pub fn predicate_matches(predicate: &Predicate, request: &PredicateRequest<'_>) -> bool {
     // Get predicate options
     let case_sensitive = predicate.parameters.case_sensitive.unwrap_or(false);
 
@@ -119,9 +149,7 @@ pub fn predicate_matches(
         }
     };
 
-    // Build request context for field access
-    let query_map = parse_query(query);
-    let body_str = body.unwrap_or("");
+    let body_str = request.body;
 
     // Handle jsonpath parameter - extract value from JSON body
     let extracted_body: String;
@@ -134,173 +162,166 @@ pub fn predicate_matches(
             selector,
             namespaces,
         }) => {
-            extracted_body = extract_xpath(body_str, selector).unwrap_or_default();
-            if namespaces.is_some() {
-                warn!("XPath namespaces are not supported yet");
-            }
+            extracted_body = extract_xpath_with_namespaces(
+                body_str,
+                selector,
+                namespaces.as_ref().unwrap_or(&HashMap::new()),
+            )
+            .unwrap_or_default();
             &extracted_body
         }
         None => body_str,
     };
 
     match &predicate.operation {
-        PredicateOperation::Equals(fields) => {
-            check_predicate_fields(
-                fields,
-                method,
-                path,
-                &query_map,
-                headers,
-                effective_body,
-                &apply_except,
-                str_equals,
-                false, // not deep equals
-                request_from,
-                client_ip,
-                form,
-                key_case_sensitive,
-            )
-        }
-        PredicateOperation::DeepEquals(fields) => {
-            check_predicate_fields(
-                fields,
-                method,
-                path,
-                &query_map,
-                headers,
-                effective_body,
-                &apply_except,
-                str_equals,
-                true, // deep equals
-                request_from,
-                client_ip,
-                form,
-                key_case_sensitive,
-            )
-        }
+        PredicateOperation::Equals(fields) => check_predicate_fields(
+            fields,
+            request,
+            effective_body,
+            &apply_except,
+            str_equals,
+            false, // not deep equals
+            key_case_sensitive,
+        ),
+        PredicateOperation::DeepEquals(fields) => check_predicate_fields(
+            fields,
+            request,
+            effective_body,
+            &apply_except,
+            str_equals,
+            true, // deep equals
+            key_case_sensitive,
+        ),
         PredicateOperation::Contains(fields) => check_predicate_fields(
             fields,
-            method,
-            path,
-            &query_map,
-            headers,
+            request,
             effective_body,
             &apply_except,
             |expected, actual| str_contains(actual, expected),
             false,
-            request_from,
-            client_ip,
-            form,
             key_case_sensitive,
         ),
         PredicateOperation::StartsWith(fields) => check_predicate_fields(
             fields,
-            method,
-            path,
-            &query_map,
-            headers,
+            request,
             effective_body,
             &apply_except,
             |expected, actual| str_starts_with(actual, expected),
             false,
-            request_from,
-            client_ip,
-            form,
             key_case_sensitive,
         ),
         PredicateOperation::EndsWith(fields) => check_predicate_fields(
             fields,
-            method,
-            path,
-            &query_map,
-            headers,
+            request,
             effective_body,
             &apply_except,
             |expected, actual| str_ends_with(actual, expected),
             false,
-            request_from,
-            client_ip,
-            form,
             key_case_sensitive,
         ),
         PredicateOperation::Matches(fields) => check_predicate_fields_regex(
             fields,
-            method,
-            path,
-            &query_map,
-            headers,
+            request,
             effective_body,
             &apply_except,
             case_sensitive,
-            request_from,
-            client_ip,
-            form,
             key_case_sensitive,
         ),
         PredicateOperation::Exists(fields) => {
-            check_exists_predicate(fields, &query_map, headers, effective_body, form)
+            check_exists_predicate(fields, request, effective_body)
         }
-        PredicateOperation::Not(inner) => !predicate_matches(
-            inner,
-            method,
-            path,
-            query,
-            headers,
-            body,
-            request_from,
-            client_ip,
-            form,
-        ),
-        PredicateOperation::Or(children) => children.iter().any(|p| {
-            predicate_matches(
-                p,
-                method,
-                path,
-                query,
-                headers,
-                body,
-                request_from,
-                client_ip,
-                form,
-            )
-        }),
-        PredicateOperation::And(children) => children.iter().all(|p| {
-            predicate_matches(
-                p,
-                method,
-                path,
-                query,
-                headers,
-                body,
-                request_from,
-                client_ip,
-                form,
-            )
+        PredicateOperation::Not(inner) => !predicate_matches(inner, request),
+        PredicateOperation::Or(children) => children.iter().any(|p| predicate_matches(p, request)),
+        PredicateOperation::And(children) => children.iter().all(|p| predicate_matches(p, request)),
+        PredicateOperation::Inject(script) => inject_matches(script, request),
+    }
+}
+
+/// Run an inject predicate's script. Errors and non-boolean results are
+/// logged and don't match.
+fn inject_matches(script: &InjectScript, request: &PredicateRequest<'_>) -> bool {
+    let engine = INJECT_ENGINE.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine.set_max_operations(INJECT_MAX_OPERATIONS);
+        engine
+    });
+    let ast = match script.compiled(|source| engine.compile(source).map_err(|e| e.to_string())) {
+        Ok(ast) => ast,
+        Err(e) => {
+            warn!("Inject predicate failed to compile: {e}");
+            return false;
+        }
+    };
+
+    let mut scope = Scope::new();
+    scope.push("request", inject_request_map(request));
+    match engine.eval_ast_with_scope::<Dynamic>(&mut scope, ast) {
+        Ok(result) => result.as_bool().unwrap_or_else(|_| {
+            warn!(
+                "Inject predicate returned {} instead of a boolean",
+                result.type_name()
+            );
+            false
         }),
+        Err(e) => {
+            warn!("Inject predicate failed: {e}");
+            false
+        }
     }
 }
 
+/// The `request` map an inject predicate sees
+fn inject_request_map(request: &PredicateRequest<'_>) -> Map {
+    let to_map = |fields: &HashMap<String, String>| -> Map {
+        fields
+            .iter()
+            .map(|(k, v)| (k.as_str().into(), Dynamic::from(v.clone())))
+            .collect()
+    };
+    let optional = |value: Option<&str>| value.map_or(Dynamic::UNIT, |v| v.to_string().into());
+
+    let mut map = Map::new();
+    map.insert("method".into(), request.method.to_string().into());
+    map.insert("path".into(), request.path.to_string().into());
+    map.insert("query".into(), to_map(&request.query).into());
+    map.insert("headers".into(), to_map(request.headers).into());
+    map.insert("body".into(), request.body.to_string().into());
+    map.insert("requestFrom".into(), optional(request.request_from));
+    map.insert("ip".into(), optional(request.client_ip));
+    map.insert(
+        "form".into(),
+        request
+            .form
+            .as_ref()
+            .map_or(Dynamic::UNIT, |form| to_map(form).into()),
+    );
+    map
+}
+
 /// Check predicate fields against request values
 /// Supports: method, path, body, query, headers, requestFrom, ip, form
-#[allow(clippy::too_many_arguments)]
 fn check_predicate_fields<F>(
     obj: &HashMap<String, serde_json::Value>,
-    method: &str,
-    path: &str,
-    query: &HashMap<String, String>,
-    headers: &HashMap<String, String>,
+    request: &PredicateRequest<'_>,
     body: &str,
     apply_except: &impl Fn(&str) -> String,
     compare: F,
     deep_equals: bool,
-    request_from: Option<&str>,
-    client_ip: Option<&str>,
-    form: Option<&HashMap<String, String>>,
     key_case_sensitive: bool,
 ) -> bool
 where
     F: Fn(&str, &str) -> bool,
 {
+    let PredicateRequest {
+        method,
+        path,
+        query,
+        headers,
+        request_from,
+        client_ip,
+        form,
+        ..
+    } = request;
     // Helper for key comparison based on keyCaseSensitive
     let key_matches = |expected_key: &str, actual_key: &str| -> bool {
         if key_case_sensitive {
@@ -327,13 +348,29 @@ where
 
     // Check body
     if let Some(expected) = obj.get("body") {
-        let expected_str = match expected {
-            serde_json::Value::String(s) => s.clone(),
-            _ => expected.to_string(),
-        };
         let actual = apply_except(body);
-        if !compare(&expected_str, &actual) {
-            return false;
+        if deep_equals && (expected.is_object() || expected.is_array()) {
+            // Compare JSON structurally, so key order and whitespace don't matter
+            let canonical = |mut value: serde_json::Value| {
+                value.sort_all_objects();
+                value.to_string()
+            };
+            match serde_json::from_str::<serde_json::Value>(&actual) {
+                Ok(actual) => {
+                    if !compare(&canonical(expected.clone()), &canonical(actual)) {
+                        return false;
+                    }
+                }
+                Err(_) => return false,
+            }
+        } else {
+            let expected_str = match expected {
+                serde_json::Value::String(s) => s.clone(),
+                _ => expected.to_string(),
+            };
+            if !compare(&expected_str, &actual) {
+                return false;
+            }
         }
     }
 
@@ -358,7 +395,8 @@ where
     // Check form fields (parsed from application/x-www-form-urlencoded) - Mountebank compatible
     if let Some(expected_form) = obj.get("form") {
         if let Some(expected_obj) = expected_form.as_object() {
-            let actual_form = form.cloned().unwrap_or_default();
+            let empty_form = HashMap::new();
+            let actual_form = form.as_ref().unwrap_or(&empty_form);
 
             // For deepEquals, check exact match (same number of fields)
             if deep_equals && expected_obj.len() != actual_form.len() {
@@ -458,21 +496,24 @@ where
 
 /// Check predicate fields with regex matching
 /// Supports: method, path, body, query, headers, requestFrom, ip, form
-#[allow(clippy::too_many_arguments)]
 fn check_predicate_fields_regex(
     obj: &HashMap<String, serde_json::Value>,
-    method: &str,
-    path: &str,
-    query: &HashMap<String, String>,
-    headers: &HashMap<String, String>,
+    request: &PredicateRequest<'_>,
     body: &str,
     apply_except: &impl Fn(&str) -> String,
     case_sensitive: bool,
-    request_from: Option<&str>,
-    client_ip: Option<&str>,
-    form: Option<&HashMap<String, String>>,
     key_case_sensitive: bool,
 ) -> bool {
+    let PredicateRequest {
+        method,
+        path,
+        query,
+        headers,
+        request_from,
+        client_ip,
+        form,
+        ..
+    } = request;
     let build_regex = |pattern: &str| -> Option<regex::Regex> {
         if case_sensitive {
             regex::Regex::new(pattern).ok()
@@ -544,7 +585,8 @@ fn check_predicate_fields_regex(
 
     // Check form fields
     if let Some(expected_form) = obj.get("form").and_then(|v| v.as_object()) {
-        let actual_form = form.cloned().unwrap_or_default();
+        let empty_form = HashMap::new();
+        let actual_form = form.as_ref().unwrap_or(&empty_form);
         for (key, pattern_val) in expected_form {
             let pattern = match pattern_val {
                 serde_json::Value::String(s) => s.as_str(),
@@ -628,11 +670,15 @@ fn check_predicate_fields_regex(
 /// Supports: body, query, headers, form
 fn check_exists_predicate(
     obj: &HashMap<String, serde_json::Value>,
-    query: &HashMap<String, String>,
-    headers: &HashMap<String, String>,
+    request: &PredicateRequest<'_>,
     body: &str,
-    form: Option<&HashMap<String, String>>,
 ) -> bool {
+    let PredicateRequest {
+        query,
+        headers,
+        form,
+        ..
+    } = request;
     // Check body exists
     if let Some(should_exist) = obj.get("body").and_then(|v| v.as_bool()) {
         let exists = !body.is_empty();
@@ -665,7 +711,8 @@ fn check_exists_predicate(
 
     // Check form fields exist
     if let Some(expected_form) = obj.get("form").and_then(|v| v.as_object()) {
-        let actual_form = form.cloned().unwrap_or_default();
+        let empty_form = HashMap::new();
+        let actual_form = form.as_ref().unwrap_or(&empty_form);
         for (key, should_exist_val) in expected_form {
             let should_exist = should_exist_val.as_bool().unwrap_or(true);
             let exists = actual_form.contains_key(key);
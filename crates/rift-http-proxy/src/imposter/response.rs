@@ -225,6 +225,36 @@ pub fn execute_stub_response_with_rift(
     }
 }
 
+/// Check whether a conditional request is satisfied by the response's `ETag`.
+///
+/// Returns true when the request's `If-None-Match` lists the response `ETag`
+/// (or `*`), meaning a 304 Not Modified should be returned instead of the body.
+/// Uses weak comparison, so `W/"v1"` matches `"v1"` (RFC 9110 §13.1.2).
+pub fn is_not_modified(
+    response_headers: &HashMap<String, String>,
+    request_headers: &HashMap<String, String>,
+) -> bool {
+    let find = |headers: &HashMap<String, String>, name: &str| {
+        headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.trim().to_string())
+    };
+
+    let (Some(etag), Some(if_none_match)) = (
+        find(response_headers, "etag"),
+        find(request_headers, "if-none-match"),
+    ) else {
+        return false;
+    };
+
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(&etag);
+    if_none_match
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == etag)
+}
+
 /// Get RiftScript config if the response is a RiftScript type
 pub fn get_rift_script_config(response: &StubResponse) -> Option<RiftScriptConfig> {
     match response {
@@ -408,4 +438,47 @@ mod tests {
     fn test_truncate_with_ellipsis_zero_max_len() {
         assert_eq!(truncate_with_ellipsis("hello", 0), "...");
     }
+
+    fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_is_not_modified_matching_etag() {
+        let response = headers(&[("ETag", "\"v1\"")]);
+
+        assert!(is_not_modified(
+            &response,
+            &headers(&[("If-None-Match", "\"v1\"")])
+        ));
+        // Weak comparison and lists of candidates
+        assert!(is_not_modified(
+            &response,
+            &headers(&[("if-none-match", "\"v0\", W/\"v1\"")])
+        ));
+        assert!(is_not_modified(
+            &response,
+            &headers(&[("If-None-Match", "*")])
+        ));
+    }
+
+    #[test]
+    fn test_is_not_modified_non_matching_etag() {
+        let response = headers(&[("ETag", "\"v2\"")]);
+
+        assert!(!is_not_modified(
+            &response,
+            &headers(&[("If-None-Match", "\"v1\"")])
+        ));
+        // No conditional header - full response
+        assert!(!is_not_modified(&response, &HashMap::new()));
+        // No ETag on the response - full response
+        assert!(!is_not_modified(
+            &HashMap::new(),
+            &headers(&[("If-None-Match", "\"v1\"")])
+        ));
+    }
 }
@@ -64,49 +64,21 @@ fn test_predicate_matching() {
     // Should match
     assert!(stub_matches(
         &stub.predicates,
-        "GET",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &empty_headers)
     ));
     assert!(stub_matches(
         &stub.predicates,
-        "get",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("get", "/test", None, &empty_headers)
     )); // case-insensitive method
 
     // Should not match
     assert!(!stub_matches(
         &stub.predicates,
-        "POST",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/test", None, &empty_headers)
     ));
     assert!(!stub_matches(
         &stub.predicates,
-        "GET",
-        "/other",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/other", None, &empty_headers)
     ));
 }
 
@@ -271,6 +243,66 @@ async fn test_imposter_manager_create_delete() {
     }
 }
 
+#[tokio::test]
+async fn test_conditional_response_only_for_successful_get() {
+    let manager = ImposterManager::new();
+    let config: ImposterConfig = serde_json::from_value(serde_json::json!({
+        "protocol": "http",
+        "host": "127.0.0.1",
+        "stubs": [
+            {
+                "predicates": [{"equals": {"path": "/doc"}}],
+                "responses": [{
+                    "is": {"statusCode": 200, "headers": {"ETag": "\"v1\""}, "body": "full body"},
+                    "_rift": {"conditional": true}
+                }]
+            },
+            {
+                "predicates": [{"equals": {"path": "/missing"}}],
+                "responses": [{
+                    "is": {"statusCode": 404, "headers": {"ETag": "\"v1\""}, "body": "not found"},
+                    "_rift": {"conditional": true}
+                }]
+            }
+        ]
+    }))
+    .unwrap();
+    let port = manager.create_imposter(config).await.unwrap();
+    let base = format!("http://127.0.0.1:{port}");
+    let client = reqwest::Client::new();
+
+    let send = |method: reqwest::Method, path: &str, etag: &str| {
+        client
+            .request(method, format!("{base}{path}"))
+            .header("If-None-Match", etag)
+            .send()
+    };
+
+    // Matching ETag on a 2xx GET: 304 with no body
+    let resp = send(reqwest::Method::GET, "/doc", "\"v1\"").await.unwrap();
+    assert_eq!(resp.status(), 304);
+    assert_eq!(resp.text().await.unwrap(), "");
+
+    // Non-matching ETag: full response
+    let resp = send(reqwest::Method::GET, "/doc", "\"v2\"").await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.text().await.unwrap(), "full body");
+
+    // Unsafe methods never become 304
+    let resp = send(reqwest::Method::POST, "/doc", "\"v1\"").await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.text().await.unwrap(), "full body");
+
+    // Non-2xx responses are returned as-is
+    let resp = send(reqwest::Method::GET, "/missing", "\"v1\"")
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+    assert_eq!(resp.text().await.unwrap(), "not found");
+
+    manager.delete_imposter(port).await.unwrap();
+}
+
 #[test]
 fn test_add_decorate_behavior_serde() {
     let json = r#"{"to":"http://localhost:4546","mode":"proxyOnce","addDecorateBehavior":"function(request, response) { response.headers['X-Proxied'] = 'true'; }"}"#;
@@ -477,52 +509,75 @@ fn test_predicate_ends_with() {
     // Should match
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/api/lender-details",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/api/lender-details", None, &empty_headers)
     ));
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/user-details",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/user-details", None, &empty_headers)
     ));
 
     // Should not match
     assert!(!stub_matches(
         &predicates,
-        "GET",
-        "/details/other",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/details/other", None, &empty_headers)
     ));
     assert!(!stub_matches(
         &predicates,
-        "GET",
-        "/api/details/v1",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/api/details/v1", None, &empty_headers)
     ));
 }
 
+#[test]
+fn test_predicate_inject_matches_odd_length_paths() {
+    let predicates = predicates_from_jsons(vec![serde_json::json!({
+        "inject": "request.path.len() % 2 == 1"
+    })]);
+    let empty_headers = HashMap::new();
+
+    assert!(stub_matches(
+        &predicates,
+        &PredicateRequest::new("GET", "/ab", None, &empty_headers)
+    ));
+    assert!(!stub_matches(
+        &predicates,
+        &PredicateRequest::new("GET", "/abc", None, &empty_headers)
+    ));
+
+    // Round-trips as the script source
+    assert_eq!(
+        serde_json::to_value(&predicates[0]).unwrap(),
+        serde_json::json!({"inject": "request.path.len() % 2 == 1"})
+    );
+}
+
+#[test]
+fn test_predicate_inject_sees_request_fields() {
+    let predicates = predicates_from_jsons(vec![serde_json::json!({
+        "inject": r#"request.method == "POST" && request.query.page == "2"
+            && request.headers["x-beta"] == "1" && request.body.contains("boom")"#
+    })]);
+    let headers = HashMap::from([("x-beta".to_string(), "1".to_string())]);
+
+    let request = PredicateRequest::new("POST", "/orders", Some("page=2"), &headers);
+    assert!(stub_matches(
+        &predicates,
+        &request.clone().with_body("it went boom")
+    ));
+    assert!(!stub_matches(&predicates, &request.with_body("fine")));
+}
+
+#[test]
+fn test_predicate_inject_errors_do_not_match() {
+    let empty_headers = HashMap::new();
+    let request = PredicateRequest::new("GET", "/", None, &empty_headers);
+    for script in ["request.path +", "request.path", "loop {}"] {
+        let predicates = predicates_from_jsons(vec![serde_json::json!({"inject": script})]);
+        // Twice, to go through the cached compilation
+        assert!(!stub_matches(&predicates, &request), "{script}");
+        assert!(!stub_matches(&predicates, &request), "{script}");
+    }
+}
+
 #[test]
 fn test_predicate_deep_equals_method() {
     let predicates = vec![serde_json::json!({
@@ -534,36 +589,15 @@ fn test_predicate_deep_equals_method() {
 
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &empty_headers)
     ));
     assert!(stub_matches(
         &predicates,
-        "get",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("get", "/test", None, &empty_headers)
     )); // case-insensitive
     assert!(!stub_matches(
         &predicates,
-        "POST",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/test", None, &empty_headers)
     ));
 }
 
@@ -579,38 +613,98 @@ fn test_predicate_deep_equals_body() {
     // Empty body should match
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        None,
-        &empty_headers,
-        Some(""),
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &empty_headers).with_body("")
     ));
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &empty_headers)
     ));
 
     // Non-empty body should not match
     assert!(!stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        None,
-        &empty_headers,
-        Some("content"),
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &empty_headers).with_body("content")
+    ));
+}
+
+#[test]
+fn test_predicate_deep_equals_objects_are_strict() {
+    let predicates = predicates_from_jsons(vec![serde_json::json!({
+        "deepEquals": {
+            "path": "/orders",
+            "query": {"status": "open"},
+            "body": {"id": 1, "tags": ["a"]}
+        }
+    })]);
+    let empty_headers = HashMap::new();
+    let matches = |path: &str, query: &str, body: &str| {
+        stub_matches(
+            &predicates,
+            &PredicateRequest::new("POST", path, Some(query), &empty_headers).with_body(body),
+        )
+    };
+
+    assert!(matches(
+        "/orders",
+        "status=open",
+        r#"{"tags": ["a"], "id": 1}"#
+    ));
+    // Scalar fields compare as whole strings
+    assert!(!matches(
+        "/orders/1",
+        "status=open",
+        r#"{"id": 1, "tags": ["a"]}"#
+    ));
+    // Extra query parameters or body keys break deep equality
+    assert!(!matches(
+        "/orders",
+        "status=open&page=2",
+        r#"{"id": 1, "tags": ["a"]}"#
+    ));
+    assert!(!matches(
+        "/orders",
+        "status=open",
+        r#"{"id": 1, "tags": ["a"], "extra": true}"#
+    ));
+    assert!(!matches(
+        "/orders",
+        "status=open",
+        r#"{"id": 1, "tags": ["a", "b"]}"#
+    ));
+}
+
+#[test]
+fn test_predicate_body_selectors() {
+    let empty_headers = HashMap::new();
+    let matches = |predicate: serde_json::Value, body: &str| {
+        stub_matches(
+            &predicates_from_jsons(vec![predicate]),
+            &PredicateRequest::new("POST", "/", None, &empty_headers).with_body(body),
+        )
+    };
+
+    // The selected value is matched, not the raw body
+    let jsonpath = serde_json::json!({
+        "equals": {"body": "Alice"},
+        "jsonpath": {"selector": "$.user.name"}
+    });
+    assert!(matches(jsonpath.clone(), r#"{"user": {"name": "Alice"}}"#));
+    assert!(!matches(
+        jsonpath,
+        r#"{"user": {"name": "Bob"}, "note": "Alice"}"#
+    ));
+
+    let xpath = serde_json::json!({
+        "equals": {"body": "42"},
+        "xpath": {"selector": "//x:id", "ns": {"x": "urn:orders"}}
+    });
+    assert!(matches(
+        xpath.clone(),
+        r#"<o:order xmlns:o="urn:orders"><o:id>42</o:id></o:order>"#
+    ));
+    assert!(!matches(
+        xpath,
+        r#"<o:order xmlns:o="urn:other"><o:id>42</o:id></o:order>"#
     ));
 }
 
@@ -626,60 +720,30 @@ fn test_predicate_contains_query() {
     // Should match - query contains "CofTest"
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        Some("lenderIds=CofTestWL"),
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", Some("lenderIds=CofTestWL"), &empty_headers)
     ));
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        Some("lenderIds=CofTest"),
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", Some("lenderIds=CofTest"), &empty_headers)
     ));
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        Some("lenderIds=123CofTest456"),
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new(
+            "GET",
+            "/test",
+            Some("lenderIds=123CofTest456"),
+            &empty_headers
+        )
     ));
 
     // Should not match
     assert!(!stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        Some("lenderIds=Other"),
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", Some("lenderIds=Other"), &empty_headers)
     ));
     assert!(!stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &empty_headers)
     ));
 }
 
@@ -695,14 +759,7 @@ fn test_predicate_equals_headers() {
 
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        None,
-        &headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &headers)
     ));
 
     // Header key lookup is case-insensitive
@@ -710,14 +767,7 @@ fn test_predicate_equals_headers() {
     headers_lower.insert("content-type".to_string(), "application/json".to_string());
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        None,
-        &headers_lower,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &headers_lower)
     ));
 
     // Wrong value
@@ -725,28 +775,14 @@ fn test_predicate_equals_headers() {
     wrong_headers.insert("Content-Type".to_string(), "text/html".to_string());
     assert!(!stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        None,
-        &wrong_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &wrong_headers)
     ));
 
     // Missing header
     let empty_headers = HashMap::new();
     assert!(!stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &empty_headers)
     ));
 }
 
@@ -767,54 +803,86 @@ fn test_predicate_exists() {
     // All exist
     assert!(stub_matches(
         &predicates,
-        "POST",
-        "/test",
-        Some("token=abc"),
-        &headers,
-        Some("body content"),
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/test", Some("token=abc"), &headers)
+            .with_body("body content")
     ));
 
     // Missing query param
     assert!(!stub_matches(
         &predicates,
-        "POST",
-        "/test",
-        None,
-        &headers,
-        Some("body content"),
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/test", None, &headers).with_body("body content")
     ));
 
     // Missing header
     let empty_headers = HashMap::new();
     assert!(!stub_matches(
         &predicates,
-        "POST",
-        "/test",
-        Some("token=abc"),
-        &empty_headers,
-        Some("body content"),
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/test", Some("token=abc"), &empty_headers)
+            .with_body("body content")
     ));
 
     // Missing body
     assert!(!stub_matches(
         &predicates,
-        "POST",
-        "/test",
-        Some("token=abc"),
-        &headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/test", Some("token=abc"), &headers)
+    ));
+}
+
+#[test]
+fn test_predicate_exists_header_absent_or_present() {
+    let present = predicates_from_jsons(vec![serde_json::json!({
+        "exists": {"headers": {"authorization": true}}
+    })]);
+    let absent = predicates_from_jsons(vec![serde_json::json!({
+        "exists": {"headers": {"authorization": false}}
+    })]);
+
+    // Header names are case-insensitive
+    let mut headers = HashMap::new();
+    headers.insert("Authorization".to_string(), "Bearer xyz".to_string());
+    let empty_headers = HashMap::new();
+
+    for (predicates, with_header, without_header) in
+        [(&present, true, false), (&absent, false, true)]
+    {
+        assert_eq!(
+            stub_matches(
+                predicates,
+                &PredicateRequest::new("GET", "/", None, &headers)
+            ),
+            with_header
+        );
+        assert_eq!(
+            stub_matches(
+                predicates,
+                &PredicateRequest::new("GET", "/", None, &empty_headers)
+            ),
+            without_header
+        );
+        // A present but empty header exists
+        let mut blank = HashMap::new();
+        blank.insert("authorization".to_string(), String::new());
+        assert_eq!(
+            stub_matches(predicates, &PredicateRequest::new("GET", "/", None, &blank)),
+            with_header
+        );
+    }
+}
+
+#[test]
+fn test_predicate_not_equals_method() {
+    let predicates = predicates_from_jsons(vec![serde_json::json!({
+        "not": {"equals": {"method": "GET"}}
+    })]);
+    let empty_headers = HashMap::new();
+
+    assert!(!stub_matches(
+        &predicates,
+        &PredicateRequest::new("GET", "/", None, &empty_headers)
+    ));
+    assert!(stub_matches(
+        &predicates,
+        &PredicateRequest::new("POST", "/", None, &empty_headers)
     ));
 }
 
@@ -830,36 +898,15 @@ fn test_predicate_logical_not() {
     // Should match anything except DELETE
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &empty_headers)
     ));
     assert!(stub_matches(
         &predicates,
-        "POST",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/test", None, &empty_headers)
     ));
     assert!(!stub_matches(
         &predicates,
-        "DELETE",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("DELETE", "/test", None, &empty_headers)
     ));
 }
 
@@ -877,36 +924,15 @@ fn test_predicate_logical_or() {
 
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/test", None, &empty_headers)
     ));
     assert!(stub_matches(
         &predicates,
-        "HEAD",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("HEAD", "/test", None, &empty_headers)
     ));
     assert!(!stub_matches(
         &predicates,
-        "POST",
-        "/test",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/test", None, &empty_headers)
     ));
 }
 
@@ -924,36 +950,15 @@ fn test_predicate_logical_and() {
 
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/api/users",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/api/users", None, &empty_headers)
     ));
     assert!(!stub_matches(
         &predicates,
-        "POST",
-        "/api/users",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/api/users", None, &empty_headers)
     ));
     assert!(!stub_matches(
         &predicates,
-        "GET",
-        "/other",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/other", None, &empty_headers)
     ));
 }
 
@@ -971,47 +976,19 @@ fn test_predicate_matches_regex_all_fields() {
 
     assert!(stub_matches(
         &predicates,
-        "GET",
-        "/api/v1/users",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/api/v1/users", None, &empty_headers)
     ));
     assert!(stub_matches(
         &predicates,
-        "POST",
-        "/api/v2/items",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/api/v2/items", None, &empty_headers)
     ));
     assert!(!stub_matches(
         &predicates,
-        "DELETE",
-        "/api/v1/users",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("DELETE", "/api/v1/users", None, &empty_headers)
     ));
     assert!(!stub_matches(
         &predicates,
-        "GET",
-        "/other/path",
-        None,
-        &empty_headers,
-        None,
-        None,
-        None,
-        None
+        &PredicateRequest::new("GET", "/other/path", None, &empty_headers)
     ));
 }
 
@@ -1026,24 +1003,12 @@ fn test_predicate_matches_body_regex() {
 
     assert!(stub_matches(
         &predicates,
-        "POST",
-        "/test",
-        None,
-        &empty_headers,
-        Some(r#"{"userId": "abc-123-def"}"#),
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/test", None, &empty_headers)
+            .with_body(r#"{"userId": "abc-123-def"}"#)
     ));
     assert!(!stub_matches(
         &predicates,
-        "POST",
-        "/test",
-        None,
-        &empty_headers,
-        Some(r#"{"userId": "invalid!"}"#),
-        None,
-        None,
-        None
+        &PredicateRequest::new("POST", "/test", None, &empty_headers)
+            .with_body(r#"{"userId": "invalid!"}"#)
     ));
 }
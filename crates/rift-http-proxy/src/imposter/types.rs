@@ -4,6 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 
 // ============================================================================
 // Recorded Request Types
@@ -152,7 +153,58 @@ pub enum PredicateOperation {
     Not(Box<Predicate>),
     Or(Vec<Predicate>),
     And(Vec<Predicate>),
-    // TODO: "inject" predicate operation is missing
+    /// Rhai script deciding the match; see [`InjectScript`]
+    Inject(InjectScript),
+}
+
+/// Script of an `inject` predicate, compiled on first use.
+///
+/// The script sees the request as a `request` map (`method`, `path`,
+/// `query`, `headers`, `body`, `requestFrom`, `ip` and `form`) and evaluates
+/// to a boolean: `"inject": "request.path.len() % 2 == 1"`. A script that
+/// fails or returns anything else doesn't match.
+///
+/// The script runs for every request that reaches its stub, which costs far
+/// more than the built-in operations. Stub analysis can't see what it
+/// matches, so no stub is reported as shadowed by one using an inject
+/// predicate unless their predicates are identical.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct InjectScript {
+    pub source: String,
+    /// Shared by clones, so a stub's copies compile the script once
+    compiled: Arc<OnceLock<Result<rhai::AST, String>>>,
+}
+
+impl InjectScript {
+    /// The compiled script, or the compile error
+    pub(crate) fn compiled(
+        &self,
+        compile: impl FnOnce(&str) -> Result<rhai::AST, String>,
+    ) -> &Result<rhai::AST, String> {
+        self.compiled.get_or_init(|| compile(&self.source))
+    }
+}
+
+impl From<String> for InjectScript {
+    fn from(source: String) -> Self {
+        Self {
+            source,
+            compiled: Arc::default(),
+        }
+    }
+}
+
+impl From<InjectScript> for String {
+    fn from(script: InjectScript) -> Self {
+        script.source
+    }
+}
+
+impl std::fmt::Debug for InjectScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("InjectScript").field(&self.source).finish()
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -783,6 +835,9 @@ pub struct RiftResponseExtension {
     /// Script-based response generation
     #[serde(skip_serializing_if = "Option::is_none")]
     pub script: Option<RiftScriptConfig>,
+    /// Honor `If-None-Match` against the response's `ETag` header (returns 304 on match)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub conditional: bool,
 }
 
 /// Fault injection configuration for responses
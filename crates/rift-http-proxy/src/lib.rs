@@ -14,6 +14,9 @@ pub mod recording;
 // ===== Rift Extensions (features beyond Mountebank) =====
 pub mod extensions;
 
+// Generating rules from other API descriptions
+pub mod import;
+
 // Re-export extension modules at top level for backward compatibility
 pub use extensions::fault;
 pub use extensions::flow_state;
@@ -16,6 +16,12 @@
 //! rift --port 3000                        # Admin API on port 3000
 //! rift --configfile imposters.json        # Load imposters from file
 //! rift --datadir ./mb-data                # Persist imposters to directory
+//! rift --config rift.yaml                 # Run the fault injection proxy
+//! cat rift.yaml | rift --config -         # Read proxy config from stdin
+//! RIFT_CONFIG="$(cat rift.yaml)" rift     # Inline proxy config
+//! rift --config rift.yaml --check         # Validate the proxy config and exit
+//! rift --config rift.yaml --print-config  # Show the config as parsed
+//! rift --import-openapi api.yaml --emit rules.yaml  # Mock rules from a spec
 //! ```
 
 // ===== Core Mountebank-compatible modules =====
@@ -31,6 +37,9 @@ mod recording;
 // ===== Rift Extensions (features beyond Mountebank) =====
 mod extensions;
 
+// Generating rules from other API descriptions
+mod import;
+
 // Internal modules
 mod scripting;
 
@@ -38,10 +47,14 @@ mod scripting;
 use extensions::metrics;
 
 use admin_api::AdminApiServer;
-use clap::{Parser, Subcommand};
+use anyhow::Context;
+use clap::{Parser, Subcommand, ValueEnum};
+use config::Config;
 use imposter::{ImposterConfig, ImposterManager};
-use std::net::SocketAddr;
-use std::path::PathBuf;
+use proxy::ProxyServer;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::sync::Arc;
 use tracing::{error, info};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
@@ -118,8 +131,53 @@ struct Cli {
     /// Metrics server port
     #[arg(long, default_value = "9090", env = "RIFT_METRICS_PORT")]
     metrics_port: u16,
+
+    // === Proxy mode options ===
+    /// Run the fault injection proxy from a YAML or JSON config file (`-` reads
+    /// YAML from stdin).
+    /// Without this flag, inline YAML from RIFT_CONFIG is used if set.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Validate the proxy config, print a summary and exit without binding
+    /// any ports
+    #[arg(long)]
+    check: bool,
+
+    /// Print the proxy config with defaults and the deployment mode filled
+    /// in, then exit
+    #[arg(long)]
+    print_config: bool,
+
+    /// Output format for --print-config
+    #[arg(long, value_enum, default_value = "yaml")]
+    format: ConfigFormat,
+
+    /// IP address the proxy listens on, overriding `listen.bind` in the
+    /// proxy config
+    #[arg(long, value_name = "IP")]
+    bind: Option<IpAddr>,
+
+    /// Generate mock rules from an OpenAPI 3 spec (YAML or JSON), write
+    /// them as a YAML rule list and exit
+    #[arg(long, value_name = "FILE")]
+    import_openapi: Option<PathBuf>,
+
+    /// File the --import-openapi rules are written to (default: stdout)
+    #[arg(long, value_name = "FILE", requires = "import_openapi")]
+    emit: Option<PathBuf>,
+}
+
+/// Serialization format for --print-config
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ConfigFormat {
+    Yaml,
+    Json,
 }
 
+/// Environment variable holding inline proxy config YAML
+const RIFT_CONFIG_ENV: &str = "RIFT_CONFIG";
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Start the Rift server (default command)
@@ -158,14 +216,9 @@ enum Commands {
     },
 }
 
-fn main() -> Result<(), anyhow::Error> {
+fn main() -> ExitCode {
     let cli = Cli::parse();
 
-    // Install default cryptographic provider for rustls
-    rustls::crypto::ring::default_provider()
-        .install_default()
-        .map_err(|_| anyhow::anyhow!("Failed to install default crypto provider"))?;
-
     // Initialize tracing based on loglevel
     let log_level = match cli.loglevel.to_lowercase().as_str() {
         "debug" => "debug",
@@ -181,6 +234,32 @@ fn main() -> Result<(), anyhow::Error> {
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(filter)))
         .init();
 
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Run the command line's mode; errors are logged by `main`
+fn run(cli: Cli) -> Result<(), anyhow::Error> {
+    // Install default cryptographic provider for rustls
+    rustls::crypto::ring::default_provider()
+        .install_default()
+        .map_err(|_| anyhow::anyhow!("Failed to install default crypto provider"))?;
+
+    if let Some(spec) = &cli.import_openapi {
+        return import_openapi(spec, cli.emit.as_deref());
+    }
+    if cli.check {
+        return check_proxy_config(cli.config.as_deref(), cli.bind);
+    }
+    if cli.print_config {
+        return print_proxy_config(cli.config.as_deref(), cli.bind, cli.format);
+    }
+
     // Write PID file if requested
     if let Some(ref pidfile) = cli.pidfile {
         let pid = std::process::id();
@@ -212,11 +291,138 @@ fn main() -> Result<(), anyhow::Error> {
         }
     }
 
+    if let Some((config, config_path)) = load_proxy_config(cli.config.as_deref(), cli.bind)? {
+        info!("Starting Rift proxy on port {}", config.listen.port);
+        return run_proxy_mode(config, config_path);
+    }
+
     // Start in Mountebank mode
     info!("Starting Rift on port {}", cli.port);
     run_mountebank_mode(cli)
 }
 
+/// Load proxy config from `--config` (a path, or `-` for stdin), falling
+/// back to inline YAML in `RIFT_CONFIG`, with `--bind` applied.
+///
+/// Returns the config and, when it came from a file, the path to re-read
+/// on reload. Returns None if no proxy config was given.
+fn load_proxy_config(
+    config_arg: Option<&Path>,
+    bind: Option<IpAddr>,
+) -> Result<Option<(Config, Option<PathBuf>)>, anyhow::Error> {
+    let loaded = read_proxy_config(config_arg)?;
+    Ok(loaded.map(|(mut config, path)| {
+        if let Some(bind) = bind {
+            config.listen.bind = bind.to_string();
+        }
+        (config, path)
+    }))
+}
+
+/// Read proxy config from `--config` or `RIFT_CONFIG`
+fn read_proxy_config(
+    config_arg: Option<&Path>,
+) -> Result<Option<(Config, Option<PathBuf>)>, anyhow::Error> {
+    match config_arg {
+        Some(path) if path == Path::new("-") => {
+            let config = Config::from_reader(std::io::stdin().lock())
+                .context("Failed to load config from stdin")?;
+            Ok(Some((config, None)))
+        }
+        Some(path) => {
+            let config = Config::from_file(path)
+                .with_context(|| format!("Failed to load config from {path:?}"))?;
+            Ok(Some((config, Some(path.to_path_buf()))))
+        }
+        None => match std::env::var(RIFT_CONFIG_ENV) {
+            Ok(yaml) => {
+                let config: Config = yaml
+                    .parse()
+                    .with_context(|| format!("Failed to load config from {RIFT_CONFIG_ENV}"))?;
+                Ok(Some((config, None)))
+            }
+            Err(_) => Ok(None),
+        },
+    }
+}
+
+/// Load and compile the proxy config, reporting what it contains.
+///
+/// Errors are returned, so the process exits non-zero on an invalid config.
+fn check_proxy_config(
+    config_arg: Option<&Path>,
+    bind: Option<IpAddr>,
+) -> Result<(), anyhow::Error> {
+    let (config, _) = load_proxy_config(config_arg, bind)?.ok_or_else(|| {
+        anyhow::anyhow!("--check needs a proxy config: pass --config or set {RIFT_CONFIG_ENV}")
+    })?;
+    ProxyServer::check(&config)?;
+
+    let upstreams = config.upstreams.len() + usize::from(config.upstream.is_some());
+    println!(
+        "Config OK: {} rules, {} script rules, {} upstreams, {} routes",
+        config.rules.len(),
+        config.script_rules.len(),
+        upstreams,
+        config.routing.len()
+    );
+    Ok(())
+}
+
+/// Print the normalized proxy config to stdout
+fn print_proxy_config(
+    config_arg: Option<&Path>,
+    bind: Option<IpAddr>,
+    format: ConfigFormat,
+) -> Result<(), anyhow::Error> {
+    let (config, _) = load_proxy_config(config_arg, bind)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "--print-config needs a proxy config: pass --config or set {RIFT_CONFIG_ENV}"
+        )
+    })?;
+    let normalized = config.normalized();
+    let output = match format {
+        ConfigFormat::Yaml => serde_yaml::to_string(&normalized)?,
+        ConfigFormat::Json => serde_json::to_string_pretty(&normalized)? + "\n",
+    };
+    print!("{output}");
+    Ok(())
+}
+
+/// Write the mock rules generated from an OpenAPI spec, as a YAML list
+/// to `!include` in a config's `rules`
+fn import_openapi(spec_path: &Path, emit: Option<&Path>) -> Result<(), anyhow::Error> {
+    let spec = std::fs::read_to_string(spec_path)
+        .with_context(|| format!("Failed to read OpenAPI spec {spec_path:?}"))?;
+    let rules = import::openapi::from_openapi(&spec)?;
+    let output = serde_yaml::to_string(&rules)?;
+    match emit {
+        Some(path) => {
+            std::fs::write(path, output)
+                .with_context(|| format!("Failed to write rules to {path:?}"))?;
+            info!("Wrote {} rules to {:?}", rules.len(), path);
+        }
+        None => print!("{output}"),
+    }
+    Ok(())
+}
+
+/// Run the YAML-configured fault injection proxy
+fn run_proxy_mode(config: Config, config_path: Option<PathBuf>) -> Result<(), anyhow::Error> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+
+    runtime.block_on(async move {
+        // The proxy serves metrics and its admin endpoints itself
+        let mut server = ProxyServer::new(config).await?;
+        if let Some(path) = config_path {
+            server = server.with_config_path(path);
+        }
+        server.run().await
+    })
+}
+
 /// Run in Mountebank-compatible mode
 fn run_mountebank_mode(cli: Cli) -> Result<(), anyhow::Error> {
     let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -252,7 +458,7 @@ fn run_mountebank_mode(cli: Cli) -> Result<(), anyhow::Error> {
             &cli.host
         };
 
-        let addr: SocketAddr = format!("{}:{}", host, cli.port).parse()?;
+        let addr = admin_addr(host, cli.port)?;
 
         // Start admin API server
         info!(
@@ -275,6 +481,27 @@ fn run_mountebank_mode(cli: Cli) -> Result<(), anyhow::Error> {
     })
 }
 
+/// Resolve the admin API's `--host` and `--port`. The host is an IP
+/// address, with or without IPv6 brackets, or a name such as `localhost`.
+fn admin_addr(host: &str, port: u16) -> Result<SocketAddr, anyhow::Error> {
+    let ip = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    if let Ok(ip) = ip.parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, port));
+    }
+    (host, port)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "invalid --host '{host}': expected an IP address or resolvable hostname"
+            )
+        })
+}
+
 /// Load imposters from a JSON config file
 async fn load_imposters_from_file(
     manager: &Arc<ImposterManager>,
@@ -433,3 +660,31 @@ async fn run_metrics_server(port: u16) -> anyhow::Result<()> {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admin_addr() {
+        assert_eq!(
+            admin_addr("127.0.0.1", 2525).unwrap(),
+            SocketAddr::from(([127, 0, 0, 1], 2525))
+        );
+        assert_eq!(
+            admin_addr("::1", 2525).unwrap(),
+            "[::1]:2525".parse().unwrap()
+        );
+        assert_eq!(
+            admin_addr("[::1]", 2525).unwrap(),
+            "[::1]:2525".parse().unwrap()
+        );
+        assert_eq!(admin_addr("localhost", 2525).unwrap().port(), 2525);
+
+        let err = admin_addr("not a host", 2525).unwrap_err();
+        assert!(
+            err.to_string().contains("invalid --host 'not a host'"),
+            "{err}"
+        );
+    }
+}
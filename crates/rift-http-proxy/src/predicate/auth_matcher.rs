@@ -0,0 +1,153 @@
+//! `Authorization` header matching for Basic and Bearer credentials.
+//!
+//! Rules can match credentials directly instead of regexing the encoded
+//! header:
+//!
+//! ```yaml
+//! auth:
+//!   basic: { username: admin, password: secret }
+//! # or
+//! auth:
+//!   bearer: { startsWith: "test-" }
+//! ```
+//!
+//! A missing or malformed header (another scheme, bad base64, no `:` in
+//! the decoded credentials) never matches.
+
+use super::string_matcher::{CompiledStringMatcher, StringMatcher};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// Credentials required in the `Authorization` header.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum AuthMatcher {
+    /// `Basic` credentials with this username and password
+    Basic { basic: BasicCredentials },
+    /// A `Bearer` token matching the string predicate
+    Bearer { bearer: BearerToken },
+}
+
+/// Username and password of a `Basic` credential
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct BasicCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// String predicate on a `Bearer` token, e.g. `{ equals: abc123 }`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct BearerToken {
+    #[serde(flatten)]
+    pub token: StringMatcher,
+}
+
+/// Compiled `Authorization` header matcher.
+#[derive(Debug, Clone)]
+pub enum CompiledAuthMatcher {
+    Basic(BasicCredentials),
+    Bearer(CompiledStringMatcher),
+}
+
+impl CompiledAuthMatcher {
+    pub fn compile(config: &AuthMatcher) -> Result<Self, regex::Error> {
+        Ok(match config {
+            AuthMatcher::Basic { basic } => CompiledAuthMatcher::Basic(basic.clone()),
+            AuthMatcher::Bearer { bearer } => {
+                CompiledAuthMatcher::Bearer(CompiledStringMatcher::compile(&bearer.token, false)?)
+            }
+        })
+    }
+
+    /// Check an `Authorization` header value. Usernames and passwords
+    /// compare exactly; `case_sensitive` applies to bearer tokens.
+    pub fn matches(&self, authorization: Option<&str>, case_sensitive: bool) -> bool {
+        match self {
+            CompiledAuthMatcher::Basic(expected) => authorization
+                .and_then(|value| credentials(value, "Basic"))
+                .and_then(decode_basic)
+                .is_some_and(|(username, password)| {
+                    username == expected.username && password == expected.password
+                }),
+            CompiledAuthMatcher::Bearer(matcher) => authorization
+                .and_then(|value| credentials(value, "Bearer"))
+                .is_some_and(|token| matcher.matches(Some(token), case_sensitive)),
+        }
+    }
+}
+
+/// The credentials following `scheme` in a header value; the scheme name
+/// is case-insensitive
+fn credentials<'a>(value: &'a str, scheme: &str) -> Option<&'a str> {
+    let (name, credentials) = value.trim().split_once(' ')?;
+    let credentials = credentials.trim_start();
+    (name.eq_ignore_ascii_case(scheme) && !credentials.is_empty()).then_some(credentials)
+}
+
+fn decode_basic(encoded: &str) -> Option<(String, String)> {
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    Some((username.to_string(), password.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn basic_header(credentials: &str) -> String {
+        format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(credentials)
+        )
+    }
+
+    fn basic() -> CompiledAuthMatcher {
+        let config: AuthMatcher =
+            serde_yaml::from_str("basic: {username: admin, password: 'p:ss'}").unwrap();
+        CompiledAuthMatcher::compile(&config).unwrap()
+    }
+
+    #[test]
+    fn test_basic_auth_correct_credentials() {
+        let matcher = basic();
+        assert!(matcher.matches(Some(&basic_header("admin:p:ss")), true));
+        // The scheme name is case-insensitive
+        let lowercase = basic_header("admin:p:ss").replace("Basic", "basic");
+        assert!(matcher.matches(Some(&lowercase), true));
+    }
+
+    #[test]
+    fn test_basic_auth_wrong_credentials() {
+        let matcher = basic();
+        assert!(!matcher.matches(Some(&basic_header("admin:wrong")), true));
+        assert!(!matcher.matches(Some(&basic_header("Admin:p:ss")), false));
+        assert!(!matcher.matches(None, true));
+    }
+
+    #[test]
+    fn test_malformed_headers_fail_closed() {
+        let matcher = basic();
+        for header in [
+            "Basic",
+            "Basic not*base64",
+            "Basic YWRtaW4=", // "admin", no colon
+            "Bearer YWRtaW46cDpzcw==",
+            "YWRtaW46cDpzcw==",
+        ] {
+            assert!(!matcher.matches(Some(header), true), "{header}");
+        }
+    }
+
+    #[test]
+    fn test_bearer_token_prefix() {
+        let config: AuthMatcher = serde_yaml::from_str("bearer: {startsWith: test-}").unwrap();
+        let matcher = CompiledAuthMatcher::compile(&config).unwrap();
+        assert!(matcher.matches(Some("Bearer test-abc123"), true));
+        assert!(!matcher.matches(Some("Bearer prod-abc123"), true));
+        assert!(!matcher.matches(Some("Bearer "), true));
+        assert!(!matcher.matches(Some(&basic_header("test-user:x")), true));
+    }
+}
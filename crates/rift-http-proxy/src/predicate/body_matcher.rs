@@ -3,16 +3,25 @@
 //! Supports various body matching strategies including JSON and XPath.
 
 use super::matcher::CachedValue;
+use super::regex_limits::build_regex;
 use super::string_matcher::{CompiledStringMatcher, StringMatcher};
 use regex::Regex;
-use serde::{Deserialize, Serialize};
+use serde::de::value::{EnumAccessDeserializer, MapAccessDeserializer};
+use serde::de::{DeserializeSeed, EnumAccess, Error as _, IntoDeserializer, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
 use std::sync::Arc;
 
 /// Body matching configuration.
 ///
 /// Supports various body matching strategies for request body content.
+///
+/// (De)serialized by hand so `ignoreArrayOrder` can sit beside `jsonEquals`;
+/// every other variant uses the derived single-key form.
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
-#[serde(rename_all = "camelCase")]
+#[serde(remote = "Self", rename_all = "camelCase")]
 pub enum BodyMatcher {
     /// Exact string match
     Equals(String),
@@ -23,9 +32,13 @@ pub enum BodyMatcher {
     /// Regex pattern match
     Matches(String),
 
-    /// JSON deep equality (for JSON bodies)
-    #[serde(rename = "jsonEquals")]
-    JsonEquals(serde_json::Value),
+    /// JSON deep equality (for JSON bodies), with options beside it:
+    /// `{ jsonEquals: ..., ignoreArrayOrder: true }`
+    #[serde(skip)]
+    JsonEquals {
+        expected: serde_json::Value,
+        options: JsonEqualsOptions,
+    },
 
     /// JSON path expression match
     #[serde(rename = "jsonPath")]
@@ -39,9 +52,146 @@ pub enum BodyMatcher {
     #[serde(rename = "xpath")]
     XPath {
         path: String,
+        /// Namespace prefixes used in `path` (prefix -> URI)
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        namespaces: HashMap<String, String>,
         #[serde(flatten)]
         matcher: StringMatcher,
     },
+
+    /// Substring occurs more than `count` times (non-overlapping)
+    #[serde(rename = "repeatsMoreThan")]
+    RepeatsMoreThan { pattern: String, count: usize },
+}
+
+/// Options for `jsonEquals`, given as keys beside it.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct JsonEqualsOptions {
+    /// Compare arrays as multisets rather than by index
+    pub ignore_array_order: bool,
+    /// Compare numbers exactly, so `30` and `30.0` differ
+    pub strict_numbers: bool,
+    /// Largest difference at which two numbers are equal, when one is a float
+    pub number_epsilon: f64,
+}
+
+impl JsonEqualsOptions {
+    const KEYS: &[&str] = &["ignoreArrayOrder", "strictNumbers", "numberEpsilon"];
+}
+
+impl Default for JsonEqualsOptions {
+    fn default() -> Self {
+        Self {
+            ignore_array_order: false,
+            strict_numbers: false,
+            number_epsilon: 1e-9,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BodyMatcher {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(BodyMatcherVisitor)
+    }
+}
+
+/// Picks out the `jsonEquals` map; anything else goes to the derived impl.
+struct BodyMatcherVisitor;
+
+impl<'de> Visitor<'de> for BodyMatcherVisitor {
+    type Value = BodyMatcher;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a body matcher")
+    }
+
+    // YAML tags, e.g. `!contains "x"`
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+        BodyMatcher::deserialize(EnumAccessDeserializer::new(data))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let Some(key) = map.next_key::<String>()? else {
+            return Err(A::Error::custom("empty body matcher"));
+        };
+        if key != "jsonEquals" && !JsonEqualsOptions::KEYS.contains(&key.as_str()) {
+            return BodyMatcher::deserialize(MapAccessDeserializer::new(ReplayKey {
+                key: Some(key),
+                map,
+            }));
+        }
+
+        let mut expected = None;
+        let mut options = serde_json::Map::new();
+        let mut key = Some(key);
+        while let Some(k) = key {
+            if k == "jsonEquals" {
+                if expected.is_some() {
+                    return Err(A::Error::duplicate_field("jsonEquals"));
+                }
+                expected = Some(map.next_value()?);
+            } else {
+                options.insert(k, map.next_value()?);
+            }
+            key = map.next_key()?;
+        }
+        Ok(BodyMatcher::JsonEquals {
+            expected: expected.ok_or_else(|| A::Error::missing_field("jsonEquals"))?,
+            options: JsonEqualsOptions::deserialize(serde_json::Value::Object(options))
+                .map_err(A::Error::custom)?,
+        })
+    }
+}
+
+/// A map whose first key has already been read, handed back to a deserializer
+struct ReplayKey<A> {
+    key: Option<String>,
+    map: A,
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for ReplayKey<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.key.take() {
+            Some(key) => seed.deserialize(key.into_deserializer()).map(Some),
+            None => self.map.next_key_seed(seed),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.map.next_value_seed(seed)
+    }
+}
+
+impl Serialize for BodyMatcher {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            BodyMatcher::JsonEquals { expected, options } => {
+                let defaults = JsonEqualsOptions::default();
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_entry("jsonEquals", expected)?;
+                if options.ignore_array_order != defaults.ignore_array_order {
+                    map.serialize_entry("ignoreArrayOrder", &options.ignore_array_order)?;
+                }
+                if options.strict_numbers != defaults.strict_numbers {
+                    map.serialize_entry("strictNumbers", &options.strict_numbers)?;
+                }
+                if options.number_epsilon != defaults.number_epsilon {
+                    map.serialize_entry("numberEpsilon", &options.number_epsilon)?;
+                }
+                map.end()
+            }
+            _ => BodyMatcher::serialize(self, serializer),
+        }
+    }
 }
 
 /// Compiled body matcher for efficient runtime evaluation.
@@ -50,15 +200,23 @@ pub enum CompiledBodyMatcher {
     Equals(CachedValue),
     Contains(CachedValue),
     Matches(Arc<Regex>),
-    JsonEquals(serde_json::Value),
+    JsonEquals {
+        expected: serde_json::Value,
+        options: JsonEqualsOptions,
+    },
     JsonPath {
         path: String,
         matcher: CompiledStringMatcher,
     },
     XPath {
         path: String,
+        namespaces: HashMap<String, String>,
         matcher: CompiledStringMatcher,
     },
+    RepeatsMoreThan {
+        pattern: CachedValue,
+        count: usize,
+    },
 }
 
 impl CompiledBodyMatcher {
@@ -67,64 +225,122 @@ impl CompiledBodyMatcher {
         match matcher {
             BodyMatcher::Equals(v) => Ok(CompiledBodyMatcher::Equals(CachedValue::new(v))),
             BodyMatcher::Contains(v) => Ok(CompiledBodyMatcher::Contains(CachedValue::new(v))),
-            BodyMatcher::Matches(pattern) => {
-                Ok(CompiledBodyMatcher::Matches(Arc::new(Regex::new(pattern)?)))
-            }
-            BodyMatcher::JsonEquals(value) => Ok(CompiledBodyMatcher::JsonEquals(value.clone())),
+            BodyMatcher::Matches(pattern) => Ok(CompiledBodyMatcher::Matches(Arc::new(
+                build_regex(pattern)?,
+            ))),
+            BodyMatcher::JsonEquals { expected, options } => Ok(CompiledBodyMatcher::JsonEquals {
+                expected: expected.clone(),
+                options: *options,
+            }),
             BodyMatcher::JsonPath { path, matcher } => Ok(CompiledBodyMatcher::JsonPath {
                 path: path.clone(),
-                matcher: CompiledStringMatcher::compile(matcher)?,
+                matcher: CompiledStringMatcher::compile(matcher, false)?,
             }),
-            BodyMatcher::XPath { path, matcher } => Ok(CompiledBodyMatcher::XPath {
+            BodyMatcher::XPath {
+                path,
+                namespaces,
+                matcher,
+            } => Ok(CompiledBodyMatcher::XPath {
                 path: path.clone(),
-                matcher: CompiledStringMatcher::compile(matcher)?,
+                namespaces: namespaces.clone(),
+                matcher: CompiledStringMatcher::compile(matcher, false)?,
             }),
+            BodyMatcher::RepeatsMoreThan { pattern, count } => {
+                Ok(CompiledBodyMatcher::RepeatsMoreThan {
+                    pattern: CachedValue::new(pattern),
+                    count: *count,
+                })
+            }
         }
     }
 
     /// Check if a body matches this matcher.
     pub fn matches(&self, body: &str, case_sensitive: bool) -> bool {
+        self.matches_buffer(body, false, case_sensitive)
+    }
+
+    /// Check a possibly truncated body.
+    ///
+    /// When `truncated` is set, `body` is only the start of the request body.
+    /// Substring, regex and repeat matchers look for a hit in that prefix;
+    /// matchers that need the whole document never match.
+    pub fn matches_buffer(&self, body: &str, truncated: bool, case_sensitive: bool) -> bool {
+        if truncated && self.needs_whole_body() {
+            return false;
+        }
         match self {
             CompiledBodyMatcher::Equals(cached) => cached.equals(body, case_sensitive),
             CompiledBodyMatcher::Contains(cached) => cached.contained_in(body, case_sensitive),
             CompiledBodyMatcher::Matches(regex) => regex.is_match(body),
-            CompiledBodyMatcher::JsonEquals(expected) => {
+            CompiledBodyMatcher::JsonEquals { expected, options } => {
                 // Parse body as JSON and compare
                 match serde_json::from_str::<serde_json::Value>(body) {
-                    Ok(actual) => json_deep_equals(&actual, expected, case_sensitive),
+                    Ok(actual) => json_deep_equals(&actual, expected, case_sensitive, options),
                     Err(_) => false,
                 }
             }
             CompiledBodyMatcher::JsonPath { path, matcher } => {
                 // Simple JSONPath implementation for common patterns
-                match extract_json_path(body, path) {
+                match extract_json_path_streaming(body, path) {
                     Some(value) => matcher.matches(Some(&value), case_sensitive),
                     None => matcher.matches(None, case_sensitive),
                 }
             }
-            CompiledBodyMatcher::XPath { path, matcher } => {
+            CompiledBodyMatcher::XPath {
+                path,
+                namespaces,
+                matcher,
+            } => {
                 // XPath extraction for XML bodies
-                match extract_xpath(body, path) {
+                match extract_xpath(body, path, Some(namespaces)) {
                     Some(value) => matcher.matches(Some(&value), case_sensitive),
                     None => matcher.matches(None, case_sensitive),
                 }
             }
+            CompiledBodyMatcher::RepeatsMoreThan { pattern, count } => {
+                let needle = pattern.pattern(case_sensitive);
+                if needle.is_empty() {
+                    return false;
+                }
+                // Stop counting as soon as the threshold is exceeded
+                if case_sensitive {
+                    body.matches(needle).nth(*count).is_some()
+                } else {
+                    body.to_lowercase().matches(needle).nth(*count).is_some()
+                }
+            }
         }
     }
+
+    fn needs_whole_body(&self) -> bool {
+        matches!(
+            self,
+            CompiledBodyMatcher::Equals(_)
+                | CompiledBodyMatcher::JsonEquals { .. }
+                | CompiledBodyMatcher::JsonPath { .. }
+                | CompiledBodyMatcher::XPath { .. }
+        )
+    }
 }
 
 /// Deep JSON equality comparison with optional case sensitivity.
+///
+/// With `ignore_array_order`, arrays are compared as multisets: each
+/// expected element must match a distinct actual element. Unless
+/// `strict_numbers` is set, a number compared with a float matches within
+/// `number_epsilon`.
 fn json_deep_equals(
     actual: &serde_json::Value,
     expected: &serde_json::Value,
     case_sensitive: bool,
+    options: &JsonEqualsOptions,
 ) -> bool {
     use serde_json::Value;
 
     match (actual, expected) {
         (Value::Null, Value::Null) => true,
         (Value::Bool(a), Value::Bool(b)) => a == b,
-        (Value::Number(a), Value::Number(b)) => a == b,
+        (Value::Number(a), Value::Number(b)) => numbers_equal(a, b, options),
         (Value::String(a), Value::String(b)) => {
             if case_sensitive {
                 a == b
@@ -132,17 +348,23 @@ fn json_deep_equals(
                 a.to_lowercase() == b.to_lowercase()
             }
         }
+        (Value::Array(a), Value::Array(b)) if options.ignore_array_order => {
+            a.len() == b.len()
+                && arrays_match_unordered(a, b, |x, y| {
+                    json_deep_equals(x, y, case_sensitive, options)
+                })
+        }
         (Value::Array(a), Value::Array(b)) => {
             a.len() == b.len()
                 && a.iter()
                     .zip(b.iter())
-                    .all(|(x, y)| json_deep_equals(x, y, case_sensitive))
+                    .all(|(x, y)| json_deep_equals(x, y, case_sensitive, options))
         }
         (Value::Object(a), Value::Object(b)) => {
             // All expected keys must be present and match
             b.iter().all(|(key, expected_val)| {
                 a.get(key).is_some_and(|actual_val| {
-                    json_deep_equals(actual_val, expected_val, case_sensitive)
+                    json_deep_equals(actual_val, expected_val, case_sensitive, options)
                 })
             })
         }
@@ -150,6 +372,72 @@ fn json_deep_equals(
     }
 }
 
+/// Number equality under `options`.
+///
+/// Two integers always compare exactly, since converting large ones to
+/// `f64` would lose precision.
+fn numbers_equal(
+    a: &serde_json::Number,
+    b: &serde_json::Number,
+    options: &JsonEqualsOptions,
+) -> bool {
+    if options.strict_numbers || !(a.is_f64() || b.is_f64()) {
+        return a == b;
+    }
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => (x - y).abs() <= options.number_epsilon,
+        _ => false,
+    }
+}
+
+/// Whether every expected element can be paired with a distinct actual one.
+///
+/// Object comparison is partial, so one actual element may satisfy several
+/// expected ones; a greedy first-fit can then miss a valid pairing. This
+/// finds a full matching with augmenting paths instead.
+fn arrays_match_unordered(
+    actual: &[serde_json::Value],
+    expected: &[serde_json::Value],
+    equals: impl Fn(&serde_json::Value, &serde_json::Value) -> bool,
+) -> bool {
+    // compatible[e] lists the actual elements expected[e] could pair with
+    let compatible: Vec<Vec<usize>> = expected
+        .iter()
+        .map(|e| {
+            (0..actual.len())
+                .filter(|&a| equals(&actual[a], e))
+                .collect()
+        })
+        .collect();
+
+    // Expected element paired with each actual element
+    let mut paired: Vec<Option<usize>> = vec![None; actual.len()];
+
+    fn augment(
+        e: usize,
+        compatible: &[Vec<usize>],
+        paired: &mut [Option<usize>],
+        visited: &mut [bool],
+    ) -> bool {
+        for &a in &compatible[e] {
+            if visited[a] {
+                continue;
+            }
+            visited[a] = true;
+            if paired[a].is_none_or(|other| augment(other, compatible, paired, visited)) {
+                paired[a] = Some(e);
+                return true;
+            }
+        }
+        false
+    }
+
+    (0..expected.len()).all(|e| {
+        let mut visited = vec![false; actual.len()];
+        augment(e, &compatible, &mut paired, &mut visited)
+    })
+}
+
 /// Extract a value from JSON using a simple JSONPath expression.
 ///
 /// Supports:
@@ -164,14 +452,123 @@ pub fn extract_json_path(body: &str, path: &str) -> Option<String> {
     let path = path.strip_prefix("$.").unwrap_or(path);
     let path = path.strip_prefix('$').unwrap_or(path);
 
-    let value = navigate_json(&json, path)?;
+    navigate_json(&json, path).map(json_value_to_string)
+}
 
+/// Render an extracted JSON value as a string for matching.
+fn json_value_to_string(value: &serde_json::Value) -> String {
     match value {
-        serde_json::Value::String(s) => Some(s.clone()),
-        serde_json::Value::Number(n) => Some(n.to_string()),
-        serde_json::Value::Bool(b) => Some(b.to_string()),
-        serde_json::Value::Null => Some("null".to_string()),
-        _ => Some(value.to_string()),
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::Bool(b) => b.to_string(),
+        serde_json::Value::Null => "null".to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// Extract a value from JSON without parsing the whole body when possible.
+///
+/// Paths made only of object fields (`$.field`, `$.field.nested`) are
+/// resolved by walking the document and stopping as soon as the target is
+/// found; sibling values are skipped without being built. Paths using array
+/// syntax fall back to [`extract_json_path`].
+///
+/// Because parsing stops early, a body that is malformed only after the
+/// target field still yields a value here.
+pub fn extract_json_path_streaming(body: &str, path: &str) -> Option<String> {
+    let trimmed = path.strip_prefix("$.").unwrap_or(path);
+    let trimmed = trimmed.strip_prefix('$').unwrap_or(trimmed);
+    if trimmed.is_empty() || trimmed.contains('[') {
+        return extract_json_path(body, path);
+    }
+
+    let keys: Vec<&str> = trimmed.split('.').collect();
+    if keys.iter().any(|k| k.is_empty()) {
+        return extract_json_path(body, path);
+    }
+
+    let mut found = None;
+    let mut deserializer = serde_json::Deserializer::from_str(body);
+    // The seed aborts with an error once the target is captured, so the
+    // result only matters when nothing was found.
+    let _ = serde::de::DeserializeSeed::deserialize(
+        JsonPathSeed {
+            keys: &keys,
+            found: &mut found,
+        },
+        &mut deserializer,
+    );
+    found
+}
+
+/// Deserialize seed that descends into the object field named by `keys[0]`.
+struct JsonPathSeed<'p, 'k> {
+    keys: &'p [&'k str],
+    found: &'p mut Option<String>,
+}
+
+/// Error message used to stop deserialization once the target is found
+const JSON_PATH_FOUND: &str = "json path target found";
+
+impl<'de> serde::de::DeserializeSeed<'de> for JsonPathSeed<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        use serde::de::Error;
+
+        if self.keys.is_empty() {
+            let value = serde_json::Value::deserialize(deserializer)?;
+            *self.found = Some(json_value_to_string(&value));
+            return Err(D::Error::custom(JSON_PATH_FOUND));
+        }
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for JsonPathSeed<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a JSON object")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let (target, rest) = (self.keys[0], &self.keys[1..]);
+        while let Some(is_target) = map.next_key_seed(KeyEquals(target))? {
+            if is_target {
+                // Unlike a full parse (last duplicate wins), the first
+                // occurrence of a key is used
+                return map.next_value_seed(JsonPathSeed {
+                    keys: rest,
+                    found: self.found,
+                });
+            }
+            map.next_value::<serde::de::IgnoredAny>()?;
+        }
+        Ok(())
+    }
+}
+
+/// Key seed comparing an object key against a target without allocating.
+struct KeyEquals<'k>(&'k str);
+
+impl<'de> serde::de::DeserializeSeed<'de> for KeyEquals<'_> {
+    type Value = bool;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for KeyEquals<'_> {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an object key")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, key: &str) -> Result<bool, E> {
+        Ok(key == self.0)
     }
 }
 
@@ -248,16 +645,29 @@ fn navigate_json<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a ser
 /// - `//element` - descendant search
 /// - `/root/element/@attribute` - attribute selection
 /// - `/root/element/text()` - text content
-pub fn extract_xpath(body: &str, path: &str) -> Option<String> {
+///
+/// Prefixed names such as `/soap:Envelope` resolve through `namespaces`
+/// (prefix -> URI).
+pub fn extract_xpath(
+    body: &str,
+    path: &str,
+    namespaces: Option<&HashMap<String, String>>,
+) -> Option<String> {
     use sxd_document::parser;
-    use sxd_xpath::{evaluate_xpath, Value};
+    use sxd_xpath::{Context, Factory, Value};
 
     // Parse the XML document
     let package = parser::parse(body).ok()?;
     let document = package.as_document();
 
+    let xpath = Factory::new().build(path).ok()??;
+    let mut context = Context::new();
+    for (prefix, uri) in namespaces.into_iter().flatten() {
+        context.set_namespace(prefix, uri);
+    }
+
     // Evaluate the XPath expression
-    match evaluate_xpath(&document, path) {
+    match xpath.evaluate(&context, document.root()) {
         Ok(value) => match value {
             Value::String(s) => Some(s),
             Value::Number(n) => {
@@ -304,6 +714,29 @@ mod tests {
         assert!(!matcher.matches("no match here", true));
     }
 
+    #[test]
+    fn test_body_matcher_truncated_buffer() {
+        let contains =
+            CompiledBodyMatcher::compile(&BodyMatcher::Contains("api".to_string())).unwrap();
+        // A hit in the prefix is a hit in the full body
+        assert!(contains.matches_buffer("call the api and", true, true));
+        assert!(!contains.matches_buffer("call the a", true, true));
+
+        // Whole-document matchers can't decide from a prefix
+        let equals =
+            CompiledBodyMatcher::compile(&BodyMatcher::Equals("hello".to_string())).unwrap();
+        assert!(equals.matches_buffer("hello", false, true));
+        assert!(!equals.matches_buffer("hello", true, true));
+
+        let json = CompiledBodyMatcher::compile(&BodyMatcher::JsonPath {
+            path: "$.id".to_string(),
+            matcher: StringMatcher::Equals("1".to_string()),
+        })
+        .unwrap();
+        assert!(json.matches_buffer(r#"{"id": "1"}"#, false, true));
+        assert!(!json.matches_buffer(r#"{"id": "1"}"#, true, true));
+    }
+
     #[test]
     fn test_body_matcher_regex() {
         let matcher =
@@ -315,13 +748,66 @@ mod tests {
         assert!(!matcher.matches("No phone number", true));
     }
 
+    #[test]
+    fn test_body_matcher_repeats_more_than() {
+        let matcher = CompiledBodyMatcher::compile(&BodyMatcher::RepeatsMoreThan {
+            pattern: "ab".to_string(),
+            count: 3,
+        })
+        .unwrap();
+
+        // Exactly at the threshold - no match
+        assert!(!matcher.matches("ab-ab-ab", true));
+        assert!(!matcher.matches("xxabxxabxxabxx", true));
+        // Just over the threshold
+        assert!(matcher.matches("ab-ab-ab-ab", true));
+        // Case sensitivity
+        assert!(!matcher.matches("AB-AB-AB-AB", true));
+        assert!(matcher.matches("AB-AB-AB-AB", false));
+
+        // Occurrences are counted without overlap: "aaaaa" holds two "aa"
+        let matcher = CompiledBodyMatcher::compile(&BodyMatcher::RepeatsMoreThan {
+            pattern: "aa".to_string(),
+            count: 2,
+        })
+        .unwrap();
+        assert!(!matcher.matches("aaaaa", true));
+        assert!(matcher.matches("aaaaaa", true));
+    }
+
+    #[test]
+    fn test_body_matcher_repeats_more_than_serde() {
+        let matcher: BodyMatcher =
+            serde_json::from_str(r#"{"repeatsMoreThan": {"pattern": "<a>", "count": 100}}"#)
+                .unwrap();
+        assert_eq!(
+            matcher,
+            BodyMatcher::RepeatsMoreThan {
+                pattern: "<a>".to_string(),
+                count: 100,
+            }
+        );
+
+        // An empty pattern never matches
+        let compiled = CompiledBodyMatcher::compile(&BodyMatcher::RepeatsMoreThan {
+            pattern: String::new(),
+            count: 0,
+        })
+        .unwrap();
+        assert!(!compiled.matches("anything", true));
+    }
+
     #[test]
     fn test_body_matcher_json_equals() {
         let expected = serde_json::json!({
             "name": "John",
             "age": 30
         });
-        let matcher = CompiledBodyMatcher::compile(&BodyMatcher::JsonEquals(expected)).unwrap();
+        let matcher = CompiledBodyMatcher::compile(&BodyMatcher::JsonEquals {
+            expected,
+            options: JsonEqualsOptions::default(),
+        })
+        .unwrap();
 
         // Exact match
         assert!(matcher.matches(r#"{"name": "John", "age": 30}"#, true));
@@ -343,6 +829,101 @@ mod tests {
         assert!(matcher.matches(r#"{"name": "JOHN", "age": 30}"#, false));
     }
 
+    #[test]
+    fn test_json_equals_ignore_array_order() {
+        let unordered: BodyMatcher =
+            serde_yaml::from_str("jsonEquals: [1, 2, 3]\nignoreArrayOrder: true").unwrap();
+        let unordered = CompiledBodyMatcher::compile(&unordered).unwrap();
+        assert!(unordered.matches("[3, 2, 1]", true));
+        assert!(unordered.matches("[1, 2, 3]", true));
+        // Multiset, not set: counts must agree
+        assert!(!unordered.matches("[1, 1, 2]", true));
+        assert!(!unordered.matches("[3, 2, 1, 4]", true));
+
+        let ordered: BodyMatcher = serde_yaml::from_str("jsonEquals: [1, 2, 3]").unwrap();
+        let ordered = CompiledBodyMatcher::compile(&ordered).unwrap();
+        assert!(ordered.matches("[1, 2, 3]", true));
+        assert!(!ordered.matches("[3, 2, 1]", true));
+    }
+
+    #[test]
+    fn test_json_equals_ignore_array_order_nested_objects() {
+        let matcher = CompiledBodyMatcher::compile(&BodyMatcher::JsonEquals {
+            expected: serde_json::json!({"items": [{"id": 1}, {"id": 1, "tag": "a"}]}),
+            options: JsonEqualsOptions {
+                ignore_array_order: true,
+                ..Default::default()
+            },
+        })
+        .unwrap();
+
+        // First-fit would pair {"id": 1} with the tagged element and strand
+        // the other expected element
+        assert!(matcher.matches(
+            r#"{"items": [{"id": 1, "tag": "a"}, {"id": 1, "tag": "b"}]}"#,
+            true
+        ));
+        assert!(!matcher.matches(
+            r#"{"items": [{"id": 1, "tag": "b"}, {"id": 1, "tag": "c"}]}"#,
+            true
+        ));
+        // Applies to arrays nested inside array elements too
+        let nested = CompiledBodyMatcher::compile(&BodyMatcher::JsonEquals {
+            expected: serde_json::json!([[1, 2], [3, 4]]),
+            options: JsonEqualsOptions {
+                ignore_array_order: true,
+                ..Default::default()
+            },
+        })
+        .unwrap();
+        assert!(nested.matches("[[4, 3], [2, 1]]", true));
+    }
+
+    #[test]
+    fn test_json_equals_round_trip() {
+        let yaml = "jsonEquals:\n  ids:\n  - 1\n  - 2\nignoreArrayOrder: true\n";
+        let matcher: BodyMatcher = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(serde_yaml::to_string(&matcher).unwrap(), yaml);
+
+        // Other variants keep the single-key form
+        let contains: BodyMatcher = serde_yaml::from_str("contains: abc").unwrap();
+        assert_eq!(contains, BodyMatcher::Contains("abc".to_string()));
+        assert!(serde_yaml::from_str::<BodyMatcher>("jsonEquals: 1\nextra: 2").is_err());
+    }
+
+    #[test]
+    fn test_json_equals_int_matches_float() {
+        let matcher: BodyMatcher = serde_json::from_str(r#"{"jsonEquals": {"age": 30}}"#).unwrap();
+        let matcher = CompiledBodyMatcher::compile(&matcher).unwrap();
+        assert!(matcher.matches(r#"{"age": 30.0}"#, true));
+        assert!(matcher.matches(r#"{"age": 30}"#, true));
+        assert!(!matcher.matches(r#"{"age": 30.5}"#, true));
+
+        let strict: BodyMatcher =
+            serde_json::from_str(r#"{"jsonEquals": {"age": 30}, "strictNumbers": true}"#).unwrap();
+        let strict = CompiledBodyMatcher::compile(&strict).unwrap();
+        assert!(strict.matches(r#"{"age": 30}"#, true));
+        assert!(!strict.matches(r#"{"age": 30.0}"#, true));
+    }
+
+    #[test]
+    fn test_json_equals_float_within_epsilon() {
+        let matcher: BodyMatcher =
+            serde_yaml::from_str("jsonEquals: {price: 0.3}\nnumberEpsilon: 0.001").unwrap();
+        let matcher = CompiledBodyMatcher::compile(&matcher).unwrap();
+        assert!(matcher.matches(r#"{"price": 0.3004}"#, true));
+        assert!(!matcher.matches(r#"{"price": 0.302}"#, true));
+
+        // Default epsilon absorbs float rounding
+        let default = CompiledBodyMatcher::compile(&BodyMatcher::JsonEquals {
+            expected: serde_json::json!({"total": 0.3}),
+            options: JsonEqualsOptions::default(),
+        })
+        .unwrap();
+        assert!(default.matches(&format!(r#"{{"total": {}}}"#, 0.1 + 0.2), true));
+        assert!(!default.matches(r#"{"total": 0.31}"#, true));
+    }
+
     #[test]
     fn test_body_matcher_json_path() {
         let matcher = CompiledBodyMatcher::compile(&BodyMatcher::JsonPath {
@@ -356,6 +937,51 @@ mod tests {
         assert!(!matcher.matches(r#"{"user": {"age": 30}}"#, true));
     }
 
+    #[test]
+    fn test_json_path_streaming_matches_full_parse() {
+        let body = r#"{
+            "id": 7,
+            "items": [{"id": 1}, {"id": 2}],
+            "user": {"name": "J\u00f6rg", "tags": ["a"], "active": true, "nickname": null},
+            "meta": {"nested": {"deep": 1.5}}
+        }"#;
+        for path in [
+            "$.id",
+            "$.user.name",
+            "$.user.active",
+            "$.user.nickname",
+            "$.user.tags",
+            "$.meta",
+            "$.meta.nested.deep",
+            "$.items[1].id",
+            "$.items[*].id",
+            "$.missing",
+            "$.user.missing",
+            "$.id.nested",
+            "$",
+        ] {
+            assert_eq!(
+                extract_json_path_streaming(body, path),
+                extract_json_path(body, path),
+                "path {path}"
+            );
+        }
+
+        assert_eq!(extract_json_path_streaming("not json", "$.id"), None);
+        assert_eq!(extract_json_path_streaming("[1, 2]", "$.id"), None);
+    }
+
+    #[test]
+    fn test_json_path_streaming_stops_at_target() {
+        // Trailing garbage after the target isn't reached
+        let body = r#"{"status": "ok", "rest": [1, 2, "#;
+        assert_eq!(
+            extract_json_path_streaming(body, "$.status"),
+            Some("ok".to_string())
+        );
+        assert_eq!(extract_json_path(body, "$.status"), None);
+    }
+
     #[test]
     fn test_json_path_simple_field() {
         let body = r#"{"name": "John", "age": 30}"#;
@@ -421,16 +1047,22 @@ mod tests {
     #[test]
     fn test_xpath_simple_element() {
         let xml = r#"<root><name>John</name><age>30</age></root>"#;
-        assert_eq!(extract_xpath(xml, "/root/name"), Some("John".to_string()));
-        assert_eq!(extract_xpath(xml, "/root/age"), Some("30".to_string()));
-        assert_eq!(extract_xpath(xml, "/root/missing"), None);
+        assert_eq!(
+            extract_xpath(xml, "/root/name", None),
+            Some("John".to_string())
+        );
+        assert_eq!(
+            extract_xpath(xml, "/root/age", None),
+            Some("30".to_string())
+        );
+        assert_eq!(extract_xpath(xml, "/root/missing", None), None);
     }
 
     #[test]
     fn test_xpath_nested() {
         let xml = r#"<root><user><profile><name>Jane</name></profile></user></root>"#;
         assert_eq!(
-            extract_xpath(xml, "/root/user/profile/name"),
+            extract_xpath(xml, "/root/user/profile/name", None),
             Some("Jane".to_string())
         );
     }
@@ -439,7 +1071,7 @@ mod tests {
     fn test_xpath_attribute() {
         let xml = r#"<root><item id="123">Content</item></root>"#;
         assert_eq!(
-            extract_xpath(xml, "/root/item/@id"),
+            extract_xpath(xml, "/root/item/@id", None),
             Some("123".to_string())
         );
     }
@@ -447,13 +1079,17 @@ mod tests {
     #[test]
     fn test_xpath_descendant() {
         let xml = r#"<root><level1><level2><target>Found</target></level2></level1></root>"#;
-        assert_eq!(extract_xpath(xml, "//target"), Some("Found".to_string()));
+        assert_eq!(
+            extract_xpath(xml, "//target", None),
+            Some("Found".to_string())
+        );
     }
 
     #[test]
     fn test_body_matcher_xpath() {
         let matcher = CompiledBodyMatcher::compile(&BodyMatcher::XPath {
             path: "/order/customer/name".to_string(),
+            namespaces: HashMap::new(),
             matcher: StringMatcher::Equals("Alice".to_string()),
         })
         .unwrap();
@@ -465,9 +1101,36 @@ mod tests {
         assert!(!matcher.matches(xml_wrong, true));
     }
 
+    #[test]
+    fn test_xpath_soap_envelope_namespaces() {
+        let xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+            <soap:Body><foo>bar</foo></soap:Body>
+        </soap:Envelope>"#;
+        let namespaces = HashMap::from([(
+            "soap".to_string(),
+            "http://schemas.xmlsoap.org/soap/envelope/".to_string(),
+        )]);
+        assert_eq!(
+            extract_xpath(xml, "/soap:Envelope/soap:Body/foo", Some(&namespaces)),
+            Some("bar".to_string())
+        );
+
+        let matcher: BodyMatcher = serde_json::from_str(
+            r#"{"xpath": {
+                "path": "/s:Envelope/s:Body/foo",
+                "namespaces": {"s": "http://schemas.xmlsoap.org/soap/envelope/"},
+                "equals": "bar"
+            }}"#,
+        )
+        .unwrap();
+        assert!(CompiledBodyMatcher::compile(&matcher)
+            .unwrap()
+            .matches(xml, true));
+    }
+
     #[test]
     fn test_xpath_invalid_xml() {
         let invalid = "not xml at all";
-        assert_eq!(extract_xpath(invalid, "/root/name"), None);
+        assert_eq!(extract_xpath(invalid, "/root/name", None), None);
     }
 }
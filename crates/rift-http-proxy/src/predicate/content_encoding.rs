@@ -0,0 +1,132 @@
+//! Content-Encoding handling for body predicates.
+//!
+//! Body predicates are written against the payload, so a gzip- or
+//! deflate-encoded request body is decoded before they run. The decoded
+//! bytes are only used for matching; the body that is forwarded, recorded
+//! or handed to behaviors is the one received. Other encodings, such as
+//! `br`, are matched as received.
+
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use std::io::Read;
+
+/// A body with its content codings undone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedBody {
+    /// At most `limit` decoded bytes
+    pub bytes: Vec<u8>,
+    /// True if decoding stopped early, at the limit or at the end of a
+    /// truncated input
+    pub truncated: bool,
+}
+
+/// Undo the codings listed in a `Content-Encoding` header value.
+///
+/// Returns `None` when there is nothing to undo, when a coding is not
+/// supported, or when the body does not decode at all.
+pub fn decode_body(content_encoding: &str, body: &[u8], limit: usize) -> Option<DecodedBody> {
+    let codings: Vec<&str> = content_encoding
+        .split(',')
+        .map(str::trim)
+        .filter(|c| !c.is_empty() && !c.eq_ignore_ascii_case("identity"))
+        .collect();
+    if codings.is_empty() {
+        return None;
+    }
+
+    // Codings are listed in the order they were applied
+    let mut decoded = DecodedBody {
+        bytes: body.to_vec(),
+        truncated: false,
+    };
+    for coding in codings.iter().rev() {
+        let next = decode_one(coding, &decoded.bytes, limit)?;
+        decoded = DecodedBody {
+            bytes: next.bytes,
+            truncated: decoded.truncated || next.truncated,
+        };
+    }
+    Some(decoded)
+}
+
+fn decode_one(coding: &str, body: &[u8], limit: usize) -> Option<DecodedBody> {
+    match coding.to_ascii_lowercase().as_str() {
+        "gzip" | "x-gzip" => read_limited(GzDecoder::new(body), limit),
+        // `deflate` is meant to be zlib-wrapped, but raw deflate streams
+        // are common enough to accept as well
+        "deflate" => read_limited(ZlibDecoder::new(body), limit)
+            .or_else(|| read_limited(DeflateDecoder::new(body), limit)),
+        _ => None,
+    }
+}
+
+/// Read up to `limit` bytes; a stream that fails part-way yields what was
+/// read before the failure
+fn read_limited(reader: impl Read, limit: usize) -> Option<DecodedBody> {
+    let mut bytes = Vec::new();
+    let result = reader.take(limit as u64 + 1).read_to_end(&mut bytes);
+    if result.is_err() && bytes.is_empty() {
+        return None;
+    }
+    let truncated = result.is_err() || bytes.len() > limit;
+    bytes.truncate(limit);
+    Some(DecodedBody { bytes, truncated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_decode_gzip_and_deflate() {
+        let payload = br#"{"order": "pending"}"#;
+        let decoded = decode_body("gzip", &gzip(payload), 1024).unwrap();
+        assert_eq!(decoded.bytes, payload);
+        assert!(!decoded.truncated);
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(payload).unwrap();
+        let decoded = decode_body("Deflate", &zlib.finish().unwrap(), 1024).unwrap();
+        assert_eq!(decoded.bytes, payload);
+    }
+
+    #[test]
+    fn test_decode_stacked_codings() {
+        let payload = b"twice compressed";
+        let decoded = decode_body("gzip, gzip", &gzip(&gzip(payload)), 1024).unwrap();
+        assert_eq!(decoded.bytes, payload);
+    }
+
+    #[test]
+    fn test_unsupported_or_absent_coding_is_not_decoded() {
+        assert_eq!(decode_body("identity", b"plain", 1024), None);
+        assert_eq!(decode_body("br", b"\x0b\x02\x80", 1024), None);
+        assert_eq!(decode_body("gzip", b"not gzip", 1024), None);
+    }
+
+    #[test]
+    fn test_decode_stops_at_limit_and_truncated_input() {
+        let payload = "a".repeat(1000);
+        let encoded = gzip(payload.as_bytes());
+
+        let decoded = decode_body("gzip", &encoded, 100).unwrap();
+        assert_eq!(decoded.bytes.len(), 100);
+        assert!(decoded.truncated);
+
+        let payload: String = (0..2000)
+            .map(|i| char::from(b'a' + (i % 26) as u8))
+            .collect();
+        let encoded = gzip(payload.as_bytes());
+        let decoded = decode_body("gzip", &encoded[..encoded.len() / 2], 4096).unwrap();
+        assert!(decoded.truncated);
+        assert!(payload.as_bytes().starts_with(&decoded.bytes));
+    }
+}
@@ -0,0 +1,45 @@
+//! Errors from compiling predicates.
+
+use std::fmt;
+
+/// A predicate pattern that failed to compile, located by field and, when
+/// it comes from config, by rule.
+#[derive(Debug)]
+pub struct PredicateCompileError {
+    /// Rule the predicate belongs to
+    pub rule: Option<String>,
+    /// Field that held the pattern, e.g. `path`, `header 'X-Api-Key'`
+    pub field: String,
+    pub source: regex::Error,
+}
+
+impl PredicateCompileError {
+    pub fn new(field: impl Into<String>, source: regex::Error) -> Self {
+        Self {
+            rule: None,
+            field: field.into(),
+            source,
+        }
+    }
+
+    /// Attribute the error to a rule
+    pub fn in_rule(mut self, rule: &str) -> Self {
+        self.rule = Some(rule.to_string());
+        self
+    }
+}
+
+impl fmt::Display for PredicateCompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(rule) = &self.rule {
+            write!(f, "Rule '{rule}': ")?;
+        }
+        write!(f, "invalid pattern in {}: {}", self.field, self.source)
+    }
+}
+
+impl std::error::Error for PredicateCompileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
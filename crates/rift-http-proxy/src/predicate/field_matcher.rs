@@ -6,8 +6,25 @@
 use super::matcher::CachedValue;
 use super::options::PredicateOptions;
 use super::string_matcher::{CompiledExcept, CompiledStringMatcher, StringMatcher};
+use hyper::HeaderMap;
 use serde::{Deserialize, Serialize};
 
+/// How a field repeated in the request (e.g. two `Accept` headers) is
+/// matched: by any one of its values, or only if every value matches.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MultiValue {
+    #[default]
+    Any,
+    All,
+}
+
+impl MultiValue {
+    fn is_any(&self) -> bool {
+        *self == MultiValue::Any
+    }
+}
+
 /// Generic field matching configuration.
 ///
 /// Used for both headers and query parameters with identical behavior.
@@ -21,6 +38,8 @@ pub enum FieldMatcher {
     Or {
         name: String,
         or: Vec<StringMatcher>,
+        #[serde(default, skip_serializing_if = "MultiValue::is_any")]
+        values: MultiValue,
         #[serde(flatten, default)]
         options: PredicateOptions,
     },
@@ -30,6 +49,8 @@ pub enum FieldMatcher {
         name: String,
         #[serde(flatten)]
         matcher: StringMatcher,
+        #[serde(default, skip_serializing_if = "MultiValue::is_any")]
+        values: MultiValue,
         #[serde(flatten, default)]
         options: PredicateOptions,
     },
@@ -66,6 +87,8 @@ pub struct CompiledFieldMatcher {
     pub not: bool,
     /// Optional except pattern for stripping content before matching
     pub except: Option<CompiledExcept>,
+    /// How repeated values of the field combine
+    pub values: MultiValue,
 }
 
 impl CompiledFieldMatcher {
@@ -92,14 +115,22 @@ impl CompiledFieldMatcher {
                 case_sensitive: true,
                 not: false,
                 except: None,
+                values: MultiValue::Any,
             }),
-            FieldMatcher::Or { name, or, options } => {
-                let compiled: Result<Vec<_>, _> =
-                    or.iter().map(CompiledStringMatcher::compile).collect();
+            FieldMatcher::Or {
+                name,
+                or,
+                values,
+                options,
+            } => {
+                let compiled: Result<Vec<_>, _> = or
+                    .iter()
+                    .map(|m| CompiledStringMatcher::compile(m, options.full_match))
+                    .collect();
                 let except = options
                     .except
                     .as_ref()
-                    .map(|p| CompiledExcept::compile(p))
+                    .map(|p| CompiledExcept::compile(p, options.case_sensitive))
                     .transpose()?;
                 Ok(CompiledFieldMatcher {
                     name: normalize_name(name),
@@ -107,26 +138,30 @@ impl CompiledFieldMatcher {
                     case_sensitive: options.case_sensitive,
                     not: options.not,
                     except,
+                    values: *values,
                 })
             }
             FieldMatcher::Full {
                 name,
                 matcher,
+                values,
                 options,
             } => {
                 let except = options
                     .except
                     .as_ref()
-                    .map(|p| CompiledExcept::compile(p))
+                    .map(|p| CompiledExcept::compile(p, options.case_sensitive))
                     .transpose()?;
                 Ok(CompiledFieldMatcher {
                     name: normalize_name(name),
                     matcher: CompiledFieldMatcherInner::Single(CompiledStringMatcher::compile(
                         matcher,
+                        options.full_match,
                     )?),
                     case_sensitive: options.case_sensitive,
                     not: options.not,
                     except,
+                    values: *values,
                 })
             }
         }
@@ -134,18 +169,45 @@ impl CompiledFieldMatcher {
 
     /// Check if a field value matches.
     pub fn matches(&self, value: Option<&str>) -> bool {
-        let result = match &self.matcher {
+        self.matches_one(value) != self.not
+    }
+
+    /// Check every value of a repeated field, combined per `values`; no
+    /// values is checked as a missing field. `not` negates the combined
+    /// result, so `not` with `any` means no value matches.
+    pub fn matches_values<'a>(&self, values: impl IntoIterator<Item = &'a str>) -> bool {
+        let mut values = values.into_iter().peekable();
+        let result = if values.peek().is_none() {
+            self.matches_one(None)
+        } else {
+            match self.values {
+                MultiValue::Any => values.any(|value| self.matches_one(Some(value))),
+                MultiValue::All => values.all(|value| self.matches_one(Some(value))),
+            }
+        };
+        result != self.not
+    }
+
+    /// Check the field against all its values in a header map. A header
+    /// that is present counts as present even if its value is empty or not
+    /// valid UTF-8; invalid bytes are compared as U+FFFD.
+    pub fn matches_headers(&self, headers: Option<&HeaderMap>) -> bool {
+        let values: Vec<_> = headers
+            .into_iter()
+            .flat_map(|headers| headers.get_all(self.name.as_str()))
+            .map(|value| String::from_utf8_lossy(value.as_bytes()))
+            .collect();
+        self.matches_values(values.iter().map(|value| value.as_ref()))
+    }
+
+    fn matches_one(&self, value: Option<&str>) -> bool {
+        match &self.matcher {
             CompiledFieldMatcherInner::Single(m) => {
                 m.matches_with_except(value, self.case_sensitive, self.except.as_ref())
             }
             CompiledFieldMatcherInner::Or(matchers) => matchers
                 .iter()
                 .any(|m| m.matches_with_except(value, self.case_sensitive, self.except.as_ref())),
-        };
-        if self.not {
-            !result
-        } else {
-            result
         }
     }
 }
@@ -153,8 +215,11 @@ impl CompiledFieldMatcher {
 // Type aliases for backward compatibility and clarity
 pub type HeaderMatcher = FieldMatcher;
 pub type QueryMatcher = FieldMatcher;
+/// Trailers are headers sent after the body; compiled like headers.
+pub type TrailerMatcher = FieldMatcher;
 pub type CompiledHeaderMatcher = CompiledFieldMatcher;
 pub type CompiledQueryMatcher = CompiledFieldMatcher;
+pub type CompiledTrailerMatcher = CompiledFieldMatcher;
 
 /// Compile a header matcher (lowercases the header name).
 pub fn compile_header_matcher(config: &FieldMatcher) -> Result<CompiledFieldMatcher, regex::Error> {
@@ -169,6 +234,7 @@ pub fn compile_query_matcher(config: &FieldMatcher) -> Result<CompiledFieldMatch
 #[cfg(test)]
 mod tests {
     use super::*;
+    use hyper::header::HeaderValue;
 
     #[test]
     fn test_header_matcher_simple() {
@@ -216,10 +282,12 @@ mod tests {
         let config = FieldMatcher::Full {
             name: "user-agent".to_string(),
             matcher: StringMatcher::Equals("Mozilla/ Firefox/".to_string()),
+            values: MultiValue::Any,
             options: PredicateOptions {
                 case_sensitive: true,
                 except: Some(r"\d+\.\d+".to_string()),
                 not: false,
+                full_match: false,
             },
         };
 
@@ -236,6 +304,7 @@ mod tests {
                 StringMatcher::Equals("active".to_string()),
                 StringMatcher::Equals("pending".to_string()),
             ],
+            values: MultiValue::Any,
             options: PredicateOptions::default(),
         };
 
@@ -250,10 +319,12 @@ mod tests {
         let config = FieldMatcher::Full {
             name: "status".to_string(),
             matcher: StringMatcher::Equals("deleted".to_string()),
+            values: MultiValue::Any,
             options: PredicateOptions {
                 case_sensitive: true,
                 except: None,
                 not: true,
+                full_match: false,
             },
         };
 
@@ -262,4 +333,96 @@ mod tests {
         assert!(compiled.matches(Some("active")));
         assert!(compiled.matches(Some("pending")));
     }
+
+    #[test]
+    fn test_header_matcher_full_match() {
+        let config: FieldMatcher =
+            serde_json::from_str(r#"{"name": "X-Path", "matches": "/api", "fullMatch": true}"#)
+                .unwrap();
+        let compiled = compile_header_matcher(&config).unwrap();
+        assert!(compiled.matches(Some("/api")));
+        assert!(!compiled.matches(Some("/api/v1")));
+
+        // Round-trips, and is omitted when off
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["fullMatch"], true);
+        let config: FieldMatcher =
+            serde_json::from_str(r#"{"name": "X-Path", "matches": "/api"}"#).unwrap();
+        assert!(serde_json::to_value(&config)
+            .unwrap()
+            .get("fullMatch")
+            .is_none());
+    }
+
+    #[test]
+    fn test_exists_distinguishes_empty_from_missing_header() {
+        let exists: FieldMatcher =
+            serde_json::from_str(r#"{"name": "X-Empty", "exists": true}"#).unwrap();
+        let exists = compile_header_matcher(&exists).unwrap();
+        let absent: FieldMatcher =
+            serde_json::from_str(r#"{"name": "X-Empty", "exists": false}"#).unwrap();
+        let absent = compile_header_matcher(&absent).unwrap();
+
+        let mut headers = HeaderMap::new();
+        assert!(!exists.matches_headers(Some(&headers)));
+        assert!(absent.matches_headers(Some(&headers)));
+        assert!(absent.matches_headers(None));
+
+        headers.insert("x-empty", HeaderValue::from_static(""));
+        assert!(exists.matches_headers(Some(&headers)));
+        assert!(!absent.matches_headers(Some(&headers)));
+
+        // A value that isn't UTF-8 is still a present header
+        headers.insert("x-empty", HeaderValue::from_bytes(b"\xff").unwrap());
+        assert!(exists.matches_headers(Some(&headers)));
+        assert!(!absent.matches_headers(Some(&headers)));
+    }
+
+    #[test]
+    fn test_repeated_header_any_or_all() {
+        let mut headers = HeaderMap::new();
+        headers.append("accept", "text/html".parse().unwrap());
+        headers.append("accept", "application/json".parse().unwrap());
+
+        let any: FieldMatcher =
+            serde_json::from_str(r#"{"name": "Accept", "contains": "json"}"#).unwrap();
+        let any = compile_header_matcher(&any).unwrap();
+        assert_eq!(any.values, MultiValue::Any);
+        assert!(any.matches_headers(Some(&headers)));
+        // The first value alone doesn't match
+        assert!(!any.matches(headers.get("accept").and_then(|v| v.to_str().ok())));
+
+        let all: FieldMatcher =
+            serde_json::from_str(r#"{"name": "Accept", "contains": "json", "values": "all"}"#)
+                .unwrap();
+        let all = compile_header_matcher(&all).unwrap();
+        assert!(!all.matches_headers(Some(&headers)));
+        headers.remove("accept");
+        headers.append("accept", "application/json".parse().unwrap());
+        headers.append("accept", "application/problem+json".parse().unwrap());
+        assert!(all.matches_headers(Some(&headers)));
+    }
+
+    #[test]
+    fn test_repeated_header_negation_and_absence() {
+        let mut headers = HeaderMap::new();
+        headers.append("x-forwarded-for", "10.0.0.1".parse().unwrap());
+        headers.append("x-forwarded-for", "192.168.0.1".parse().unwrap());
+
+        // `not` negates the combined result: no value may match
+        let none: FieldMatcher = serde_json::from_str(
+            r#"{"name": "X-Forwarded-For", "startsWith": "192.168.", "not": true}"#,
+        )
+        .unwrap();
+        let none = compile_header_matcher(&none).unwrap();
+        assert!(!none.matches_headers(Some(&headers)));
+        assert!(none.matches_headers(Some(&HeaderMap::new())));
+
+        let exists: FieldMatcher =
+            serde_json::from_str(r#"{"name": "X-Forwarded-For", "exists": true, "values": "all"}"#)
+                .unwrap();
+        let exists = compile_header_matcher(&exists).unwrap();
+        assert!(exists.matches_headers(Some(&headers)));
+        assert!(!exists.matches_headers(None));
+    }
 }
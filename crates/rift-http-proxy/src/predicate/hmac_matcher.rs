@@ -0,0 +1,178 @@
+//! HMAC signature matching for webhook-style requests.
+//!
+//! Validates that a request header carries a valid HMAC of the raw body,
+//! computed with a configured shared secret.
+
+use base64::Engine;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+
+/// HMAC signature matcher configuration.
+///
+/// The header value may be hex or base64 encoded, and may carry an
+/// `<algorithm>=` prefix (e.g. `sha256=...`, as sent by GitHub webhooks).
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct HmacMatcher {
+    /// Header containing the signature (e.g. "X-Signature")
+    pub header: String,
+    /// Hash algorithm: "sha256" (default), "sha384", "sha512" or "sha1"
+    #[serde(default = "default_algorithm")]
+    pub algorithm: String,
+    /// Shared secret used as the HMAC key
+    pub secret: String,
+}
+
+fn default_algorithm() -> String {
+    "sha256".to_string()
+}
+
+/// Compiled HMAC matcher holding a prepared signing key.
+#[derive(Debug, Clone)]
+pub struct CompiledHmacMatcher {
+    /// Header name (lowercased)
+    pub header: String,
+    /// Normalized algorithm name, used to strip `<algorithm>=` prefixes
+    algorithm: String,
+    key: hmac::Key,
+}
+
+impl CompiledHmacMatcher {
+    /// Compile an HmacMatcher configuration.
+    pub fn compile(config: &HmacMatcher) -> Result<Self, String> {
+        let algorithm = config.algorithm.to_lowercase().replace('-', "");
+        let hmac_algorithm = match algorithm.as_str() {
+            "sha256" | "hmacsha256" => hmac::HMAC_SHA256,
+            "sha384" | "hmacsha384" => hmac::HMAC_SHA384,
+            "sha512" | "hmacsha512" => hmac::HMAC_SHA512,
+            "sha1" | "hmacsha1" => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+            other => return Err(format!("Unsupported HMAC algorithm: {other}")),
+        };
+        let algorithm = algorithm.trim_start_matches("hmac").to_string();
+
+        Ok(CompiledHmacMatcher {
+            header: config.header.to_lowercase(),
+            algorithm,
+            key: hmac::Key::new(hmac_algorithm, config.secret.as_bytes()),
+        })
+    }
+
+    /// Check that `signature` is a valid HMAC of `body`.
+    ///
+    /// The comparison is constant-time with respect to the signature bytes.
+    pub fn matches(&self, body: &[u8], signature: Option<&str>) -> bool {
+        let Some(signature) = signature else {
+            return false;
+        };
+        let signature = signature.trim();
+        let encoded = match signature.split_once('=') {
+            Some((prefix, rest)) if prefix.eq_ignore_ascii_case(&self.algorithm) => rest,
+            _ => signature,
+        };
+
+        decode_signature(encoded).is_some_and(|tag| hmac::verify(&self.key, body, &tag).is_ok())
+    }
+}
+
+/// Decode a signature from hex, falling back to base64.
+fn decode_signature(encoded: &str) -> Option<Vec<u8>> {
+    decode_hex(encoded).or_else(|| {
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .ok()
+    })
+}
+
+fn decode_hex(encoded: &str) -> Option<Vec<u8>> {
+    if encoded.is_empty() || !encoded.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..encoded.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(encoded.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign_hex(secret: &str, body: &[u8]) -> String {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+        hmac::sign(&key, body)
+            .as_ref()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    fn matcher() -> CompiledHmacMatcher {
+        CompiledHmacMatcher::compile(&HmacMatcher {
+            header: "X-Signature".to_string(),
+            algorithm: "sha256".to_string(),
+            secret: "webhook-secret".to_string(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_hmac_valid_signature() {
+        let compiled = matcher();
+        let body = br#"{"event":"push"}"#;
+        let signature = sign_hex("webhook-secret", body);
+
+        assert_eq!(compiled.header, "x-signature");
+        assert!(compiled.matches(body, Some(&signature)));
+        assert!(compiled.matches(body, Some(&format!("sha256={signature}"))));
+        assert!(compiled.matches(body, Some(&signature.to_uppercase())));
+    }
+
+    #[test]
+    fn test_hmac_base64_signature() {
+        let compiled = matcher();
+        let body = b"payload";
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"webhook-secret");
+        let signature =
+            base64::engine::general_purpose::STANDARD.encode(hmac::sign(&key, body).as_ref());
+
+        assert!(compiled.matches(body, Some(&signature)));
+    }
+
+    #[test]
+    fn test_hmac_tampered_signature() {
+        let compiled = matcher();
+        let body = br#"{"event":"push"}"#;
+        let signature = sign_hex("webhook-secret", body);
+
+        // Tampered body
+        assert!(!compiled.matches(br#"{"event":"pull"}"#, Some(&signature)));
+        // Wrong secret
+        assert!(!compiled.matches(body, Some(&sign_hex("other-secret", body))));
+        // Flipped signature byte
+        let mut tampered = signature.clone();
+        tampered.replace_range(0..2, if &signature[0..2] == "00" { "01" } else { "00" });
+        assert!(!compiled.matches(body, Some(&tampered)));
+        // Missing or malformed header
+        assert!(!compiled.matches(body, None));
+        assert!(!compiled.matches(body, Some("not-a-signature")));
+    }
+
+    #[test]
+    fn test_hmac_unsupported_algorithm() {
+        let result = CompiledHmacMatcher::compile(&HmacMatcher {
+            header: "X-Signature".to_string(),
+            algorithm: "md5".to_string(),
+            secret: "secret".to_string(),
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hmac_matcher_serde_default_algorithm() {
+        let yaml = r#"
+header: X-Signature
+secret: webhook-secret
+"#;
+        let config: HmacMatcher = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.algorithm, "sha256");
+    }
+}
@@ -0,0 +1,128 @@
+//! Client address matching against CIDR ranges.
+//!
+//! Ranges are written as `192.168.0.0/16` or `2001:db8::/32`; a bare
+//! address is a single-host range. IPv4 clients reaching an IPv6 listener
+//! arrive as mapped addresses (`::ffff:10.0.0.1`) and match IPv4 ranges.
+
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Client address predicate.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct IpMatcher {
+    /// Ranges the client address must fall in
+    pub cidrs: Vec<String>,
+    /// Match clients outside the ranges instead (a denylist)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub not: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+/// Compiled client address predicate.
+#[derive(Debug, Clone)]
+pub struct CompiledIpMatcher {
+    ranges: Vec<IpNet>,
+    not: bool,
+}
+
+impl CompiledIpMatcher {
+    /// Parse the ranges, naming the first one that isn't a CIDR or address
+    pub fn compile(config: &IpMatcher) -> Result<Self, String> {
+        let ranges = config
+            .cidrs
+            .iter()
+            .map(|cidr| parse_range(cidr))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            ranges,
+            not: config.not,
+        })
+    }
+
+    /// Check the client address. An unknown address matches neither an
+    /// allowlist nor a denylist.
+    pub fn matches(&self, client_ip: Option<IpAddr>) -> bool {
+        let Some(ip) = client_ip else {
+            return false;
+        };
+        let ip = ip.to_canonical();
+        self.ranges.iter().any(|range| range.contains(&ip)) != self.not
+    }
+}
+
+fn parse_range(cidr: &str) -> Result<IpNet, String> {
+    let cidr = cidr.trim();
+    if cidr.contains('/') {
+        cidr.parse::<IpNet>()
+            .map(|net| net.trunc())
+            .map_err(|e| format!("invalid CIDR '{cidr}': {e}"))
+    } else {
+        cidr.parse::<IpAddr>()
+            .map(IpNet::from)
+            .map_err(|e| format!("invalid IP address '{cidr}': {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(cidrs: &[&str], not: bool) -> CompiledIpMatcher {
+        CompiledIpMatcher::compile(&IpMatcher {
+            cidrs: cidrs.iter().map(|c| c.to_string()).collect(),
+            not,
+        })
+        .unwrap()
+    }
+
+    fn ip(s: &str) -> Option<IpAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_ipv4_cidr_containment() {
+        let private = matcher(&["192.168.0.0/16", "10.1.2.3"], false);
+        assert!(private.matches(ip("192.168.4.20")));
+        assert!(private.matches(ip("10.1.2.3")));
+        assert!(!private.matches(ip("10.1.2.4")));
+        assert!(!private.matches(ip("192.169.0.1")));
+        // Mapped addresses from a dual-stack listener
+        assert!(private.matches(ip("::ffff:192.168.0.1")));
+    }
+
+    #[test]
+    fn test_ipv6_cidr_containment() {
+        let docs = matcher(&["2001:db8::/32"], false);
+        assert!(docs.matches(ip("2001:db8:1234::1")));
+        assert!(!docs.matches(ip("2001:db9::1")));
+        assert!(!docs.matches(ip("192.168.0.1")));
+    }
+
+    #[test]
+    fn test_denylist_and_unknown_client() {
+        let outside = matcher(&["10.0.0.0/8"], true);
+        assert!(!outside.matches(ip("10.9.9.9")));
+        assert!(outside.matches(ip("172.16.0.1")));
+        assert!(!outside.matches(None));
+        assert!(!matcher(&["10.0.0.0/8"], false).matches(None));
+    }
+
+    #[test]
+    fn test_invalid_ranges_are_named() {
+        for (cidr, expected) in [
+            ("10.0.0.0/33", "invalid CIDR '10.0.0.0/33'"),
+            ("not-an-ip", "invalid IP address 'not-an-ip'"),
+        ] {
+            let error = CompiledIpMatcher::compile(&IpMatcher {
+                cidrs: vec![cidr.to_string()],
+                not: false,
+            })
+            .unwrap_err();
+            assert!(error.contains(expected), "{error}");
+        }
+    }
+}
@@ -56,7 +56,7 @@ impl CompiledLogicalMatcher {
                 Ok(CompiledLogicalMatcher::And(compiled?))
             }
             LogicalMatcher::Leaf(string_matcher) => Ok(CompiledLogicalMatcher::Leaf(
-                CompiledStringMatcher::compile(string_matcher)?,
+                CompiledStringMatcher::compile(string_matcher, false)?,
             )),
         }
     }
@@ -5,6 +5,7 @@
 //! - `StringMatchCore` - Core string matching operations used across all matcher types
 //! - Helper functions for consistent case-sensitive/insensitive comparisons
 
+use super::regex_limits::build_regex;
 use regex::Regex;
 use std::sync::Arc;
 
@@ -132,7 +133,7 @@ impl StringMatchCore {
 
     /// Create a Regex matcher.
     pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
-        Ok(Self::Regex(Arc::new(Regex::new(pattern)?)))
+        Ok(Self::Regex(Arc::new(build_regex(pattern)?)))
     }
 
     /// Check if a value matches this matcher.
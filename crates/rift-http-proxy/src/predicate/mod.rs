@@ -16,9 +16,18 @@
 //! - `matcher` - Core matching traits and helpers (CachedValue, StringMatchCore)
 //! - `string_matcher` - Core string matching (equals, contains, startsWith, etc.)
 //! - `options` - Predicate options (caseSensitive, except, not)
-//! - `field_matcher` - Generic field matcher for headers and query parameters
+//! - `field_matcher` - Generic field matcher for headers, query parameters and trailers
 //! - `path_matcher` - Path matching with backward compatibility
+//! - `path_template` - Path templates capturing variables (`/users/{id}`)
+//! - `regex_limits` - Size limits applied to every regex compiled from config
 //! - `body_matcher` - Body matching (JSON, XPath, regex)
+//! - `content_encoding` - gzip/deflate decoding of bodies before matching
+//! - `error` - Compile errors naming the field (and rule) at fault
+//! - `payload_matcher` - Request body size and content type
+//! - `hmac_matcher` - HMAC signature validation over the raw body
+//! - `auth_matcher` - Basic and Bearer credentials in the `Authorization` header
+//! - `ip_matcher` - Client address matching against CIDR ranges
+//! - `user_agent` - User-Agent family classification (bot, mobile, desktop)
 //! - `logical` - Logical operators (NOT, OR, AND)
 //! - `deep_equals` - Deep equality for objects
 //! - `request` - Unified request predicate
@@ -26,28 +35,47 @@
 // Allow dead code while predicate system is being fully integrated
 #![allow(dead_code)]
 
+mod auth_matcher;
 mod body_matcher;
+mod content_encoding;
 mod deep_equals;
+mod error;
 mod field_matcher;
+mod hmac_matcher;
+mod ip_matcher;
 mod logical;
 mod matcher;
 mod options;
 mod path_matcher;
+mod path_template;
+mod payload_matcher;
+mod regex_limits;
 mod request;
 mod string_matcher;
+mod user_agent;
 
 // Re-export all public types for external consumers
 // Some are not yet used internally but are part of the public API
+pub use auth_matcher::{AuthMatcher, CompiledAuthMatcher};
 #[allow(unused_imports)]
-pub use body_matcher::{extract_json_path, extract_xpath, BodyMatcher, CompiledBodyMatcher};
+pub use body_matcher::{
+    extract_json_path, extract_json_path_streaming, extract_xpath, BodyMatcher,
+    CompiledBodyMatcher, JsonEqualsOptions,
+};
+pub use content_encoding::{decode_body, DecodedBody};
 #[allow(unused_imports)]
 pub use deep_equals::{parse_query_string, CompiledDeepEquals, DeepEquals};
+pub use error::PredicateCompileError;
 #[allow(unused_imports)]
 pub use field_matcher::{
     compile_header_matcher, compile_query_matcher, CompiledFieldMatcher, CompiledFieldMatcherInner,
-    CompiledHeaderMatcher, CompiledQueryMatcher, FieldMatcher, HeaderMatcher, QueryMatcher,
+    CompiledHeaderMatcher, CompiledQueryMatcher, CompiledTrailerMatcher, FieldMatcher,
+    HeaderMatcher, MultiValue, QueryMatcher, TrailerMatcher,
 };
 #[allow(unused_imports)]
+pub use hmac_matcher::{CompiledHmacMatcher, HmacMatcher};
+pub use ip_matcher::{CompiledIpMatcher, IpMatcher};
+#[allow(unused_imports)]
 pub use logical::{CompiledLogicalMatcher, LogicalMatcher};
 #[allow(unused_imports)]
 pub use matcher::{CachedValue, StringMatchCore};
@@ -55,10 +83,17 @@ pub use matcher::{CachedValue, StringMatchCore};
 pub use options::PredicateOptions;
 #[allow(unused_imports)]
 pub use path_matcher::{CompiledPathMatch, CompiledPathMatcher, PathMatcher};
+pub use path_template::PathTemplate;
+pub use payload_matcher::{BodySize, BodySizeMatcher, CompiledContentType};
+pub use regex_limits::{build_regex, set_regex_limits, DEFAULT_DFA_SIZE_LIMIT, DEFAULT_SIZE_LIMIT};
 #[allow(unused_imports)]
-pub use request::{CompiledRequestPredicate, RequestPredicate};
+pub use request::{
+    CompiledMethodMatcher, CompiledRequestPredicate, MethodMatcher, RequestPredicate,
+};
 #[allow(unused_imports)]
 pub use string_matcher::{CompiledExcept, CompiledStringMatcher, StringMatcher};
+#[allow(unused_imports)]
+pub use user_agent::UserAgentFamily;
 
 #[cfg(test)]
 mod tests {
@@ -17,6 +17,11 @@ pub struct PredicateOptions {
     /// Negate the match result (NOT operator)
     #[serde(default, skip_serializing_if = "is_false")]
     pub not: bool,
+
+    /// Require `matches` regexes to match the whole value. By default a
+    /// regex matches anywhere in the value, as in Mountebank.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub full_match: bool,
 }
 
 fn is_false(b: &bool) -> bool {
@@ -29,6 +34,7 @@ impl Default for PredicateOptions {
             case_sensitive: true, // Rift default - more performant
             except: None,
             not: false,
+            full_match: false,
         }
     }
 }
@@ -47,5 +53,6 @@ mod tests {
         assert!(options.case_sensitive); // Rift default is case-sensitive
         assert!(options.except.is_none());
         assert!(!options.not);
+        assert!(!options.full_match);
     }
 }
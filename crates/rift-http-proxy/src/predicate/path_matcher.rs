@@ -5,7 +5,8 @@
 
 use super::matcher::CachedValue;
 use super::options::PredicateOptions;
-use super::string_matcher::StringMatcher;
+use super::regex_limits::build_regex;
+use super::string_matcher::{anchor_pattern, StringMatcher};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -14,6 +15,11 @@ use std::sync::Arc;
 ///
 /// Backward compatible with existing Rift config format while supporting
 /// new Mountebank-style predicates.
+///
+/// `{ contains: ... }` and `{ endsWith: ... }` parse as [`PathMatcher::Full`],
+/// which keeps options such as `caseSensitive` beside them; the `Contains`
+/// and `EndsWith` variants are only built in code. See
+/// [`PathMatcher::canonicalize`].
 #[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
 #[serde(untagged)]
 pub enum PathMatcher {
@@ -30,6 +36,14 @@ pub enum PathMatcher {
     /// Regex match (backward compatible): { regex: "^/api/v\\d+/" }
     Regex { regex: String },
 
+    /// Full predicate with options
+    Full {
+        #[serde(flatten)]
+        matcher: StringMatcher,
+        #[serde(flatten, default)]
+        options: PredicateOptions,
+    },
+
     /// Contains substring: { contains: "/api" }
     Contains { contains: String },
 
@@ -38,14 +52,24 @@ pub enum PathMatcher {
         #[serde(rename = "endsWith")]
         ends_with: String,
     },
+}
 
-    /// Full predicate with options
-    Full {
-        #[serde(flatten)]
-        matcher: StringMatcher,
-        #[serde(flatten, default)]
-        options: PredicateOptions,
-    },
+impl PathMatcher {
+    /// The variant this matcher parses back as once serialized: `Contains`
+    /// and `EndsWith` become `Full` matchers with default options
+    pub fn canonicalize(self) -> Self {
+        match self {
+            PathMatcher::Contains { contains } => PathMatcher::Full {
+                matcher: StringMatcher::Contains(contains),
+                options: PredicateOptions::default(),
+            },
+            PathMatcher::EndsWith { ends_with } => PathMatcher::Full {
+                matcher: StringMatcher::EndsWith(ends_with),
+                options: PredicateOptions::default(),
+            },
+            other => other,
+        }
+    }
 }
 
 /// Compiled path matcher for efficient runtime evaluation.
@@ -86,7 +110,7 @@ impl CompiledPathMatch {
             }),
 
             PathMatcher::Regex { regex } => Ok(CompiledPathMatch {
-                matcher: CompiledPathMatcher::Regex(Arc::new(Regex::new(regex)?)),
+                matcher: CompiledPathMatcher::Regex(Arc::new(build_regex(regex)?)),
                 case_sensitive: true,
             }),
 
@@ -112,9 +136,9 @@ impl CompiledPathMatch {
                     StringMatcher::EndsWith(v) => {
                         CompiledPathMatcher::EndsWith(CachedValue::new(v))
                     }
-                    StringMatcher::Matches(pattern) => {
-                        CompiledPathMatcher::Regex(Arc::new(Regex::new(pattern)?))
-                    }
+                    StringMatcher::Matches(pattern) => CompiledPathMatcher::Regex(Arc::new(
+                        build_regex(&anchor_pattern(pattern, options.full_match))?,
+                    )),
                     StringMatcher::Exists(_) => CompiledPathMatcher::Any, // Path always exists
                 };
 
@@ -187,6 +211,19 @@ mod tests {
         assert!(!ends_with.matches("/data.xml"));
     }
 
+    #[test]
+    fn test_path_matcher_full_match_option() {
+        let unanchored: PathMatcher = serde_json::from_str(r#"{"matches": "/api"}"#).unwrap();
+        let anchored: PathMatcher =
+            serde_json::from_str(r#"{"matches": "/api", "fullMatch": true}"#).unwrap();
+        let unanchored = CompiledPathMatch::compile(&unanchored).unwrap();
+        let anchored = CompiledPathMatch::compile(&anchored).unwrap();
+
+        assert!(unanchored.matches("/api/v1"));
+        assert!(!anchored.matches("/api/v1"));
+        assert!(anchored.matches("/api"));
+    }
+
     #[test]
     fn test_path_matcher_serde() {
         // Exact path (backward compatible)
@@ -204,14 +241,28 @@ mod tests {
         let matcher: PathMatcher = serde_json::from_str(json).unwrap();
         assert!(matches!(matcher, PathMatcher::Regex { .. }));
 
-        // New contains
-        let json = r#"{"contains": "users"}"#;
+        // Contains and endsWith keep their options
+        let json = r#"{"contains": "users", "caseSensitive": false}"#;
         let matcher: PathMatcher = serde_json::from_str(json).unwrap();
-        assert!(matches!(matcher, PathMatcher::Contains { .. }));
+        assert!(matches!(
+            matcher,
+            PathMatcher::Full {
+                matcher: StringMatcher::Contains(_),
+                options: PredicateOptions {
+                    case_sensitive: false,
+                    ..
+                },
+            }
+        ));
 
-        // New endsWith
         let json = r#"{"endsWith": ".json"}"#;
         let matcher: PathMatcher = serde_json::from_str(json).unwrap();
-        assert!(matches!(matcher, PathMatcher::EndsWith { .. }));
+        assert_eq!(
+            matcher,
+            PathMatcher::EndsWith {
+                ends_with: ".json".to_string()
+            }
+            .canonicalize()
+        );
     }
 }
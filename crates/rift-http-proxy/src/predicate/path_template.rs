@@ -0,0 +1,149 @@
+//! Path templates with captured variables.
+//!
+//! A template such as `/users/{id}/orders/{orderId}` matches paths with the
+//! same segments, capturing `id` and `orderId`. A variable may constrain its
+//! segment with a regex after a colon: `/users/{id:\d+}` only matches numeric
+//! ids. Templates compile to a single anchored regex with named groups.
+
+use super::regex_limits::build_regex;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Compiled path template
+#[derive(Debug, Clone)]
+pub struct PathTemplate {
+    regex: Regex,
+}
+
+impl PathTemplate {
+    /// Compile a template; variables without a constraint match one whole
+    /// segment
+    pub fn compile(template: &str, case_sensitive: bool) -> Result<Self, String> {
+        let mut pattern = String::from(if case_sensitive { "^" } else { "(?i)^" });
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            pattern.push_str(&regex::escape(&rest[..start]));
+            let (variable, after) = split_variable(&rest[start + 1..])
+                .ok_or_else(|| format!("unclosed '{{' in path template '{template}'"))?;
+            let (name, constraint) = match variable.split_once(':') {
+                Some((name, constraint)) => (name, constraint),
+                None => (variable, "[^/]+"),
+            };
+            if !is_identifier(name) {
+                return Err(format!(
+                    "invalid variable name '{name}' in path template '{template}'"
+                ));
+            }
+            pattern.push_str(&format!("(?P<{name}>{constraint})"));
+            rest = after;
+        }
+        pattern.push_str(&regex::escape(rest));
+        pattern.push('$');
+
+        let regex = build_regex(&pattern)
+            .map_err(|e| format!("invalid path template '{template}': {e}"))?;
+        Ok(Self { regex })
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        self.regex.is_match(path)
+    }
+
+    /// Variables captured from `path`, or `None` if it doesn't match
+    pub fn captures(&self, path: &str) -> Option<HashMap<String, String>> {
+        let captures = self.regex.captures(path)?;
+        Some(
+            self.regex
+                .capture_names()
+                .flatten()
+                .filter_map(|name| {
+                    let value = captures.name(name)?;
+                    Some((name.to_string(), value.as_str().to_string()))
+                })
+                .collect(),
+        )
+    }
+
+    /// The regex the template compiled to
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+}
+
+/// Split `{...}` contents from the rest of the template, allowing braces
+/// nested inside a constraint such as `\d{4}`
+fn split_variable(s: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some((&s[..i], &s[i + 1..])),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captures_variables() {
+        let template = PathTemplate::compile("/users/{id}/orders/{orderId}", true).unwrap();
+        let captures = template.captures("/users/42/orders/a-7").unwrap();
+        assert_eq!(captures.len(), 2);
+        assert_eq!(captures["id"], "42");
+        assert_eq!(captures["orderId"], "a-7");
+    }
+
+    #[test]
+    fn test_segment_constraints() {
+        let template = PathTemplate::compile(r"/users/{id:\d+}/posts/{year:\d{4}}", true).unwrap();
+        assert!(template.is_match("/users/42/posts/2024"));
+        assert!(!template.is_match("/users/bob/posts/2024"));
+        assert!(!template.is_match("/users/42/posts/24"));
+    }
+
+    #[test]
+    fn test_segment_counts_must_agree() {
+        let template = PathTemplate::compile("/users/{id}", true).unwrap();
+        assert!(!template.is_match("/users"));
+        assert!(!template.is_match("/users/"));
+        assert!(!template.is_match("/users/42/orders"));
+        assert!(!template.is_match("/api/users/42"));
+        assert!(template.is_match("/users/42"));
+    }
+
+    #[test]
+    fn test_literals_are_escaped_and_case_folded() {
+        let template = PathTemplate::compile("/v1.0/{name}.json", true).unwrap();
+        assert!(template.is_match("/v1.0/report.json"));
+        assert!(!template.is_match("/v1x0/report.json"));
+
+        let insensitive = PathTemplate::compile("/Users/{id}", false).unwrap();
+        assert!(insensitive.is_match("/users/42"));
+        let sensitive = PathTemplate::compile("/Users/{id}", true).unwrap();
+        assert!(!sensitive.is_match("/users/42"));
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(PathTemplate::compile("/users/{id", true)
+            .unwrap_err()
+            .contains("unclosed"));
+        assert!(PathTemplate::compile("/users/{1st}", true)
+            .unwrap_err()
+            .contains("invalid variable name"));
+        assert!(PathTemplate::compile("/users/{id:(}", true).is_err());
+    }
+}
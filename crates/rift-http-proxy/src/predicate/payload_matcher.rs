@@ -0,0 +1,150 @@
+//! Request body size and content type matching.
+//!
+//! ```yaml
+//! bodySize: { gt: 1048576 }
+//! contentType: application/json
+//! ```
+//!
+//! The content type is compared without its parameters, so
+//! `application/json; charset=utf-8` matches `application/json`, and
+//! `text/*` matches any text type.
+
+use serde::{Deserialize, Serialize};
+
+/// Numeric bounds on the request body size in bytes; all given bounds must
+/// hold.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct BodySizeMatcher {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gt: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gte: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lt: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lte: Option<u64>,
+}
+
+/// What is known about a request body's size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodySize {
+    Exact(u64),
+    /// The body was only partly read
+    AtLeast(u64),
+    Unknown,
+}
+
+impl BodySizeMatcher {
+    /// Check the bounds. A partly read body satisfies lower bounds its
+    /// prefix already exceeds and never satisfies upper bounds.
+    pub fn matches(&self, size: BodySize) -> bool {
+        let (size, exact) = match size {
+            BodySize::Exact(size) => (size, true),
+            BodySize::AtLeast(size) => (size, false),
+            BodySize::Unknown => return false,
+        };
+        self.gt.is_none_or(|bound| size > bound)
+            && self.gte.is_none_or(|bound| size >= bound)
+            && self.lt.is_none_or(|bound| exact && size < bound)
+            && self.lte.is_none_or(|bound| exact && size <= bound)
+    }
+
+    /// Human-readable bounds, e.g. `> 1024 and <= 4096`
+    pub fn describe(&self) -> String {
+        [
+            (">", self.gt),
+            (">=", self.gte),
+            ("<", self.lt),
+            ("<=", self.lte),
+        ]
+        .into_iter()
+        .filter_map(|(op, bound)| Some(format!("{op} {}", bound?)))
+        .collect::<Vec<_>>()
+        .join(" and ")
+    }
+}
+
+/// Compiled content type matcher
+#[derive(Debug, Clone)]
+pub struct CompiledContentType {
+    /// Lowercase type, e.g. `application`
+    main: String,
+    /// Lowercase subtype, or `*` for any
+    sub: String,
+}
+
+impl CompiledContentType {
+    pub fn compile(content_type: &str) -> Result<Self, String> {
+        media_type(content_type)
+            .filter(|(main, _)| main != "*")
+            .map(|(main, sub)| Self { main, sub })
+            .ok_or_else(|| format!("invalid content type '{content_type}'"))
+    }
+
+    /// Check a `Content-Type` header value; a missing header never matches
+    pub fn matches(&self, header: Option<&str>) -> bool {
+        header
+            .and_then(media_type)
+            .is_some_and(|(main, sub)| main == self.main && (self.sub == "*" || sub == self.sub))
+    }
+}
+
+/// Type and subtype of a content type, lowercased and without parameters
+fn media_type(value: &str) -> Option<(String, String)> {
+    let essence = value.split(';').next()?.trim();
+    let (main, sub) = essence.split_once('/')?;
+    let (main, sub) = (main.trim(), sub.trim());
+    if main.is_empty() || sub.is_empty() {
+        return None;
+    }
+    Some((main.to_ascii_lowercase(), sub.to_ascii_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_threshold() {
+        let large: BodySizeMatcher = serde_yaml::from_str("gt: 1024").unwrap();
+        assert!(large.matches(BodySize::Exact(1025)));
+        assert!(!large.matches(BodySize::Exact(1024)));
+        assert!(!large.matches(BodySize::Unknown));
+        // A truncated prefix already past the threshold is enough
+        assert!(large.matches(BodySize::AtLeast(2048)));
+        assert!(!large.matches(BodySize::AtLeast(512)));
+
+        let range: BodySizeMatcher = serde_yaml::from_str("{gte: 10, lte: 20}").unwrap();
+        assert!(range.matches(BodySize::Exact(10)));
+        assert!(range.matches(BodySize::Exact(20)));
+        assert!(!range.matches(BodySize::Exact(21)));
+        assert!(!range.matches(BodySize::AtLeast(15)));
+        assert_eq!(range.describe(), ">= 10 and <= 20");
+
+        assert!(serde_yaml::from_str::<BodySizeMatcher>("above: 10").is_err());
+    }
+
+    #[test]
+    fn test_content_type_ignores_parameters_and_case() {
+        let json = CompiledContentType::compile("application/json").unwrap();
+        assert!(json.matches(Some("application/json")));
+        assert!(json.matches(Some("application/json; charset=utf-8")));
+        assert!(json.matches(Some("Application/JSON ;charset=UTF-8")));
+        assert!(!json.matches(Some("application/jsonp")));
+        assert!(!json.matches(Some("text/plain")));
+        assert!(!json.matches(Some("garbage")));
+        assert!(!json.matches(None));
+    }
+
+    #[test]
+    fn test_content_type_wildcard_subtype() {
+        let text = CompiledContentType::compile("text/*").unwrap();
+        assert!(text.matches(Some("text/html; charset=utf-8")));
+        assert!(text.matches(Some("text/csv")));
+        assert!(!text.matches(Some("application/xml")));
+
+        assert!(CompiledContentType::compile("json").is_err());
+        assert!(CompiledContentType::compile("*/*").is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Size limits for regexes compiled from config.
+//!
+//! The `regex` crate matches in linear time, so a pathological pattern
+//! costs memory and compile time rather than match time. Capping the
+//! compiled size makes such a pattern fail when its config loads instead
+//! of stalling the proxy. The limits are process-wide and set from the
+//! `regex` config section before rules compile.
+
+use regex::{Regex, RegexBuilder};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Default compiled program size limit, as in the `regex` crate
+pub const DEFAULT_SIZE_LIMIT: usize = 10 * (1 << 20);
+/// Default lazy DFA cache size limit, as in the `regex` crate
+pub const DEFAULT_DFA_SIZE_LIMIT: usize = 2 * (1 << 20);
+
+static SIZE_LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_SIZE_LIMIT);
+static DFA_SIZE_LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_DFA_SIZE_LIMIT);
+
+/// Longest stretch of a pattern quoted in a limit error
+const QUOTED_PATTERN_CHARS: usize = 80;
+
+/// Set the limits used by [`build_regex`] from now on
+pub fn set_regex_limits(size_limit: usize, dfa_size_limit: usize) {
+    SIZE_LIMIT.store(size_limit, Ordering::Relaxed);
+    DFA_SIZE_LIMIT.store(dfa_size_limit, Ordering::Relaxed);
+}
+
+/// Compile a pattern within the configured limits
+pub fn build_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    build_regex_with(pattern, |builder| builder)
+}
+
+/// Compile a pattern within the configured limits, with further options
+pub fn build_regex_with(
+    pattern: &str,
+    configure: impl FnOnce(&mut RegexBuilder) -> &mut RegexBuilder,
+) -> Result<Regex, regex::Error> {
+    let mut builder = RegexBuilder::new(pattern);
+    builder
+        .size_limit(SIZE_LIMIT.load(Ordering::Relaxed))
+        .dfa_size_limit(DFA_SIZE_LIMIT.load(Ordering::Relaxed));
+    configure(&mut builder).build().map_err(|e| match e {
+        // The crate's message doesn't say which pattern was too big
+        regex::Error::CompiledTooBig(limit) => regex::Error::Syntax(format!(
+            "pattern '{}' exceeds the regex size limit of {limit} bytes \
+             (see regex.size_limit)",
+            quoted(pattern)
+        )),
+        other => other,
+    })
+}
+
+fn quoted(pattern: &str) -> String {
+    match pattern.char_indices().nth(QUOTED_PATTERN_CHARS) {
+        Some((end, _)) => format!("{}...", &pattern[..end]),
+        None => pattern.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enormous_pattern_is_rejected() {
+        let pattern = r"\w{1000}{1000}";
+        let error = build_regex(pattern).unwrap_err().to_string();
+        assert!(error.contains(pattern), "{error}");
+        assert!(error.contains("regex size limit"), "{error}");
+
+        assert!(build_regex(r"^/api/v\d+/").is_ok());
+    }
+
+    #[test]
+    fn test_long_patterns_are_quoted_truncated() {
+        let pattern = format!("{}\\w{{1000}}{{1000}}", "x".repeat(200));
+        let error = build_regex(&pattern).unwrap_err().to_string();
+        assert!(
+            error.contains(&format!("'{}...'", "x".repeat(80))),
+            "{error}"
+        );
+    }
+}
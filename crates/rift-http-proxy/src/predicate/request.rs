@@ -1,10 +1,15 @@
 //! Unified request predicate for matching against request fields.
 
 use super::body_matcher::{BodyMatcher, CompiledBodyMatcher};
-use super::field_matcher::{compile_header_matcher, compile_query_matcher, FieldMatcher};
+use super::error::PredicateCompileError;
+use super::field_matcher::{
+    compile_header_matcher, compile_query_matcher, CompiledFieldMatcher, FieldMatcher,
+    TrailerMatcher,
+};
 use super::options::PredicateOptions;
 use super::path_matcher::{CompiledPathMatch, PathMatcher};
 use super::string_matcher::{CompiledStringMatcher, StringMatcher};
+use hyper::HeaderMap;
 use serde::{Deserialize, Serialize};
 
 /// A complete request predicate that can match against various request fields.
@@ -16,7 +21,7 @@ use serde::{Deserialize, Serialize};
 pub struct RequestPredicate {
     /// HTTP method match
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub method: Option<StringMatcher>,
+    pub method: Option<MethodMatcher>,
 
     /// Path match
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -30,6 +35,13 @@ pub struct RequestPredicate {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub query: Vec<FieldMatcher>,
 
+    /// Request trailer matchers (all must match).
+    ///
+    /// Trailers follow the body, so a predicate using them needs the whole
+    /// body buffered before it can be evaluated.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trailers: Vec<TrailerMatcher>,
+
     /// Body matcher
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub body: Option<BodyMatcher>,
@@ -39,55 +51,168 @@ pub struct RequestPredicate {
     pub options: PredicateOptions,
 }
 
+impl RequestPredicate {
+    /// The predicate this one parses back as once serialized, so echoed
+    /// rules (`--print-config`, the admin API) compare equal to the original
+    pub fn canonicalize(self) -> Self {
+        Self {
+            // `path: null` reads back as no path
+            path: self
+                .path
+                .map(PathMatcher::canonicalize)
+                .filter(|path| *path != PathMatcher::Any),
+            ..self
+        }
+    }
+}
+
+/// HTTP method match: either a list of allowed methods or a string matcher.
+///
+/// ```yaml
+/// method: [GET, POST]
+/// method: { matches: "^(PUT|PATCH)$" }
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum MethodMatcher {
+    /// Any of the listed methods, compared case-insensitively
+    OneOf(Vec<String>),
+    /// String matcher against the method name
+    Matcher(StringMatcher),
+}
+
+impl From<StringMatcher> for MethodMatcher {
+    fn from(matcher: StringMatcher) -> Self {
+        MethodMatcher::Matcher(matcher)
+    }
+}
+
+/// Compiled method matcher.
+#[derive(Debug, Clone)]
+pub enum CompiledMethodMatcher {
+    OneOf(Vec<String>),
+    Matcher(CompiledStringMatcher),
+}
+
+impl CompiledMethodMatcher {
+    /// Compile a MethodMatcher configuration.
+    pub fn compile(matcher: &MethodMatcher) -> Result<Self, regex::Error> {
+        match matcher {
+            MethodMatcher::OneOf(methods) => Ok(CompiledMethodMatcher::OneOf(
+                methods.iter().map(|m| m.to_uppercase()).collect(),
+            )),
+            MethodMatcher::Matcher(matcher) => {
+                CompiledStringMatcher::compile(matcher, false).map(CompiledMethodMatcher::Matcher)
+            }
+        }
+    }
+
+    /// Check if a request method matches.
+    ///
+    /// Method lists ignore case regardless of `case_sensitive`; string
+    /// matchers honour it.
+    pub fn matches(&self, method: &str, case_sensitive: bool) -> bool {
+        match self {
+            CompiledMethodMatcher::OneOf(methods) => {
+                methods.iter().any(|m| m.eq_ignore_ascii_case(method))
+            }
+            CompiledMethodMatcher::Matcher(matcher) => {
+                matcher.matches(Some(method), case_sensitive)
+            }
+        }
+    }
+}
+
 /// Compiled request predicate for efficient runtime evaluation.
 #[derive(Debug, Clone)]
 pub struct CompiledRequestPredicate {
-    pub method: Option<CompiledStringMatcher>,
+    pub method: Option<CompiledMethodMatcher>,
     pub path: Option<CompiledPathMatch>,
-    pub headers: Vec<super::field_matcher::CompiledFieldMatcher>,
-    pub query: Vec<super::field_matcher::CompiledFieldMatcher>,
+    pub headers: Vec<CompiledFieldMatcher>,
+    pub query: Vec<CompiledFieldMatcher>,
+    pub trailers: Vec<CompiledFieldMatcher>,
     pub body: Option<CompiledBodyMatcher>,
     pub case_sensitive: bool,
 }
 
 impl CompiledRequestPredicate {
     /// Compile a RequestPredicate configuration.
-    pub fn compile(predicate: &RequestPredicate) -> Result<Self, regex::Error> {
+    pub fn compile(predicate: &RequestPredicate) -> Result<Self, PredicateCompileError> {
         let method = predicate
             .method
             .as_ref()
-            .map(CompiledStringMatcher::compile)
-            .transpose()?;
+            .map(CompiledMethodMatcher::compile)
+            .transpose()
+            .map_err(|e| PredicateCompileError::new("method", e))?;
 
         let path = predicate
             .path
             .as_ref()
             .map(CompiledPathMatch::compile)
-            .transpose()?;
+            .transpose()
+            .map_err(|e| PredicateCompileError::new("path", e))?;
 
-        let headers: Result<Vec<_>, _> = predicate
+        let headers = predicate
             .headers
             .iter()
-            .map(compile_header_matcher)
-            .collect();
+            .map(|header| {
+                compile_header_matcher(header).map_err(|e| {
+                    PredicateCompileError::new(format!("header '{}'", header.name()), e)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let query = predicate
+            .query
+            .iter()
+            .map(|param| {
+                compile_query_matcher(param)
+                    .map_err(|e| PredicateCompileError::new(format!("query '{}'", param.name()), e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let query: Result<Vec<_>, _> = predicate.query.iter().map(compile_query_matcher).collect();
+        let trailers = predicate
+            .trailers
+            .iter()
+            .map(|trailer| {
+                compile_header_matcher(trailer).map_err(|e| {
+                    PredicateCompileError::new(format!("trailer '{}'", trailer.name()), e)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         let body = predicate
             .body
             .as_ref()
             .map(CompiledBodyMatcher::compile)
-            .transpose()?;
+            .transpose()
+            .map_err(|e| PredicateCompileError::new("body", e))?;
 
         Ok(CompiledRequestPredicate {
             method,
             path,
-            headers: headers?,
-            query: query?,
+            headers,
+            query,
+            trailers,
             body,
             case_sensitive: predicate.options.case_sensitive,
         })
     }
+
+    /// Check the trailer matchers against the request's trailers, if any
+    /// arrived.
+    pub fn matches_trailers(&self, trailers: Option<&HeaderMap>) -> bool {
+        self.trailers
+            .iter()
+            .all(|matcher| matcher.matches_headers(trailers))
+    }
+
+    /// Check the method constraint; predicates without one match any method.
+    pub fn matches_method(&self, method: &str) -> bool {
+        self.method
+            .as_ref()
+            .is_none_or(|m| m.matches(method, self.case_sensitive))
+    }
 }
 
 #[cfg(test)]
@@ -97,7 +222,7 @@ mod tests {
     #[test]
     fn test_request_predicate_compile() {
         let predicate = RequestPredicate {
-            method: Some(StringMatcher::Equals("GET".to_string())),
+            method: Some(StringMatcher::Equals("GET".to_string()).into()),
             path: Some(PathMatcher::Prefix {
                 prefix: "/api".to_string(),
             }),
@@ -109,6 +234,7 @@ mod tests {
                 name: "page".to_string(),
                 value: "1".to_string(),
             }],
+            trailers: vec![],
             body: None,
             options: PredicateOptions::default(),
         };
@@ -122,4 +248,211 @@ mod tests {
         assert_eq!(compiled.headers.len(), 1);
         assert_eq!(compiled.query.len(), 1);
     }
+
+    #[test]
+    fn test_method_list_matches_any_listed_method() {
+        let predicate: RequestPredicate =
+            serde_json::from_str(r#"{"method": ["GET", "POST"]}"#).unwrap();
+        assert_eq!(
+            predicate.method,
+            Some(MethodMatcher::OneOf(vec!["GET".into(), "POST".into()]))
+        );
+
+        let compiled = CompiledRequestPredicate::compile(&predicate).unwrap();
+        assert!(compiled.matches_method("GET"));
+        assert!(compiled.matches_method("POST"));
+        assert!(compiled.matches_method("post"));
+        assert!(!compiled.matches_method("DELETE"));
+    }
+
+    #[test]
+    fn test_method_string_matcher_still_supported() {
+        let predicate: RequestPredicate =
+            serde_json::from_str(r#"{"method": {"equals": "PUT"}}"#).unwrap();
+        let compiled = CompiledRequestPredicate::compile(&predicate).unwrap();
+        assert!(compiled.matches_method("PUT"));
+        assert!(!compiled.matches_method("GET"));
+
+        let any = CompiledRequestPredicate::compile(&RequestPredicate::default()).unwrap();
+        assert!(any.matches_method("DELETE"));
+    }
+
+    #[test]
+    fn test_compile_error_names_field() {
+        let predicate: RequestPredicate =
+            serde_json::from_str(r#"{"query": [{"name": "page", "matches": "[0-9"}]}"#).unwrap();
+        let err = CompiledRequestPredicate::compile(&predicate).unwrap_err();
+        assert_eq!(err.field, "query 'page'");
+        assert!(err.rule.is_none());
+        assert!(
+            err.to_string()
+                .starts_with("invalid pattern in query 'page': "),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn test_trailer_predicate() {
+        let predicate: RequestPredicate =
+            serde_json::from_str(r#"{"trailers": [{"name": "Grpc-Status", "equals": "0"}]}"#)
+                .unwrap();
+        let compiled = CompiledRequestPredicate::compile(&predicate).unwrap();
+
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+        assert!(compiled.matches_trailers(Some(&trailers)));
+        trailers.insert("grpc-status", "13".parse().unwrap());
+        assert!(!compiled.matches_trailers(Some(&trailers)));
+        assert!(!compiled.matches_trailers(None));
+
+        // No trailer matchers: anything goes
+        let any = CompiledRequestPredicate::compile(&RequestPredicate::default()).unwrap();
+        assert!(any.matches_trailers(None));
+    }
+
+    mod round_trip {
+        use super::*;
+        use crate::predicate::MultiValue;
+        use proptest::collection::vec;
+        use proptest::option;
+        use proptest::prelude::*;
+
+        fn text() -> impl Strategy<Value = String> {
+            // Includes strings YAML would otherwise read as null, bools or numbers
+            prop_oneof![
+                "[a-zA-Z0-9/_.-]{0,12}",
+                Just("null".to_string()),
+                Just("1".to_string())
+            ]
+        }
+
+        fn string_matcher() -> impl Strategy<Value = StringMatcher> {
+            prop_oneof![
+                text().prop_map(StringMatcher::Equals),
+                text().prop_map(StringMatcher::Contains),
+                text().prop_map(StringMatcher::StartsWith),
+                text().prop_map(StringMatcher::EndsWith),
+                text().prop_map(StringMatcher::Matches),
+                any::<bool>().prop_map(StringMatcher::Exists),
+            ]
+        }
+
+        fn options() -> impl Strategy<Value = PredicateOptions> {
+            (
+                any::<bool>(),
+                option::of(text()),
+                any::<bool>(),
+                any::<bool>(),
+            )
+                .prop_map(|(case_sensitive, except, not, full_match)| {
+                    PredicateOptions {
+                        case_sensitive,
+                        except,
+                        not,
+                        full_match,
+                    }
+                })
+        }
+
+        fn path_matcher() -> impl Strategy<Value = PathMatcher> {
+            prop_oneof![
+                Just(PathMatcher::Any),
+                text().prop_map(|exact| PathMatcher::Exact { exact }),
+                text().prop_map(|prefix| PathMatcher::Prefix { prefix }),
+                text().prop_map(|regex| PathMatcher::Regex { regex }),
+                text().prop_map(|contains| PathMatcher::Contains { contains }),
+                text().prop_map(|ends_with| PathMatcher::EndsWith { ends_with }),
+                (string_matcher(), options())
+                    .prop_map(|(matcher, options)| PathMatcher::Full { matcher, options }),
+            ]
+        }
+
+        fn field_matcher() -> impl Strategy<Value = FieldMatcher> {
+            let values = prop_oneof![Just(MultiValue::Any), Just(MultiValue::All)];
+            prop_oneof![
+                (text(), text()).prop_map(|(name, value)| FieldMatcher::Simple { name, value }),
+                (
+                    text(),
+                    vec(string_matcher(), 0..3),
+                    values.clone(),
+                    options()
+                )
+                    .prop_map(|(name, or, values, options)| FieldMatcher::Or {
+                        name,
+                        or,
+                        values,
+                        options,
+                    }),
+                (text(), string_matcher(), values, options()).prop_map(
+                    |(name, matcher, values, options)| FieldMatcher::Full {
+                        name,
+                        matcher,
+                        values,
+                        options,
+                    }
+                ),
+            ]
+        }
+
+        fn method_matcher() -> impl Strategy<Value = MethodMatcher> {
+            prop_oneof![
+                vec("[A-Z]{1,7}", 0..3).prop_map(MethodMatcher::OneOf),
+                string_matcher().prop_map(MethodMatcher::Matcher),
+            ]
+        }
+
+        fn body_matcher() -> impl Strategy<Value = BodyMatcher> {
+            prop_oneof![
+                text().prop_map(BodyMatcher::Equals),
+                text().prop_map(BodyMatcher::Contains),
+                text().prop_map(BodyMatcher::Matches),
+                (text(), string_matcher())
+                    .prop_map(|(path, matcher)| BodyMatcher::JsonPath { path, matcher }),
+                (text(), any::<usize>())
+                    .prop_map(|(pattern, count)| BodyMatcher::RepeatsMoreThan { pattern, count }),
+            ]
+        }
+
+        fn request_predicate() -> impl Strategy<Value = RequestPredicate> {
+            (
+                option::of(method_matcher()),
+                option::of(path_matcher()),
+                vec(field_matcher(), 0..3),
+                vec(field_matcher(), 0..3),
+                vec(field_matcher(), 0..2),
+                option::of(body_matcher()),
+                options(),
+            )
+                .prop_map(|(method, path, headers, query, trailers, body, options)| {
+                    RequestPredicate {
+                        method,
+                        path,
+                        headers,
+                        query,
+                        trailers,
+                        body,
+                        options,
+                    }
+                })
+        }
+
+        proptest! {
+            #[test]
+            fn test_yaml_round_trip(predicate in request_predicate()) {
+                let predicate = predicate.canonicalize();
+                let yaml = serde_yaml::to_string(&predicate).unwrap();
+                let parsed: RequestPredicate = serde_yaml::from_str(&yaml)
+                    .map_err(|e| TestCaseError::fail(format!("{e} in:\n{yaml}")))?;
+                prop_assert_eq!(parsed, predicate, "{}", yaml);
+            }
+
+            #[test]
+            fn test_json_round_trip(predicate in request_predicate()) {
+                let predicate = predicate.canonicalize();
+                let json = serde_json::to_string(&predicate).unwrap();
+                let parsed: RequestPredicate = serde_json::from_str(&json).unwrap();
+                prop_assert_eq!(parsed, predicate, "{}", json);
+            }
+        }
+    }
 }
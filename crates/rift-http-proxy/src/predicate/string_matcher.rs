@@ -4,14 +4,20 @@
 //! the predicate system. It supports all Mountebank string matching operations.
 
 use super::matcher::CachedValue;
+use super::regex_limits::{build_regex, build_regex_with};
 use regex::Regex;
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
 use std::sync::Arc;
 
 /// String matching operator for comparing string values.
 ///
 /// Supports all Mountebank string matching operations.
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+///
+/// Always serialized as a single-key map (`{ equals: x }`): serde_yaml
+/// would otherwise write a `!equals x` tag, which the untagged enums
+/// holding string matchers can't read back.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum StringMatcher {
     /// Exact string equality
@@ -30,7 +36,8 @@ pub enum StringMatcher {
     #[serde(rename = "endsWith")]
     EndsWith(String),
 
-    /// Regex pattern match
+    /// Regex pattern match, anywhere in the value unless the predicate sets
+    /// `fullMatch: true`
     #[serde(rename = "matches")]
     Matches(String),
 
@@ -45,6 +52,21 @@ impl Default for StringMatcher {
     }
 }
 
+impl Serialize for StringMatcher {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        match self {
+            StringMatcher::Equals(v) => map.serialize_entry("equals", v)?,
+            StringMatcher::Contains(v) => map.serialize_entry("contains", v)?,
+            StringMatcher::StartsWith(v) => map.serialize_entry("startsWith", v)?,
+            StringMatcher::EndsWith(v) => map.serialize_entry("endsWith", v)?,
+            StringMatcher::Matches(v) => map.serialize_entry("matches", v)?,
+            StringMatcher::Exists(v) => map.serialize_entry("exists", v)?,
+        }
+        map.end()
+    }
+}
+
 /// Compiled string matcher for efficient runtime evaluation.
 #[derive(Debug, Clone)]
 pub enum CompiledStringMatcher {
@@ -58,7 +80,9 @@ pub enum CompiledStringMatcher {
 
 impl CompiledStringMatcher {
     /// Compile a StringMatcher into an efficient runtime form.
-    pub fn compile(matcher: &StringMatcher) -> Result<Self, regex::Error> {
+    ///
+    /// With `full_match`, a `matches` regex is anchored to the whole value.
+    pub fn compile(matcher: &StringMatcher, full_match: bool) -> Result<Self, regex::Error> {
         match matcher {
             StringMatcher::Equals(v) => Ok(CompiledStringMatcher::Equals(CachedValue::new(v))),
             StringMatcher::Contains(v) => Ok(CompiledStringMatcher::Contains(CachedValue::new(v))),
@@ -67,7 +91,7 @@ impl CompiledStringMatcher {
             }
             StringMatcher::EndsWith(v) => Ok(CompiledStringMatcher::EndsWith(CachedValue::new(v))),
             StringMatcher::Matches(pattern) => {
-                let regex = Regex::new(pattern)?;
+                let regex = build_regex(&anchor_pattern(pattern, full_match))?;
                 Ok(CompiledStringMatcher::Matches(Arc::new(regex)))
             }
             StringMatcher::Exists(exists) => Ok(CompiledStringMatcher::Exists(*exists)),
@@ -129,6 +153,15 @@ impl CompiledStringMatcher {
     }
 }
 
+/// Wrap a regex so it must match the whole value when `full_match` is set
+pub fn anchor_pattern(pattern: &str, full_match: bool) -> String {
+    if full_match {
+        format!("^(?:{pattern})$")
+    } else {
+        pattern.to_string()
+    }
+}
+
 /// Compiled except regex for stripping patterns before matching.
 #[derive(Debug, Clone)]
 pub struct CompiledExcept {
@@ -137,9 +170,13 @@ pub struct CompiledExcept {
 
 impl CompiledExcept {
     /// Compile an except regex pattern.
-    pub fn compile(pattern: &str) -> Result<Self, regex::Error> {
+    ///
+    /// Follows the predicate's `case_sensitive` option, so a case-insensitive
+    /// predicate strips matches regardless of case.
+    pub fn compile(pattern: &str, case_sensitive: bool) -> Result<Self, regex::Error> {
+        let regex = build_regex_with(pattern, |b| b.case_insensitive(!case_sensitive))?;
         Ok(CompiledExcept {
-            regex: Arc::new(Regex::new(pattern)?),
+            regex: Arc::new(regex),
         })
     }
 
@@ -156,7 +193,8 @@ mod tests {
     #[test]
     fn test_string_matcher_equals() {
         let matcher =
-            CompiledStringMatcher::compile(&StringMatcher::Equals("test".to_string())).unwrap();
+            CompiledStringMatcher::compile(&StringMatcher::Equals("test".to_string()), false)
+                .unwrap();
 
         assert!(matcher.matches(Some("test"), true));
         assert!(!matcher.matches(Some("TEST"), true));
@@ -168,7 +206,8 @@ mod tests {
     #[test]
     fn test_string_matcher_contains() {
         let matcher =
-            CompiledStringMatcher::compile(&StringMatcher::Contains("api".to_string())).unwrap();
+            CompiledStringMatcher::compile(&StringMatcher::Contains("api".to_string()), false)
+                .unwrap();
 
         assert!(matcher.matches(Some("/api/v1"), true));
         assert!(matcher.matches(Some("my-api-service"), true));
@@ -181,7 +220,8 @@ mod tests {
     #[test]
     fn test_string_matcher_starts_with() {
         let matcher =
-            CompiledStringMatcher::compile(&StringMatcher::StartsWith("/api".to_string())).unwrap();
+            CompiledStringMatcher::compile(&StringMatcher::StartsWith("/api".to_string()), false)
+                .unwrap();
 
         assert!(matcher.matches(Some("/api/v1"), true));
         assert!(matcher.matches(Some("/api"), true));
@@ -194,7 +234,8 @@ mod tests {
     #[test]
     fn test_string_matcher_ends_with() {
         let matcher =
-            CompiledStringMatcher::compile(&StringMatcher::EndsWith(".json".to_string())).unwrap();
+            CompiledStringMatcher::compile(&StringMatcher::EndsWith(".json".to_string()), false)
+                .unwrap();
 
         assert!(matcher.matches(Some("/data.json"), true));
         assert!(matcher.matches(Some(".json"), true));
@@ -206,9 +247,11 @@ mod tests {
 
     #[test]
     fn test_string_matcher_regex() {
-        let matcher =
-            CompiledStringMatcher::compile(&StringMatcher::Matches(r"^/api/v\d+/".to_string()))
-                .unwrap();
+        let matcher = CompiledStringMatcher::compile(
+            &StringMatcher::Matches(r"^/api/v\d+/".to_string()),
+            false,
+        )
+        .unwrap();
 
         assert!(matcher.matches(Some("/api/v1/users"), true));
         assert!(matcher.matches(Some("/api/v99/items"), true));
@@ -216,10 +259,31 @@ mod tests {
         assert!(!matcher.matches(None, true));
     }
 
+    #[test]
+    fn test_string_matcher_regex_full_match() {
+        let pattern = StringMatcher::Matches("/api".to_string());
+        let unanchored = CompiledStringMatcher::compile(&pattern, false).unwrap();
+        let anchored = CompiledStringMatcher::compile(&pattern, true).unwrap();
+
+        // By default the regex matches anywhere in the value
+        assert!(unanchored.matches(Some("/api/v1"), true));
+        assert!(!anchored.matches(Some("/api/v1"), true));
+        assert!(anchored.matches(Some("/api"), true));
+
+        // Alternations are anchored as a whole
+        let pattern = StringMatcher::Matches("/api|/admin".to_string());
+        let anchored = CompiledStringMatcher::compile(&pattern, true).unwrap();
+        assert!(anchored.matches(Some("/admin"), true));
+        assert!(!anchored.matches(Some("/api/v1"), true));
+        assert!(!anchored.matches(Some("/v1/admin/x"), true));
+    }
+
     #[test]
     fn test_string_matcher_exists() {
-        let exists_true = CompiledStringMatcher::compile(&StringMatcher::Exists(true)).unwrap();
-        let exists_false = CompiledStringMatcher::compile(&StringMatcher::Exists(false)).unwrap();
+        let exists_true =
+            CompiledStringMatcher::compile(&StringMatcher::Exists(true), false).unwrap();
+        let exists_false =
+            CompiledStringMatcher::compile(&StringMatcher::Exists(false), false).unwrap();
 
         assert!(exists_true.matches(Some("any value"), true));
         assert!(exists_true.matches(Some(""), true));
@@ -264,7 +328,7 @@ mod tests {
 
     #[test]
     fn test_except_parameter() {
-        let except = CompiledExcept::compile(r"\d+").unwrap();
+        let except = CompiledExcept::compile(r"\d+", true).unwrap();
 
         // Strips all digits
         assert_eq!(except.apply("abc123def456"), "abcdef");
@@ -274,10 +338,12 @@ mod tests {
 
     #[test]
     fn test_string_matcher_with_except() {
-        let matcher =
-            CompiledStringMatcher::compile(&StringMatcher::Equals("Hello World".to_string()))
-                .unwrap();
-        let except = CompiledExcept::compile(r"\d+").unwrap();
+        let matcher = CompiledStringMatcher::compile(
+            &StringMatcher::Equals("Hello World".to_string()),
+            false,
+        )
+        .unwrap();
+        let except = CompiledExcept::compile(r"\d+", true).unwrap();
 
         // Without except - doesn't match
         assert!(!matcher.matches(Some("Hello123 World456"), true));
@@ -285,4 +351,22 @@ mod tests {
         // With except - strips digits and matches
         assert!(matcher.matches_with_except(Some("Hello123 World456"), true, Some(&except)));
     }
+
+    #[test]
+    fn test_except_follows_case_sensitivity() {
+        let matcher =
+            CompiledStringMatcher::compile(&StringMatcher::Equals("api-".to_string()), false)
+                .unwrap();
+
+        // Case-insensitive: `version` is stripped whatever its case
+        let except = CompiledExcept::compile("version", false).unwrap();
+        assert_eq!(except.apply("api-VERSION"), "api-");
+        assert!(matcher.matches_with_except(Some("api-Version"), false, Some(&except)));
+        assert!(matcher.matches_with_except(Some("API-version"), false, Some(&except)));
+
+        // Case-sensitive: only the exact case is stripped
+        let except = CompiledExcept::compile("version", true).unwrap();
+        assert_eq!(except.apply("api-VERSION"), "api-VERSION");
+        assert!(!matcher.matches_with_except(Some("api-Version"), true, Some(&except)));
+    }
 }
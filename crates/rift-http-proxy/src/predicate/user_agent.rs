@@ -0,0 +1,129 @@
+//! Lightweight User-Agent classification.
+//!
+//! Classifies a User-Agent header into a coarse family (bot, mobile, desktop)
+//! using keyword checks, so rules can target device classes without
+//! maintaining raw UA regexes.
+
+use serde::{Deserialize, Serialize};
+
+/// User-Agent family
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UserAgentFamily {
+    /// Crawlers, monitoring agents and HTTP libraries
+    Bot,
+    /// Phones and tablets
+    Mobile,
+    /// Everything else
+    Desktop,
+}
+
+/// Substrings identifying crawlers and non-browser clients (lowercase)
+const BOT_KEYWORDS: &[&str] = &[
+    "bot",
+    "crawler",
+    "spider",
+    "slurp",
+    "crawl",
+    "facebookexternalhit",
+    "headlesschrome",
+    "lighthouse",
+    "curl/",
+    "wget/",
+    "python-requests",
+    "python-urllib",
+    "go-http-client",
+    "okhttp",
+    "java/",
+    "apache-httpclient",
+];
+
+/// Substrings identifying mobile devices (lowercase)
+const MOBILE_KEYWORDS: &[&str] = &[
+    "mobile",
+    "android",
+    "iphone",
+    "ipad",
+    "ipod",
+    "windows phone",
+    "blackberry",
+    "opera mini",
+];
+
+impl UserAgentFamily {
+    /// Classify a User-Agent string.
+    ///
+    /// Returns None for an empty User-Agent.
+    pub fn classify(user_agent: &str) -> Option<Self> {
+        let ua = user_agent.trim().to_ascii_lowercase();
+        if ua.is_empty() {
+            return None;
+        }
+
+        // Bots are checked first: many crawlers also advertise "Mobile"
+        if BOT_KEYWORDS.iter().any(|k| ua.contains(k)) {
+            Some(UserAgentFamily::Bot)
+        } else if MOBILE_KEYWORDS.iter().any(|k| ua.contains(k)) {
+            Some(UserAgentFamily::Mobile)
+        } else {
+            Some(UserAgentFamily::Desktop)
+        }
+    }
+
+    /// Check whether a (possibly missing) User-Agent belongs to this family
+    pub fn matches(self, user_agent: Option<&str>) -> bool {
+        user_agent.and_then(Self::classify) == Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_googlebot_as_bot() {
+        let ua = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+        assert_eq!(UserAgentFamily::classify(ua), Some(UserAgentFamily::Bot));
+
+        // Smartphone Googlebot advertises Mobile but is still a bot
+        let ua = "Mozilla/5.0 (Linux; Android 6.0.1; Nexus 5X Build/MMB29P) AppleWebKit/537.36 \
+                  (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36 \
+                  (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+        assert_eq!(UserAgentFamily::classify(ua), Some(UserAgentFamily::Bot));
+        assert_eq!(
+            UserAgentFamily::classify("curl/8.4.0"),
+            Some(UserAgentFamily::Bot)
+        );
+    }
+
+    #[test]
+    fn test_classify_mobile_and_desktop() {
+        let iphone = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 \
+                      (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+        assert_eq!(
+            UserAgentFamily::classify(iphone),
+            Some(UserAgentFamily::Mobile)
+        );
+
+        let android = "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 \
+                       (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36";
+        assert_eq!(
+            UserAgentFamily::classify(android),
+            Some(UserAgentFamily::Mobile)
+        );
+
+        let desktop = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+                       (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+        assert_eq!(
+            UserAgentFamily::classify(desktop),
+            Some(UserAgentFamily::Desktop)
+        );
+    }
+
+    #[test]
+    fn test_matches_missing_user_agent() {
+        assert!(!UserAgentFamily::Desktop.matches(None));
+        assert!(!UserAgentFamily::Desktop.matches(Some("  ")));
+        assert!(UserAgentFamily::Desktop.matches(Some("Mozilla/5.0 (X11; Linux x86_64)")));
+    }
+}
@@ -0,0 +1,736 @@
+//! Built-in admin endpoints.
+//!
+//! Served by the proxy itself, under `/_rift/`:
+//!
+//! - `POST /_rift/recordings/save` - persist recordings to the configured backend
+//! - `POST /_rift/recordings/load` - load recordings from the configured backend
+//!
+//! Served on the metrics port, under `/__rift/`, so they neither shadow
+//! upstream paths nor are reachable by clients of the proxy:
+//!
+//! - `GET /__rift/recordings` - export recordings as Mountebank-compatible stubs
+//! - `DELETE /__rift/recordings` - clear all recordings
+//! - `GET /__rift/rules` - list fault rules with what they match and how often
+//! - `POST /__rift/match` - dry-run a described request against rules and routes
+//! - `POST /__rift/rules/{id}/enable` - enable a rule until the next reload
+//! - `POST /__rift/rules/{id}/disable` - disable a rule until the next reload
+
+use super::engine::{evaluate_rules, MatchedFault, RequestContext};
+use super::handler::CompiledScriptRule;
+use crate::config::RecordingPersistence;
+use crate::extensions::fault::FaultDecision;
+use crate::extensions::matcher::{CompiledRule, MatchBody};
+use crate::extensions::routing::Router;
+use crate::predicate::parse_query_string;
+use crate::recording::{backend_from_config, RecordingStore};
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::{HeaderMap, Method, Request, Response, StatusCode, Uri};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use tracing::{error, info};
+
+/// Path prefix for proxy admin endpoints
+pub const ADMIN_PATH_PREFIX: &str = "/_rift/";
+
+/// Path prefix for admin endpoints served on the metrics port
+pub const ADMIN_PORT_PATH_PREFIX: &str = "/__rift/";
+
+/// Path of the dry-run match endpoint on the metrics port. It needs the
+/// request body, so it is handled by [`handle_match_request`] rather than
+/// [`handle_admin_port_request`]
+pub const ADMIN_PORT_MATCH_PATH: &str = "/__rift/match";
+
+/// Handle a proxy admin request.
+///
+/// Returns None if the path isn't an admin endpoint, so the request
+/// should be proxied as usual.
+pub fn handle_admin_request(
+    method: &Method,
+    path: &str,
+    recording_store: &RecordingStore,
+    persistence: Option<&RecordingPersistence>,
+) -> Option<Response<Full<Bytes>>> {
+    let route = path.strip_prefix(ADMIN_PATH_PREFIX)?;
+
+    let response = match (method, route) {
+        (&Method::POST, "recordings/save") => handle_recordings(recording_store, persistence, true),
+        (&Method::POST, "recordings/load") => {
+            handle_recordings(recording_store, persistence, false)
+        }
+        (_, "recordings/save" | "recordings/load") => json_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            json!({"error": "Use POST for this endpoint"}),
+        ),
+        _ => return None,
+    };
+
+    Some(response)
+}
+
+/// Handle a request to a `/__rift/` endpoint on the metrics port.
+///
+/// Returns None if the path isn't one of these endpoints.
+pub fn handle_admin_port_request(
+    method: &Method,
+    path: &str,
+    query: Option<&str>,
+    recording_store: &RecordingStore,
+    rules: &[CompiledRule],
+    script_rules: &[CompiledScriptRule],
+) -> Option<Response<Full<Bytes>>> {
+    let route = path.strip_prefix(ADMIN_PORT_PATH_PREFIX)?;
+
+    if let Some((id, enabled)) = rule_toggle(route) {
+        let response = if method == Method::POST {
+            handle_rule_toggle(rules, script_rules, id, enabled)
+        } else {
+            json_response(
+                StatusCode::METHOD_NOT_ALLOWED,
+                json!({"error": "Use POST for this endpoint"}),
+            )
+        };
+        return Some(response);
+    }
+
+    let response = match (method, route) {
+        (&Method::GET, "rules") => handle_rules(rules, query),
+        (&Method::GET, "recordings") => handle_export(recording_store, query),
+        (&Method::DELETE, "recordings") => {
+            let cleared = recording_store.len();
+            recording_store.clear();
+            json_response(StatusCode::OK, json!({ "cleared": cleared }))
+        }
+        (_, "recordings") => json_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            json!({"error": "Use GET or DELETE for this endpoint"}),
+        ),
+        (_, "rules") => json_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            json!({"error": "Use GET for this endpoint"}),
+        ),
+        (_, "match") => json_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            json!({"error": "Use POST for this endpoint"}),
+        ),
+        _ => return None,
+    };
+
+    Some(response)
+}
+
+/// Export recordings as stubs.
+///
+/// Query parameters select the generated predicates: `method` and `path`
+/// (default true), `query` (default false) and `headers`, a comma-separated
+/// list of header names.
+fn handle_export(recording_store: &RecordingStore, query: Option<&str>) -> Response<Full<Bytes>> {
+    let params = parse_query_string(query);
+    let flag = |name: &str, default: bool| {
+        params
+            .get(name)
+            .map_or(default, |v| v.is_empty() || v.eq_ignore_ascii_case("true"))
+    };
+    let headers: Vec<String> = params
+        .get("headers")
+        .map(|list| {
+            list.split(',')
+                .map(|h| h.trim().to_lowercase())
+                .filter(|h| !h.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let stubs = recording_store.export_as_stubs(
+        flag("method", true),
+        flag("path", true),
+        flag("query", false),
+        &headers,
+    );
+    json_response(StatusCode::OK, json!({ "stubs": stubs }))
+}
+
+/// Request described to `POST /__rift/match`
+#[derive(Debug, Deserialize)]
+struct MatchRequest {
+    #[serde(default = "default_match_method")]
+    method: String,
+    #[serde(default = "default_match_path")]
+    path: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    query: HashMap<String, String>,
+    /// Text, or JSON that is matched as its serialized form
+    #[serde(default)]
+    body: Option<serde_json::Value>,
+    /// Address the request comes from, for rules matching client ranges
+    #[serde(default)]
+    client_ip: Option<String>,
+}
+
+fn default_match_method() -> String {
+    "GET".to_string()
+}
+
+fn default_match_path() -> String {
+    "/".to_string()
+}
+
+/// Dry-run a request described as JSON: report the route it would take,
+/// the fault rule it would match, with the rule's description and metadata,
+/// and the fault that rule could inject.
+///
+/// Nothing is proxied and no state changes: `once` rules aren't consumed,
+/// response sequences don't advance and rate limits aren't counted. Rules
+/// scoped to an upstream are checked against the route's target.
+pub fn handle_match_request(
+    body: &[u8],
+    rules: &[CompiledRule],
+    router: Option<&Router>,
+) -> Response<Full<Bytes>> {
+    let described: MatchRequest = match serde_json::from_slice(body) {
+        Ok(described) => described,
+        Err(e) => {
+            return json_response(
+                StatusCode::BAD_REQUEST,
+                json!({"error": format!("Invalid match request: {e}")}),
+            )
+        }
+    };
+
+    let Ok(method) = described.method.to_uppercase().parse::<Method>() else {
+        return json_response(
+            StatusCode::BAD_REQUEST,
+            json!({"error": format!("Invalid method: {}", described.method)}),
+        );
+    };
+    let mut target = described.path.clone();
+    for (i, (name, value)) in described.query.iter().enumerate() {
+        target.push(if i == 0 && !target.contains('?') {
+            '?'
+        } else {
+            '&'
+        });
+        target.push_str(&format!(
+            "{}={}",
+            urlencoding::encode(name),
+            urlencoding::encode(value)
+        ));
+    }
+    let Ok(uri) = target.parse::<Uri>() else {
+        return json_response(
+            StatusCode::BAD_REQUEST,
+            json!({"error": format!("Invalid path: {}", described.path)}),
+        );
+    };
+    let mut headers = HeaderMap::new();
+    for (name, value) in &described.headers {
+        match (
+            HeaderName::try_from(name.as_str()),
+            HeaderValue::try_from(value.as_str()),
+        ) {
+            (Ok(name), Ok(value)) => {
+                headers.append(name, value);
+            }
+            _ => {
+                return json_response(
+                    StatusCode::BAD_REQUEST,
+                    json!({"error": format!("Invalid header: {name}")}),
+                )
+            }
+        }
+    }
+    let client_ip = match described.client_ip.as_deref().map(str::parse::<IpAddr>) {
+        None => None,
+        Some(Ok(ip)) => Some(ip),
+        Some(Err(_)) => {
+            return json_response(
+                StatusCode::BAD_REQUEST,
+                json!({"error": format!("Invalid client_ip: {}", described.client_ip.unwrap_or_default())}),
+            )
+        }
+    };
+    let body = described.body.map(|body| match body {
+        serde_json::Value::String(text) => text,
+        other => other.to_string(),
+    });
+
+    let mut request = Request::new(());
+    *request.method_mut() = method.clone();
+    *request.uri_mut() = uri.clone();
+    *request.headers_mut() = headers.clone();
+    let route = router.and_then(|router| router.find_route(&request));
+
+    let request = RequestContext {
+        body: MatchBody::text(body.as_deref()),
+        client_ip,
+        upstream: route.map(|(_, upstream)| upstream),
+        ..RequestContext::new(&method, &uri, &headers)
+    };
+    let matched = evaluate_rules(rules, &request);
+    let fault = match &matched {
+        MatchedFault::NoMatch | MatchedFault::Allow { .. } => None,
+        MatchedFault::Deny { deny, .. } => Some(json!({"type": "deny", "status": deny.status})),
+        MatchedFault::Fault {
+            rule,
+            decision,
+            probability,
+        } => describe_fault(rule, decision, *probability),
+    };
+
+    json_response(
+        StatusCode::OK,
+        json!({
+            "method": method.as_str(),
+            "path": uri.to_string(),
+            "route": route.map(|(name, upstream)| json!({"name": name, "upstream": upstream})),
+            "matched": matched.rule().map(|rule| rule.id.as_str()),
+            "description": matched.rule().and_then(|rule| rule.rule.description.as_deref()),
+            "metadata": matched.rule().map(|rule| &rule.rule.metadata),
+            "fault": fault,
+        }),
+    )
+}
+
+/// JSON description of the fault `rule` injects with `probability`; None
+/// if it injects none
+fn describe_fault(
+    rule: &CompiledRule,
+    decision: &FaultDecision,
+    probability: f64,
+) -> Option<serde_json::Value> {
+    let mut described = match decision {
+        FaultDecision::None => return None,
+        FaultDecision::Latency { duration_ms, .. } => match &rule.rule.fault.latency {
+            Some(latency) => {
+                json!({"type": "latency", "min_ms": latency.min_ms, "max_ms": latency.max_ms})
+            }
+            None => json!({"type": "latency", "min_ms": duration_ms, "max_ms": duration_ms}),
+        },
+        FaultDecision::Error { status, .. } => json!({"type": "error", "status": status}),
+        FaultDecision::TcpFault { fault_type, .. } => {
+            json!({"type": "tcp_fault", "fault": fault_type})
+        }
+        FaultDecision::Drip { drip, .. } => json!({
+            "type": "drip",
+            "initial_bytes": drip.initial_bytes,
+            "stall_ms": drip.stall_ms,
+        }),
+        FaultDecision::Mock { mock, .. } => json!({"type": "mock", "status": mock.status}),
+        FaultDecision::Chunked { chunked, .. } => {
+            json!({"type": "chunked", "mode": chunked.mode})
+        }
+    };
+    described["probability"] = json!(probability);
+    Some(described)
+}
+
+/// List fault rules with a summary of their matcher, how many requests
+/// each matched since the rules were loaded, and when it last matched.
+///
+/// Header and trailer values in summaries are redacted unless the request
+/// passes `redact=false`.
+fn handle_rules(rules: &[CompiledRule], query: Option<&str>) -> Response<Full<Bytes>> {
+    let redact = parse_query_string(query)
+        .get("redact")
+        .is_none_or(|v| !v.eq_ignore_ascii_case("false"));
+    let listed: Vec<serde_json::Value> = rules
+        .iter()
+        .map(|compiled| {
+            json!({
+                "id": compiled.id,
+                "summary": compiled.summary(redact),
+                "matches": compiled.match_count(),
+                "last_matched": compiled.last_matched().map(|at| at.to_rfc3339()),
+                "active": compiled.is_active(),
+                "exhausted": compiled.is_exhausted(),
+                "description": compiled.rule.description,
+            })
+        })
+        .collect();
+    json_response(StatusCode::OK, json!({ "rules": listed }))
+}
+
+/// The rule id and new `enabled` flag of a `rules/{id}/enable` or
+/// `rules/{id}/disable` route
+fn rule_toggle(route: &str) -> Option<(&str, bool)> {
+    let (id, action) = route.strip_prefix("rules/")?.rsplit_once('/')?;
+    match action {
+        _ if id.is_empty() => None,
+        "enable" => Some((id, true)),
+        "disable" => Some((id, false)),
+        _ => None,
+    }
+}
+
+/// Enable or disable a rule in the live rules. The override lasts until the
+/// rules are recompiled by a reload.
+fn handle_rule_toggle(
+    rules: &[CompiledRule],
+    script_rules: &[CompiledScriptRule],
+    id: &str,
+    enabled: bool,
+) -> Response<Full<Bytes>> {
+    let script_matchers = script_rules.iter().map(|(_, rule, _)| rule);
+    let Some(rule) = rules
+        .iter()
+        .chain(script_matchers)
+        .find(|rule| rule.id == id)
+    else {
+        return json_response(
+            StatusCode::NOT_FOUND,
+            json!({"error": format!("Unknown rule: {id}")}),
+        );
+    };
+    rule.set_enabled(enabled);
+    info!(
+        "Rule '{id}' {} through the admin API",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    json_response(
+        StatusCode::OK,
+        json!({"id": id, "enabled": enabled, "active": rule.is_active()}),
+    )
+}
+
+/// Save or load recordings using the configured persistence backend
+fn handle_recordings(
+    recording_store: &RecordingStore,
+    persistence: Option<&RecordingPersistence>,
+    save: bool,
+) -> Response<Full<Bytes>> {
+    let Some(persistence) = persistence else {
+        return json_response(
+            StatusCode::CONFLICT,
+            json!({"error": "Recording persistence is not configured"}),
+        );
+    };
+
+    let result = backend_from_config(persistence).and_then(|backend| {
+        if save {
+            recording_store.save_to_backend(backend.as_ref())
+        } else {
+            recording_store.load_from_backend(backend.as_ref())
+        }
+    });
+
+    let (action, count_field) = if save {
+        ("save", "saved")
+    } else {
+        ("load", "loaded")
+    };
+    let mut body = serde_json::Map::new();
+    match result {
+        Ok(count) => {
+            body.insert(count_field.to_string(), json!(count));
+            json_response(StatusCode::OK, body.into())
+        }
+        Err(e) => {
+            error!("Failed to {} recordings: {}", action, e);
+            body.insert(count_field.to_string(), json!(0));
+            body.insert("error".to_string(), json!(e.to_string()));
+            json_response(StatusCode::INTERNAL_SERVER_ERROR, body.into())
+        }
+    }
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Full::new(Bytes::from(body.to_string())))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::{ProxyMode, RecordedResponse, RequestSignature};
+    use http_body_util::BodyExt;
+    use std::collections::HashMap;
+
+    fn file_persistence(path: &std::path::Path) -> RecordingPersistence {
+        RecordingPersistence {
+            backend: "file".to_string(),
+            path: Some(path.display().to_string()),
+            redis_url: None,
+            fallback: None,
+        }
+    }
+
+    async fn body_json(response: Response<Full<Bytes>>) -> serde_json::Value {
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    fn record(store: &RecordingStore, path: &str) {
+        store.record(
+            RequestSignature::new("GET", path, None, &[]),
+            RecordedResponse {
+                status: 200,
+                headers: HashMap::new(),
+                body: b"ok".to_vec(),
+                latency_ms: None,
+                timestamp_secs: 0,
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_endpoints() {
+        let dir = tempfile::tempdir().unwrap();
+        let persistence = file_persistence(&dir.path().join("recordings.json"));
+
+        let store = RecordingStore::new(ProxyMode::ProxyOnce);
+        record(&store, "/a");
+        record(&store, "/b");
+
+        let response = handle_admin_request(
+            &Method::POST,
+            "/_rift/recordings/save",
+            &store,
+            Some(&persistence),
+        )
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await, json!({"saved": 2}));
+
+        let fresh = RecordingStore::new(ProxyMode::ProxyOnce);
+        let response = handle_admin_request(
+            &Method::POST,
+            "/_rift/recordings/load",
+            &fresh,
+            Some(&persistence),
+        )
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await, json!({"loaded": 2}));
+        assert_eq!(fresh.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_load_endpoint_reports_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recordings.json");
+        std::fs::write(&path, "not json").unwrap();
+
+        let store = RecordingStore::new(ProxyMode::ProxyOnce);
+        let response = handle_admin_request(
+            &Method::POST,
+            "/_rift/recordings/load",
+            &store,
+            Some(&file_persistence(&path)),
+        )
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = body_json(response).await;
+        assert_eq!(body["loaded"], 0);
+        assert!(body["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_require_persistence_config() {
+        let store = RecordingStore::new(ProxyMode::ProxyOnce);
+        let response =
+            handle_admin_request(&Method::POST, "/_rift/recordings/save", &store, None).unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert!(body_json(response).await["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_export_and_clear_endpoints() {
+        let store = RecordingStore::new(ProxyMode::ProxyOnce);
+        store.record(
+            RequestSignature::new(
+                "GET",
+                "/users",
+                Some("page=2"),
+                &[("x-tenant".to_string(), "acme".to_string())],
+            ),
+            RecordedResponse {
+                status: 200,
+                headers: HashMap::new(),
+                body: b"[]".to_vec(),
+                latency_ms: None,
+                timestamp_secs: 0,
+            },
+        );
+
+        let response = handle_admin_port_request(
+            &Method::GET,
+            "/__rift/recordings",
+            Some("method=false&query=true&headers=X-Tenant"),
+            &store,
+            &[],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        let predicates = &body["stubs"][0]["predicates"][0]["and"];
+        assert!(predicates.get("method").is_none());
+        assert_eq!(predicates["path"]["equals"], "/users");
+        assert_eq!(predicates["query"]["equals"]["page"], "2");
+        assert_eq!(predicates["headers"]["equals"]["x-tenant"], "acme");
+
+        let response = handle_admin_port_request(
+            &Method::DELETE,
+            "/__rift/recordings",
+            None,
+            &store,
+            &[],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(body_json(response).await, json!({"cleared": 1}));
+        assert!(store.is_empty());
+
+        // Not served by the proxy listener, so the request is proxied
+        assert!(handle_admin_request(&Method::DELETE, "/_rift/recordings", &store, None).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_match_reports_rule_description_and_metadata() {
+        let yaml = r#"
+- id: "checkout-errors"
+  description: "Fail checkout for the payments game day"
+  metadata:
+    owner: "payments-team"
+    tags: ["gameday", "checkout"]
+  match:
+    methods: ["POST"]
+    path:
+      prefix: "/checkout"
+  fault:
+    error:
+      probability: 1.0
+      status: 503
+- id: "catalog-latency"
+  match:
+    path:
+      prefix: "/catalog"
+  fault: {}
+"#;
+        let rules: Vec<crate::config::Rule> = serde_yaml::from_str(yaml).unwrap();
+        let compiled: Vec<CompiledRule> = rules
+            .into_iter()
+            .map(|rule| CompiledRule::compile(rule).unwrap())
+            .collect();
+        let explain = |description: &[u8]| {
+            let response = handle_match_request(description, &compiled, None);
+            assert_eq!(response.status(), StatusCode::OK);
+            body_json(response)
+        };
+
+        let body = explain(br#"{"method": "post", "path": "/checkout/cart"}"#).await;
+        assert_eq!(body["matched"], "checkout-errors");
+        assert_eq!(
+            body["description"],
+            "Fail checkout for the payments game day"
+        );
+        assert_eq!(body["metadata"]["owner"], "payments-team");
+        assert_eq!(body["metadata"]["tags"][1], "checkout");
+
+        let body = explain(br#"{"path": "/catalog"}"#).await;
+        assert_eq!(body["matched"], "catalog-latency");
+        assert!(body["description"].is_null());
+
+        // Not served by the proxy listener, where it would shadow upstream
+        // paths and expose rule details to its clients
+        let store = RecordingStore::new(ProxyMode::ProxyOnce);
+        assert!(handle_admin_request(&Method::GET, "/_rift/match", &store, None).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rules_lists_stats_and_redacts_header_values() {
+        let yaml = r#"
+- id: "admin-errors"
+  match:
+    methods: ["POST"]
+    path:
+      prefix: "/admin"
+    headers:
+      - name: "x-api-key"
+        value: "hunter2"
+    query:
+      - name: "debug"
+        value: "1"
+  fault: {}
+- id: "anything"
+  match: {}
+  fault: {}
+"#;
+        let rules: Vec<crate::config::Rule> = serde_yaml::from_str(yaml).unwrap();
+        let compiled: Vec<CompiledRule> = rules
+            .into_iter()
+            .map(|rule| CompiledRule::compile(rule).unwrap())
+            .collect();
+        compiled[0].record_match();
+        compiled[0].record_match();
+        let store = RecordingStore::new(ProxyMode::ProxyOnce);
+        let list = |query| {
+            handle_admin_port_request(&Method::GET, "/__rift/rules", query, &store, &compiled, &[])
+                .unwrap()
+        };
+
+        let body = body_json(list(None)).await;
+        let admin = &body["rules"][0];
+        assert_eq!(admin["id"], "admin-errors");
+        assert_eq!(
+            admin["summary"],
+            r#"method POST and path starts with "/admin" and header x-api-key = <redacted> and query debug = "1""#
+        );
+        assert_eq!(admin["matches"], 2);
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(admin["last_matched"].as_str().unwrap()).is_ok()
+        );
+        let anything = &body["rules"][1];
+        assert_eq!(anything["summary"], "any request");
+        assert_eq!(anything["matches"], 0);
+        assert!(anything["last_matched"].is_null());
+
+        let body = body_json(list(Some("redact=false"))).await;
+        let summary = body["rules"][0]["summary"].as_str().unwrap();
+        assert!(
+            summary.contains(r#"header x-api-key = "hunter2""#),
+            "{summary}"
+        );
+
+        // Not served by the proxy listener, so the request is proxied
+        assert!(handle_admin_request(&Method::GET, "/_rift/rules", &store, None).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_match_request_rejects_bad_descriptions() {
+        for body in [
+            &b"not json"[..],
+            br#"{"method": "NOT A METHOD"}"#,
+            br#"{"path": "no spaces allowed"}"#,
+            br#"{"headers": {"bad header": "x"}}"#,
+            br#"{"client_ip": "10.0.0"}"#,
+        ] {
+            let response = handle_match_request(body, &[], None);
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            assert!(body_json(response).await["error"].is_string());
+        }
+    }
+
+    #[test]
+    fn test_non_admin_paths_are_proxied() {
+        let store = RecordingStore::new(ProxyMode::ProxyOnce);
+        assert!(
+            handle_admin_request(&Method::POST, "/api/recordings/save", &store, None).is_none()
+        );
+        assert!(handle_admin_request(&Method::GET, "/_rift/unknown", &store, None).is_none());
+        assert_eq!(
+            handle_admin_request(&Method::GET, "/_rift/recordings/save", &store, None)
+                .unwrap()
+                .status(),
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+    }
+}
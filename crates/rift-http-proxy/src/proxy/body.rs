@@ -0,0 +1,287 @@
+//! Request body buffering for body matchers.
+//!
+//! Request and response bodies are streamed between client and upstream.
+//! A body is only read into memory when something needs its bytes:
+//! recording (to store the exchange), script rules (to build the script
+//! context), or a rule with a body or HMAC matcher. For the matcher case at
+//! most `max_body_bytes` are buffered; the buffered frames are then replayed
+//! ahead of the unread remainder, so the upstream still receives the whole
+//! body as a stream.
+//!
+//! A gzip- or deflate-encoded prefix is also decoded for content matchers.
+//! HMAC signatures are still checked over the bytes as received.
+//!
+//! Trailers only arrive after the last data frame, so when a rule matches
+//! on trailers the whole body is buffered, whatever `max_body_bytes` says.
+
+use crate::extensions::matcher::MatchBody;
+use crate::predicate::{decode_body, DecodedBody};
+use bytes::BytesMut;
+use http_body_util::BodyExt;
+use hyper::body::{Body, Bytes, Frame, SizeHint};
+use hyper::header::CONTENT_ENCODING;
+use hyper::HeaderMap;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The first bytes of a request body, read for matching
+#[derive(Debug)]
+pub struct BodyPrefix {
+    /// At most `limit` bytes from the start of the body
+    pub bytes: Bytes,
+    /// True if the body is longer than the limit
+    pub truncated: bool,
+    /// `bytes` with the request's `Content-Encoding` undone, if it had a
+    /// supported one
+    pub decoded: Option<DecodedBody>,
+    /// Trailers, if the body was read to the end and had any
+    pub trailers: Option<HeaderMap>,
+}
+
+impl BodyPrefix {
+    /// The buffered bytes as text, if they are UTF-8.
+    ///
+    /// A character split by truncation is dropped rather than failing the
+    /// whole prefix.
+    pub fn as_str(&self) -> Option<&str> {
+        utf8_prefix(&self.bytes, self.truncated)
+    }
+
+    /// Decode the prefix as described by the request's `Content-Encoding`,
+    /// producing at most `limit` bytes
+    pub fn decode_content(&mut self, headers: &HeaderMap, limit: usize) {
+        let Some(coding) = headers.get(CONTENT_ENCODING).and_then(|v| v.to_str().ok()) else {
+            return;
+        };
+        self.decoded = decode_body(coding, &self.bytes, limit).map(|mut decoded| {
+            decoded.truncated |= self.truncated;
+            decoded
+        });
+    }
+
+    /// The body as the rule matchers see it
+    pub fn match_body(&self) -> MatchBody<'_> {
+        let (text, text_truncated) = match &self.decoded {
+            Some(decoded) => (
+                utf8_prefix(&decoded.bytes, decoded.truncated),
+                decoded.truncated,
+            ),
+            None => (self.as_str(), self.truncated),
+        };
+        MatchBody {
+            raw: Some(&self.bytes),
+            truncated: self.truncated,
+            text,
+            text_truncated,
+            trailers: self.trailers.as_ref(),
+        }
+    }
+}
+
+fn utf8_prefix(bytes: &[u8], truncated: bool) -> Option<&str> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text),
+        Err(e) if truncated && e.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..e.valid_up_to()]).ok()
+        }
+        Err(_) => None,
+    }
+}
+
+/// Read up to `limit` bytes of `body`, or all of it with `to_end` so that
+/// its trailers are seen.
+///
+/// Returns the prefix and a body that yields the buffered frames followed
+/// by the rest of the original body.
+pub async fn buffer_prefix<B>(
+    mut body: B,
+    limit: usize,
+    to_end: bool,
+) -> Result<(BodyPrefix, ReplayBody<B>), B::Error>
+where
+    B: Body<Data = Bytes> + Unpin,
+{
+    let mut buffered = VecDeque::new();
+    let mut bytes = BytesMut::new();
+    let mut trailers = None;
+    let mut complete = body.is_end_stream();
+
+    while !complete && (to_end || bytes.len() <= limit) {
+        match body.frame().await {
+            Some(frame) => {
+                let frame = frame?;
+                if let Some(data) = frame.data_ref() {
+                    // Past the limit the frames are kept only for replay
+                    if bytes.len() <= limit {
+                        bytes.extend_from_slice(data);
+                    }
+                }
+                if let Some(frame_trailers) = frame.trailers_ref() {
+                    trailers = Some(frame_trailers.clone());
+                }
+                buffered.push_back(frame);
+                complete = body.is_end_stream();
+            }
+            None => complete = true,
+        }
+    }
+    // Reading stops only once more than `limit` bytes were seen, so a body
+    // of exactly `limit` bytes is not truncated
+    let truncated = bytes.len() > limit;
+    bytes.truncate(limit);
+
+    let prefix = BodyPrefix {
+        bytes: bytes.freeze(),
+        truncated,
+        decoded: None,
+        trailers,
+    };
+    Ok((
+        prefix,
+        ReplayBody {
+            buffered,
+            rest: body,
+        },
+    ))
+}
+
+/// Body replaying buffered frames before streaming the remainder
+pub struct ReplayBody<B> {
+    buffered: VecDeque<Frame<Bytes>>,
+    rest: B,
+}
+
+impl<B> Body for ReplayBody<B>
+where
+    B: Body<Data = Bytes> + Unpin,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        if let Some(frame) = self.buffered.pop_front() {
+            return Poll::Ready(Some(Ok(frame)));
+        }
+        Pin::new(&mut self.rest).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.buffered.is_empty() && self.rest.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let buffered: u64 = self
+            .buffered
+            .iter()
+            .filter_map(Frame::data_ref)
+            .map(|data| data.len() as u64)
+            .sum();
+        let rest = self.rest.size_hint();
+        let mut hint = SizeHint::new();
+        hint.set_lower(rest.lower() + buffered);
+        if let Some(upper) = rest.upper() {
+            hint.set_upper(upper + buffered);
+        }
+        hint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+    use http_body_util::StreamBody;
+    use std::convert::Infallible;
+
+    fn chunked(chunks: &[&'static str]) -> impl Body<Data = Bytes, Error = Infallible> + Unpin {
+        let frames: Vec<_> = chunks
+            .iter()
+            .map(|c| Ok(Frame::data(Bytes::from_static(c.as_bytes()))))
+            .collect();
+        StreamBody::new(stream::iter(frames))
+    }
+
+    #[tokio::test]
+    async fn test_body_at_limit_is_not_truncated() {
+        let (prefix, body) = buffer_prefix(chunked(&["hello ", "world"]), 11, false)
+            .await
+            .unwrap();
+        assert!(!prefix.truncated);
+        assert_eq!(prefix.as_str(), Some("hello world"));
+
+        let replayed = body.collect().await.unwrap().to_bytes();
+        assert_eq!(replayed, "hello world");
+    }
+
+    #[tokio::test]
+    async fn test_body_over_limit_streams_remainder() {
+        let (prefix, body) = buffer_prefix(chunked(&["abc", "def", "ghi", "jkl"]), 4, false)
+            .await
+            .unwrap();
+        assert!(prefix.truncated);
+        assert_eq!(prefix.as_str(), Some("abcd"));
+
+        let replayed = body.collect().await.unwrap().to_bytes();
+        assert_eq!(replayed, "abcdefghijkl");
+    }
+
+    #[tokio::test]
+    async fn test_gzip_prefix_is_decoded_for_matching() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(br#"{"status": "pending"}"#).unwrap();
+        let encoded = Bytes::from(encoder.finish().unwrap());
+        let body = StreamBody::new(stream::iter([Ok::<_, Infallible>(Frame::data(
+            encoded.clone(),
+        ))]));
+
+        let (mut prefix, body) = buffer_prefix(body, 1024, false).await.unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, "gzip".parse().unwrap());
+        prefix.decode_content(&headers, 1024);
+
+        let matched = prefix.match_body();
+        assert_eq!(matched.text, Some(r#"{"status": "pending"}"#));
+        assert_eq!(matched.raw, Some(&encoded[..]));
+        // The forwarded body is still the compressed one
+        assert_eq!(body.collect().await.unwrap().to_bytes(), encoded);
+    }
+
+    #[tokio::test]
+    async fn test_truncation_drops_split_character() {
+        let (prefix, _) = buffer_prefix(chunked(&["caf\u{e9}!"]), 4, false)
+            .await
+            .unwrap();
+        assert!(prefix.truncated);
+        assert_eq!(prefix.as_str(), Some("caf"));
+    }
+
+    #[tokio::test]
+    async fn test_to_end_reads_trailers_past_limit() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("x-checksum", "abc123".parse().unwrap());
+        let frames = vec![
+            Ok::<_, Infallible>(Frame::data(Bytes::from_static(b"abc"))),
+            Ok(Frame::data(Bytes::from_static(b"def"))),
+            Ok(Frame::trailers(trailers.clone())),
+        ];
+
+        let (prefix, body) = buffer_prefix(StreamBody::new(stream::iter(frames)), 2, true)
+            .await
+            .unwrap();
+        assert!(prefix.truncated);
+        assert_eq!(prefix.as_str(), Some("ab"));
+        assert_eq!(prefix.trailers.as_ref(), Some(&trailers));
+
+        // Trailers are forwarded too
+        let collected = body.collect().await.unwrap();
+        assert_eq!(collected.trailers(), Some(&trailers));
+        assert_eq!(collected.to_bytes(), "abcdef");
+    }
+}
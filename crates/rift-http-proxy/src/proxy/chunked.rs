@@ -0,0 +1,212 @@
+//! Chunked transfer fault: a chunked response whose framing breaks at the end.
+//!
+//! hyper always frames a body correctly, so the fault bypasses it. Every
+//! client connection is wrapped in a [`TakeoverIo`], and requests carry the
+//! connection's [`ConnectionTakeover`]. A handler injecting the fault encodes
+//! the whole response itself and hands it over; the connection then writes
+//! those bytes in place of whatever hyper sends next, and discards the rest.
+
+use crate::config::ChunkedFaultMode;
+use hyper::body::{Buf, Bytes};
+use hyper::header::{CONNECTION, CONTENT_LENGTH, TRANSFER_ENCODING};
+use hyper::http::response::Parts;
+use parking_lot::Mutex;
+use std::io::{self, IoSlice};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Handle for replacing the next response written on a connection
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionTakeover(Arc<Mutex<Option<Bytes>>>);
+
+impl ConnectionTakeover {
+    /// Write `raw` instead of the next response. The response hyper is
+    /// given should close the connection, as nothing after `raw` is sent.
+    pub fn take_over(&self, raw: Bytes) {
+        *self.0.lock() = Some(raw);
+    }
+}
+
+enum State {
+    /// Passing hyper's writes through
+    Hyper,
+    /// Writing the replacement response
+    Raw(Bytes),
+    /// Replacement sent; hyper's writes are discarded
+    Done,
+}
+
+/// Connection that can be taken over through its [`ConnectionTakeover`]
+pub struct TakeoverIo<T> {
+    inner: T,
+    takeover: ConnectionTakeover,
+    state: State,
+}
+
+impl<T> TakeoverIo<T> {
+    pub fn new(inner: T) -> (Self, ConnectionTakeover) {
+        let takeover = ConnectionTakeover::default();
+        let io = Self {
+            inner,
+            takeover: takeover.clone(),
+            state: State::Hyper,
+        };
+        (io, takeover)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> TakeoverIo<T> {
+    /// Send any pending replacement, returning whether hyper's writes are
+    /// to be discarded
+    fn poll_takeover(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<bool>> {
+        loop {
+            match &mut self.state {
+                State::Hyper => match self.takeover.0.lock().take() {
+                    Some(raw) => self.state = State::Raw(raw),
+                    None => return Poll::Ready(Ok(false)),
+                },
+                State::Raw(raw) if raw.is_empty() => self.state = State::Done,
+                State::Raw(raw) => {
+                    let written = ready!(Pin::new(&mut self.inner).poll_write(cx, raw))?;
+                    if written == 0 {
+                        return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+                    }
+                    raw.advance(written);
+                }
+                State::Done => return Poll::Ready(Ok(true)),
+            }
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for TakeoverIo<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for TakeoverIo<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if ready!(this.poll_takeover(cx))? {
+            return Poll::Ready(Ok(buf.len()));
+        }
+        Pin::new(&mut this.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if ready!(this.poll_takeover(cx))? {
+            return Poll::Ready(Ok(bufs.iter().map(|buf| buf.len()).sum()));
+        }
+        Pin::new(&mut this.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_takeover(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_takeover(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+/// Encode a response as HTTP/1.1 with its body as one chunk, broken at the
+/// end as `mode` says. The connection is closed after it.
+pub fn encode_response(head: &Parts, body: &[u8], mode: ChunkedFaultMode) -> Bytes {
+    let mut out = Vec::with_capacity(body.len() + 512);
+    let status = head.status;
+    out.extend_from_slice(
+        format!(
+            "HTTP/1.1 {} {}\r\n",
+            status.as_str(),
+            status.canonical_reason().unwrap_or_default()
+        )
+        .as_bytes(),
+    );
+    for (name, value) in &head.headers {
+        if [CONTENT_LENGTH, TRANSFER_ENCODING, CONNECTION].contains(name) {
+            continue;
+        }
+        out.extend_from_slice(name.as_str().as_bytes());
+        out.extend_from_slice(b": ");
+        out.extend_from_slice(value.as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+    out.extend_from_slice(b"transfer-encoding: chunked\r\nconnection: close\r\n\r\n");
+
+    if !body.is_empty() {
+        out.extend_from_slice(format!("{:x}\r\n", body.len()).as_bytes());
+        out.extend_from_slice(body);
+        out.extend_from_slice(b"\r\n");
+    }
+    match mode {
+        // The connection closes where the last chunk should be
+        ChunkedFaultMode::MissingTerminator => {}
+        ChunkedFaultMode::BadChunkSize => out.extend_from_slice(b"zz\r\n\r\n"),
+    }
+    out.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::Response;
+    use tokio::io::AsyncWriteExt;
+
+    fn head() -> Parts {
+        let response = Response::builder()
+            .status(200)
+            .header("content-type", "text/plain")
+            .header("content-length", "5")
+            .body(())
+            .unwrap();
+        response.into_parts().0
+    }
+
+    #[test]
+    fn test_encode_response() {
+        let missing = encode_response(&head(), b"hello", ChunkedFaultMode::MissingTerminator);
+        assert_eq!(
+            &missing[..],
+            b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\n\
+              transfer-encoding: chunked\r\nconnection: close\r\n\r\n5\r\nhello\r\n"
+        );
+
+        let bad = encode_response(&head(), b"hello", ChunkedFaultMode::BadChunkSize);
+        assert!(bad.ends_with(b"5\r\nhello\r\nzz\r\n\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_takeover_replaces_later_writes() {
+        let (mut io, takeover) = TakeoverIo::new(Vec::new());
+        io.write_all(b"first ").await.unwrap();
+        takeover.take_over(Bytes::from_static(b"raw"));
+        io.write_all(b"discarded").await.unwrap();
+        io.write_all(b"also discarded").await.unwrap();
+        io.shutdown().await.unwrap();
+        assert_eq!(io.inner, b"first raw");
+    }
+}
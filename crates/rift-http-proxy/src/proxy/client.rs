@@ -2,87 +2,485 @@
 //!
 //! This module provides functionality for creating and configuring
 //! the shared HTTP client used for proxying requests.
+//!
+//! hyper keeps its connection pool private, so the pool gauges are kept
+//! from the outside: the connector counts the connections it opens until
+//! they close, and the forwarding path counts requests from the moment
+//! they are sent until their response body ends. Open connections not
+//! carrying a request are the idle ones.
 
-use super::tls::NoVerifier;
-use crate::config::Config;
+use super::tls::create_client_tls_config;
+use crate::config::{Config, ConnectionPoolConfig, UpstreamTlsConfig};
+use crate::extensions::metrics;
 use http_body_util::combinators::BoxBody;
-use hyper::body::Bytes;
+use hyper::body::{Body, Bytes, Frame, Incoming, SizeHint};
+use hyper::{Response, Uri};
+use hyper_util::client::legacy::connect::{Connected, Connection, HttpConnector};
 use hyper_util::client::legacy::Client;
-use hyper_util::rt::TokioExecutor;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{ready, Context, Poll};
 use std::time::Duration;
+use tokio::net::TcpStream;
+use tower::Service;
 use tracing::{info, warn};
 
 /// Type alias for the HTTP client used by the proxy.
-pub type HttpClient = Client<
-    hyper_rustls::HttpsConnector<hyper_util::client::legacy::connect::HttpConnector>,
-    BoxBody<Bytes, hyper::Error>,
->;
+pub type HttpClient =
+    Client<hyper_rustls::HttpsConnector<TrackedConnector>, BoxBody<Bytes, hyper::Error>>;
+
+/// Pool usage for one upstream host, as `host:port` (or `host`)
+struct HostStats {
+    host: String,
+    /// Open connections and requests in flight
+    counts: Mutex<(i64, i64)>,
+}
+
+impl HostStats {
+    fn for_uri(uri: &Uri) -> Arc<Self> {
+        static HOSTS: Lazy<Mutex<HashMap<String, Arc<HostStats>>>> = Lazy::new(Default::default);
+
+        let host = uri.authority().map(|a| a.as_str()).unwrap_or_default();
+        let mut hosts = HOSTS.lock();
+        if let Some(stats) = hosts.get(host) {
+            return Arc::clone(stats);
+        }
+        let stats = Arc::new(HostStats {
+            host: host.to_string(),
+            counts: Mutex::new((0, 0)),
+        });
+        hosts.insert(host.to_string(), Arc::clone(&stats));
+        stats
+    }
+
+    /// Apply a change to the counts and publish the result; the lock keeps
+    /// concurrent updates from publishing out of order
+    fn update(&self, open_delta: i64, in_flight_delta: i64) {
+        let mut counts = self.counts.lock();
+        counts.0 += open_delta;
+        counts.1 += in_flight_delta;
+        let (open, in_flight) = *counts;
+        metrics::set_upstream_pool(&self.host, (open - in_flight).max(0), in_flight);
+    }
+}
+
+/// Counts a request as using an upstream connection until dropped
+pub struct InFlight(Option<Arc<HostStats>>);
+
+impl InFlight {
+    /// Start counting a request to `uri`
+    pub fn start(uri: &str) -> Self {
+        let stats = uri.parse().ok().map(|uri| HostStats::for_uri(&uri));
+        if let Some(stats) = &stats {
+            stats.update(0, 1);
+        }
+        Self(stats)
+    }
+
+    /// Move the count onto an upstream response, ending it once the body
+    /// has been read to the end (or dropped)
+    pub fn release_with(
+        self,
+        response: Response<Incoming>,
+    ) -> Response<BoxBody<Bytes, hyper::Error>> {
+        response.map(|body| {
+            BoxBody::new(TrackedBody {
+                body,
+                in_flight: Some(self),
+            })
+        })
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Some(stats) = &self.0 {
+            stats.update(0, -1);
+        }
+    }
+}
+
+struct TrackedBody {
+    body: Incoming,
+    in_flight: Option<InFlight>,
+}
+
+impl Body for TrackedBody {
+    type Data = Bytes;
+    type Error = hyper::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, hyper::Error>>> {
+        let frame = ready!(Pin::new(&mut self.body).poll_frame(cx));
+        // hyper hands the connection back to the pool when the body ends
+        if frame.is_none() || self.body.is_end_stream() {
+            self.in_flight = None;
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+/// TCP connector counting the upstream connections it has open
+#[derive(Clone)]
+pub struct TrackedConnector(HttpConnector);
+
+impl Service<Uri> for TrackedConnector {
+    type Response = TrackedConnection;
+    type Error = <HttpConnector as Service<Uri>>::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let stats = HostStats::for_uri(&uri);
+        let connecting = self.0.call(uri);
+        Box::pin(async move {
+            let io = connecting.await?;
+            stats.update(1, 0);
+            Ok(TrackedConnection { io, stats })
+        })
+    }
+}
+
+/// Upstream TCP connection, counted as open until dropped
+pub struct TrackedConnection {
+    io: TokioIo<TcpStream>,
+    stats: Arc<HostStats>,
+}
+
+impl Drop for TrackedConnection {
+    fn drop(&mut self) {
+        self.stats.update(-1, 0);
+    }
+}
+
+impl Connection for TrackedConnection {
+    fn connected(&self) -> Connected {
+        self.io.connected()
+    }
+}
+
+impl hyper::rt::Read for TrackedConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: hyper::rt::ReadBufCursor<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl hyper::rt::Write for TrackedConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write_vectored(cx, bufs)
+    }
+}
 
 /// Create a shared HTTP client with connection pooling.
 ///
-/// # Arguments
-/// * `config` - The proxy configuration
-/// * `skip_tls_verify` - Whether to skip TLS certificate verification
+/// TLS settings, including `tls_skip_verify`, come from the sidecar
+/// upstream; without one, certificates are verified against the system
+/// roots.
 ///
 /// # Returns
-/// A configured HTTP client ready for proxying requests.
-pub fn create_http_client(config: &Config, skip_tls_verify: bool) -> HttpClient {
+/// A configured HTTP client ready for proxying requests, or an error if
+/// the sidecar upstream's TLS files can't be loaded.
+pub fn create_http_client(config: &Config) -> Result<HttpClient, anyhow::Error> {
+    let (skip_tls_verify, tls) = match &config.upstream {
+        Some(upstream) => {
+            if upstream.tls_skip_verify {
+                warn_skip_verify(&format!("{}:{}", upstream.host, upstream.port));
+            }
+            (upstream.tls_skip_verify, upstream.tls.clone())
+        }
+        None => (false, UpstreamTlsConfig::default()),
+    };
+    let http_client = build_client(&config.connection_pool, skip_tls_verify, &tls)?;
+
+    info!(
+        "Connection pool configured (HTTP/1.1): max_idle={}, idle_timeout={}s, keepalive={}s",
+        config.connection_pool.max_idle_per_host,
+        config.connection_pool.idle_timeout_secs,
+        config.connection_pool.keepalive_timeout_secs
+    );
+
+    Ok(http_client)
+}
+
+/// Upstream clients, each with its own connection pool.
+///
+/// hyper already keeps idle connections apart by scheme and authority, but
+/// the pool and its limits belong to one client. A client per named
+/// upstream keeps a slow upstream from tying up the pool the others use,
+/// and lets an upstream override the `connection_pool` settings.
+pub struct UpstreamClients {
+    /// Used in sidecar mode and for requests without a named upstream
+    default: HttpClient,
+    by_upstream: HashMap<String, HttpClient>,
+}
+
+impl UpstreamClients {
+    pub fn new(config: &Config) -> Result<Self, anyhow::Error> {
+        let by_upstream = config
+            .upstreams
+            .iter()
+            .map(|upstream| {
+                let pool = match &upstream.connection_pool {
+                    Some(overrides) => {
+                        let pool = overrides.apply(&config.connection_pool);
+                        info!(
+                            "Connection pool for upstream '{}': max_idle={}, idle_timeout={}s",
+                            upstream.name, pool.max_idle_per_host, pool.idle_timeout_secs
+                        );
+                        pool
+                    }
+                    None => config.connection_pool.clone(),
+                };
+                if upstream.tls_skip_verify {
+                    warn_skip_verify(&upstream.name);
+                }
+                let client = build_client(&pool, upstream.tls_skip_verify, &upstream.tls)
+                    .map_err(|e| anyhow::anyhow!("Upstream '{}': {e}", upstream.name))?;
+                Ok((upstream.name.clone(), client))
+            })
+            .collect::<Result<_, anyhow::Error>>()?;
+        Ok(Self {
+            default: create_http_client(config)?,
+            by_upstream,
+        })
+    }
+
+    /// The client for `upstream`, falling back to the default client
+    pub fn get(&self, upstream: Option<&str>) -> &HttpClient {
+        upstream
+            .and_then(|name| self.by_upstream.get(name))
+            .unwrap_or(&self.default)
+    }
+}
+
+fn build_client(
+    pool: &ConnectionPoolConfig,
+    skip_tls_verify: bool,
+    tls: &UpstreamTlsConfig,
+) -> Result<HttpClient, anyhow::Error> {
     // Create HTTP connector with connection pool settings
-    let mut http_connector = hyper_util::client::legacy::connect::HttpConnector::new();
-    http_connector.set_keepalive(Some(Duration::from_secs(
-        config.connection_pool.keepalive_timeout_secs,
-    )));
-    http_connector.set_connect_timeout(Some(Duration::from_secs(
-        config.connection_pool.connect_timeout_secs,
-    )));
+    let mut http_connector = HttpConnector::new();
+    http_connector.set_keepalive(Some(Duration::from_secs(pool.keepalive_timeout_secs)));
+    http_connector.set_connect_timeout(Some(Duration::from_secs(pool.connect_timeout_secs)));
     http_connector.enforce_http(false); // Allow both HTTP and HTTPS
 
     // Build HTTPS connector for HTTP/1.1 only
-    let https_connector = if skip_tls_verify {
-        warn!("TLS certificate verification DISABLED for one or more upstreams (development/testing only)");
+    let https_connector = if skip_tls_verify || tls.is_configured() {
         hyper_rustls::HttpsConnectorBuilder::new()
-            .with_tls_config(
-                rustls::ClientConfig::builder()
-                    .dangerous()
-                    .with_custom_certificate_verifier(Arc::new(NoVerifier))
-                    .with_no_client_auth(),
-            )
+            .with_tls_config(create_client_tls_config(tls, skip_tls_verify)?)
             .https_or_http()
             .enable_http1()
-            .wrap_connector(http_connector)
+            .wrap_connector(TrackedConnector(http_connector))
     } else {
         hyper_rustls::HttpsConnectorBuilder::new()
             .with_native_roots()
             .expect("Failed to load native root certificates")
             .https_or_http()
             .enable_http1()
-            .wrap_connector(http_connector)
+            .wrap_connector(TrackedConnector(http_connector))
     };
 
-    let http_client = Client::builder(TokioExecutor::new())
-        .pool_idle_timeout(Duration::from_secs(
-            config.connection_pool.idle_timeout_secs,
-        ))
-        .pool_max_idle_per_host(config.connection_pool.max_idle_per_host)
-        .build(https_connector);
+    Ok(Client::builder(TokioExecutor::new())
+        .pool_idle_timeout(Duration::from_secs(pool.idle_timeout_secs))
+        .pool_max_idle_per_host(pool.max_idle_per_host)
+        .build(https_connector))
+}
 
-    info!(
-        "Connection pool configured (HTTP/1.1): max_idle={}, idle_timeout={}s, keepalive={}s",
-        config.connection_pool.max_idle_per_host,
-        config.connection_pool.idle_timeout_secs,
-        config.connection_pool.keepalive_timeout_secs
+fn warn_skip_verify(upstream: &str) {
+    warn!(
+        "TLS certificate verification DISABLED for upstream '{upstream}': \
+         any certificate is accepted (development/testing only)"
     );
-
-    http_client
 }
 
-/// Check if any upstream needs TLS verification skipped.
-pub fn should_skip_tls_verify(config: &Config) -> bool {
-    config.upstreams.iter().any(|u| u.tls_skip_verify)
-        || config
-            .upstream
-            .as_ref()
-            .map(|u| u.tls_skip_verify)
-            .unwrap_or(false)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::forwarding::{forward_request_streaming, full_body, UpstreamPolicy};
+    use http_body_util::{BodyExt, Full};
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use hyper::Request;
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Keep-alive upstream answering 200 "ok"; returns its address and a
+    /// count of connections accepted
+    async fn spawn_counting_upstream() -> (SocketAddr, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&connections);
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                let service = service_fn(|_req: Request<Incoming>| async {
+                    Ok::<_, Infallible>(Response::new(Full::new(Bytes::from("ok"))))
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+        (addr, connections)
+    }
+
+    fn client_with(connection_pool: &str) -> HttpClient {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config: Config = serde_yaml::from_str(&format!(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\n{connection_pool}"
+        ))
+        .unwrap();
+        create_http_client(&config).unwrap()
+    }
+
+    /// Send a GET through the forwarding path and read the whole response
+    async fn get(client: &HttpClient, upstream: SocketAddr) {
+        let request = Request::get("/").body(full_body(Bytes::new())).unwrap();
+        let response = forward_request_streaming(
+            client,
+            request,
+            &format!("http://{upstream}"),
+            UpstreamPolicy::default(),
+        )
+        .await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "ok");
+        // hyper returns the connection to the pool from a background task
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    #[tokio::test]
+    async fn test_pooled_connection_is_reused() {
+        let (upstream, connections) = spawn_counting_upstream().await;
+        let client = client_with("");
+
+        get(&client, upstream).await;
+        get(&client, upstream).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        let host = upstream.to_string();
+        assert_eq!(
+            metrics::UPSTREAM_POOL_IDLE
+                .with_label_values(&[&host])
+                .get(),
+            1.0
+        );
+        assert_eq!(
+            metrics::UPSTREAM_POOL_ACTIVE
+                .with_label_values(&[&host])
+                .get(),
+            0.0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_idle_per_host_zero_disables_reuse() {
+        let (upstream, connections) = spawn_counting_upstream().await;
+        let client = client_with("connection_pool:\n  max_idle_per_host: 0\n");
+
+        get(&client, upstream).await;
+        get(&client, upstream).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_expires_pooled_connection() {
+        let (upstream, connections) = spawn_counting_upstream().await;
+        let client = client_with("connection_pool:\n  idle_timeout_secs: 1\n");
+
+        get(&client, upstream).await;
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        get(&client, upstream).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    fn upstream_clients(upstream: SocketAddr) -> UpstreamClients {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config: Config = serde_yaml::from_str(&format!(
+            "listen:\n  port: 8080\nupstreams:\n\
+             \x20 - name: a\n    url: http://{upstream}\n\
+             \x20 - name: b\n    url: http://{upstream}\n\
+             \x20   connection_pool:\n      max_idle_per_host: 0\n"
+        ))
+        .unwrap();
+        UpstreamClients::new(&config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_upstreams_do_not_share_connections() {
+        let (upstream, connections) = spawn_counting_upstream().await;
+        let clients = upstream_clients(upstream);
+
+        get(clients.get(Some("a")), upstream).await;
+        get(clients.get(None), upstream).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+        // Each pool reuses its own idle connection
+        get(clients.get(Some("a")), upstream).await;
+        get(clients.get(None), upstream).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_upstream_pool_override_applies_only_to_that_upstream() {
+        let (upstream, connections) = spawn_counting_upstream().await;
+        let clients = upstream_clients(upstream);
+
+        // 'b' keeps no idle connections, so every request connects anew
+        get(clients.get(Some("b")), upstream).await;
+        get(clients.get(Some("b")), upstream).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+        get(clients.get(Some("a")), upstream).await;
+        get(clients.get(Some("a")), upstream).await;
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+    }
 }
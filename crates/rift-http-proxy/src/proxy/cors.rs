@@ -0,0 +1,88 @@
+//! CORS preflight responses and `Access-Control-*` response headers.
+
+use crate::config::CorsConfig;
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::header::{
+    HeaderValue, ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+    ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE,
+    ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN, VARY,
+};
+use hyper::{HeaderMap, Method, Request, Response, StatusCode};
+
+/// Answer a preflight request, or return None if `req` isn't one.
+///
+/// A preflight from a disallowed origin, or asking for a disallowed
+/// method, gets a 403 without CORS headers, which browsers treat as a
+/// refusal.
+pub fn preflight_response<B>(cors: &CorsConfig, req: &Request<B>) -> Option<Response<Full<Bytes>>> {
+    if req.method() != Method::OPTIONS {
+        return None;
+    }
+    let headers = req.headers();
+    let origin = headers.get(ORIGIN)?;
+    let requested_method = headers.get(ACCESS_CONTROL_REQUEST_METHOD)?;
+
+    let method_allowed = requested_method.to_str().is_ok_and(|method| {
+        cors.allowed_methods
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(method))
+    });
+    let mut response = Response::new(Full::new(Bytes::new()));
+    if !(method_allowed && allow_origin(cors, origin, response.headers_mut())) {
+        *response.status_mut() = StatusCode::FORBIDDEN;
+        return Some(response);
+    }
+    *response.status_mut() = StatusCode::NO_CONTENT;
+
+    let response_headers = response.headers_mut();
+    if let Ok(methods) = HeaderValue::from_str(&cors.allowed_methods.join(", ")) {
+        response_headers.insert(ACCESS_CONTROL_ALLOW_METHODS, methods);
+    }
+    let allowed_headers = if cors.allowed_headers.is_empty() {
+        headers.get(ACCESS_CONTROL_REQUEST_HEADERS).cloned()
+    } else {
+        HeaderValue::from_str(&cors.allowed_headers.join(", ")).ok()
+    };
+    if let Some(allowed_headers) = allowed_headers {
+        response_headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, allowed_headers);
+    }
+    if let Some(max_age) = cors.max_age_secs {
+        response_headers.insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from(max_age));
+    }
+    Some(response)
+}
+
+/// Add `Access-Control-Allow-Origin` (and credentials) to a response for
+/// a request from an allowed `origin`
+pub fn apply_cors_headers(
+    cors: &CorsConfig,
+    origin: Option<&HeaderValue>,
+    headers: &mut HeaderMap,
+) {
+    if let Some(origin) = origin {
+        allow_origin(cors, origin, headers);
+    }
+}
+
+/// Set the allow-origin headers if `origin` is allowed, returning whether
+/// it is
+fn allow_origin(cors: &CorsConfig, origin: &HeaderValue, headers: &mut HeaderMap) -> bool {
+    if !origin.to_str().is_ok_and(|o| cors.allows_origin(o)) {
+        return false;
+    }
+    if cors.allowed_origins.iter().any(|o| o == "*") {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    } else {
+        // The response depends on the request's origin
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+        headers.append(VARY, HeaderValue::from_static("Origin"));
+    }
+    if cors.allow_credentials {
+        headers.insert(
+            ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            HeaderValue::from_static("true"),
+        );
+    }
+    true
+}
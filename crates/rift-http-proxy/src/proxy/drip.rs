@@ -0,0 +1,171 @@
+//! Drip fault: a response that stalls part-way through its body.
+//!
+//! The status line, headers and the first `initial_bytes` of the upstream
+//! body are sent straight away. The body then stops for `stall_ms` before
+//! the rest follows, or, with `finish: false`, stays open without sending
+//! anything more until the client gives up. Both exercise client read
+//! timeouts rather than connect or first-byte timeouts.
+
+use crate::config::DripFault;
+use hyper::body::{Body, Bytes, Frame, SizeHint};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+use tokio::time::Sleep;
+
+enum Phase {
+    /// Passing frames through until this many more bytes were sent
+    Initial { remaining: usize },
+    /// Waiting out the stall; `held` is the unsent part of a split frame
+    Stalled {
+        sleep: Pin<Box<Sleep>>,
+        held: Option<Bytes>,
+    },
+    /// Streaming the remainder
+    Rest { held: Option<Bytes> },
+    /// Never sending anything more
+    Hung,
+}
+
+/// Response body applying a [`DripFault`] to `inner`
+pub struct DripBody<B> {
+    inner: B,
+    phase: Phase,
+    stall: Duration,
+    finish: bool,
+}
+
+impl<B> DripBody<B> {
+    pub fn new(inner: B, fault: &DripFault) -> Self {
+        Self {
+            inner,
+            phase: Phase::Initial {
+                remaining: fault.initial_bytes,
+            },
+            stall: Duration::from_millis(fault.stall_ms),
+            finish: fault.finish,
+        }
+    }
+
+    fn stall(&self, held: Option<Bytes>) -> Phase {
+        if self.finish {
+            Phase::Stalled {
+                sleep: Box::pin(tokio::time::sleep(self.stall)),
+                held,
+            }
+        } else {
+            Phase::Hung
+        }
+    }
+}
+
+impl<B> Body for DripBody<B>
+where
+    B: Body<Data = Bytes> + Unpin,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        let this = &mut *self;
+        loop {
+            match &mut this.phase {
+                Phase::Initial { remaining: 0 } => this.phase = this.stall(None),
+                Phase::Initial { remaining } => {
+                    let frame = match ready!(Pin::new(&mut this.inner).poll_frame(cx)) {
+                        Some(Ok(frame)) => frame,
+                        other => return Poll::Ready(other),
+                    };
+                    let mut data = match frame.into_data() {
+                        Ok(data) => data,
+                        Err(trailers) => return Poll::Ready(Some(Ok(trailers))),
+                    };
+                    if data.len() <= *remaining {
+                        *remaining -= data.len();
+                    } else {
+                        let rest = data.split_off(*remaining);
+                        this.phase = this.stall(Some(rest));
+                    }
+                    return Poll::Ready(Some(Ok(Frame::data(data))));
+                }
+                Phase::Stalled { sleep, held } => {
+                    ready!(sleep.as_mut().poll(cx));
+                    this.phase = Phase::Rest { held: held.take() };
+                }
+                Phase::Rest { held } => {
+                    if let Some(data) = held.take() {
+                        return Poll::Ready(Some(Ok(Frame::data(data))));
+                    }
+                    return Pin::new(&mut this.inner).poll_frame(cx);
+                }
+                Phase::Hung => return Poll::Pending,
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        matches!(self.phase, Phase::Rest { held: None }) && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::{BodyExt, Full};
+    use std::time::Instant;
+
+    fn drip(initial_bytes: usize, stall_ms: u64, finish: bool) -> DripFault {
+        DripFault {
+            probability: 1.0,
+            initial_bytes,
+            stall_ms,
+            finish,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drip_stalls_then_finishes() {
+        let body = Full::new(Bytes::from_static(b"hello world"));
+        let mut body = DripBody::new(body, &drip(5, 50, true));
+
+        let start = Instant::now();
+        let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        assert_eq!(&first[..], b"hello");
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        let rest = body.collect().await.unwrap().to_bytes();
+        assert_eq!(&rest[..], b" world");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_drip_without_finish_never_ends() {
+        let body = Full::new(Bytes::from_static(b"hello world"));
+        let mut body = DripBody::new(body, &drip(5, 10, false));
+
+        let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        assert_eq!(&first[..], b"hello");
+        let next = tokio::time::timeout(Duration::from_millis(100), body.frame()).await;
+        assert!(next.is_err(), "body should stay open");
+        assert!(!body.is_end_stream());
+    }
+
+    #[tokio::test]
+    async fn test_short_body_is_sent_whole() {
+        let body = Full::new(Bytes::from_static(b"ok"));
+        let body = DripBody::new(body, &drip(64, 10_000, true));
+
+        let start = Instant::now();
+        let bytes = body.collect().await.unwrap().to_bytes();
+        assert_eq!(&bytes[..], b"ok");
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
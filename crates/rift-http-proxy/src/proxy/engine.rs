@@ -0,0 +1,403 @@
+//! Rule evaluation without a server: which rule a request matches and the
+//! fault it could get.
+//!
+//! The proxy selects rules through [`find_access_rule`], [`find_fault_rule`]
+//! and [`rule_applies`] too, so a dry run matches the rules a request would.
+//! Access rules are checked before fault rules, as in the proxy. Nothing
+//! changes state: `once` rules aren't consumed, response sequences don't
+//! advance and rate limits aren't counted. `is_retry` conditions aren't
+//! checked, since checking one records the request's idempotency key.
+
+use super::handler::rule_applies_to_upstream;
+use crate::config::{DenyAction, RuleAction};
+use crate::extensions::fault::FaultDecision;
+use crate::extensions::matcher::{CompiledRule, MatchBody};
+use hyper::{HeaderMap, Method, Uri};
+use regex::SetMatches;
+use std::net::IpAddr;
+
+/// A request as rule matching sees it
+#[derive(Debug, Clone, Copy)]
+pub struct RequestContext<'a> {
+    pub method: &'a Method,
+    pub uri: &'a Uri,
+    pub headers: &'a HeaderMap,
+    pub body: MatchBody<'a>,
+    pub client_ip: Option<IpAddr>,
+    /// Upstream the request is routed to, for rules scoped to one
+    pub upstream: Option<&'a str>,
+}
+
+impl<'a> RequestContext<'a> {
+    /// A request without a body, client address or routed upstream
+    pub fn new(method: &'a Method, uri: &'a Uri, headers: &'a HeaderMap) -> Self {
+        Self {
+            method,
+            uri,
+            headers,
+            body: MatchBody::default(),
+            client_ip: None,
+            upstream: None,
+        }
+    }
+}
+
+/// Outcome of evaluating the rules for a request
+// Returned once per evaluation, so boxing the decision would gain nothing
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
+pub enum MatchedFault<'a> {
+    /// No rule matches
+    NoMatch,
+    /// An allow rule matches; the request is forwarded without a fault
+    Allow { rule: &'a CompiledRule },
+    /// A deny rule matches
+    Deny {
+        rule: &'a CompiledRule,
+        deny: &'a DenyAction,
+    },
+    /// A fault rule matches, injecting `decision` with `probability`. The
+    /// decision is `FaultDecision::None` if no fault of the rule can fire.
+    Fault {
+        rule: &'a CompiledRule,
+        decision: FaultDecision,
+        probability: f64,
+    },
+}
+
+impl<'a> MatchedFault<'a> {
+    /// The rule that matched, if any
+    pub fn rule(&self) -> Option<&'a CompiledRule> {
+        match self {
+            MatchedFault::NoMatch => None,
+            MatchedFault::Allow { rule }
+            | MatchedFault::Deny { rule, .. }
+            | MatchedFault::Fault { rule, .. } => Some(rule),
+        }
+    }
+}
+
+/// Whether `rule` applies to `request`: it's active and matches the
+/// request's client address, method, path, headers, body and upstream
+pub fn rule_applies(rule: &CompiledRule, request: &RequestContext<'_>) -> bool {
+    rule.is_active()
+        && rule.matches_client_ip(request.client_ip)
+        && rule.matches_with_body_prefix(request.method, request.uri, request.headers, request.body)
+        && rule_applies_to_upstream(&rule.rule.upstream, request.upstream)
+}
+
+/// The first access rule applying to `request` and passing `check`.
+///
+/// `paths`, the rules' path prefilter matches for the request path, skips
+/// rules whose path can't match. `check` runs last, only on rules that
+/// otherwise apply; the proxy checks `is_retry` conditions with it.
+pub fn find_access_rule<'a>(
+    rules: &'a [CompiledRule],
+    request: &RequestContext<'_>,
+    paths: Option<&SetMatches>,
+    check: impl FnMut(&CompiledRule) -> bool,
+) -> Option<&'a CompiledRule> {
+    find_rule(rules, true, request, paths, check)
+}
+
+/// The first fault rule applying to `request` and passing `check`, as
+/// for [`find_access_rule`]
+pub fn find_fault_rule<'a>(
+    rules: &'a [CompiledRule],
+    request: &RequestContext<'_>,
+    paths: Option<&SetMatches>,
+    check: impl FnMut(&CompiledRule) -> bool,
+) -> Option<&'a CompiledRule> {
+    find_rule(rules, false, request, paths, check)
+}
+
+fn find_rule<'a>(
+    rules: &'a [CompiledRule],
+    access: bool,
+    request: &RequestContext<'_>,
+    paths: Option<&SetMatches>,
+    mut check: impl FnMut(&CompiledRule) -> bool,
+) -> Option<&'a CompiledRule> {
+    rules
+        .iter()
+        .enumerate()
+        .filter(|(idx, rule)| {
+            rule.is_access_rule() == access && paths.is_none_or(|paths| paths.matched(*idx))
+        })
+        .find(|(_, rule)| rule_applies(rule, request) && check(rule))
+        .map(|(_, rule)| rule)
+}
+
+/// Find the first rule matching `request`, access rules first, and the
+/// fault it could inject
+pub fn evaluate_rules<'a>(
+    rules: &'a [CompiledRule],
+    request: &RequestContext<'_>,
+) -> MatchedFault<'a> {
+    let matched = find_access_rule(rules, request, None, |_| true)
+        .or_else(|| find_fault_rule(rules, request, None, |_| true));
+    let Some(rule) = matched else {
+        return MatchedFault::NoMatch;
+    };
+    match &rule.rule.action {
+        RuleAction::Fault => {
+            let (decision, probability) = rule.preview_fault();
+            MatchedFault::Fault {
+                rule,
+                decision,
+                probability,
+            }
+        }
+        RuleAction::Allow => MatchedFault::Allow { rule },
+        RuleAction::Deny(deny) => MatchedFault::Deny { rule, deny },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Rule;
+
+    const RULES: &str = r#"
+- id: allow-health
+  match:
+    path: {exact: /health}
+  action: allow
+- id: deny-admin
+  match:
+    path: {prefix: /admin}
+  action:
+    deny: {status: 403}
+- id: internal-only
+  match:
+    ip: {cidrs: [10.0.0.0/8]}
+    path: {prefix: /internal}
+  fault:
+    error: {probability: 1.0, status: 503}
+- id: payments-latency
+  upstream: payments
+  match:
+    path: {prefix: /pay}
+  fault:
+    latency: {probability: 1.0, min_ms: 100, max_ms: 100}
+- id: beta-body
+  match:
+    methods: [POST]
+    headerPredicates:
+      - {name: X-Beta, exists: true}
+    body: {contains: boom}
+  fault:
+    error: {probability: 1.0, status: 500}
+- id: never-fires
+  match:
+    path: {prefix: /quiet}
+  fault:
+    error: {probability: 0.0, status: 500}
+- id: catch-all
+  match:
+    path: {prefix: /}
+  fault:
+    error: {probability: 1.0, status: 502}
+"#;
+
+    fn compile_rules() -> Vec<CompiledRule> {
+        let rules: Vec<Rule> = serde_yaml::from_str(RULES).unwrap();
+        rules
+            .into_iter()
+            .map(|rule| CompiledRule::compile(rule).unwrap())
+            .collect()
+    }
+
+    /// The matched rule id and a short description of the outcome
+    fn outcome(matched: &MatchedFault<'_>) -> (Option<String>, String) {
+        let described = match matched {
+            MatchedFault::NoMatch => "none".to_string(),
+            MatchedFault::Allow { .. } => "allow".to_string(),
+            MatchedFault::Deny { deny, .. } => format!("deny {}", deny.status),
+            MatchedFault::Fault { decision, .. } => match decision {
+                FaultDecision::None => "no fault".to_string(),
+                FaultDecision::Error { status, .. } => format!("error {status}"),
+                FaultDecision::Latency { duration_ms, .. } => format!("latency {duration_ms}"),
+                other => format!("{other:?}"),
+            },
+        };
+        (matched.rule().map(|rule| rule.id.clone()), described)
+    }
+
+    /// Method, path, headers, body, client ip and upstream of a request,
+    /// then the rule it matches and the outcome
+    type Case<'a> = (
+        &'a str,
+        &'a str,
+        &'a [(&'a str, &'a str)],
+        Option<&'a str>,
+        Option<&'a str>,
+        Option<&'a str>,
+        &'a str,
+        &'a str,
+    );
+
+    #[test]
+    fn test_evaluate_rules() {
+        let rules = compile_rules();
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            ("GET", "/health", &[], None, None, None, "allow-health", "allow"),
+            ("GET", "/admin/users", &[], None, None, None, "deny-admin", "deny 403"),
+            ("GET", "/internal/stats", &[], None, Some("10.1.2.3"), None, "internal-only", "error 503"),
+            ("GET", "/internal/stats", &[], None, Some("192.168.0.1"), None, "catch-all", "error 502"),
+            ("GET", "/internal/stats", &[], None, None, None, "catch-all", "error 502"),
+            ("GET", "/pay/123", &[], None, None, Some("payments"), "payments-latency", "latency 100"),
+            ("GET", "/pay/123", &[], None, None, Some("orders"), "catch-all", "error 502"),
+            ("GET", "/pay/123", &[], None, None, None, "payments-latency", "latency 100"),
+            ("POST", "/orders", &[("x-beta", "1")], Some("it went boom"), None, None, "beta-body", "error 500"),
+            ("POST", "/orders", &[("x-beta", "1")], Some("fine"), None, None, "catch-all", "error 502"),
+            ("POST", "/orders", &[], Some("it went boom"), None, None, "catch-all", "error 502"),
+            ("GET", "/quiet", &[], None, None, None, "never-fires", "no fault"),
+        ];
+
+        for &(method, path, headers, body, client_ip, upstream, rule, expected) in cases {
+            let method: Method = method.parse().unwrap();
+            let uri: Uri = path.parse().unwrap();
+            let headers: HeaderMap = headers
+                .iter()
+                .map(|(name, value)| (name.parse().unwrap(), value.parse().unwrap()))
+                .collect();
+            let request = RequestContext {
+                body: MatchBody::text(body),
+                client_ip: client_ip.map(|ip| ip.parse().unwrap()),
+                upstream,
+                ..RequestContext::new(&method, &uri, &headers)
+            };
+            assert_eq!(
+                outcome(&evaluate_rules(&rules, &request)),
+                (Some(rule.to_string()), expected.to_string()),
+                "{method} {path} from {client_ip:?} to {upstream:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_fault_rule_applies_paths_and_check() {
+        let rules = compile_rules();
+        let paths = crate::extensions::matcher::RulePathSet::new(&rules).unwrap();
+        let (method, uri, headers) = (Method::GET, "/quiet".parse().unwrap(), HeaderMap::new());
+        let request = RequestContext::new(&method, &uri, &headers);
+        let candidates = paths.candidates("/quiet");
+
+        let mut checked = Vec::new();
+        let matched = find_fault_rule(&rules, &request, Some(&candidates), |rule| {
+            checked.push(rule.id.clone());
+            rule.id != "never-fires"
+        });
+        assert_eq!(matched.map(|rule| rule.id.as_str()), Some("catch-all"));
+        // Only rules that otherwise apply reach the check
+        assert_eq!(checked, ["never-fires", "catch-all"]);
+
+        assert!(find_access_rule(&rules, &request, Some(&candidates), |_| true).is_none());
+    }
+
+    #[test]
+    fn test_evaluate_rules_without_match() {
+        let rules: Vec<CompiledRule> = compile_rules()
+            .into_iter()
+            .filter(|rule| rule.id != "catch-all")
+            .collect();
+        let (method, uri, headers) = (Method::GET, "/elsewhere".parse().unwrap(), HeaderMap::new());
+        let matched = evaluate_rules(&rules, &RequestContext::new(&method, &uri, &headers));
+        assert_eq!(outcome(&matched), (None, "none".to_string()));
+    }
+
+    #[test]
+    fn test_evaluate_rules_skips_inactive_rules() {
+        let rules: Vec<Rule> = serde_yaml::from_str(
+            r#"
+- id: disabled
+  enabled: false
+  match: {}
+  fault:
+    error: {probability: 1.0, status: 500}
+- id: past-window
+  active_window: {from: "2020-01-01T00:00:00Z", to: "2020-01-02T00:00:00Z"}
+  match: {}
+  fault:
+    error: {probability: 1.0, status: 501}
+- id: open-window
+  active_window: {from: "2020-01-01T00:00:00Z", to: "9999-01-01T00:00:00Z"}
+  match: {}
+  fault:
+    error: {probability: 1.0, status: 502}
+"#,
+        )
+        .unwrap();
+        let rules: Vec<CompiledRule> = rules
+            .into_iter()
+            .map(|rule| CompiledRule::compile(rule).unwrap())
+            .collect();
+        let (method, uri, headers) = (Method::GET, "/".parse().unwrap(), HeaderMap::new());
+        assert_eq!(
+            outcome(&evaluate_rules(
+                &rules,
+                &RequestContext::new(&method, &uri, &headers)
+            )),
+            (Some("open-window".to_string()), "error 502".to_string())
+        );
+    }
+
+    #[test]
+    fn test_evaluate_rules_reports_probability_without_sampling() {
+        let rules: Vec<Rule> = serde_yaml::from_str(
+            r#"
+- id: coin-flip
+  match: {}
+  fault:
+    error: {probability: 0.5, status: 503}
+    latency: {probability: 1.0, min_ms: 10, max_ms: 20}
+"#,
+        )
+        .unwrap();
+        let rules: Vec<CompiledRule> = rules
+            .into_iter()
+            .map(|rule| CompiledRule::compile(rule).unwrap())
+            .collect();
+        let (method, uri, headers) = (Method::GET, "/".parse().unwrap(), HeaderMap::new());
+        let request = RequestContext::new(&method, &uri, &headers);
+        for _ in 0..50 {
+            let matched = evaluate_rules(&rules, &request);
+            let MatchedFault::Fault { probability, .. } = matched else {
+                panic!("coin-flip should match");
+            };
+            assert_eq!(probability, 0.5);
+            assert_eq!(
+                outcome(&matched),
+                (Some("coin-flip".to_string()), "error 503".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluate_rules_leaves_once_rules_unconsumed() {
+        let rules: Vec<Rule> = serde_yaml::from_str(
+            r#"
+- id: first-only
+  once: true
+  match: {}
+  fault:
+    error: {probability: 1.0, status: 500}
+"#,
+        )
+        .unwrap();
+        let rules: Vec<CompiledRule> = rules
+            .into_iter()
+            .map(|rule| CompiledRule::compile(rule).unwrap())
+            .collect();
+        let (method, uri, headers) = (Method::GET, "/".parse().unwrap(), HeaderMap::new());
+        let request = RequestContext::new(&method, &uri, &headers);
+        for _ in 0..3 {
+            assert_eq!(
+                outcome(&evaluate_rules(&rules, &request)),
+                (Some("first-only".to_string()), "error 500".to_string())
+            );
+        }
+    }
+}
@@ -3,20 +3,144 @@
 //! This module handles forwarding requests to upstream servers,
 //! including support for recording (Mountebank-compatible).
 
-use super::client::HttpClient;
+use super::client::{HttpClient, InFlight};
 use super::headers::{
     RiftHeadersExt, VALUE_TRUE, X_RIFT_PROXIED, X_RIFT_RECORDED, X_RIFT_REPLAYED,
 };
 use super::response_ext::ResponseExt;
-use crate::recording::{ProxyMode, RecordedResponse, RecordingStore, RequestSignature};
+use super::websocket::{client_upgrade, http_upstream, is_upgrade_request, splice};
+use crate::config::{ForwardHeaders, RecordingConfig, ResponseHeaders, RetryConfig};
+use crate::recording::{ProxyMode, RecordedResponse, RecordingStore};
 use http_body_util::combinators::BoxBody;
 use http_body_util::{BodyExt, Full};
-use hyper::body::Bytes;
-use hyper::{Request, Response};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{HeaderName, HeaderValue, HOST};
+use hyper::upgrade::OnUpgrade;
+use hyper::{HeaderMap, Request, Response};
 use std::collections::HashMap;
 use std::convert::Infallible;
+use std::future::Future;
+use std::net::IpAddr;
 use std::sync::Arc;
-use tracing::{debug, error};
+use std::time::Duration;
+use tracing::{debug, error, warn};
+
+static X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+static X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+static X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
+
+/// Prepare client request headers for forwarding.
+///
+/// The client address is appended to `X-Forwarded-For`, and
+/// `X-Forwarded-Proto` / `X-Forwarded-Host` are filled in unless an earlier
+/// proxy already set them. The configured `remove`, `set` and `add` changes
+/// are applied afterwards, so they can override the forwarded headers.
+pub fn apply_forward_headers(
+    headers: &mut HeaderMap,
+    client_ip: Option<IpAddr>,
+    proto: &str,
+    config: Option<&ForwardHeaders>,
+) {
+    if let Some(ip) = client_ip {
+        let existing: Vec<&str> = headers
+            .get_all(&X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .collect();
+        let forwarded_for = if existing.is_empty() {
+            ip.to_string()
+        } else {
+            format!("{}, {}", existing.join(", "), ip)
+        };
+        if let Ok(value) = HeaderValue::from_str(&forwarded_for) {
+            headers.insert(X_FORWARDED_FOR.clone(), value);
+        }
+    }
+    if !headers.contains_key(&X_FORWARDED_PROTO) {
+        if let Ok(value) = HeaderValue::from_str(proto) {
+            headers.insert(X_FORWARDED_PROTO.clone(), value);
+        }
+    }
+    if !headers.contains_key(&X_FORWARDED_HOST) {
+        if let Some(host) = headers.get(HOST).cloned() {
+            headers.insert(X_FORWARDED_HOST.clone(), host);
+        }
+    }
+
+    let Some(config) = config else {
+        return;
+    };
+    for name in &config.remove_headers {
+        headers.remove(name.as_str());
+    }
+    for (name, value) in &config.set_headers {
+        match (
+            HeaderName::try_from(name.as_str()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => warn!("Skipping invalid forward header: {}", name),
+        }
+    }
+    for (name, value) in &config.add_headers {
+        match (
+            HeaderName::try_from(name.as_str()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                headers.append(name, value);
+            }
+            _ => warn!("Skipping invalid forward header: {}", name),
+        }
+    }
+}
+
+/// Put configured headers on a response about to be sent to the client.
+///
+/// `set` headers replace existing values and `add` headers only fill in
+/// missing ones. A header the route configures replaces the global entry
+/// for it.
+pub fn apply_response_headers(
+    headers: &mut HeaderMap,
+    global: &ResponseHeaders,
+    route: Option<&ResponseHeaders>,
+) {
+    let overridden = |name: &str| route.is_some_and(|route| route.contains(name));
+    insert_response_headers(headers, global, overridden);
+    if let Some(route) = route {
+        insert_response_headers(headers, route, |_| false);
+    }
+}
+
+fn insert_response_headers(
+    headers: &mut HeaderMap,
+    config: &ResponseHeaders,
+    skip: impl Fn(&str) -> bool,
+) {
+    let entries = config
+        .set
+        .iter()
+        .map(|entry| (entry, true))
+        .chain(config.add.iter().map(|entry| (entry, false)));
+    for ((name, value), replace) in entries {
+        if skip(name) {
+            continue;
+        }
+        match (
+            HeaderName::try_from(name.as_str()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                if replace || !headers.contains_key(&name) {
+                    headers.insert(name, value);
+                }
+            }
+            _ => warn!("Skipping invalid response header: {}", name),
+        }
+    }
+}
 
 /// Helper function to create an error response.
 pub fn error_response(status: u16, message: &str) -> Response<Full<Bytes>> {
@@ -29,6 +153,10 @@ pub fn error_response(status: u16, message: &str) -> Response<Full<Bytes>> {
 }
 
 /// Forward a request with a pre-collected body.
+///
+/// The upstream response body is streamed back, not buffered. `upgrade` is
+/// the client connection of a WebSocket handshake, if any.
+#[allow(clippy::too_many_arguments)]
 pub async fn forward_request_with_body(
     http_client: &HttpClient,
     method: hyper::Method,
@@ -36,96 +164,183 @@ pub async fn forward_request_with_body(
     headers: hyper::HeaderMap,
     body_bytes: Bytes,
     upstream_uri: &str,
-) -> Response<Full<Bytes>> {
+    upgrade: Option<OnUpgrade>,
+    policy: UpstreamPolicy<'_>,
+) -> Response<BoxBody<Bytes, hyper::Error>> {
+    send_upstream(
+        http_client,
+        method,
+        &uri,
+        &headers,
+        full_body(body_bytes),
+        upstream_uri,
+        upgrade,
+        policy,
+    )
+    .await
+}
+
+/// Forward a request with streaming body (no buffering).
+pub async fn forward_request_streaming(
+    http_client: &HttpClient,
+    req: Request<BoxBody<Bytes, hyper::Error>>,
+    upstream_uri: &str,
+    policy: UpstreamPolicy<'_>,
+) -> Response<BoxBody<Bytes, hyper::Error>> {
+    let (parts, body) = req.into_parts();
+    let upgrade = client_upgrade(&parts);
+    send_upstream(
+        http_client,
+        parts.method,
+        &parts.uri,
+        &parts.headers,
+        body,
+        upstream_uri,
+        upgrade,
+        policy,
+    )
+    .await
+}
+
+/// Retry and timeout settings for requests to one upstream
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UpstreamPolicy<'a> {
+    pub retry: Option<&'a RetryConfig>,
+    /// Longest wait for the upstream's response head, across all attempts
+    pub timeout: Option<Duration>,
+}
+
+pub(super) fn full_body(bytes: Bytes) -> BoxBody<Bytes, hyper::Error> {
+    BoxBody::new(Full::new(bytes).map_err(|never: Infallible| match never {}))
+}
+
+/// Send a request upstream, streaming both bodies.
+///
+/// When the policy retries this request, its body is buffered instead so
+/// that it can be sent again. An upstream that does not answer within the
+/// policy timeout gets a 504.
+#[allow(clippy::too_many_arguments)]
+async fn send_upstream(
+    http_client: &HttpClient,
+    method: hyper::Method,
+    uri: &hyper::Uri,
+    headers: &hyper::HeaderMap,
+    body: BoxBody<Bytes, hyper::Error>,
+    upstream_uri: &str,
+    upgrade: Option<OnUpgrade>,
+    policy: UpstreamPolicy<'_>,
+) -> Response<BoxBody<Bytes, hyper::Error>> {
+    // Build upstream URI
     let upstream_path = uri.path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
-    let full_uri = format!("{upstream_uri}{upstream_path}");
+    let full_uri = format!("{}{upstream_path}", http_upstream(upstream_uri));
 
     debug!("Forwarding to: {}", full_uri);
 
-    // Create new request to upstream
-    let mut upstream_req = Request::builder().method(method).uri(full_uri);
+    let build_request = |body| {
+        let mut upstream_req = Request::builder().method(method.clone()).uri(&full_uri);
 
-    // Copy headers (skip host)
-    for (key, value) in headers.iter() {
-        if key != "host" {
-            upstream_req = upstream_req.header(key, value);
+        // Copy headers (skip host)
+        for (key, value) in headers.iter() {
+            if key != "host" {
+                upstream_req = upstream_req.header(key, value);
+            }
         }
-    }
 
-    let upstream_req = upstream_req
-        .body(BoxBody::new(
-            Full::new(body_bytes).map_err(|never: Infallible| match never {}),
-        ))
-        .unwrap();
+        upstream_req.body(body).unwrap()
+    };
 
-    match http_client.request(upstream_req).await {
-        Ok(upstream_response) => {
-            let (parts, body) = upstream_response.into_parts();
+    let in_flight = InFlight::start(&full_uri);
+
+    // A WebSocket handshake hands over the client connection, so it is
+    // never repeated
+    let retry = policy
+        .retry
+        .filter(|r| r.max_attempts > 1 && upgrade.is_none() && r.allows_method(method.as_str()));
+    let result = match retry {
+        Some(retry) => {
             let body_bytes = match body.collect().await {
                 Ok(collected) => collected.to_bytes(),
                 Err(e) => {
-                    error!("Failed to collect upstream response body: {}", e);
-                    return error_response(502, "Failed to read upstream response");
+                    error!("Failed to read request body: {}", e);
+                    return error_response(500, "Failed to read request body").into_boxed();
                 }
             };
-            let mut response = Response::from_parts(parts, Full::new(body_bytes));
+            let attempts = send_with_retries(http_client, retry, || {
+                build_request(full_body(body_bytes.clone()))
+            });
+            within(policy.timeout, attempts).await
+        }
+        None => within(policy.timeout, http_client.request(build_request(body))).await,
+    };
+    let Some(result) = result else {
+        warn!(
+            "Upstream did not respond within {:?}: {}",
+            policy.timeout.unwrap_or_default(),
+            full_uri
+        );
+        return error_response(504, "Gateway Timeout").into_boxed();
+    };
+
+    match result {
+        Ok(mut upstream_response) => {
+            if let Some(upgrade) = upgrade {
+                splice(upgrade, &mut upstream_response);
+            }
+            let mut response = in_flight.release_with(upstream_response);
             response.set_header(&X_RIFT_PROXIED, &VALUE_TRUE);
             response
         }
         Err(e) => {
             error!("Failed to forward request to upstream: {}", e);
-            error_response(502, "Bad Gateway")
+            error_response(502, "Bad Gateway").into_boxed()
         }
     }
 }
 
-/// Forward a request with streaming body (no buffering).
-pub async fn forward_request_streaming(
-    http_client: &HttpClient,
-    req: Request<hyper::body::Incoming>,
-    upstream_uri: &str,
-) -> Response<BoxBody<Bytes, hyper::Error>> {
-    let method = req.method().clone();
-    let uri = req.uri().clone();
-    let headers = req.headers().clone();
-
-    // Build upstream URI
-    let upstream_path = uri.path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
-    let full_uri = format!("{upstream_uri}{upstream_path}");
-
-    debug!("Forwarding (streaming) to: {}", full_uri);
-
-    // Create upstream request with streaming body (no collect!)
-    let mut upstream_req = Request::builder().method(method).uri(full_uri);
-
-    // Copy headers (skip host)
-    for (key, value) in headers.iter() {
-        if key != "host" {
-            upstream_req = upstream_req.header(key, value);
-        }
+/// Run `future` to completion, or until `limit` passes
+async fn within<F: Future>(limit: Option<Duration>, future: F) -> Option<F::Output> {
+    match limit {
+        Some(limit) => tokio::time::timeout(limit, future).await.ok(),
+        None => Some(future.await),
     }
+}
 
-    // Pass request body through directly without buffering
-    let upstream_req = upstream_req.body(BoxBody::new(req.into_body())).unwrap();
-
-    // Forward with streaming response
-    match http_client.request(upstream_req).await {
-        Ok(upstream_response) => {
-            let (mut parts, body) = upstream_response.into_parts();
-            parts.set_header(&X_RIFT_PROXIED, &VALUE_TRUE);
-            Response::from_parts(parts, BoxBody::new(body))
+/// Send a request until it succeeds, stops matching `retry.retry_on`, or
+/// runs out of attempts; the last outcome is returned.
+async fn send_with_retries(
+    http_client: &HttpClient,
+    retry: &RetryConfig,
+    build_request: impl Fn() -> Request<BoxBody<Bytes, hyper::Error>>,
+) -> Result<Response<Incoming>, hyper_util::client::legacy::Error> {
+    let mut attempt = 1;
+    loop {
+        let result = http_client.request(build_request()).await;
+        let retryable = match &result {
+            Ok(response) => retry.retries_status(response.status().as_u16()),
+            Err(e) => e.is_connect() && retry.retries_connect_errors(),
+        };
+        if !retryable || attempt >= retry.max_attempts {
+            return result;
         }
-        Err(e) => {
-            error!("Failed to forward request to upstream: {}", e);
-            Response::builder()
-                .status(502)
-                .header("content-type", "application/json")
-                .body(BoxBody::new(
-                    Full::new(Bytes::from(r#"{"error": "Bad Gateway"}"#))
-                        .map_err(|never: Infallible| match never {}),
-                ))
-                .unwrap()
+
+        let backoff = retry.backoff(attempt);
+        match &result {
+            Ok(response) => warn!(
+                "Upstream returned {} (attempt {}/{}), retrying in {:?}",
+                response.status(),
+                attempt,
+                retry.max_attempts,
+                backoff
+            ),
+            Err(e) => warn!(
+                "Failed to connect to upstream (attempt {}/{}), retrying in {:?}: {}",
+                attempt, retry.max_attempts, backoff, e
+            ),
         }
+        // Release the connection before waiting
+        drop(result);
+        tokio::time::sleep(backoff).await;
+        attempt += 1;
     }
 }
 
@@ -133,9 +348,11 @@ pub async fn forward_request_streaming(
 pub async fn forward_with_recording(
     http_client: &HttpClient,
     recording_store: &Arc<RecordingStore>,
+    recording_config: &RecordingConfig,
     signature_headers: &[(String, String)],
-    req: Request<hyper::body::Incoming>,
+    req: Request<BoxBody<Bytes, hyper::Error>>,
     upstream_uri: &str,
+    policy: UpstreamPolicy<'_>,
 ) -> Response<BoxBody<Bytes, hyper::Error>> {
     let method = req.method().clone();
     let uri = req.uri().clone();
@@ -143,9 +360,10 @@ pub async fn forward_with_recording(
 
     // For recording modes, we need to collect the body to create a signature
     let mode = recording_store.mode();
-    if mode == ProxyMode::ProxyTransparent {
-        // Transparent mode - no recording, use streaming
-        return forward_request_streaming(http_client, req, upstream_uri).await;
+    if mode == ProxyMode::ProxyTransparent || is_upgrade_request(&headers) {
+        // Transparent mode - no recording, use streaming. WebSocket
+        // handshakes are never recorded
+        return forward_request_streaming(http_client, req, upstream_uri, policy).await;
     }
 
     // Collect body for signature creation
@@ -165,7 +383,7 @@ pub async fn forward_with_recording(
 
     // Create request signature for recording lookup
     let signature =
-        RequestSignature::new(method.as_str(), uri.path(), uri.query(), signature_headers);
+        recording_config.signature(method.as_str(), uri.path(), uri.query(), signature_headers);
 
     // Check if we should proxy or replay
     if !recording_store.should_proxy(&signature) {
@@ -178,24 +396,7 @@ pub async fn forward_with_recording(
                 recorded.status
             );
 
-            let mut response = Response::builder().status(recorded.status);
-
-            // Restore recorded headers
-            for (key, value) in &recorded.headers {
-                if let Ok(header_value) = value.parse::<hyper::header::HeaderValue>() {
-                    response = response.header(key.as_str(), header_value);
-                }
-            }
-
-            // Add replay indicator header
-            response = response.header(X_RIFT_REPLAYED.clone(), VALUE_TRUE.clone());
-
-            return response
-                .body(BoxBody::new(
-                    Full::new(Bytes::from(recorded.body.clone()))
-                        .map_err(|never: Infallible| match never {}),
-                ))
-                .unwrap();
+            return replay_recorded(&recorded, recording_config.add_wait_behavior).await;
         }
     }
 
@@ -208,11 +409,11 @@ pub async fn forward_with_recording(
         headers,
         body_bytes,
         upstream_uri,
+        None,
+        policy,
     )
     .await;
 
-    let latency_ms = start.elapsed().as_millis() as u64;
-
     // Record the response
     let status = response.status().as_u16();
     let (parts, body) = response.into_parts();
@@ -220,8 +421,12 @@ pub async fn forward_with_recording(
     // Extract body bytes for recording
     let response_body_bytes: Bytes = match body.collect().await {
         Ok(collected) => collected.to_bytes(),
-        Err(_) => Bytes::new(),
+        Err(e) => {
+            error!("Failed to collect upstream response body: {}", e);
+            return error_response(502, "Failed to read upstream response").into_boxed();
+        }
     };
+    let latency_ms = start.elapsed().as_millis() as u64;
 
     // Extract headers for recording
     let mut recorded_headers = HashMap::new();
@@ -259,10 +464,343 @@ pub async fn forward_with_recording(
     response.into_boxed()
 }
 
+/// Build a response from a recording, waiting for the recorded latency
+/// first when `add_wait_behavior` is enabled.
+pub async fn replay_recorded(
+    recorded: &RecordedResponse,
+    add_wait_behavior: bool,
+) -> Response<BoxBody<Bytes, hyper::Error>> {
+    if add_wait_behavior {
+        if let Some(latency_ms) = recorded.latency_ms.filter(|ms| *ms > 0) {
+            tokio::time::sleep(std::time::Duration::from_millis(latency_ms)).await;
+        }
+    }
+
+    let mut response = Response::builder().status(recorded.status);
+
+    // Restore recorded headers
+    for (key, value) in &recorded.headers {
+        if let Ok(header_value) = value.parse::<hyper::header::HeaderValue>() {
+            response = response.header(key.as_str(), header_value);
+        }
+    }
+
+    // Add replay indicator header
+    response = response.header(X_RIFT_REPLAYED.clone(), VALUE_TRUE.clone());
+
+    response
+        .body(BoxBody::new(
+            Full::new(Bytes::from(recorded.body.clone()))
+                .map_err(|never: Infallible| match never {}),
+        ))
+        .unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_replay_recorded_applies_recorded_latency() {
+        let recorded = RecordedResponse {
+            status: 201,
+            headers: HashMap::from([("x-upstream".to_string(), "a".to_string())]),
+            body: b"created".to_vec(),
+            latency_ms: Some(50),
+            timestamp_secs: 0,
+        };
+
+        let start = std::time::Instant::now();
+        let response = replay_recorded(&recorded, true).await;
+        assert!(start.elapsed() >= std::time::Duration::from_millis(50));
+        assert_eq!(response.status(), 201);
+        assert_eq!(response.headers().get("x-upstream").unwrap(), "a");
+        assert!(response.headers().contains_key(X_RIFT_REPLAYED.clone()));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"created");
+
+        // Without addWaitBehavior the replay is immediate
+        let start = std::time::Instant::now();
+        replay_recorded(&recorded, false).await;
+        assert!(start.elapsed() < std::time::Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_forwarded_for_appends_client_address() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "203.0.113.7".parse().unwrap());
+        headers.insert("x-forwarded-proto", "https".parse().unwrap());
+        headers.insert("host", "shop.example.com".parse().unwrap());
+
+        apply_forward_headers(
+            &mut headers,
+            Some("10.0.0.5".parse().unwrap()),
+            "http",
+            None,
+        );
+
+        assert_eq!(headers["x-forwarded-for"], "203.0.113.7, 10.0.0.5");
+        // Set by an earlier proxy, so kept as-is
+        assert_eq!(headers["x-forwarded-proto"], "https");
+        assert_eq!(headers["x-forwarded-host"], "shop.example.com");
+    }
+
+    #[tokio::test]
+    async fn test_forward_headers_reach_upstream() {
+        use hyper::server::conn::http1;
+        use hyper::service::service_fn;
+        use hyper_util::rt::TokioIo;
+
+        // Stub upstream echoing request headers back as JSON
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let service = service_fn(|req: Request<hyper::body::Incoming>| async move {
+                let mut echoed: HashMap<String, Vec<String>> = HashMap::new();
+                for (name, value) in req.headers() {
+                    echoed
+                        .entry(name.to_string())
+                        .or_default()
+                        .push(value.to_str().unwrap().to_string());
+                }
+                let body = serde_json::to_vec(&echoed).unwrap();
+                Ok::<_, Infallible>(Response::new(Full::new(Bytes::from(body))))
+            });
+            let _ = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+        });
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer client".parse().unwrap());
+        headers.insert("x-debug", "1".parse().unwrap());
+        headers.insert("accept", "text/plain".parse().unwrap());
+        let config = ForwardHeaders {
+            add_headers: HashMap::from([("accept".to_string(), "application/json".to_string())]),
+            set_headers: HashMap::from([(
+                "authorization".to_string(),
+                "Bearer service".to_string(),
+            )]),
+            remove_headers: vec!["x-debug".to_string()],
+        };
+        apply_forward_headers(
+            &mut headers,
+            Some("192.0.2.1".parse().unwrap()),
+            "http",
+            Some(&config),
+        );
+
+        let client_config: crate::config::Config = serde_yaml::from_str(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\n",
+        )
+        .unwrap();
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let client = super::super::client::create_http_client(&client_config).unwrap();
+        let response = forward_request_with_body(
+            &client,
+            hyper::Method::GET,
+            "/orders".parse().unwrap(),
+            headers,
+            Bytes::new(),
+            &format!("http://{addr}"),
+            None,
+            UpstreamPolicy::default(),
+        )
+        .await;
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let echoed: HashMap<String, Vec<String>> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(echoed["authorization"], ["Bearer service"]);
+        assert_eq!(echoed["accept"], ["text/plain", "application/json"]);
+        assert_eq!(echoed["x-forwarded-for"], ["192.0.2.1"]);
+        assert_eq!(echoed["x-forwarded-proto"], ["http"]);
+        assert!(!echoed.contains_key("x-debug"));
+    }
+
+    /// Stub upstream answering 503 to the first `failures` requests and
+    /// echoing the request body after that; returns its address and a
+    /// count of requests received
+    async fn spawn_flaky_upstream(
+        failures: usize,
+    ) -> (std::net::SocketAddr, Arc<std::sync::atomic::AtomicUsize>) {
+        use hyper::server::conn::http1;
+        use hyper::service::service_fn;
+        use hyper_util::rt::TokioIo;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let counter = Arc::clone(&counter);
+                let service = service_fn(move |req: Request<Incoming>| {
+                    let seen = counter.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        let body = req.into_body().collect().await.unwrap().to_bytes();
+                        let status = if seen < failures { 503 } else { 200 };
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(status)
+                                .body(Full::new(body))
+                                .unwrap(),
+                        )
+                    }
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+        (addr, requests)
+    }
+
+    fn retry_config(yaml: &str) -> RetryConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    async fn send_with_retry(
+        method: hyper::Method,
+        body: &'static str,
+        upstream: std::net::SocketAddr,
+        retry: &RetryConfig,
+    ) -> Response<BoxBody<Bytes, hyper::Error>> {
+        let client_config: crate::config::Config = serde_yaml::from_str(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\n",
+        )
+        .unwrap();
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let client = super::super::client::create_http_client(&client_config).unwrap();
+        forward_request_with_body(
+            &client,
+            method,
+            "/orders".parse().unwrap(),
+            HeaderMap::new(),
+            Bytes::from_static(body.as_bytes()),
+            &format!("http://{upstream}"),
+            None,
+            UpstreamPolicy {
+                retry: Some(retry),
+                timeout: None,
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_request_is_retried_until_success() {
+        let (addr, requests) = spawn_flaky_upstream(2).await;
+        let retry = retry_config("max_attempts: 3\nbackoff_ms: 10");
+
+        let start = std::time::Instant::now();
+        let response = send_with_retry(hyper::Method::PUT, "order-1", addr, &retry).await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 3);
+        // 10ms, then 20ms
+        assert!(start.elapsed() >= std::time::Duration::from_millis(30));
+        // The buffered body is sent again on every attempt
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"order-1");
+    }
+
+    #[tokio::test]
+    async fn test_retries_stop_at_max_attempts() {
+        let (addr, requests) = spawn_flaky_upstream(5).await;
+        let retry = retry_config("max_attempts: 2\nbackoff_ms: 1");
+
+        let response = send_with_retry(hyper::Method::GET, "", addr, &retry).await;
+        assert_eq!(response.status(), 503);
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_non_idempotent_request_is_not_retried_by_default() {
+        let (addr, requests) = spawn_flaky_upstream(1).await;
+        let retry = retry_config("backoff_ms: 1");
+
+        let response = send_with_retry(hyper::Method::POST, "payment", addr, &retry).await;
+        assert_eq!(response.status(), 503);
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let retry = retry_config("backoff_ms: 1\nonly_idempotent: false");
+        let response = send_with_retry(hyper::Method::POST, "payment", addr, &retry).await;
+        assert_eq!(response.status(), 200);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"payment");
+    }
+
+    #[tokio::test]
+    async fn test_unlisted_status_is_not_retried() {
+        let (addr, requests) = spawn_flaky_upstream(1).await;
+        let retry = retry_config("retry_on: [connect_error, 502]\nbackoff_ms: 1");
+
+        let response = send_with_retry(hyper::Method::GET, "", addr, &retry).await;
+        assert_eq!(response.status(), 503);
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_connect_errors_are_retried() {
+        // Reserve a port, then close it so connections are refused
+        let addr = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap()
+        };
+        let retry = retry_config("max_attempts: 3\nbackoff_ms: 20");
+
+        let start = std::time::Instant::now();
+        let response = send_with_retry(hyper::Method::GET, "", addr, &retry).await;
+        assert_eq!(response.status(), 502);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(60));
+    }
+
+    #[tokio::test]
+    async fn test_hung_upstream_times_out_with_504() {
+        use hyper::server::conn::http1;
+        use hyper::service::service_fn;
+        use hyper_util::rt::TokioIo;
+
+        // Stub upstream that answers long after the timeout
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let service = service_fn(|_req: Request<Incoming>| async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok::<_, Infallible>(Response::new(Full::new(Bytes::new())))
+            });
+            let _ = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+        });
+
+        let client_config: crate::config::Config = serde_yaml::from_str(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\n",
+        )
+        .unwrap();
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let client = super::super::client::create_http_client(&client_config).unwrap();
+        let start = std::time::Instant::now();
+        let response = forward_request_with_body(
+            &client,
+            hyper::Method::GET,
+            "/slow".parse().unwrap(),
+            HeaderMap::new(),
+            Bytes::new(),
+            &format!("http://{addr}"),
+            None,
+            UpstreamPolicy {
+                retry: None,
+                timeout: Some(Duration::from_millis(100)),
+            },
+        )
+        .await;
+
+        assert_eq!(response.status(), 504);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
     #[test]
     fn test_error_response_basic() {
         let response = error_response(500, "Internal Server Error");
@@ -1,33 +1,54 @@
 //! Request handling and fault injection logic.
 //!
 //! This module contains the core request handling logic including:
+//! - Access rules (allow/deny), checked before any fault
 //! - Script rule matching and execution
 //! - YAML rule matching and fault injection
 //! - Response behavior application (wait, copy, lookup, shell, decorate)
 
-use super::client::HttpClient;
-use super::forwarding::{error_response, forward_request_with_body, forward_with_recording};
+use super::body::{buffer_prefix, BodyPrefix};
+use super::chunked::{encode_response, ConnectionTakeover};
+use super::client::{HttpClient, UpstreamClients};
+use super::drip::DripBody;
+use super::engine::{self, find_access_rule, find_fault_rule, rule_applies};
+use super::forwarding::{
+    apply_forward_headers, error_response, forward_request_streaming, forward_request_with_body,
+    forward_with_recording, full_body, UpstreamPolicy,
+};
 use super::headers::{
-    RiftHeadersExt, VALUE_ERROR, VALUE_LATENCY, VALUE_TCP, VALUE_TRUE, X_RIFT_BEHAVIOR_COPY,
-    X_RIFT_BEHAVIOR_DECORATE, X_RIFT_BEHAVIOR_LOOKUP, X_RIFT_BEHAVIOR_SHELL, X_RIFT_BEHAVIOR_WAIT,
-    X_RIFT_FAULT, X_RIFT_LATENCY_MS, X_RIFT_RULE_ID, X_RIFT_SCRIPT, X_RIFT_TCP_FAULT,
+    RiftHeadersExt, VALUE_CHUNKED, VALUE_DRIP, VALUE_ERROR, VALUE_LATENCY, VALUE_MOCK,
+    VALUE_RATE_LIMIT, VALUE_TCP, VALUE_TRUE, X_RIFT_BEHAVIOR_COPY, X_RIFT_BEHAVIOR_DECORATE,
+    X_RIFT_BEHAVIOR_LOOKUP, X_RIFT_BEHAVIOR_SHELL, X_RIFT_BEHAVIOR_WAIT, X_RIFT_FAULT,
+    X_RIFT_LATENCY_MS, X_RIFT_PROXIED, X_RIFT_RULE_ID, X_RIFT_SCRIPT, X_RIFT_TCP_FAULT,
 };
+use super::request_id::ensure_request_id;
+use super::response_cache::ResponseCache;
 use super::response_ext::ResponseExt;
+use super::websocket::client_upgrade;
 use crate::behaviors::{
     apply_copy_behaviors, apply_decorate, apply_lookup_behaviors, apply_shell_transform, CsvCache,
     RequestContext,
 };
-use crate::config::TcpFault;
-use crate::extensions::fault::{apply_latency, create_error_response, decide_fault, FaultDecision};
+use crate::config::{
+    BodyOverflow, ForwardHeaders, RequestIdConfig, RetryConfig, RuleAction, TcpFault,
+};
+use crate::extensions::fault::{
+    apply_latency, create_error_response, create_grpc_error_response, is_grpc_request,
+    FaultDecision, FaultRng,
+};
 use crate::extensions::flow_state::FlowStore;
-use crate::extensions::matcher::CompiledRule;
+use crate::extensions::matcher::{CompiledRule, RulePathSet};
 use crate::extensions::metrics;
+use crate::extensions::retry::RetryTracker;
 use crate::extensions::routing::Router;
-use crate::extensions::template::{has_template_variables, process_template, RequestData};
+use crate::extensions::template::{
+    has_template_variables, process_template, render_generated_values, render_mock_body,
+    RequestData,
+};
 use crate::recording::RecordingStore;
 use crate::scripting::{
-    CacheKey, CompiledScript, DecisionCache, FaultDecision as ScriptFaultDecision, ScriptPool,
-    ScriptRequest,
+    CacheKey, CacheKeyField, CompiledScript, DecisionCache, FaultDecision as ScriptFaultDecision,
+    ScriptContext, ScriptPool, ScriptPoolError, ScriptRequest, ScriptResponse,
 };
 use http_body_util::combinators::BoxBody;
 use http_body_util::BodyExt;
@@ -36,28 +57,85 @@ use hyper::{Request, Response};
 use std::collections::HashMap;
 use std::convert::Infallible;
 use std::sync::Arc;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
+
+/// A precompiled script rule: script, matcher and cache key fields.
+pub type CompiledScriptRule = (CompiledScript, CompiledRule, Vec<CacheKeyField>);
 
 /// Context for handling a request, containing all necessary state.
 pub struct RequestHandlerContext<'a> {
-    pub http_client: &'a HttpClient,
+    pub http_clients: &'a UpstreamClients,
     pub compiled_rules: &'a [CompiledRule],
-    pub rule_upstreams: &'a [Option<String>],
+    /// Path prefilter over `compiled_rules`, if it could be built
+    pub rule_paths: Option<&'a RulePathSet>,
     pub upstream_uri: &'a str,
     pub router: Option<&'a Router>,
     pub upstreams: &'a [crate::config::Upstream],
     pub flow_store: &'a Arc<dyn FlowStore>,
     pub script_pool: Option<&'a Arc<ScriptPool>>,
-    pub compiled_scripts: Option<&'a [(CompiledScript, CompiledRule, Option<String>)]>,
+    pub compiled_scripts: Option<&'a [CompiledScriptRule]>,
     pub decision_cache: Option<&'a Arc<DecisionCache>>,
     pub csv_cache: &'a Arc<CsvCache>,
     pub recording_store: &'a Arc<RecordingStore>,
+    pub recording_config: &'a crate::config::RecordingConfig,
     pub recording_signature_headers: &'a [(String, String)],
     pub flow_state_configured: bool,
+    pub fault_rng: &'a Arc<FaultRng>,
+    /// Client address, appended to `X-Forwarded-For`
+    pub client_ip: Option<std::net::IpAddr>,
+    /// Listener scheme, sent as `X-Forwarded-Proto`
+    pub forwarded_proto: &'static str,
+    /// Header changes for the sidecar upstream
+    pub sidecar_forward_headers: Option<&'a ForwardHeaders>,
+    /// Retries for the sidecar upstream
+    pub sidecar_retry: Option<&'a RetryConfig>,
+    /// Default limit on waiting for an upstream response
+    pub request_timeout: Option<std::time::Duration>,
+    /// Most body bytes buffered for body matchers
+    pub max_body_bytes: usize,
+    pub body_overflow: BodyOverflow,
+    /// Header carrying request ids, if they are enabled
+    pub request_id: Option<&'a RequestIdConfig>,
+    /// Cache of responses forwarded without a fault, if enabled
+    pub response_cache: Option<&'a ResponseCache>,
 }
 
 /// Handle an incoming request with fault injection and forwarding.
+///
+/// With request ids enabled, the request's id is added to it if missing,
+/// recorded on every log line for the request and echoed in the response.
 pub async fn handle_request(
+    ctx: &RequestHandlerContext<'_>,
+    mut req: Request<hyper::body::Incoming>,
+) -> Result<Response<BoxBody<Bytes, hyper::Error>>, Infallible> {
+    let request_id = ctx
+        .request_id
+        .and_then(|config| ensure_request_id(config, req.headers_mut()));
+    let span = match &request_id {
+        Some((_, id)) => info_span!("request", request_id = %id.to_str().unwrap_or_default()),
+        None => Span::none(),
+    };
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+
+    let mut response = route_request(ctx, req).instrument(span.clone()).await?;
+    span.in_scope(|| {
+        debug!(
+            "Completed request: {} {} -> {}",
+            method,
+            path,
+            response.status().as_u16()
+        )
+    });
+    if let Some((name, id)) = request_id {
+        response.headers_mut().insert(name, id);
+    }
+    Ok(response)
+}
+
+/// Apply access rules, script rules and YAML rules to a request, then
+/// forward it or inject the matched fault
+async fn route_request(
     ctx: &RequestHandlerContext<'_>,
     req: Request<hyper::body::Incoming>,
 ) -> Result<Response<BoxBody<Bytes, hyper::Error>>, Infallible> {
@@ -69,27 +147,152 @@ pub async fn handle_request(
     debug!("Received request: {} {}", method, uri);
 
     // Select upstream for this request (reverse proxy mode)
-    let selected_upstream = select_upstream(ctx.router, ctx.upstreams, &req);
-    let (selected_upstream_url, selected_upstream_name) = match selected_upstream {
-        Some((url, name)) => (Some(url), Some(name)),
-        None => (None, None),
+    let selected_upstream = select_upstream(ctx.router, ctx.upstreams, &req, ctx.client_ip);
+    let (selected_upstream_url, selected_upstream_name, rewritten_path, forward_headers, policy) =
+        match selected_upstream {
+            Some(selected) => (
+                Some(selected.url),
+                Some(selected.name),
+                selected.rewritten_path,
+                Some(selected.forward_headers),
+                UpstreamPolicy {
+                    retry: selected.retry,
+                    timeout: selected.request_timeout.or(ctx.request_timeout),
+                },
+            ),
+            None => (
+                None,
+                None,
+                None,
+                ctx.sidecar_forward_headers,
+                UpstreamPolicy {
+                    retry: ctx.sidecar_retry,
+                    timeout: ctx.request_timeout,
+                },
+            ),
+        };
+
+    let http_client = ctx.http_clients.get(selected_upstream_name.as_deref());
+
+    // Rules keep matching the original `uri` and `headers`; only the
+    // forwarded request is rewritten
+    let mut req = req;
+    apply_forward_headers(
+        req.headers_mut(),
+        ctx.client_ip,
+        ctx.forwarded_proto,
+        forward_headers,
+    );
+    if let Some(path) = rewritten_path {
+        match rewrite_uri_path(&uri, &path) {
+            Some(rewritten) => {
+                debug!("Rewrote path {} -> {}", uri.path(), rewritten.path());
+                *req.uri_mut() = rewritten;
+            }
+            None => warn!("Ignoring invalid rewritten path for {}: {}", uri, path),
+        }
+    }
+
+    // Buffer the body only if a rule inspects it; everything else streams
+    let needs_body = ctx.compiled_rules.iter().any(CompiledRule::needs_body)
+        || ctx
+            .compiled_scripts
+            .is_some_and(|scripts| scripts.iter().any(|(_, rule, _)| rule.needs_body()));
+    let (req, body_prefix) = if needs_body {
+        // Trailer matchers need the body read to its end
+        let needs_trailers = ctx.compiled_rules.iter().any(CompiledRule::needs_trailers)
+            || ctx
+                .compiled_scripts
+                .is_some_and(|scripts| scripts.iter().any(|(_, rule, _)| rule.needs_trailers()));
+        let (parts, body) = req.into_parts();
+        match buffer_prefix(body, ctx.max_body_bytes, needs_trailers).await {
+            Ok((mut prefix, body)) => {
+                prefix.decode_content(&parts.headers, ctx.max_body_bytes);
+                if prefix.truncated {
+                    if ctx.body_overflow == BodyOverflow::Reject {
+                        info!(
+                            "Rejecting request body larger than max_body_bytes ({})",
+                            ctx.max_body_bytes
+                        );
+                        return Ok(error_response(413, "Request body too large").into_boxed());
+                    }
+                    debug!(
+                        "Request body exceeds max_body_bytes ({}), matching against its prefix",
+                        ctx.max_body_bytes
+                    );
+                }
+                (Request::from_parts(parts, BoxBody::new(body)), Some(prefix))
+            }
+            Err(e) => {
+                error!("Failed to read request body: {}", e);
+                return Ok(error_response(500, "Failed to read request body").into_boxed());
+            }
+        }
+    } else {
+        (req.map(BoxBody::new), None)
+    };
+    let body = body_prefix.as_ref();
+    let match_request = engine::RequestContext {
+        body: body.map(BodyPrefix::match_body).unwrap_or_default(),
+        client_ip: ctx.client_ip,
+        upstream: selected_upstream_name.as_deref(),
+        ..engine::RequestContext::new(&method, &uri, &headers)
     };
 
+    // Idempotency keys are recorded at most once per request
+    let mut retry_tracker = RetryTracker::new(ctx.flow_store.as_ref(), &headers);
+
+    // Access rules decide before any fault rule is considered
+    let path_candidates = ctx.rule_paths.map(|paths| paths.candidates(uri.path()));
+    let access_rule = find_access_rule(
+        ctx.compiled_rules,
+        &match_request,
+        path_candidates.as_ref(),
+        |rule| rule.matches_retry(&mut retry_tracker),
+    );
+    if let Some(rule) = access_rule {
+        info!("Request matched access rule: {}", rule.id);
+        metrics::record_rule_match(&rule.id);
+        rule.record_match();
+        if let RuleAction::Deny(deny) = &rule.rule.action {
+            let duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+            metrics::record_proxy_duration(method.as_str(), duration_ms, "deny");
+            metrics::record_request(method.as_str(), deny.status);
+            let mut response =
+                create_error_response(deny.status, deny.body.clone(), Some(&deny.headers), None)
+                    .unwrap();
+            response.set_header_value(&X_RIFT_RULE_ID, &rule.id);
+            return Ok(response.into_boxed());
+        }
+        let upstream_url = selected_upstream_url.as_deref().unwrap_or(ctx.upstream_uri);
+        return Ok(forward_without_fault(
+            ctx,
+            http_client,
+            req,
+            upstream_url,
+            policy,
+            &method,
+            start_time,
+        )
+        .await);
+    }
+
     // Check script rules first (if configured) - optimized path with pool and cache
     let req = if let (Some(compiled_scripts), Some(script_pool), Some(decision_cache)) =
         (ctx.compiled_scripts, ctx.script_pool, ctx.decision_cache)
     {
         match handle_script_rules(
             ctx,
+            http_client,
             compiled_scripts,
             script_pool,
             decision_cache,
             req,
-            &method,
-            &uri,
-            &headers,
+            &match_request,
+            &mut retry_tracker,
             selected_upstream_url.as_deref(),
             selected_upstream_name.as_deref(),
+            policy,
             start_time,
         )
         .await
@@ -101,32 +304,30 @@ pub async fn handle_request(
         req
     };
 
-    // Find matching YAML rule that applies to selected upstream
-    let matched_rule_index = ctx
-        .compiled_rules
-        .iter()
-        .enumerate()
-        .find(|(idx, rule)| {
-            rule.matches(&method, &uri, &headers)
-                && rule_applies_to_upstream(
-                    &ctx.rule_upstreams[*idx],
-                    selected_upstream_name.as_deref(),
-                )
-        })
-        .map(|(idx, _)| idx);
+    // Find matching YAML rule, checking only rules whose path matches
+    let matched_rule = find_fault_rule(
+        ctx.compiled_rules,
+        &match_request,
+        path_candidates.as_ref(),
+        |rule| rule.matches_retry(&mut retry_tracker),
+    );
 
-    if let Some(rule_idx) = matched_rule_index {
-        let rule = &ctx.compiled_rules[rule_idx];
+    if let Some(rule) = matched_rule {
         info!("Request matched rule: {}", rule.id);
+        metrics::record_rule_match(&rule.id);
+        rule.record_match();
 
         match handle_yaml_rule(
             ctx,
+            http_client,
             rule,
             req,
             &method,
             &uri,
             &headers,
+            body,
             selected_upstream_url.as_deref(),
+            policy,
             start_time,
         )
         .await
@@ -135,38 +336,75 @@ pub async fn handle_request(
             RuleHandlingResult::NoFault(r) => {
                 // Continue to forward without fault
                 let upstream_url = selected_upstream_url.as_deref().unwrap_or(ctx.upstream_uri);
-                let response = forward_with_recording(
-                    ctx.http_client,
-                    ctx.recording_store,
-                    ctx.recording_signature_headers,
+                return Ok(forward_without_fault(
+                    ctx,
+                    http_client,
                     r,
                     upstream_url,
+                    policy,
+                    &method,
+                    start_time,
                 )
-                .await;
-                let status = response.status().as_u16();
-                let duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
-                metrics::record_proxy_duration(method.as_str(), duration_ms, "none");
-                metrics::record_request(method.as_str(), status);
-                return Ok(response);
+                .await);
             }
         }
     }
 
-    // Forward request without fault (with recording support if enabled)
     let upstream_url = selected_upstream_url.as_deref().unwrap_or(ctx.upstream_uri);
-    let response = forward_with_recording(
-        ctx.http_client,
-        ctx.recording_store,
-        ctx.recording_signature_headers,
+    Ok(forward_without_fault(
+        ctx,
+        http_client,
         req,
         upstream_url,
+        policy,
+        &method,
+        start_time,
     )
-    .await;
+    .await)
+}
+
+/// Forward a request without fault (with recording support if enabled),
+/// answering from the response cache when it can
+async fn forward_without_fault(
+    ctx: &RequestHandlerContext<'_>,
+    http_client: &HttpClient,
+    req: Request<BoxBody<Bytes, hyper::Error>>,
+    upstream_url: &str,
+    policy: UpstreamPolicy<'_>,
+    method: &hyper::Method,
+    start_time: std::time::Instant,
+) -> Response<BoxBody<Bytes, hyper::Error>> {
+    let cache = ctx.response_cache.and_then(|cache| {
+        let key = cache.key(req.method(), req.uri(), req.headers(), upstream_url)?;
+        Some((cache, key))
+    });
+    let response = match cache.as_ref().and_then(|(cache, key)| cache.get(key)) {
+        Some(response) => {
+            debug!("Serving cached response for {} {}", method, req.uri());
+            response
+        }
+        None => {
+            let response = forward_with_recording(
+                http_client,
+                ctx.recording_store,
+                ctx.recording_config,
+                ctx.recording_signature_headers,
+                req,
+                upstream_url,
+                policy,
+            )
+            .await;
+            match cache {
+                Some((cache, key)) => cache.store(key, response).await,
+                None => response,
+            }
+        }
+    };
     let status = response.status().as_u16();
     let duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
     metrics::record_proxy_duration(method.as_str(), duration_ms, "none");
     metrics::record_request(method.as_str(), status);
-    Ok(response)
+    response
 }
 
 /// Result of rule handling - either a response or the request back
@@ -174,40 +412,43 @@ pub enum RuleHandlingResult {
     /// A rule matched and returned a response
     Response(Response<BoxBody<Bytes, hyper::Error>>),
     /// No fault injected, here's the request back for forwarding
-    NoFault(Request<hyper::body::Incoming>),
+    NoFault(Request<BoxBody<Bytes, hyper::Error>>),
 }
 
 /// Handle script rules - returns either a response or the request back if no script matched.
 #[allow(clippy::too_many_arguments)]
 async fn handle_script_rules(
     ctx: &RequestHandlerContext<'_>,
-    compiled_scripts: &[(CompiledScript, CompiledRule, Option<String>)],
+    http_client: &HttpClient,
+    compiled_scripts: &[CompiledScriptRule],
     script_pool: &Arc<ScriptPool>,
     decision_cache: &Arc<DecisionCache>,
-    req: Request<hyper::body::Incoming>,
-    method: &hyper::Method,
-    uri: &hyper::Uri,
-    headers: &hyper::HeaderMap,
+    req: Request<BoxBody<Bytes, hyper::Error>>,
+    request: &engine::RequestContext<'_>,
+    retry_tracker: &mut RetryTracker<'_>,
     selected_upstream_url: Option<&str>,
     selected_upstream_name: Option<&str>,
+    upstream_policy: UpstreamPolicy<'_>,
     start_time: std::time::Instant,
 ) -> RuleHandlingResult {
-    // Find first matching script rule that applies to selected upstream
-    let matching_script = compiled_scripts
-        .iter()
-        .find(|(_, compiled_rule, rule_upstream)| {
-            compiled_rule.matches(method, uri, headers)
-                && rule_applies_to_upstream(rule_upstream, selected_upstream_name)
-        });
-
-    let (compiled_script, compiled_rule, _) = match matching_script {
+    let (method, uri, headers) = (request.method, request.uri, request.headers);
+    let matching_script = compiled_scripts.iter().find(|(_, compiled_rule, _)| {
+        rule_applies(compiled_rule, request) && compiled_rule.matches_retry(retry_tracker)
+    });
+
+    let (compiled_script, compiled_rule, cache_key_fields) = match matching_script {
         Some(m) => m,
         None => return RuleHandlingResult::NoFault(req),
     };
     info!("Request matched script rule: {}", compiled_rule.id);
+    metrics::record_rule_match(&compiled_rule.id);
+    compiled_rule.record_match();
+
+    // Forward with the (possibly rewritten) request URI and headers
+    let (forward, body) = req.into_parts();
 
     // Collect body for script (needed for script context)
-    let body_bytes = match req.collect().await {
+    let body_bytes = match body.collect().await {
         Ok(collected) => collected.to_bytes(),
         Err(e) => {
             error!("Failed to collect request body: {}", e);
@@ -236,28 +477,56 @@ async fn handle_script_rules(
         path: uri.path().to_string(),
         headers: headers_map.clone(),
         body: body_json.clone(),
-        query: query_params,
+        query: query_params.clone(),
         path_params: HashMap::new(),
     };
-
-    // Create cache key
-    let cache_key = CacheKey::new(
-        method.to_string(),
-        uri.path().to_string(),
-        headers_map.into_iter().collect(),
-        &body_json,
-        compiled_rule.id.clone(),
-    );
+    let hook_request = compiled_script
+        .has_response_hook()
+        .then(|| script_request.clone());
+
+    // Create cache key, restricted to the rule's declared fields if any
+    let cache_key = if cache_key_fields.is_empty() {
+        CacheKey::new(
+            method.to_string(),
+            uri.path().to_string(),
+            headers_map.into_iter().collect(),
+            &body_json,
+            compiled_rule.id.clone(),
+        )
+    } else {
+        CacheKey::from_fields(
+            cache_key_fields,
+            method.as_str(),
+            uri.path(),
+            &headers_map,
+            &query_params,
+            &body_json,
+            compiled_rule.id.clone(),
+        )
+    };
 
     // Determine if caching should be used
     // If flow_state is configured (not NoOpFlowStore), disable caching
     // because scripts using flow_store are stateful and results vary
-    let use_cache = !ctx.flow_state_configured;
+    // Scripts taking `ctx` may read the clock or RNG, so their decisions vary
+    // too
+    let use_cache = !ctx.flow_state_configured
+        && decision_cache.is_enabled()
+        && !compiled_script.takes_context();
+    let script_context = ScriptContext::new(Arc::clone(ctx.fault_rng));
 
     // Check cache first (only for stateless scripts), then execute via pool
     let script_start = std::time::Instant::now();
     let result = if use_cache {
-        if let Some(cached_decision) = decision_cache.get(&cache_key) {
+        let cached = decision_cache.get(&cache_key);
+        let cache_metrics = decision_cache.metrics();
+        metrics::record_decision_cache_lookup(
+            cached.is_some(),
+            cache_metrics.hit_rate(),
+            cache_metrics.size,
+        );
+
+        if let Some(cached_decision) = cached {
             debug!("Cache hit for rule: {} (stateless)", compiled_rule.id);
             Ok(cached_decision)
         } else {
@@ -269,6 +538,7 @@ async fn handle_script_rules(
                     compiled_script.clone(),
                     script_request,
                     Arc::clone(ctx.flow_store),
+                    script_context,
                 )
                 .await;
 
@@ -287,41 +557,168 @@ async fn handle_script_rules(
                 compiled_script.clone(),
                 script_request,
                 Arc::clone(ctx.flow_store),
+                script_context,
             )
             .await
     };
     let script_duration = script_start.elapsed().as_secs_f64() * 1000.0;
 
-    RuleHandlingResult::Response(
-        handle_script_result(
-            ctx,
-            result.map_err(|e| e.to_string()),
-            compiled_rule,
-            method,
-            uri,
-            headers,
-            body_bytes,
-            selected_upstream_url,
-            selected_upstream_name,
-            start_time,
-            script_duration,
-        )
-        .await,
+    // A script the pool could not run in time falls back to the pool's
+    // policy; the fallback is never cached
+    let result = match result {
+        Err(e) => match e.downcast_ref::<ScriptPoolError>() {
+            Some(unavailable) => {
+                warn!("Script for rule {} did not run: {}", compiled_rule.id, e);
+                metrics::record_script_error(&compiled_rule.id, unavailable.as_str());
+                Ok(script_pool.unavailable_decision(&compiled_rule.id, unavailable))
+            }
+            None => Err(e),
+        },
+        result => result,
+    };
+
+    let response = handle_script_result(
+        ctx,
+        http_client,
+        result.map_err(|e| e.to_string()),
+        compiled_rule,
+        method,
+        uri,
+        &forward,
+        headers,
+        body_bytes,
+        selected_upstream_url,
+        selected_upstream_name,
+        upstream_policy,
+        start_time,
+        script_duration,
     )
+    .await;
+
+    // Only responses that came from the upstream go through on_response;
+    // injected errors and proxy-generated failures are returned as-is
+    let response = match hook_request {
+        Some(request)
+            if response.headers().contains_key(&X_RIFT_PROXIED)
+                && response.status() != hyper::StatusCode::SWITCHING_PROTOCOLS =>
+        {
+            apply_response_hook(
+                ctx,
+                script_pool,
+                compiled_script,
+                &compiled_rule.id,
+                request,
+                response,
+            )
+            .await
+        }
+        _ => response,
+    };
+    RuleHandlingResult::Response(response)
+}
+
+/// Run a script's `on_response` hook and apply what it returns.
+///
+/// The body is buffered to hand it to the script. If the hook fails the
+/// upstream response is returned unchanged.
+async fn apply_response_hook(
+    ctx: &RequestHandlerContext<'_>,
+    script_pool: &ScriptPool,
+    compiled_script: &CompiledScript,
+    rule_id: &str,
+    request: ScriptRequest,
+    response: Response<BoxBody<Bytes, hyper::Error>>,
+) -> Response<BoxBody<Bytes, hyper::Error>> {
+    let (mut parts, body) = response.into_parts();
+    let body_bytes = match body.collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) => {
+            error!("Failed to read upstream response body: {}", e);
+            return error_response(502, "Failed to read upstream response").into_boxed();
+        }
+    };
+
+    let mut headers = HashMap::new();
+    for (k, v) in parts.headers.iter() {
+        if let Ok(value_str) = v.to_str() {
+            headers.insert(k.as_str().to_string(), value_str.to_string());
+        }
+    }
+    let body_json = serde_json::from_slice(&body_bytes).unwrap_or_else(|_| {
+        std::str::from_utf8(&body_bytes)
+            .map_or(serde_json::Value::Null, |text| text.to_string().into())
+    });
+    let original = ScriptResponse {
+        status: parts.status.as_u16(),
+        headers,
+        body: body_json,
+    };
+
+    let updated = match script_pool
+        .execute_on_response(
+            compiled_script.clone(),
+            request,
+            original.clone(),
+            Arc::clone(ctx.flow_store),
+        )
+        .await
+    {
+        Ok(Some(updated)) => updated,
+        Ok(None) => return Response::from_parts(parts, full_body(body_bytes)),
+        Err(e) => {
+            error!("on_response hook failed for rule {}: {}", rule_id, e);
+            metrics::record_script_error(rule_id, "on_response");
+            return Response::from_parts(parts, full_body(body_bytes));
+        }
+    };
+
+    if let Ok(status) = hyper::StatusCode::from_u16(updated.status) {
+        parts.status = status;
+    }
+    if updated.headers != original.headers {
+        parts.headers.clear();
+        for (name, value) in &updated.headers {
+            match (
+                hyper::header::HeaderName::from_bytes(name.as_bytes()),
+                hyper::header::HeaderValue::from_str(value),
+            ) {
+                (Ok(name), Ok(value)) => {
+                    parts.headers.insert(name, value);
+                }
+                _ => warn!("on_response set invalid header {}: {}", name, value),
+            }
+        }
+    }
+    let body_bytes = if updated.body == original.body {
+        body_bytes
+    } else {
+        match updated.body {
+            serde_json::Value::String(text) => Bytes::from(text),
+            serde_json::Value::Null => Bytes::new(),
+            body => Bytes::from(body.to_string()),
+        }
+    };
+    // The body has been buffered and may have changed length
+    parts.headers.remove(hyper::header::CONTENT_LENGTH);
+    parts.headers.remove(hyper::header::TRANSFER_ENCODING);
+    Response::from_parts(parts, full_body(body_bytes))
 }
 
 /// Handle the result of a script execution.
 #[allow(clippy::too_many_arguments)]
 async fn handle_script_result(
     ctx: &RequestHandlerContext<'_>,
+    http_client: &HttpClient,
     result: Result<ScriptFaultDecision, String>,
     compiled_rule: &CompiledRule,
     method: &hyper::Method,
     uri: &hyper::Uri,
+    forward: &hyper::http::request::Parts,
     headers: &hyper::HeaderMap,
     body_bytes: Bytes,
     selected_upstream_url: Option<&str>,
     selected_upstream_name: Option<&str>,
+    upstream_policy: UpstreamPolicy<'_>,
     start_time: std::time::Instant,
     script_duration: f64,
 ) -> Response<BoxBody<Bytes, hyper::Error>> {
@@ -350,16 +747,12 @@ async fn handle_script_result(
             let fixed_headers = ctx
                 .compiled_rules
                 .iter()
-                .enumerate()
-                .find(|(idx, rule)| {
+                .find(|rule| {
                     rule.matches(method, uri, headers)
-                        && rule_applies_to_upstream(
-                            &ctx.rule_upstreams[*idx],
-                            selected_upstream_name,
-                        )
+                        && rule_applies_to_upstream(&rule.rule.upstream, selected_upstream_name)
                         && rule.rule.fault.error.is_some()
                 })
-                .and_then(|(_, rule)| rule.rule.fault.error.as_ref().map(|e| e.headers.clone()));
+                .and_then(|rule| rule.rule.fault.error.as_ref().map(|e| e.headers.clone()));
 
             let mut response =
                 create_error_response(status, body, fixed_headers.as_ref(), Some(&script_headers))
@@ -387,12 +780,14 @@ async fn handle_script_result(
             // Forward with body for latency fault
             let upstream_url = selected_upstream_url.unwrap_or(ctx.upstream_uri);
             let mut response = forward_request_with_body(
-                ctx.http_client,
+                http_client,
                 method.clone(),
-                uri.clone(),
-                headers.clone(),
+                forward.uri.clone(),
+                forward.headers.clone(),
                 body_bytes,
                 upstream_url,
+                client_upgrade(forward),
+                upstream_policy,
             )
             .await;
             let status = response.status().as_u16();
@@ -405,7 +800,7 @@ async fn handle_script_result(
             response.set_header_value(&X_RIFT_RULE_ID, &rule_id);
             response.set_header(&X_RIFT_SCRIPT, &VALUE_TRUE);
             response.set_header_value(&X_RIFT_LATENCY_MS, &duration_ms.to_string());
-            response.into_boxed()
+            response
         }
         Ok(ScriptFaultDecision::None) => {
             debug!(
@@ -417,19 +812,21 @@ async fn handle_script_result(
             // Forward request
             let upstream_url = selected_upstream_url.unwrap_or(ctx.upstream_uri);
             let response = forward_request_with_body(
-                ctx.http_client,
+                http_client,
                 method.clone(),
-                uri.clone(),
-                headers.clone(),
+                forward.uri.clone(),
+                forward.headers.clone(),
                 body_bytes,
                 upstream_url,
+                client_upgrade(forward),
+                upstream_policy,
             )
             .await;
             let status = response.status().as_u16();
             let duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
             metrics::record_proxy_duration(method.as_str(), duration_ms, "none");
             metrics::record_request(method.as_str(), status);
-            response.into_boxed()
+            response
         }
         Err(e) => {
             error!(
@@ -442,19 +839,21 @@ async fn handle_script_result(
             // Forward request on error
             let upstream_url = selected_upstream_url.unwrap_or(ctx.upstream_uri);
             let response = forward_request_with_body(
-                ctx.http_client,
+                http_client,
                 method.clone(),
-                uri.clone(),
-                headers.clone(),
+                forward.uri.clone(),
+                forward.headers.clone(),
                 body_bytes,
                 upstream_url,
+                client_upgrade(forward),
+                upstream_policy,
             )
             .await;
             let status = response.status().as_u16();
             let duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
             metrics::record_proxy_duration(method.as_str(), duration_ms, "none");
             metrics::record_request(method.as_str(), status);
-            response.into_boxed()
+            response
         }
     }
 }
@@ -463,16 +862,45 @@ async fn handle_script_result(
 #[allow(clippy::too_many_arguments)]
 async fn handle_yaml_rule(
     ctx: &RequestHandlerContext<'_>,
+    http_client: &HttpClient,
     rule: &CompiledRule,
-    req: Request<hyper::body::Incoming>,
+    req: Request<BoxBody<Bytes, hyper::Error>>,
     method: &hyper::Method,
     uri: &hyper::Uri,
     headers: &hyper::HeaderMap,
+    body: Option<&BodyPrefix>,
     selected_upstream_url: Option<&str>,
+    upstream_policy: UpstreamPolicy<'_>,
     start_time: std::time::Instant,
 ) -> RuleHandlingResult {
+    if let Some(retry_after) = rule.check_rate_limit(method, uri, headers, ctx.client_ip) {
+        // Whole seconds, rounded up so the client never retries too early
+        let retry_after_secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        warn!(
+            "Injecting rate limit: retry after {}s, rule={}",
+            retry_after_secs, rule.id
+        );
+        metrics::record_fault_injection("rate_limit", &rule.id, "v1");
+        let duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+        metrics::record_proxy_duration(method.as_str(), duration_ms, "rate_limit");
+        metrics::record_request(method.as_str(), 429);
+
+        let retry_header =
+            HashMap::from([("retry-after".to_string(), retry_after_secs.to_string())]);
+        let mut response = create_error_response(
+            429,
+            r#"{"error": "Too many requests"}"#.to_string(),
+            Some(&retry_header),
+            None,
+        )
+        .unwrap();
+        response.set_header(&X_RIFT_FAULT, &VALUE_RATE_LIMIT);
+        response.set_header_value(&X_RIFT_RULE_ID, &rule.id);
+        return RuleHandlingResult::Response(response.into_boxed());
+    }
+
     // Decide fault
-    let fault_decision = decide_fault(&rule.rule.fault, &rule.id);
+    let fault_decision = rule.decide_fault(ctx.fault_rng);
 
     match fault_decision {
         FaultDecision::TcpFault {
@@ -521,6 +949,25 @@ async fn handle_yaml_rule(
                 }
             }
 
+            // gRPC clients get a gRPC status instead of the HTTP error
+            let grpc_fault = rule.rule.fault.error.as_ref().and_then(|e| e.grpc.as_ref());
+            if let Some(grpc) = grpc_fault.filter(|_| is_grpc_request(headers)) {
+                warn!(
+                    "Injecting gRPC status {} instead, rule={}",
+                    grpc.status_code, rule_id
+                );
+                metrics::record_error_injection(&rule_id, 200);
+                let duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+                metrics::record_proxy_duration(method.as_str(), duration_ms, "error");
+                metrics::record_request(method.as_str(), 200);
+
+                let mut response = create_grpc_error_response(grpc)
+                    .map(|b| BoxBody::new(b.map_err(|never: Infallible| match never {})));
+                response.set_header(&X_RIFT_FAULT, &VALUE_ERROR);
+                response.set_header_value(&X_RIFT_RULE_ID, &rule_id);
+                return RuleHandlingResult::Response(response);
+            }
+
             // Record metrics
             metrics::record_error_injection(&rule_id, status);
             let duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
@@ -528,21 +975,25 @@ async fn handle_yaml_rule(
             metrics::record_request(method.as_str(), status);
 
             // Build request context for behaviors
+            let path_params = rule.path_params(uri.path());
             let request_context = RequestContext::from_request(
                 method.as_str(),
                 uri,
                 headers,
                 None, // Body not available for YAML rules
-            );
+            )
+            .with_path_params(path_params.clone());
 
             // Process template variables in response body if present
             let mut processed_body = if has_template_variables(&body) {
-                let request_data =
+                let mut request_data =
                     RequestData::new(method.as_str(), uri.path(), uri.query(), headers, None);
+                request_data.path_params = path_params;
                 process_template(&body, &request_data)
             } else {
                 body
             };
+            processed_body = render_generated_values(&processed_body, ctx.fault_rng);
 
             // Clone headers for mutation
             let mut response_headers = fault_headers.clone();
@@ -653,29 +1104,10 @@ async fn handle_yaml_rule(
 
             apply_latency(duration_ms).await;
 
-            // Collect body for retry capability
-            let body_bytes = match req.collect().await {
-                Ok(collected) => collected.to_bytes(),
-                Err(e) => {
-                    error!("Failed to collect request body: {}", e);
-                    let mut response = error_response(500, "Failed to read request body");
-                    response.set_header(&X_RIFT_FAULT, &VALUE_LATENCY);
-                    response.set_header_value(&X_RIFT_RULE_ID, &rule_id);
-                    return RuleHandlingResult::Response(response.into_boxed());
-                }
-            };
-
             // Forward request with latency header
             let upstream_url = selected_upstream_url.unwrap_or(ctx.upstream_uri);
-            let mut response = forward_request_with_body(
-                ctx.http_client,
-                method.clone(),
-                uri.clone(),
-                headers.clone(),
-                body_bytes,
-                upstream_url,
-            )
-            .await;
+            let mut response =
+                forward_request_streaming(http_client, req, upstream_url, upstream_policy).await;
             let status = response.status().as_u16();
             let total_duration = start_time.elapsed().as_secs_f64() * 1000.0;
             metrics::record_proxy_duration(method.as_str(), total_duration, "latency");
@@ -684,8 +1116,97 @@ async fn handle_yaml_rule(
             response.set_header(&X_RIFT_FAULT, &VALUE_LATENCY);
             response.set_header_value(&X_RIFT_RULE_ID, &rule_id);
             response.set_header_value(&X_RIFT_LATENCY_MS, &duration_ms.to_string());
+            RuleHandlingResult::Response(response)
+        }
+        FaultDecision::Mock { mock, rule_id } => {
+            info!(
+                "Returning mock response: status={}, rule={}",
+                mock.status, rule_id
+            );
+            metrics::record_fault_injection("mock", &rule_id, "v1");
+            let duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+            metrics::record_proxy_duration(method.as_str(), duration_ms, "mock");
+            metrics::record_request(method.as_str(), mock.status);
+
+            let body_text = body.and_then(|b| b.match_body().text);
+            let mut request_data =
+                RequestData::new(method.as_str(), uri.path(), uri.query(), headers, body_text);
+            request_data.path_params = rule.path_params(uri.path());
+            let generated = render_generated_values(&mock.body, ctx.fault_rng);
+            let rendered = render_mock_body(&generated, &request_data);
+            let mut response =
+                create_error_response(mock.status, rendered, Some(&mock.headers), None).unwrap();
+            response.set_header(&X_RIFT_FAULT, &VALUE_MOCK);
+            response.set_header_value(&X_RIFT_RULE_ID, &rule_id);
             RuleHandlingResult::Response(response.into_boxed())
         }
+        FaultDecision::Drip { drip, rule_id } => {
+            info!(
+                "Injecting drip fault: {} bytes then {}ms stall (finish={}), rule={}",
+                drip.initial_bytes, drip.stall_ms, drip.finish, rule_id
+            );
+            metrics::record_fault_injection("drip", &rule_id, "v1");
+
+            let upstream_url = selected_upstream_url.unwrap_or(ctx.upstream_uri);
+            let mut response =
+                forward_request_streaming(http_client, req, upstream_url, upstream_policy).await;
+            let status = response.status().as_u16();
+            let duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+            metrics::record_proxy_duration(method.as_str(), duration_ms, "drip");
+            metrics::record_request(method.as_str(), status);
+
+            response.set_header(&X_RIFT_FAULT, &VALUE_DRIP);
+            response.set_header_value(&X_RIFT_RULE_ID, &rule_id);
+            RuleHandlingResult::Response(
+                response.map(|body| BoxBody::new(DripBody::new(body, &drip))),
+            )
+        }
+        FaultDecision::Chunked { chunked, rule_id } => {
+            let takeover = req.extensions().get::<ConnectionTakeover>().cloned();
+            let Some(takeover) = takeover.filter(|_| req.version() == hyper::Version::HTTP_11)
+            else {
+                debug!(
+                    "Not injecting chunked fault into a non-HTTP/1.1 response, rule={}",
+                    rule_id
+                );
+                return RuleHandlingResult::NoFault(req);
+            };
+            info!(
+                "Injecting chunked fault: {:?}, rule={}",
+                chunked.mode, rule_id
+            );
+            metrics::record_fault_injection("chunked", &rule_id, "v1");
+
+            let upstream_url = selected_upstream_url.unwrap_or(ctx.upstream_uri);
+            let response =
+                forward_request_streaming(http_client, req, upstream_url, upstream_policy).await;
+            let (mut parts, body) = response.into_parts();
+            let body = match body.collect().await {
+                Ok(collected) => collected.to_bytes(),
+                Err(e) => {
+                    error!("Failed to read upstream response body: {}", e);
+                    return RuleHandlingResult::Response(
+                        error_response(502, "Failed to read upstream response").into_boxed(),
+                    );
+                }
+            };
+            let duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+            metrics::record_proxy_duration(method.as_str(), duration_ms, "chunked");
+            metrics::record_request(method.as_str(), parts.status.as_u16());
+
+            parts.set_header(&X_RIFT_FAULT, &VALUE_CHUNKED);
+            parts.set_header_value(&X_RIFT_RULE_ID, &rule_id);
+            takeover.take_over(encode_response(&parts, &body, chunked.mode));
+
+            // Never sent: the connection writes the encoded response instead,
+            // then closes because of this header
+            let mut response = Response::new(full_body(Bytes::new()));
+            response.headers_mut().insert(
+                hyper::header::CONNECTION,
+                hyper::header::HeaderValue::from_static("close"),
+            );
+            RuleHandlingResult::Response(response)
+        }
         FaultDecision::None => {
             debug!("No fault injected for matched rule: {}", rule.id);
             RuleHandlingResult::NoFault(req)
@@ -693,23 +1214,59 @@ async fn handle_yaml_rule(
     }
 }
 
+/// Upstream chosen by the router for a request
+struct SelectedUpstream<'a> {
+    url: String,
+    name: String,
+    /// Set if the route rewrites the request path
+    rewritten_path: Option<String>,
+    forward_headers: &'a ForwardHeaders,
+    retry: Option<&'a RetryConfig>,
+    /// Set if the route overrides the request timeout
+    request_timeout: Option<std::time::Duration>,
+}
+
 /// Select upstream for the request based on routing rules.
-/// Returns the upstream URL and name if matched, None for sidecar mode.
-fn select_upstream<B>(
-    router: Option<&Router>,
-    upstreams: &[crate::config::Upstream],
+/// Returns None for sidecar mode or when no route matches.
+fn select_upstream<'a, B>(
+    router: Option<&'a Router>,
+    upstreams: &'a [crate::config::Upstream],
     req: &Request<B>,
-) -> Option<(String, String)> {
+    client_ip: Option<std::net::IpAddr>,
+) -> Option<SelectedUpstream<'a>> {
     // If no router configured, use sidecar mode (return None)
     let router = router?;
 
     // Match request to an upstream name
-    let upstream_name = router.match_request(req)?;
+    let target = router.match_route(req, client_ip)?;
+    metrics::record_route_match(target.route);
+    let upstream_name = target.upstream;
 
     // Find upstream by name
     let upstream = upstreams.iter().find(|u| u.name == upstream_name)?;
     debug!("Routed to upstream: {} ({})", upstream_name, upstream.url);
-    Some((upstream.url.clone(), upstream_name.to_string()))
+    let rewritten_path = target
+        .rewrite
+        .and_then(|rewrite| rewrite.apply(req.uri().path()));
+    Some(SelectedUpstream {
+        url: upstream.url.clone(),
+        name: upstream_name.to_string(),
+        rewritten_path,
+        forward_headers: target.forward_headers,
+        retry: upstream.retry.as_ref(),
+        request_timeout: target.request_timeout,
+    })
+}
+
+/// Replace the path of a URI, keeping its query string
+fn rewrite_uri_path(uri: &hyper::Uri, path: &str) -> Option<hyper::Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    hyper::Uri::from_parts(parts).ok()
 }
 
 /// Check if a rule applies to the given upstream.
@@ -735,6 +1292,16 @@ pub fn rule_applies_to_upstream(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_rewrite_uri_path_keeps_query() {
+        let uri: hyper::Uri = "/api/users?page=2".parse().unwrap();
+        let rewritten = rewrite_uri_path(&uri, "/users").unwrap();
+        assert_eq!(rewritten, "/users?page=2");
+
+        let uri: hyper::Uri = "/api".parse().unwrap();
+        assert_eq!(rewrite_uri_path(&uri, "/").unwrap(), "/");
+    }
+
     #[test]
     fn test_rule_applies_to_upstream_no_filter() {
         // Rule with no upstream filter should apply to all upstreams
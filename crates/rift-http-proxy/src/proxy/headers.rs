@@ -20,6 +20,7 @@ pub static X_RIFT_TCP_FAULT: HeaderName = HeaderName::from_static("x-rift-tcp-fa
 pub static X_RIFT_PROXIED: HeaderName = HeaderName::from_static("x-rift-proxied");
 pub static X_RIFT_RECORDED: HeaderName = HeaderName::from_static("x-rift-recorded");
 pub static X_RIFT_REPLAYED: HeaderName = HeaderName::from_static("x-rift-replayed");
+pub static X_RIFT_CACHED: HeaderName = HeaderName::from_static("x-rift-cached");
 pub static X_RIFT_BEHAVIOR_WAIT: HeaderName = HeaderName::from_static("x-rift-behavior-wait");
 pub static X_RIFT_BEHAVIOR_COPY: HeaderName = HeaderName::from_static("x-rift-behavior-copy");
 pub static X_RIFT_BEHAVIOR_LOOKUP: HeaderName = HeaderName::from_static("x-rift-behavior-lookup");
@@ -32,6 +33,10 @@ pub static VALUE_TRUE: HeaderValue = HeaderValue::from_static("true");
 pub static VALUE_ERROR: HeaderValue = HeaderValue::from_static("error");
 pub static VALUE_LATENCY: HeaderValue = HeaderValue::from_static("latency");
 pub static VALUE_TCP: HeaderValue = HeaderValue::from_static("tcp");
+pub static VALUE_DRIP: HeaderValue = HeaderValue::from_static("drip");
+pub static VALUE_CHUNKED: HeaderValue = HeaderValue::from_static("chunked");
+pub static VALUE_MOCK: HeaderValue = HeaderValue::from_static("mock");
+pub static VALUE_RATE_LIMIT: HeaderValue = HeaderValue::from_static("rate_limit");
 
 /// Extension trait for inserting Rift headers into responses.
 pub trait RiftHeadersExt {
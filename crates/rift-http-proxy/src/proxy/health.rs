@@ -0,0 +1,157 @@
+//! Active health checks for named upstreams.
+//!
+//! Each upstream with a `health_check` is probed with a GET to its health
+//! path every `interval_seconds`; a 2xx answer within `timeout_seconds` is a
+//! success. After `unhealthy_threshold` consecutive failures the upstream is
+//! marked unhealthy, and skipped by the upstream groups it belongs to, until
+//! `healthy_threshold` consecutive probes succeed.
+
+use super::client::HttpClient;
+use super::forwarding::full_body;
+use crate::config::{HealthCheckConfig, Upstream};
+use hyper::body::Bytes;
+use hyper::{Request, Uri};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// Consecutive probe results for one upstream
+struct HealthState {
+    healthy: bool,
+    successes: u32,
+    failures: u32,
+}
+
+impl HealthState {
+    /// Upstreams start out healthy, as before their first probe
+    fn new() -> Self {
+        Self {
+            healthy: true,
+            successes: 0,
+            failures: 0,
+        }
+    }
+
+    /// Record a probe result, returning the new health if it changed
+    fn record(&mut self, success: bool, config: &HealthCheckConfig) -> Option<bool> {
+        if success {
+            self.successes += 1;
+            self.failures = 0;
+        } else {
+            self.failures += 1;
+            self.successes = 0;
+        }
+        let healthy = if self.healthy {
+            self.failures < config.unhealthy_threshold.max(1)
+        } else {
+            self.successes >= config.healthy_threshold.max(1)
+        };
+        if healthy == self.healthy {
+            return None;
+        }
+        self.healthy = healthy;
+        Some(healthy)
+    }
+}
+
+/// The URI probed for `upstream`
+fn probe_uri(upstream: &Upstream, config: &HealthCheckConfig) -> Option<Uri> {
+    let path = config.path.trim_start_matches('/');
+    format!("{}/{}", upstream.url.trim_end_matches('/'), path)
+        .parse()
+        .ok()
+}
+
+/// Whether one probe of `uri` succeeds
+async fn probe(client: &HttpClient, uri: &Uri, timeout: Duration) -> bool {
+    let request = match Request::get(uri.clone()).body(full_body(Bytes::new())) {
+        Ok(request) => request,
+        Err(e) => {
+            error!("Failed to build health check request for {}: {}", uri, e);
+            return false;
+        }
+    };
+    match tokio::time::timeout(timeout, client.request(request)).await {
+        Ok(Ok(response)) => {
+            debug!("Health check {} returned {}", uri, response.status());
+            response.status().is_success()
+        }
+        Ok(Err(e)) => {
+            debug!("Health check {} failed: {}", uri, e);
+            false
+        }
+        Err(_) => {
+            debug!("Health check {} timed out after {:?}", uri, timeout);
+            false
+        }
+    }
+}
+
+/// Probe `upstream` until the task running this is dropped, calling
+/// `set_health` each time its health changes
+pub(super) async fn run_health_checks(
+    upstream: Upstream,
+    config: HealthCheckConfig,
+    client: HttpClient,
+    set_health: impl Fn(&str, bool),
+) {
+    let Some(uri) = probe_uri(&upstream, &config) else {
+        error!(
+            "Upstream '{}': invalid health check address '{}{}'",
+            upstream.name, upstream.url, config.path
+        );
+        return;
+    };
+    let timeout = Duration::from_secs(config.timeout_seconds.max(1));
+    let mut interval = tokio::time::interval(Duration::from_secs(config.interval_seconds.max(1)));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut state = HealthState::new();
+    loop {
+        interval.tick().await;
+        let success = probe(&client, &uri, timeout).await;
+        match state.record(success, &config) {
+            Some(true) => info!("Upstream '{}' is healthy again", upstream.name),
+            Some(false) => warn!("Upstream '{}' failed health checks", upstream.name),
+            None => continue,
+        }
+        set_health(&upstream.name, state.healthy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_changes_after_consecutive_results() {
+        let config = HealthCheckConfig {
+            unhealthy_threshold: 2,
+            healthy_threshold: 2,
+            ..Default::default()
+        };
+        let mut state = HealthState::new();
+
+        // A single failure, interrupted by a success, doesn't count
+        assert_eq!(state.record(false, &config), None);
+        assert_eq!(state.record(true, &config), None);
+        assert_eq!(state.record(false, &config), None);
+        assert_eq!(state.record(false, &config), Some(false));
+        assert_eq!(state.record(false, &config), None);
+
+        assert_eq!(state.record(true, &config), None);
+        assert_eq!(state.record(true, &config), Some(true));
+    }
+
+    #[test]
+    fn test_probe_uri_joins_url_and_path() {
+        let upstream: Upstream =
+            serde_yaml::from_str("name: api\nurl: \"http://127.0.0.1:8080/\"").unwrap();
+        let config = HealthCheckConfig {
+            path: "ready".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            probe_uri(&upstream, &config).unwrap(),
+            "http://127.0.0.1:8080/ready"
+        );
+    }
+}
@@ -6,26 +6,47 @@
 //! - Mountebank-compatible response behaviors (wait, copy, lookup, decorate)
 //! - Request recording and replay (proxyOnce, proxyAlways modes)
 //! - Multi-upstream routing
+//! - Admin endpoints for saving and loading recordings on demand
 //! - TLS/HTTPS support
+//! - WebSocket pass-through
 //!
 //! # Module Structure
 //!
 //! - `server` - ProxyServer struct and main run loop
+//! - `body` - Request body buffering for body matchers
+//! - `drip` - Response body that stalls part-way (drip fault)
+//! - `engine` - Rule evaluation without a server, for tests and dry runs
+//! - `admin` - Built-in admin endpoints, on the proxy and the metrics port
 //! - `handler` - Request handling and fault injection logic
 //! - `forwarding` - Request forwarding to upstream servers
 //! - `client` - HTTP client creation and configuration
+//! - `health` - Active health checks taking upstreams out of groups
+//! - `cors` - CORS preflight responses and `Access-Control-*` headers
 //! - `tls` - TLS utilities and certificate handling
 //! - `network` - Network listener utilities (SO_REUSEPORT)
+//! - `request_id` - Request ids forwarded upstream and echoed to clients
+//! - `response_cache` - Cache of upstream responses to GET requests
 //! - `response_ext` - Response extension traits for body transformations
+//! - `websocket` - WebSocket upgrade pass-through
 
+mod admin;
+mod body;
+mod chunked;
 mod client;
+mod cors;
+mod drip;
+mod engine;
 mod forwarding;
 mod handler;
 mod headers;
+mod health;
 mod network;
+mod request_id;
+mod response_cache;
 mod response_ext;
 mod server;
 mod tls;
+mod websocket;
 
 #[cfg(test)]
 mod tests;
@@ -33,8 +54,12 @@ mod tests;
 // Re-export public API types
 // These are used by main.rs and may be used by external consumers
 #[allow(unused_imports)]
+pub use engine::{evaluate_rules, MatchedFault, RequestContext};
+#[allow(unused_imports)]
 pub use forwarding::error_response;
 #[allow(unused_imports)]
 pub use handler::rule_applies_to_upstream;
 #[allow(unused_imports)]
 pub use server::ProxyServer;
+#[allow(unused_imports)]
+pub use tls::create_client_tls_config;
@@ -0,0 +1,57 @@
+//! Request ids: taken from the request or generated, then forwarded
+//! upstream and echoed in the response.
+
+use crate::config::RequestIdConfig;
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::HeaderMap;
+
+/// Return the request's id, adding a generated one to `headers` if the
+/// request has none. Returns None if the configured header name is invalid.
+pub fn ensure_request_id(
+    config: &RequestIdConfig,
+    headers: &mut HeaderMap,
+) -> Option<(HeaderName, HeaderValue)> {
+    let name = HeaderName::try_from(config.header.as_str()).ok()?;
+    if let Some(id) = headers.get(&name).filter(|id| !id.is_empty()) {
+        return Some((name, id.clone()));
+    }
+    let id = HeaderValue::try_from(uuid::Uuid::new_v4().to_string()).ok()?;
+    headers.insert(name.clone(), id.clone());
+    Some((name, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incoming_id_is_kept() {
+        let config = RequestIdConfig {
+            header: "X-Correlation-Id".to_string(),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("x-correlation-id", HeaderValue::from_static("abc-123"));
+
+        let (name, id) = ensure_request_id(&config, &mut headers).unwrap();
+        assert_eq!(name, "x-correlation-id");
+        assert_eq!(id, "abc-123");
+        assert_eq!(headers.len(), 1);
+    }
+
+    #[test]
+    fn test_missing_or_empty_id_is_generated() {
+        let config = RequestIdConfig::default();
+        for mut headers in [HeaderMap::new(), {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-request-id", HeaderValue::from_static(""));
+            headers
+        }] {
+            let (_, id) = ensure_request_id(&config, &mut headers).unwrap();
+            assert!(
+                uuid::Uuid::parse_str(id.to_str().unwrap()).is_ok(),
+                "{id:?}"
+            );
+            assert_eq!(headers["x-request-id"], id);
+        }
+    }
+}
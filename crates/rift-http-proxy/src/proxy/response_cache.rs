@@ -0,0 +1,544 @@
+//! Cache of upstream responses to GET requests forwarded without a fault.
+//!
+//! Entries expire after the configured TTL and the least recently used one
+//! is evicted when the cache is full, as in the script decision cache.
+//! Expired entries are dropped when they are next looked up or evicted.
+
+use super::forwarding::error_response;
+use super::headers::{RiftHeadersExt, VALUE_TRUE, X_RIFT_CACHED};
+use super::response_ext::ResponseExt;
+use super::websocket::is_upgrade_request;
+use crate::config::ResponseCacheConfig;
+use crate::extensions::metrics;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::header::{
+    HeaderName, HeaderValue, AUTHORIZATION, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, COOKIE,
+    SET_COOKIE, VARY,
+};
+use hyper::http::response::Parts;
+use hyper::{HeaderMap, Method, Response, StatusCode, Uri};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{error, trace};
+
+/// What a cached response is keyed by
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResponseCacheKey {
+    method: Method,
+    upstream: String,
+    /// Path and query
+    target: String,
+    /// Values of the vary headers, in configured order
+    vary: Vec<Option<HeaderValue>>,
+    /// The request's credentials, so one client's response never answers
+    /// another
+    authorization: Vec<HeaderValue>,
+    cookie: Vec<HeaderValue>,
+}
+
+struct CacheEntry {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    created_at: Instant,
+    last_accessed: Instant,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<ResponseCacheKey, CacheEntry>,
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheState {
+    fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+pub struct ResponseCache {
+    max_size: usize,
+    ttl: Duration,
+    max_body_bytes: u64,
+    vary_headers: Vec<HeaderName>,
+    state: Mutex<CacheState>,
+}
+
+impl ResponseCache {
+    pub fn new(config: &ResponseCacheConfig) -> Self {
+        Self {
+            max_size: config.max_size,
+            ttl: Duration::from_secs(config.ttl_seconds),
+            max_body_bytes: config.max_body_bytes,
+            // Names are checked by config validation
+            vary_headers: config
+                .vary_headers
+                .iter()
+                .filter_map(|name| HeaderName::try_from(name.as_str()).ok())
+                .collect(),
+            state: Mutex::default(),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The key for a request to `upstream`, or None if its response can't
+    /// be cached
+    pub fn key(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        upstream: &str,
+    ) -> Option<ResponseCacheKey> {
+        // A cached response must never answer a protocol upgrade
+        if method != Method::GET || is_upgrade_request(headers) {
+            return None;
+        }
+        Some(ResponseCacheKey {
+            method: method.clone(),
+            upstream: upstream.to_string(),
+            target: uri
+                .path_and_query()
+                .map_or_else(|| uri.path().to_string(), ToString::to_string),
+            vary: self
+                .vary_headers
+                .iter()
+                .map(|name| headers.get(name).cloned())
+                .collect(),
+            authorization: headers.get_all(AUTHORIZATION).iter().cloned().collect(),
+            cookie: headers.get_all(COOKIE).iter().cloned().collect(),
+        })
+    }
+
+    /// The cached response for `key`, marked with `X-Rift-Cached`
+    pub fn get(&self, key: &ResponseCacheKey) -> Option<Response<BoxBody<Bytes, hyper::Error>>> {
+        let mut state = self.state.lock();
+        let ttl = self.ttl;
+        let now = Instant::now();
+        let response = match state.entries.get_mut(key) {
+            Some(entry) if now.duration_since(entry.created_at) < ttl => {
+                entry.last_accessed = now;
+                let mut response = Response::new(Full::new(entry.body.clone()));
+                *response.status_mut() = entry.status;
+                *response.headers_mut() = entry.headers.clone();
+                response.set_header(&X_RIFT_CACHED, &VALUE_TRUE);
+                Some(response.into_boxed())
+            }
+            Some(_) => {
+                trace!("Response cache entry expired for {:?}", key);
+                state.entries.remove(key);
+                None
+            }
+            None => None,
+        };
+        if response.is_some() {
+            state.hits += 1;
+        } else {
+            state.misses += 1;
+        }
+        metrics::record_response_cache_lookup(
+            response.is_some(),
+            state.hit_rate(),
+            state.entries.len(),
+        );
+        response
+    }
+
+    /// Cache `response` under `key` if it may be cached, returning it for
+    /// sending on. A cacheable response's body is buffered first; anything
+    /// else, including bodies of unknown or excessive length, streams
+    /// through untouched.
+    pub async fn store(
+        &self,
+        key: ResponseCacheKey,
+        response: Response<BoxBody<Bytes, hyper::Error>>,
+    ) -> Response<BoxBody<Bytes, hyper::Error>> {
+        if !self.is_cacheable(&key, &response) {
+            return response;
+        }
+        let (parts, body) = response.into_parts();
+        let body = match body.collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(e) => {
+                error!("Failed to collect upstream response body: {}", e);
+                return error_response(502, "Failed to read upstream response").into_boxed();
+            }
+        };
+        self.insert(key, &parts, body.clone());
+        Response::from_parts(parts, Full::new(body)).into_boxed()
+    }
+
+    fn insert(&self, key: ResponseCacheKey, parts: &Parts, body: Bytes) {
+        let mut state = self.state.lock();
+        if state.entries.len() >= self.max_size && !state.entries.contains_key(&key) {
+            let ttl = self.ttl;
+            // Expired entries go first, then the least recently used
+            let evict = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| (entry.created_at.elapsed() < ttl, entry.last_accessed))
+                .map(|(key, _)| key.clone());
+            if let Some(evict) = evict {
+                state.entries.remove(&evict);
+            }
+        }
+        let now = Instant::now();
+        state.entries.insert(
+            key,
+            CacheEntry {
+                status: parts.status,
+                headers: parts.headers.clone(),
+                body,
+                created_at: now,
+                last_accessed: now,
+            },
+        );
+    }
+
+    /// Whether a response to the request `key` was made for may be cached:
+    /// storable and not marked `no-store` or `private`. A response to a
+    /// request with `Authorization` must also be marked `public`,
+    /// `s-maxage` or `must-revalidate`, and a response with `Vary` may only
+    /// vary on headers that are part of the key.
+    fn is_cacheable<B>(&self, key: &ResponseCacheKey, response: &Response<B>) -> bool {
+        let headers = response.headers();
+        let directives: Vec<&str> = headers
+            .get_all(CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|directive| directive.split('=').next())
+            .map(str::trim)
+            .collect();
+        let has_directive = |names: &[&str]| {
+            directives.iter().any(|directive| {
+                names
+                    .iter()
+                    .any(|name| directive.eq_ignore_ascii_case(name))
+            })
+        };
+        let shared = has_directive(&["public", "s-maxage", "must-revalidate"]);
+        is_storable(response, self.max_body_bytes)
+            && !has_directive(&["no-store", "private"])
+            && (key.authorization.is_empty() || shared)
+            && self.varies_on_key(headers)
+    }
+
+    /// Whether every header a response's `Vary` names is part of the key
+    fn varies_on_key(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get_all(VARY)
+            .iter()
+            .map(|value| value.to_str().unwrap_or("*"))
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .all(|name| match HeaderName::try_from(name) {
+                Ok(name) => {
+                    name == AUTHORIZATION || name == COOKIE || self.vary_headers.contains(&name)
+                }
+                // Including `*`: the response varies on more than the request
+                Err(_) => false,
+            })
+    }
+}
+
+/// Whether a response can be buffered and replayed: successful, complete,
+/// of a known length no larger than `max_body_bytes`, not setting cookies
+/// and not an event stream
+fn is_storable<B>(response: &Response<B>, max_body_bytes: u64) -> bool {
+    let headers = response.headers();
+    let event_stream = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(';')
+                .next()
+                .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("text/event-stream"))
+        });
+    let fits = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .is_some_and(|length| length <= max_body_bytes);
+    response.status().is_success()
+        && response.status() != StatusCode::PARTIAL_CONTENT
+        && fits
+        && !event_stream
+        && !headers.contains_key(SET_COOKIE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_size: usize) -> ResponseCache {
+        ResponseCache::new(&ResponseCacheConfig {
+            max_size,
+            vary_headers: vec!["Accept".to_string()],
+            ..Default::default()
+        })
+    }
+
+    fn key(cache: &ResponseCache, target: &str, accept: Option<&'static str>) -> ResponseCacheKey {
+        let mut headers = HeaderMap::new();
+        if let Some(accept) = accept {
+            headers.insert("accept", HeaderValue::from_static(accept));
+        }
+        cache
+            .key(
+                &Method::GET,
+                &target.parse().unwrap(),
+                &headers,
+                "http://upstream",
+            )
+            .unwrap()
+    }
+
+    fn upstream_response(
+        body: &'static str,
+        cache_control: Option<&'static str>,
+    ) -> Response<BoxBody<Bytes, hyper::Error>> {
+        let mut response = Response::new(Full::new(Bytes::from_static(body.as_bytes())));
+        response
+            .headers_mut()
+            .insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+        if let Some(cache_control) = cache_control {
+            response
+                .headers_mut()
+                .insert(CACHE_CONTROL, HeaderValue::from_static(cache_control));
+        }
+        response.into_boxed()
+    }
+
+    async fn body(response: Response<BoxBody<Bytes, hyper::Error>>) -> Bytes {
+        response.into_body().collect().await.unwrap().to_bytes()
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit() {
+        let cache = cache(10);
+        let first = key(&cache, "/items?page=1", Some("text/plain"));
+        assert!(cache.get(&first).is_none());
+        let response = cache
+            .store(first.clone(), upstream_response("items", None))
+            .await;
+        assert_eq!(body(response).await, "items");
+
+        let hit = cache.get(&first).unwrap();
+        assert_eq!(hit.headers()[&X_RIFT_CACHED], "true");
+        assert_eq!(body(hit).await, "items");
+
+        // Query and vary headers are part of the key
+        assert!(cache
+            .get(&key(&cache, "/items?page=2", Some("text/plain")))
+            .is_none());
+        assert!(cache.get(&key(&cache, "/items?page=1", None)).is_none());
+        assert!(cache
+            .key(
+                &Method::POST,
+                &"/items".parse().unwrap(),
+                &HeaderMap::new(),
+                "http://upstream"
+            )
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_no_store_and_errors_are_not_cached() {
+        let cache = cache(10);
+        for cache_control in ["no-store", "private, No-Store", "Private"] {
+            let key = key(&cache, "/secret", None);
+            cache
+                .store(
+                    key.clone(),
+                    upstream_response("secret", Some(cache_control)),
+                )
+                .await;
+            assert!(cache.get(&key).is_none(), "{cache_control}");
+        }
+
+        let key = key(&cache, "/broken", None);
+        let mut response = upstream_response("down", None);
+        *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+        cache.store(key.clone(), response).await;
+        assert!(cache.get(&key).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_streams_cookies_and_large_bodies_are_not_cached() {
+        let cache = ResponseCache::new(&ResponseCacheConfig {
+            max_body_bytes: 8,
+            ..Default::default()
+        });
+
+        let large = key(&cache, "/large", None);
+        cache
+            .store(large.clone(), upstream_response("too large to cache", None))
+            .await;
+        assert!(cache.get(&large).is_none());
+
+        // No Content-Length: the body may never end, so it isn't buffered
+        let unknown = key(&cache, "/unknown", None);
+        let mut response = upstream_response("items", None);
+        response.headers_mut().remove(CONTENT_LENGTH);
+        let response = cache.store(unknown.clone(), response).await;
+        assert_eq!(body(response).await, "items");
+        assert!(cache.get(&unknown).is_none());
+
+        let events = key(&cache, "/events", None);
+        let mut response = upstream_response("data: 1", None);
+        response.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/event-stream; charset=utf-8"),
+        );
+        cache.store(events.clone(), response).await;
+        assert!(cache.get(&events).is_none());
+
+        let login = key(&cache, "/login", None);
+        let mut response = upstream_response("ok", None);
+        response
+            .headers_mut()
+            .insert(SET_COOKIE, HeaderValue::from_static("session=1"));
+        cache.store(login.clone(), response).await;
+        assert!(cache.get(&login).is_none());
+
+        let small = key(&cache, "/small", None);
+        cache
+            .store(small.clone(), upstream_response("small", None))
+            .await;
+        assert!(cache.get(&small).is_some());
+    }
+
+    fn authorized_key(cache: &ResponseCache, authorization: &'static str) -> ResponseCacheKey {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static(authorization));
+        cache
+            .key(
+                &Method::GET,
+                &"/account".parse().unwrap(),
+                &headers,
+                "http://upstream",
+            )
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_authorized_responses_are_not_shared() {
+        let cache = cache(10);
+        let alice = authorized_key(&cache, "Bearer alice");
+        let bob = authorized_key(&cache, "Bearer bob");
+
+        // Without a directive allowing it, an authorized response isn't cached
+        cache
+            .store(
+                alice.clone(),
+                upstream_response("alice", Some("max-age=60")),
+            )
+            .await;
+        assert!(cache.get(&alice).is_none());
+
+        cache
+            .store(alice.clone(), upstream_response("alice", Some("public")))
+            .await;
+        assert_eq!(body(cache.get(&alice).unwrap()).await, "alice");
+        assert!(cache.get(&bob).is_none());
+        assert!(cache.get(&key(&cache, "/account", None)).is_none());
+
+        for cache_control in ["s-maxage=60", "Must-Revalidate"] {
+            cache
+                .store(bob.clone(), upstream_response("bob", Some(cache_control)))
+                .await;
+            assert_eq!(
+                body(cache.get(&bob).unwrap()).await,
+                "bob",
+                "{cache_control}"
+            );
+            cache.state.lock().entries.remove(&bob);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_responses_varying_on_unkeyed_headers_are_not_cached() {
+        let cache = cache(10);
+        for (vary, cached) in [
+            ("Accept", true),
+            ("accept, Authorization", true),
+            ("Accept-Encoding", false),
+            ("Accept, Accept-Language", false),
+            ("*", false),
+        ] {
+            let key = key(&cache, "/items", Some("text/plain"));
+            let mut response = upstream_response("items", None);
+            response
+                .headers_mut()
+                .insert(VARY, HeaderValue::from_static(vary));
+            cache.store(key.clone(), response).await;
+            assert_eq!(cache.get(&key).is_some(), cached, "{vary}");
+            cache.state.lock().entries.clear();
+        }
+    }
+
+    #[test]
+    fn test_upgrade_requests_have_no_key() {
+        let cache = cache(10);
+        let mut headers = HeaderMap::new();
+        headers.insert("connection", HeaderValue::from_static("Upgrade"));
+        headers.insert("upgrade", HeaderValue::from_static("websocket"));
+        assert!(cache
+            .key(
+                &Method::GET,
+                &"/socket".parse().unwrap(),
+                &headers,
+                "http://upstream"
+            )
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_entries_expire_after_ttl() {
+        let cache = cache(10).with_ttl(Duration::from_millis(50));
+        let key = key(&cache, "/items", None);
+        cache
+            .store(key.clone(), upstream_response("items", None))
+            .await;
+        assert!(cache.get(&key).is_some());
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(cache.get(&key).is_none());
+        assert!(cache.state.lock().entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_entry_is_evicted() {
+        let cache = cache(2);
+        let (a, b, c) = (
+            key(&cache, "/a", None),
+            key(&cache, "/b", None),
+            key(&cache, "/c", None),
+        );
+        cache.store(a.clone(), upstream_response("a", None)).await;
+        cache.store(b.clone(), upstream_response("b", None)).await;
+        assert!(cache.get(&a).is_some());
+        cache.store(c.clone(), upstream_response("c", None)).await;
+
+        assert!(cache.get(&a).is_some());
+        assert!(cache.get(&b).is_none());
+        assert!(cache.get(&c).is_some());
+    }
+}
@@ -3,49 +3,242 @@
 //! This module contains the ProxyServer struct which holds all state,
 //! and the main run loop that accepts connections and handles requests.
 
-use super::client::{create_http_client, should_skip_tls_verify, HttpClient};
-use super::handler::{handle_request, RequestHandlerContext};
+use super::admin::{
+    handle_admin_port_request, handle_admin_request, handle_match_request, ADMIN_PORT_MATCH_PATH,
+};
+use super::chunked::TakeoverIo;
+use super::client::UpstreamClients;
+use super::cors::{apply_cors_headers, preflight_response};
+use super::forwarding::{apply_response_headers, error_response};
+use super::handler::{handle_request, CompiledScriptRule, RequestHandlerContext};
+use super::health::run_health_checks;
 use super::network::create_reusable_listener;
-use super::tls::create_tls_acceptor;
+use super::response_cache::ResponseCache;
+use super::response_ext::ResponseExt;
+use super::tls::{create_tls_acceptor, ALPN_H2};
 use crate::behaviors::{CsvCache, ResponseCycler};
 use crate::config::{Config, Protocol as RiftProtocol, Upstream};
+use crate::extensions::fault::FaultRng;
 use crate::extensions::flow_state::{create_flow_store, FlowStore};
-use crate::extensions::matcher::CompiledRule;
+use crate::extensions::matcher::{CompiledRule, RulePathSet};
+use crate::extensions::metrics;
 use crate::extensions::routing::Router;
-use crate::recording::{ProxyMode, RecordingStore};
+use crate::recording::{backend_from_config, ProxyMode, RecordingStore};
 #[cfg(feature = "javascript")]
 use crate::scripting::compile_js_to_bytecode;
-#[cfg(feature = "lua")]
-use crate::scripting::compile_to_bytecode;
 use crate::scripting::RhaiEngine;
+#[cfg(feature = "lua")]
+use crate::scripting::{compile_to_bytecode, defines_response_hook, takes_context};
 use crate::scripting::{
-    CompiledScript, DecisionCache, DecisionCacheConfig, ScriptPool, ScriptPoolConfig,
+    CacheKeyField, CompiledScript, DecisionCache, DecisionCacheConfig, ScriptPool, ScriptPoolConfig,
 };
-#[cfg(any(feature = "lua", feature = "javascript"))]
 use anyhow::Context;
 use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, LengthLimitError, Limited};
 use hyper::body::Bytes;
-use hyper::server::conn::http1;
+use hyper::server::conn::{http1, http2};
 use hyper::service::service_fn;
-use hyper_util::rt::TokioIo;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use parking_lot::RwLock;
 use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tracing::{error, info};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+use tracing::{error, info, warn};
+
+/// HTTP versions a client connection is served with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum HttpVersions {
+    Http1,
+    /// Negotiated through ALPN
+    Http2,
+    /// HTTP/1.1, or HTTP/2 if the client opens with the HTTP/2 preface
+    Http1OrH2c,
+}
+
+/// Give an HTTP/2 request the `Host` header it would have over HTTP/1.1.
+///
+/// HTTP/2 sends the host as the `:authority` pseudo-header, which hyper
+/// puts in the URI; routing, rule matching and `X-Forwarded-Host` all read
+/// `Host`.
+fn host_from_authority<B>(req: &mut hyper::Request<B>) {
+    if req.headers().contains_key(hyper::header::HOST) {
+        return;
+    }
+    let host = req
+        .uri()
+        .authority()
+        .and_then(|authority| hyper::header::HeaderValue::from_str(authority.as_str()).ok());
+    if let Some(host) = host {
+        req.headers_mut().insert(hyper::header::HOST, host);
+    }
+}
+
+/// Configuration and compiled fault rules, swapped as a unit on reload.
+struct LiveConfig {
+    config: Arc<Config>,
+    compiled_rules: Vec<CompiledRule>,
+    rule_paths: Option<RulePathSet>, // Path prefilter over compiled_rules
+    fault_rng: Arc<FaultRng>,        // Re-seeded from `seed` on reload
+    response_cache: Option<ResponseCache>, // Emptied on reload
+}
+
+impl LiveConfig {
+    fn compile(config: Config) -> Result<Self, anyhow::Error> {
+        config.regex.apply();
+        let mut compiled_rules = Vec::new();
+
+        for rule in &config.rules {
+            // Errors name the rule and field
+            compiled_rules.push(CompiledRule::compile(rule.clone())?);
+        }
+        metrics::init_match_counters(config.rules.iter().map(|r| r.id.as_str()), []);
+
+        // Falls back to a linear scan if the combined set can't be built
+        let rule_paths = match RulePathSet::new(&compiled_rules) {
+            Ok(paths) => Some(paths),
+            Err(e) => {
+                warn!("Rule path prefilter disabled: {}", e);
+                None
+            }
+        };
+
+        Ok(Self {
+            fault_rng: Arc::new(FaultRng::new(config.seed)),
+            response_cache: config.response_cache.as_ref().map(ResponseCache::new),
+            config: Arc::new(config),
+            compiled_rules,
+            rule_paths,
+        })
+    }
+}
+
+/// Router for multi-upstream mode, if routes are configured
+fn build_router(config: &Config) -> Result<Option<Router>, anyhow::Error> {
+    if config.routing.is_empty() {
+        return Ok(None);
+    }
+    let router = Router::new(config.routing.clone())
+        .and_then(|r| r.with_upstream_groups(&config.upstream_groups, &config.upstreams))
+        .map_err(|e| anyhow::anyhow!("Failed to create router: {e}"))?;
+    Ok(Some(router))
+}
+
+/// Request matcher for a script rule
+fn compile_script_matcher(
+    script_rule: &crate::config::ScriptRule,
+) -> Result<CompiledRule, anyhow::Error> {
+    CompiledRule::compile(script_rule.match_rule())
+}
+
+/// Restore the sections of `new` that are fixed at startup from `current`,
+/// returning the names of those `new` tried to change.
+///
+/// Upstreams, routes, listeners and the script engine are built once in
+/// `ProxyServer::new`, so a reload must not swap in config the running
+/// server doesn't reflect.
+fn keep_fixed_sections(current: &Config, new: &mut Config) -> Vec<&'static str> {
+    fn keep<T: serde::Serialize + Clone>(
+        section: &'static str,
+        current: &T,
+        new: &mut T,
+        changed: &mut Vec<&'static str>,
+    ) {
+        if serde_json::to_value(current).ok() != serde_json::to_value(&*new).ok() {
+            changed.push(section);
+            *new = current.clone();
+        }
+    }
+
+    let mut changed = Vec::new();
+    keep("listen", &current.listen, &mut new.listen, &mut changed);
+    keep("metrics", &current.metrics, &mut new.metrics, &mut changed);
+    keep(
+        "upstream",
+        &current.upstream,
+        &mut new.upstream,
+        &mut changed,
+    );
+    keep(
+        "upstreams",
+        &current.upstreams,
+        &mut new.upstreams,
+        &mut changed,
+    );
+    keep(
+        "upstream_groups",
+        &current.upstream_groups,
+        &mut new.upstream_groups,
+        &mut changed,
+    );
+    keep("routing", &current.routing, &mut new.routing, &mut changed);
+    keep(
+        "script_engine",
+        &current.script_engine,
+        &mut new.script_engine,
+        &mut changed,
+    );
+    keep(
+        "flow_state",
+        &current.flow_state,
+        &mut new.flow_state,
+        &mut changed,
+    );
+    keep(
+        "script_rules",
+        &current.script_rules,
+        &mut new.script_rules,
+        &mut changed,
+    );
+    keep(
+        "connection_pool",
+        &current.connection_pool,
+        &mut new.connection_pool,
+        &mut changed,
+    );
+    keep(
+        "script_pool",
+        &current.script_pool,
+        &mut new.script_pool,
+        &mut changed,
+    );
+    keep(
+        "decision_cache",
+        &current.decision_cache,
+        &mut new.decision_cache,
+        &mut changed,
+    );
+    keep(
+        "recording.mode",
+        &current.recording.mode,
+        &mut new.recording.mode,
+        &mut changed,
+    );
+    changed
+}
+
+/// How long open connections may finish their requests after a shutdown
+/// signal before they are closed
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// The main proxy server struct.
 pub struct ProxyServer {
-    config: Arc<Config>,
-    compiled_rules: Arc<Vec<CompiledRule>>,
-    rule_upstreams: Arc<Vec<Option<String>>>, // Upstream filter for each rule (parallel to compiled_rules)
-    upstream_uri: String,                     // Used for sidecar mode
-    upstreams: Vec<Upstream>,                 // Used for reverse proxy mode
+    live: RwLock<Arc<LiveConfig>>, // Reloadable config and fault rules
+    config_path: Option<PathBuf>,  // Config file re-read on SIGHUP
+    upstream_uri: String,          // Used for sidecar mode
+    upstreams: Vec<Upstream>,      // Used for reverse proxy mode
     router: Option<Router>,
     flow_store: Arc<dyn FlowStore>, // Flow store for scripts (may be NoOp if not configured)
     script_pool: Option<Arc<ScriptPool>>, // Script pool for optimized execution
-    compiled_scripts: Option<Vec<(CompiledScript, CompiledRule, Option<String>)>>, // Precompiled scripts for pool
+    compiled_scripts: Option<Vec<CompiledScriptRule>>, // Precompiled scripts for pool
     decision_cache: Option<Arc<DecisionCache>>, // Decision cache for memoization
-    http_client: HttpClient,                    // Shared HTTP client for HTTP/1.1
+    http_clients: UpstreamClients,  // HTTP/1.1 clients, one pool per upstream
     // Mountebank-compatible behavior state
     // Will be wired up when response cycling is fully integrated
     response_cycler: Arc<ResponseCycler>, // Response cycling state (repeat behavior)
@@ -71,15 +264,6 @@ impl ProxyServer {
         config: Config,
         shared_flow_store: Option<Arc<dyn FlowStore>>,
     ) -> Result<Self, anyhow::Error> {
-        // Compile rules and extract upstream filters
-        let mut compiled_rules = Vec::new();
-        let mut rule_upstreams = Vec::new();
-
-        for rule in &config.rules {
-            compiled_rules.push(CompiledRule::compile(rule.clone())?);
-            rule_upstreams.push(rule.upstream.clone());
-        }
-
         // Get upstream URI (backward compatible with sidecar mode)
         let upstream_uri = if let Some(ref upstream) = config.upstream {
             let protocol = upstream.get_protocol();
@@ -95,15 +279,18 @@ impl ProxyServer {
         } else {
             anyhow::bail!("Config must specify either 'upstream' (sidecar mode) or 'upstreams' (reverse proxy mode)");
         };
+        // Fail here rather than on the first request or at bind time
+        upstream_uri
+            .parse::<hyper::Uri>()
+            .with_context(|| format!("invalid upstream address '{upstream_uri}'"))?;
+        config.listen.socket_addr().map_err(anyhow::Error::msg)?;
 
         // Create router for multi-upstream mode
-        let router = if !config.routing.is_empty() {
-            let r = Router::new(config.routing.clone())
-                .map_err(|e| anyhow::anyhow!("Failed to create router: {e}"))?;
-            Some(r)
-        } else {
-            None
-        };
+        let router = build_router(&config)?;
+        metrics::init_match_counters(
+            config.script_rules.iter().map(|r| r.id.as_str()),
+            router.iter().flat_map(Router::route_names),
+        );
 
         // Use shared flow store if provided, otherwise initialize new one
         let flow_store: Arc<dyn FlowStore> = if let Some(store) = shared_flow_store {
@@ -152,6 +339,8 @@ impl ProxyServer {
                         CompiledScript::Lua {
                             bytecode: Arc::new(bytecode),
                             rule_id: script_rule.id.clone(),
+                            response_hook: defines_response_hook(&script_rule.script),
+                            takes_context: takes_context(&script_rule.script),
                         }
                     }
                     #[cfg(not(feature = "lua"))]
@@ -181,14 +370,16 @@ impl ProxyServer {
                     other => anyhow::bail!("Unknown script engine type: {other}"),
                 };
 
-                let matcher = CompiledRule::compile(crate::config::Rule {
-                    id: script_rule.id.clone(),
-                    match_config: script_rule.match_config.clone(),
-                    fault: Default::default(),
-                    upstream: None,
-                })?;
+                let matcher = compile_script_matcher(script_rule)?;
 
-                scripts.push((compiled, matcher, script_rule.upstream.clone()));
+                let cache_key_fields = script_rule
+                    .cache_key_fields
+                    .iter()
+                    .map(|field| field.parse::<CacheKeyField>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| anyhow::anyhow!("Script rule '{}': {}", script_rule.id, e))?;
+
+                scripts.push((compiled, matcher, cache_key_fields));
             }
 
             // Create script pool with config (or defaults)
@@ -197,6 +388,7 @@ impl ProxyServer {
                     workers: pool_cfg.workers,
                     queue_size: pool_cfg.queue_size,
                     timeout_ms: pool_cfg.timeout_ms,
+                    fail_closed: pool_cfg.fail_closed,
                 }
             } else {
                 ScriptPoolConfig::default()
@@ -213,6 +405,7 @@ impl ProxyServer {
                     enabled: cache_cfg.enabled,
                     max_size: cache_cfg.max_size,
                     ttl_seconds: cache_cfg.ttl_seconds,
+                    cleanup_interval: cache_cfg.cleanup_interval_seconds.map(Duration::from_secs),
                 }
             } else {
                 DecisionCacheConfig::default()
@@ -230,19 +423,23 @@ impl ProxyServer {
 
         let upstreams = config.upstreams.clone();
 
-        // Check if any upstream needs TLS verification skipped
-        let skip_tls_verify = should_skip_tls_verify(&config);
-
-        // Create shared HTTP client
-        let http_client = create_http_client(&config, skip_tls_verify);
+        // Create a client (and connection pool) per upstream
+        let http_clients = UpstreamClients::new(&config)?;
 
         // Extract recording mode before moving config into Arc
         let recording_mode = config.recording.mode;
+        let recording_store = Arc::new(RecordingStore::new(recording_mode));
+        if let Some(persistence) = &config.recording.persistence {
+            let loaded = backend_from_config(persistence)
+                .and_then(|backend| recording_store.load_from_backend(backend.as_ref()));
+            if let Err(e) = loaded {
+                warn!("Failed to load recordings, starting fresh: {}", e);
+            }
+        }
 
         Ok(Self {
-            config: Arc::new(config),
-            compiled_rules: Arc::new(compiled_rules),
-            rule_upstreams: Arc::new(rule_upstreams),
+            live: RwLock::new(Arc::new(LiveConfig::compile(config)?)),
+            config_path: None,
             upstream_uri,
             upstreams,
             router,
@@ -250,24 +447,122 @@ impl ProxyServer {
             script_pool,
             compiled_scripts,
             decision_cache,
-            http_client,
+            http_clients,
             // Initialize behavior state
             response_cycler: Arc::new(ResponseCycler::new()),
             csv_cache: Arc::new(CsvCache::new()),
-            recording_store: Arc::new(RecordingStore::new(recording_mode)),
+            recording_store,
         })
     }
 
+    /// Compile a config's rules, script matchers and routes without
+    /// creating a client, flow store or listener.
+    ///
+    /// Catches errors `Config::validate` leaves to startup, such as routes
+    /// to unknown upstream groups, and of configs that skipped validation.
+    pub fn check(config: &Config) -> Result<(), anyhow::Error> {
+        LiveConfig::compile(config.clone())?;
+        for script_rule in &config.script_rules {
+            compile_script_matcher(script_rule)?;
+        }
+        build_router(config)?;
+        Ok(())
+    }
+
+    /// Set the config file to re-read when the process receives SIGHUP.
+    pub fn with_config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Current live configuration
+    pub(super) fn config(&self) -> Arc<Config> {
+        Arc::clone(&self.live.read().config)
+    }
+
+    /// Re-read, validate and apply a config file.
+    ///
+    /// On error the current configuration stays in place.
+    pub fn reload_from_file(&self, path: &Path) -> Result<(), anyhow::Error> {
+        let config = Config::from_file(path)
+            .with_context(|| format!("Failed to load config from {path:?}"))?;
+        self.reload(config)
+    }
+
+    /// Atomically replace the live configuration and recompile fault rules.
+    ///
+    /// Sections fixed at startup (see `keep_fixed_sections`) keep their
+    /// current values; a warning is logged if the new config changes them.
+    pub fn reload(&self, mut config: Config) -> Result<(), anyhow::Error> {
+        let current = self.config();
+        for section in keep_fixed_sections(&current, &mut config) {
+            warn!("Config section '{}' changed; restart to apply it", section);
+        }
+
+        let live = LiveConfig::compile(config)?;
+        let rule_count = live.compiled_rules.len();
+        *self.live.write() = Arc::new(live);
+        info!(
+            "Configuration reloaded: {} fault injection rules",
+            rule_count
+        );
+        Ok(())
+    }
+
+    /// Probe upstreams that configure a `health_check` until the returned
+    /// tasks are dropped, taking unhealthy ones out of their groups.
+    pub(super) fn spawn_health_checks(self: &Arc<Self>) -> Vec<AbortOnDrop> {
+        if self.router.is_none() {
+            return Vec::new();
+        }
+        self.upstreams
+            .iter()
+            .filter_map(|upstream| {
+                let check = upstream.health_check.clone()?;
+                let client = self.http_clients.get(Some(&upstream.name)).clone();
+                let server = Arc::downgrade(self);
+                let handle = tokio::spawn(run_health_checks(
+                    upstream.clone(),
+                    check,
+                    client,
+                    move |name, healthy| {
+                        if let Some(router) =
+                            server.upgrade().as_ref().and_then(|s| s.router.as_ref())
+                        {
+                            router.set_upstream_health(name, healthy);
+                        }
+                    },
+                ));
+                Some(AbortOnDrop(handle))
+            })
+            .collect()
+    }
+
+    /// ID of the first live fault rule matching a request
+    #[cfg(test)]
+    pub(super) fn matching_rule_id(
+        &self,
+        method: &hyper::Method,
+        uri: &hyper::Uri,
+        headers: &hyper::HeaderMap,
+    ) -> Option<String> {
+        let live = Arc::clone(&self.live.read());
+        crate::extensions::matcher::find_matching_rule(&live.compiled_rules, method, uri, headers)
+            .map(|rule| rule.rule.id.clone())
+    }
+
     /// Run the proxy server, accepting connections and handling requests.
     pub async fn run(self) -> Result<(), anyhow::Error> {
-        let addr = SocketAddr::from(([0, 0, 0, 0], self.config.listen.port));
-        let listener = create_reusable_listener(addr)?;
-        let protocol = self.config.listen.protocol;
+        let config = self.config();
+        let addr = config.listen.socket_addr().map_err(anyhow::Error::msg)?;
+        let listener = create_reusable_listener(addr)
+            .with_context(|| format!("Failed to listen on {addr}"))?;
+        let protocol = config.listen.protocol;
 
         // Create TLS acceptor if protocol is HTTPS
         let tls_acceptor = if protocol == RiftProtocol::Https {
             let tls_config =
-                self.config.listen.tls.as_ref().ok_or_else(|| {
+                config.listen.tls.as_ref().ok_or_else(|| {
                     anyhow::anyhow!("TLS configuration required for HTTPS listener")
                 })?;
             Some(create_tls_acceptor(
@@ -280,7 +575,7 @@ impl ProxyServer {
 
         info!("Listening on {}://{}", protocol.as_str(), addr);
         info!("Proxying to {}", self.upstream_uri);
-        info!("Loaded {} fault injection rules", self.compiled_rules.len());
+        info!("Loaded {} fault injection rules", config.rules.len());
         if let Some(ref scripts) = self.compiled_scripts {
             info!("Loaded {} script rules", scripts.len());
         }
@@ -288,50 +583,46 @@ impl ProxyServer {
             info!("Recording mode: {:?}", self.recording_store.mode());
         }
 
+        // Sweep expired decision cache entries until the server stops
+        let _cache_cleanup = self
+            .decision_cache
+            .as_ref()
+            .and_then(|cache| cache.spawn_cleanup_task());
+
         let server = Arc::new(self);
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+        let _reload = spawn_reload_on_sighup(&server);
+        let _health_checks = server.spawn_health_checks();
+        let _admin = server.spawn_admin_listener().await;
+        let mut connections = JoinSet::new();
 
         loop {
-            let (stream, remote_addr) = listener.accept().await?;
+            let (stream, remote_addr) = tokio::select! {
+                accepted = listener.accept() => accepted?,
+                // Reap finished connections so the set doesn't grow unbounded
+                Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+                _ = &mut shutdown => {
+                    info!("Shutdown signal received, stopping proxy server");
+                    break;
+                }
+            };
             let server = Arc::clone(&server);
             let tls_acceptor = tls_acceptor.clone();
 
-            tokio::spawn(async move {
+            connections.spawn(async move {
                 match protocol {
                     RiftProtocol::Https => {
                         // HTTPS: perform TLS handshake first
                         let acceptor =
                             tls_acceptor.expect("TLS acceptor must be present for HTTPS");
-                        match acceptor.accept(stream).await {
-                            Ok(tls_stream) => {
-                                let io = TokioIo::new(tls_stream);
-                                let service = service_fn(move |req| {
-                                    let server = Arc::clone(&server);
-                                    async move { server.handle_request_internal(req).await }
-                                });
-
-                                if let Err(err) =
-                                    http1::Builder::new().serve_connection(io, service).await
-                                {
-                                    error!(
-                                        "Error serving HTTPS connection from {}: {}",
-                                        remote_addr, err
-                                    );
-                                }
-                            }
-                            Err(err) => {
-                                error!("TLS handshake failed from {}: {}", remote_addr, err);
-                            }
-                        }
+                        server.serve_tls(&acceptor, stream, remote_addr).await;
                     }
-                    RiftProtocol::Http => {
+                    RiftProtocol::Http | RiftProtocol::WebSocket => {
                         // HTTP: serve directly
-                        let io = TokioIo::new(stream);
-                        let service = service_fn(move |req| {
-                            let server = Arc::clone(&server);
-                            async move { server.handle_request_internal(req).await }
-                        });
-
-                        if let Err(err) = http1::Builder::new().serve_connection(io, service).await
+                        let version = server.cleartext_versions();
+                        if let Err(err) =
+                            server.serve_connection(stream, remote_addr, version).await
                         {
                             error!(
                                 "Error serving HTTP connection from {}: {}",
@@ -345,15 +636,225 @@ impl ProxyServer {
                 }
             });
         }
+
+        // Stop accepting, then let open connections finish so responses in
+        // flight are recorded before the store is saved
+        drop(listener);
+        let drained = tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT, async {
+            while connections.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            warn!(
+                "Closing {} connections still open after {:?}",
+                connections.len(),
+                SHUTDOWN_DRAIN_TIMEOUT
+            );
+            connections.shutdown().await;
+        }
+
+        server.persist_recordings();
+        Ok(())
+    }
+
+    /// Save recordings to the configured persistence backend, if any.
+    fn persist_recordings(&self) {
+        let config = self.config();
+        let Some(persistence) = &config.recording.persistence else {
+            return;
+        };
+        let saved = backend_from_config(persistence)
+            .and_then(|backend| self.recording_store.save_to_backend(backend.as_ref()));
+        if let Err(e) = saved {
+            error!("Failed to save recordings: {}", e);
+        }
+    }
+
+    /// Complete the TLS handshake on an accepted connection and serve it
+    /// with the HTTP version negotiated through ALPN
+    pub(super) async fn serve_tls(
+        self: Arc<Self>,
+        acceptor: &TlsAcceptor,
+        stream: TcpStream,
+        remote_addr: SocketAddr,
+    ) {
+        let tls_stream = match acceptor.accept(stream).await {
+            Ok(tls_stream) => tls_stream,
+            Err(err) => {
+                error!("TLS handshake failed from {}: {}", remote_addr, err);
+                return;
+            }
+        };
+        let version = if tls_stream.get_ref().1.alpn_protocol() == Some(ALPN_H2) {
+            HttpVersions::Http2
+        } else {
+            HttpVersions::Http1
+        };
+        if let Err(err) = self
+            .serve_connection(tls_stream, remote_addr, version)
+            .await
+        {
+            error!(
+                "Error serving HTTPS connection from {}: {}",
+                remote_addr, err
+            );
+        }
+    }
+
+    /// HTTP versions accepted on cleartext connections
+    pub(super) fn cleartext_versions(&self) -> HttpVersions {
+        if self.config().listen.h2c {
+            HttpVersions::Http1OrH2c
+        } else {
+            HttpVersions::Http1
+        }
+    }
+
+    /// Serve requests on an accepted connection. HTTP/1.1 connections may be
+    /// taken over by chunked faults.
+    pub(super) async fn serve_connection<I>(
+        self: Arc<Self>,
+        io: I,
+        remote_addr: SocketAddr,
+        version: HttpVersions,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    where
+        I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (io, takeover) = TakeoverIo::new(io);
+        let io = TokioIo::new(io);
+        let service = service_fn(move |mut req: hyper::Request<hyper::body::Incoming>| {
+            req.extensions_mut().insert(takeover.clone());
+            host_from_authority(&mut req);
+            let server = Arc::clone(&self);
+            async move { server.handle_request_internal(req, remote_addr).await }
+        });
+        match version {
+            HttpVersions::Http1 => http1::Builder::new()
+                .serve_connection(io, service)
+                .with_upgrades()
+                .await
+                .map_err(Into::into),
+            HttpVersions::Http2 => http2::Builder::new(TokioExecutor::new())
+                .serve_connection(io, service)
+                .await
+                .map_err(Into::into),
+            HttpVersions::Http1OrH2c => {
+                auto::Builder::new(TokioExecutor::new())
+                    .serve_connection_with_upgrades(io, service)
+                    .await
+            }
+        }
+    }
+
+    /// Serve `/metrics` and the `/__rift/` admin endpoints on the metrics
+    /// port until the returned task is dropped.
+    ///
+    /// The proxy keeps running without them if the port can't be bound.
+    async fn spawn_admin_listener(self: &Arc<Self>) -> Option<AbortOnDrop> {
+        let addr = SocketAddr::from(([0, 0, 0, 0], self.config().metrics.port));
+        let listener = match TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Failed to listen for metrics on {}: {}", addr, e);
+                return None;
+            }
+        };
+        info!("Metrics and admin endpoints listening on http://{}", addr);
+        let server = Arc::downgrade(self);
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let Some(server) = server.upgrade() else {
+                    break;
+                };
+                tokio::spawn(async move {
+                    if let Err(err) = server.serve_admin_connection(stream).await {
+                        error!("Metrics server connection error: {}", err);
+                    }
+                });
+            }
+        });
+        Some(AbortOnDrop(handle))
+    }
+
+    /// Serve the metrics port's endpoints on an accepted connection
+    pub(super) async fn serve_admin_connection<I>(
+        self: Arc<Self>,
+        io: I,
+    ) -> Result<(), hyper::Error>
+    where
+        I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let service = service_fn(move |req: hyper::Request<hyper::body::Incoming>| {
+            let server = Arc::clone(&self);
+            async move { server.handle_admin(req).await }
+        });
+        http1::Builder::new()
+            .serve_connection(TokioIo::new(io), service)
+            .await
+    }
+
+    /// Handle a request to the metrics port: `/metrics` or one of the
+    /// `/__rift/` admin endpoints
+    async fn handle_admin(
+        &self,
+        req: hyper::Request<hyper::body::Incoming>,
+    ) -> Result<hyper::Response<BoxBody<Bytes, hyper::Error>>, Infallible> {
+        let live = Arc::clone(&self.live.read());
+
+        if req.uri().path() == "/metrics" {
+            let metrics = Bytes::from(metrics::collect_metrics());
+            return Ok(hyper::Response::new(http_body_util::Full::new(metrics)).into_boxed());
+        }
+
+        if req.method() == hyper::Method::POST && req.uri().path() == ADMIN_PORT_MATCH_PATH {
+            let limit = live.config.max_body_bytes;
+            let body = match Limited::new(req.into_body(), limit).collect().await {
+                Ok(collected) => collected.to_bytes(),
+                Err(e) if e.is::<LengthLimitError>() => {
+                    return Ok(error_response(413, "Request body too large").into_boxed());
+                }
+                Err(e) => {
+                    error!("Failed to read match request body: {}", e);
+                    return Ok(error_response(400, "Failed to read request body").into_boxed());
+                }
+            };
+            let response = handle_match_request(&body, &live.compiled_rules, self.router.as_ref());
+            return Ok(response.into_boxed());
+        }
+
+        if let Some(response) = handle_admin_port_request(
+            req.method(),
+            req.uri().path(),
+            req.uri().query(),
+            &self.recording_store,
+            &live.compiled_rules,
+            self.compiled_scripts.as_deref().unwrap_or_default(),
+        ) {
+            return Ok(response.into_boxed());
+        }
+        Ok(error_response(404, "Not Found").into_boxed())
     }
 
     /// Internal request handler that builds the context and delegates to handler module.
-    async fn handle_request_internal(
+    pub(super) async fn handle_request_internal(
         &self,
         req: hyper::Request<hyper::body::Incoming>,
+        remote_addr: SocketAddr,
     ) -> Result<hyper::Response<BoxBody<Bytes, hyper::Error>>, Infallible> {
+        let live = Arc::clone(&self.live.read());
+
+        if let Some(response) = handle_admin_request(
+            req.method(),
+            req.uri().path(),
+            &self.recording_store,
+            live.config.recording.persistence.as_ref(),
+        ) {
+            return Ok(response.into_boxed());
+        }
+
         // Build recording signature headers from config
-        let signature_headers: Vec<(String, String)> = self
+        let signature_headers: Vec<(String, String)> = live
             .config
             .recording
             .predicate_generators
@@ -368,9 +869,9 @@ impl ProxyServer {
             .collect();
 
         let ctx = RequestHandlerContext {
-            http_client: &self.http_client,
-            compiled_rules: &self.compiled_rules,
-            rule_upstreams: &self.rule_upstreams,
+            http_clients: &self.http_clients,
+            compiled_rules: &live.compiled_rules,
+            rule_paths: live.rule_paths.as_ref(),
             upstream_uri: &self.upstream_uri,
             router: self.router.as_ref(),
             upstreams: &self.upstreams,
@@ -380,10 +881,128 @@ impl ProxyServer {
             decision_cache: self.decision_cache.as_ref(),
             csv_cache: &self.csv_cache,
             recording_store: &self.recording_store,
+            recording_config: &live.config.recording,
             recording_signature_headers: &signature_headers,
-            flow_state_configured: self.config.flow_state.is_some(),
+            flow_state_configured: live.config.flow_state.is_some(),
+            fault_rng: &live.fault_rng,
+            client_ip: Some(remote_addr.ip()),
+            // A websocket listener is plain HTTP that also accepts upgrades
+            forwarded_proto: match live.config.listen.protocol {
+                RiftProtocol::WebSocket => RiftProtocol::Http.as_str(),
+                protocol => protocol.as_str(),
+            },
+            sidecar_forward_headers: live.config.upstream.as_ref().map(|u| &u.forward_headers),
+            sidecar_retry: live.config.upstream.as_ref().and_then(|u| u.retry.as_ref()),
+            request_timeout: live
+                .config
+                .connection_pool
+                .request_timeout_secs
+                .map(std::time::Duration::from_secs),
+            max_body_bytes: live.config.max_body_bytes,
+            body_overflow: live.config.body_overflow,
+            request_id: live.config.request_id.as_ref(),
+            response_cache: live.response_cache.as_ref(),
+        };
+
+        let route_headers = self
+            .router
+            .as_ref()
+            .and_then(|router| router.response_headers(&req));
+        let cors = live.config.cors.as_ref();
+        let origin = cors.and_then(|_| req.headers().get(hyper::header::ORIGIN).cloned());
+
+        let preflight = cors.and_then(|cors| preflight_response(cors, &req));
+        let mut response = match preflight {
+            Some(response) => response.into_boxed(),
+            None => {
+                let mut response = handle_request(&ctx, req).await?;
+                if let Some(cors) = cors {
+                    apply_cors_headers(cors, origin.as_ref(), response.headers_mut());
+                }
+                response
+            }
+        };
+        apply_response_headers(
+            response.headers_mut(),
+            &live.config.response_headers,
+            route_headers,
+        );
+        Ok(response)
+    }
+}
+
+/// Reload the config file on SIGHUP until the returned task is aborted.
+///
+/// Returns None if no config path is set or signals are unsupported.
+fn spawn_reload_on_sighup(server: &Arc<ProxyServer>) -> Option<AbortOnDrop> {
+    let path = server.config_path.clone()?;
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                error!("Failed to listen for SIGHUP: {}", e);
+                return None;
+            }
         };
+        let server = Arc::downgrade(server);
+        let handle = tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                let Some(server) = server.upgrade() else {
+                    break;
+                };
+                info!("SIGHUP received, reloading {:?}", path);
+                if let Err(e) = server.reload_from_file(&path) {
+                    error!("Config reload failed, keeping current config: {:#}", e);
+                }
+            }
+        });
+        Some(AbortOnDrop(handle))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Aborts a background task when dropped
+pub(super) struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Resolve when the process receives Ctrl+C (or SIGTERM on Unix).
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-        handle_request(&ctx, req).await
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
     }
 }
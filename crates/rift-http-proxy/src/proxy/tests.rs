@@ -4,7 +4,10 @@
 
 #[cfg(test)]
 mod script_pool_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
     use crate::scripting::ScriptPoolConfig;
+    use std::time::{Duration, Instant};
 
     #[test]
     fn test_script_pool_config_creation() {
@@ -12,6 +15,62 @@ mod script_pool_tests {
         assert!(config.workers >= 2);
         assert!(config.workers <= 16);
     }
+
+    fn runaway_config(port: u16, fail_closed: bool) -> crate::config::Config {
+        serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {port}
+script_pool:
+  workers: 1
+  timeout_ms: 200
+  fail_closed: {fail_closed}
+script_rules:
+  - id: runaway
+    script: |
+      fn should_inject(request, flow_store) {{
+        loop {{}}
+      }}
+    match:
+      path:
+        prefix: /
+"#
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_runaway_script_request_completes_after_timeout() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config = runaway_config(upstream.port(), false);
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        // The single worker must be freed for the second request to pass
+        for _ in 0..2 {
+            let start = Instant::now();
+            let response = reqwest::get(format!("http://{addr}/")).await.unwrap();
+            let elapsed = start.elapsed();
+            assert_eq!(response.status(), 200);
+            assert_eq!(response.text().await.unwrap(), "ok");
+            assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+            assert!(elapsed < Duration::from_millis(1500), "{elapsed:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runaway_script_fails_closed() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config = runaway_config(upstream.port(), true);
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let response = reqwest::get(format!("http://{addr}/")).await.unwrap();
+        assert_eq!(response.status(), 503);
+    }
 }
 
 #[cfg(test)]
@@ -65,6 +124,10 @@ mod router_tests {
                 path_prefix: Some("/api".to_string()),
                 ..Default::default()
             },
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
         }];
         let router = Router::new(routes);
         assert!(router.is_ok());
@@ -80,6 +143,10 @@ mod router_tests {
                     path_prefix: Some("/api/v1".to_string()),
                     ..Default::default()
                 },
+                rewrite: None,
+                forward_headers: Default::default(),
+                request_timeout_secs: None,
+                response_headers: Default::default(),
             },
             Route {
                 name: "v2-route".to_string(),
@@ -88,6 +155,10 @@ mod router_tests {
                     path_prefix: Some("/api/v2".to_string()),
                     ..Default::default()
                 },
+                rewrite: None,
+                forward_headers: Default::default(),
+                request_timeout_secs: None,
+                response_headers: Default::default(),
             },
         ];
         let router = Router::new(routes).unwrap();
@@ -117,6 +188,10 @@ mod router_tests {
                 path_prefix: Some("/api".to_string()),
                 ..Default::default()
             },
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
         }];
         let router = Router::new(routes).unwrap();
 
@@ -137,6 +212,10 @@ mod router_tests {
                 path_exact: Some("/exact/path".to_string()),
                 ..Default::default()
             },
+            rewrite: None,
+            forward_headers: Default::default(),
+            request_timeout_secs: None,
+            response_headers: Default::default(),
         }];
         let router = Router::new(routes).unwrap();
 
@@ -169,11 +248,25 @@ mod compiled_rule_tests {
                 headers: vec![],
                 header_predicates: vec![],
                 query: vec![],
+                trailers: vec![],
                 body: None,
+                body_size: None,
+                content_type: None,
+                hmac: None,
+                user_agent_family: None,
+                auth: None,
+                ip: None,
+                is_retry: None,
                 case_sensitive: true,
             },
             fault: FaultConfig::default(),
             upstream: None,
+            once: false,
+            action: Default::default(),
+            description: None,
+            metadata: Default::default(),
+            enabled: true,
+            active_window: None,
         }
     }
 
@@ -503,3 +596,2414 @@ mod recording_store_tests {
         assert!(!store.should_proxy(&sig));
     }
 }
+
+#[cfg(test)]
+mod reload_tests {
+    use crate::proxy::server::ProxyServer;
+    use hyper::{HeaderMap, Method, Uri};
+
+    fn config_yaml(rule_path: &str) -> String {
+        format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 8000
+rules:
+  - id: "error-{rule_path}"
+    match:
+      methods: ["GET"]
+      path:
+        exact: "/{rule_path}"
+    fault:
+      error:
+        probability: 1.0
+        status: 503
+"#
+        )
+    }
+
+    async fn server_from_file(path: &std::path::Path) -> ProxyServer {
+        // Ignore the error if another test already installed the provider
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config = crate::config::Config::from_file(path).unwrap();
+        ProxyServer::new(config).await.unwrap()
+    }
+
+    fn matching_rule(server: &ProxyServer, path: &str) -> Option<String> {
+        let uri: Uri = format!("http://localhost{path}").parse().unwrap();
+        server.matching_rule_id(&Method::GET, &uri, &HeaderMap::new())
+    }
+
+    #[tokio::test]
+    async fn test_reload_from_file_swaps_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rift.yaml");
+        std::fs::write(&path, config_yaml("old")).unwrap();
+
+        let server = server_from_file(&path).await.with_config_path(&path);
+        assert_eq!(
+            matching_rule(&server, "/old"),
+            Some("error-old".to_string())
+        );
+        assert_eq!(matching_rule(&server, "/new"), None);
+
+        std::fs::write(&path, config_yaml("new")).unwrap();
+        server.reload_from_file(&path).unwrap();
+        assert_eq!(matching_rule(&server, "/old"), None);
+        assert_eq!(
+            matching_rule(&server, "/new"),
+            Some("error-new".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reload_keeps_sections_fixed_at_startup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rift.yaml");
+        std::fs::write(&path, config_yaml("old")).unwrap();
+
+        let server = server_from_file(&path).await;
+
+        let moved = config_yaml("new").replace("port: 8000", "port: 9000");
+        std::fs::write(&path, moved).unwrap();
+        server.reload_from_file(&path).unwrap();
+
+        // Rules are swapped, the upstream the clients were built for is kept
+        assert_eq!(
+            matching_rule(&server, "/new"),
+            Some("error-new".to_string())
+        );
+        let config = server.config();
+        assert_eq!(config.upstream.as_ref().unwrap().port, 8000);
+        assert_eq!(config.rules[0].id, "error-new");
+    }
+
+    #[test]
+    fn test_check_compiles_rules_without_starting() {
+        let config: crate::config::Config = config_yaml("ok").parse().unwrap();
+        assert!(ProxyServer::check(&config).is_ok());
+
+        // Validation already rejects the bad regex; check catches it too
+        let invalid_yaml = config_yaml("ok").replace("exact: \"/ok\"", "regex: \"^/ok/(\"");
+        let err = invalid_yaml.parse::<crate::config::Config>().unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Rule 'error-ok': invalid pattern in path"),
+            "unexpected error: {err}"
+        );
+        let invalid: crate::config::Config = serde_yaml::from_str(&invalid_yaml).unwrap();
+        let err = ProxyServer::check(&invalid).unwrap_err();
+        assert!(format!("{err:#}").contains("Rule 'error-ok'"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_reload_keeps_current_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rift.yaml");
+        std::fs::write(&path, config_yaml("old")).unwrap();
+
+        let server = server_from_file(&path).await;
+
+        // is_retry without flow_state fails validation
+        let invalid = config_yaml("new").replace("    fault:", "      is_retry: {}\n    fault:");
+        std::fs::write(&path, invalid).unwrap();
+        assert!(server.reload_from_file(&path).is_err());
+        assert_eq!(
+            matching_rule(&server, "/old"),
+            Some("error-old".to_string())
+        );
+    }
+}
+
+/// Serve `server` on an ephemeral port, returning its address
+#[cfg(test)]
+async fn serve(
+    server: impl Into<std::sync::Arc<crate::proxy::server::ProxyServer>>,
+) -> std::net::SocketAddr {
+    use std::sync::Arc;
+
+    let server: Arc<_> = server.into();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, remote_addr)) = listener.accept().await {
+            let server = Arc::clone(&server);
+            let version = server.cleartext_versions();
+            tokio::spawn(server.serve_connection(stream, remote_addr, version));
+        }
+    });
+    addr
+}
+
+/// Serve `server`'s metrics port endpoints on an ephemeral port, returning
+/// its address
+#[cfg(test)]
+async fn serve_admin(
+    server: &std::sync::Arc<crate::proxy::server::ProxyServer>,
+) -> std::net::SocketAddr {
+    use std::sync::Arc;
+
+    let server = Arc::clone(server);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(Arc::clone(&server).serve_admin_connection(stream));
+        }
+    });
+    addr
+}
+
+/// Upstream answering every request with 200 "ok"
+#[cfg(test)]
+async fn spawn_upstream() -> std::net::SocketAddr {
+    use http_body_util::Full;
+    use hyper::body::Bytes;
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use hyper_util::rt::TokioIo;
+    use std::convert::Infallible;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let service = service_fn(|_req| async {
+                Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from("ok"))))
+            });
+            tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+        }
+    });
+    addr
+}
+
+#[cfg(test)]
+mod metrics_tests {
+    use super::serve;
+    use crate::extensions::metrics::{ROUTE_MATCHES_TOTAL, RULE_MATCHES_TOTAL};
+    use crate::proxy::server::ProxyServer;
+
+    #[tokio::test]
+    async fn test_matched_rule_and_route_increment_counters() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config: crate::config::Config = serde_yaml::from_str(
+            r#"
+listen:
+  port: 8080
+upstreams:
+  - name: backend
+    url: "http://127.0.0.1:9"
+routing:
+  - name: counted-route
+    match:
+      path_prefix: /
+    upstream: backend
+rules:
+  - id: counted-rule
+    match:
+      path:
+        exact: /counted
+    fault:
+      error:
+        probability: 1.0
+        status: 503
+  - id: idle-rule
+    match:
+      path:
+        exact: /never
+    fault:
+      error:
+        probability: 1.0
+        status: 500
+"#,
+        )
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let response = reqwest::get(format!("http://{addr}/counted"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 503);
+
+        let rule_hits = |rule| RULE_MATCHES_TOTAL.with_label_values(&[rule]).get();
+        assert_eq!(rule_hits("counted-rule"), 1.0);
+        assert_eq!(rule_hits("idle-rule"), 0.0);
+        assert_eq!(
+            ROUTE_MATCHES_TOTAL
+                .with_label_values(&["counted-route"])
+                .get(),
+            1.0
+        );
+    }
+}
+
+#[cfg(test)]
+mod body_encoding_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    fn gzip(data: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_body_contains_matches_gzip_request() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {}
+rules:
+  - id: pending-orders
+    match:
+      body: !contains '"status": "pending"'
+    fault:
+      error:
+        probability: 1.0
+        status: 503
+"#,
+            upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+        let client = reqwest::Client::new();
+        let post = |body: &str| {
+            client
+                .post(format!("http://{addr}/orders"))
+                .header("content-encoding", "gzip")
+                .body(gzip(body))
+                .send()
+        };
+
+        let response = post(r#"{"status": "pending"}"#).await.unwrap();
+        assert_eq!(response.status(), 503);
+
+        let response = post(r#"{"status": "shipped"}"#).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "ok");
+    }
+}
+
+#[cfg(test)]
+mod trailer_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Send a chunked POST with a trailer over a raw connection, returning
+    /// the response's status line
+    async fn post_with_trailer(addr: std::net::SocketAddr, checksum: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "POST /upload HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+             Transfer-Encoding: chunked\r\nTrailer: x-checksum\r\n\r\n\
+             5\r\nhello\r\n0\r\nx-checksum: {checksum}\r\n\r\n"
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_rule_matches_request_trailer() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {}
+rules:
+  - id: bad-checksum
+    match:
+      trailers:
+        - name: X-Checksum
+          equals: bad
+    fault:
+      error:
+        probability: 1.0
+        status: 422
+"#,
+            upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        assert_eq!(
+            post_with_trailer(addr, "bad").await,
+            "HTTP/1.1 422 Unprocessable Entity"
+        );
+        assert_eq!(post_with_trailer(addr, "good").await, "HTTP/1.1 200 OK");
+    }
+}
+
+#[cfg(test)]
+mod empty_header_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Send a GET with extra raw header lines, returning the status line
+    async fn get(addr: std::net::SocketAddr, extra_headers: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "GET /flags HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+             {extra_headers}\r\n"
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_exists_matches_present_but_empty_header() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {}
+rules:
+  - id: has-empty
+    match:
+      headerPredicates:
+        - name: X-Empty
+          exists: true
+    fault:
+      error:
+        probability: 1.0
+        status: 418
+  - id: no-empty
+    match:
+      headerPredicates:
+        - name: X-Empty
+          exists: false
+    fault:
+      error:
+        probability: 1.0
+        status: 503
+"#,
+            upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        assert_eq!(get(addr, "X-Empty:\r\n").await, "HTTP/1.1 418 I'm a teapot");
+        assert_eq!(get(addr, "").await, "HTTP/1.1 503 Service Unavailable");
+    }
+}
+
+#[cfg(test)]
+mod chunked_fault_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn proxy(mode: &str) -> std::net::SocketAddr {
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {}
+rules:
+  - id: broken-chunks
+    match:
+      path:
+        prefix: /stream
+    fault:
+      chunked:
+        probability: 1.0
+        mode: {mode}
+"#,
+            upstream.port()
+        ))
+        .unwrap();
+        serve(ProxyServer::new(config).await.unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn test_client_sees_protocol_error() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        for mode in ["missing_terminator", "bad_chunk_size"] {
+            let addr = proxy(mode).await;
+            let response = reqwest::get(format!("http://{addr}/stream")).await.unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(response.headers()["transfer-encoding"], "chunked");
+            assert_eq!(response.headers()["x-rift-fault"], "chunked");
+            assert!(response.bytes().await.is_err(), "{mode}");
+
+            // Other requests are unaffected
+            let response = reqwest::get(format!("http://{addr}/other")).await.unwrap();
+            assert_eq!(response.text().await.unwrap(), "ok");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_1_0_request_is_not_faulted() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let addr = proxy("bad_chunk_size").await;
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /stream HTTP/1.0\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.0 200 OK"), "{response}");
+        assert!(response.ends_with("\r\n\r\nok"), "{response}");
+    }
+}
+
+#[cfg(test)]
+mod http2_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+    use crate::proxy::tls::create_tls_acceptor;
+    use std::sync::Arc;
+
+    fn config(upstream: std::net::SocketAddr, listen: &str) -> crate::config::Config {
+        serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+{listen}
+upstream:
+  host: 127.0.0.1
+  port: {}
+rules:
+  - id: fail
+    match:
+      path:
+        prefix: /fail
+    fault:
+      error:
+        probability: 1.0
+        status: 503
+  - id: needs-host
+    match:
+      path:
+        prefix: /host
+      headerPredicates:
+        - name: Host
+          exists: false
+    fault:
+      error:
+        probability: 1.0
+        status: 400
+"#,
+            upstream.port()
+        ))
+        .unwrap()
+    }
+
+    /// Check that faults and forwarding behave the same for `client`
+    async fn assert_proxied(client: &reqwest::Client, base: &str, version: reqwest::Version) {
+        let response = client.get(format!("{base}/ok")).send().await.unwrap();
+        assert_eq!(response.version(), version);
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["x-rift-proxied"], "true");
+        assert_eq!(response.text().await.unwrap(), "ok");
+
+        let response = client.get(format!("{base}/fail")).send().await.unwrap();
+        assert_eq!(response.version(), version);
+        assert_eq!(response.status(), 503);
+        assert_eq!(response.headers()["x-rift-rule-id"], "fail");
+
+        // HTTP/2 requests see the `:authority` as a Host header
+        let response = client.get(format!("{base}/host")).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_h2c_prior_knowledge() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let proxy = ProxyServer::new(config(upstream, "  h2c: true"))
+            .await
+            .unwrap();
+        let base = format!("http://{}", serve(proxy).await);
+
+        let h2 = reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .build()
+            .unwrap();
+        assert_proxied(&h2, &base, reqwest::Version::HTTP_2).await;
+        // HTTP/1.1 clients are still served
+        assert_proxied(&reqwest::Client::new(), &base, reqwest::Version::HTTP_11).await;
+    }
+
+    #[tokio::test]
+    async fn test_h2c_is_off_by_default() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let proxy = ProxyServer::new(config(upstream, "")).await.unwrap();
+        let addr = serve(proxy).await;
+
+        let h2 = reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .build()
+            .unwrap();
+        assert!(h2.get(format!("http://{addr}/ok")).send().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tls_negotiates_h2_through_alpn() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let cert_path = dir.path().join("cert.pem");
+        let key_path = dir.path().join("key.pem");
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+        let acceptor =
+            create_tls_acceptor(cert_path.to_str().unwrap(), key_path.to_str().unwrap()).unwrap();
+
+        let server = Arc::new(ProxyServer::new(config(upstream, "")).await.unwrap());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, remote_addr)) = listener.accept().await {
+                let server = Arc::clone(&server);
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move { server.serve_tls(&acceptor, stream, remote_addr).await });
+            }
+        });
+        let base = format!("https://localhost:{port}");
+
+        let client = |http1_only: bool| {
+            let builder = reqwest::Client::builder()
+                .use_rustls_tls()
+                .danger_accept_invalid_certs(true)
+                .resolve("localhost", ([127, 0, 0, 1], port).into());
+            let builder = if http1_only {
+                builder.http1_only()
+            } else {
+                builder
+            };
+            builder.build().unwrap()
+        };
+        assert_proxied(&client(false), &base, reqwest::Version::HTTP_2).await;
+        assert_proxied(&client(true), &base, reqwest::Version::HTTP_11).await;
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod bind_tests {
+    use super::spawn_upstream;
+    use crate::proxy::server::ProxyServer;
+    use std::time::Duration;
+    use tokio::net::TcpStream;
+
+    /// Run a proxy with the given `listen.bind` line, returning its port
+    async fn run_proxy(bind: &str) -> u16 {
+        let upstream = spawn_upstream().await;
+        let port = port_check::free_local_port().unwrap();
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            "listen:\n  port: {port}\n{bind}upstream:\n  host: 127.0.0.1\n  port: {}\n",
+            upstream.port()
+        ))
+        .unwrap();
+        let server = ProxyServer::new(config).await.unwrap();
+        tokio::spawn(server.run());
+        for _ in 0..50 {
+            if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+                return port;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("proxy never started listening on port {port}");
+    }
+
+    #[tokio::test]
+    async fn test_bind_to_loopback_only() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        // Every 127.x.x.x address reaches loopback on Linux, but a socket
+        // bound to 127.0.0.1 only accepts connections to that address
+        let port = run_proxy("  bind: 127.0.0.1\n").await;
+        assert!(TcpStream::connect(("127.0.0.2", port)).await.is_err());
+
+        let port = run_proxy("").await;
+        assert!(TcpStream::connect(("127.0.0.2", port)).await.is_ok());
+    }
+}
+
+#[cfg(test)]
+mod startup_error_tests {
+    use crate::proxy::server::ProxyServer;
+
+    async fn new_server(yaml: &str) -> Result<ProxyServer, anyhow::Error> {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        ProxyServer::new(serde_yaml::from_str(yaml).unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn test_invalid_upstream_host_is_an_error() {
+        let result =
+            new_server("listen:\n  port: 8080\nupstream:\n  host: not a host\n  port: 8000\n")
+                .await;
+        let err = result.err().expect("invalid upstream host accepted");
+        assert!(
+            err.to_string()
+                .contains("invalid upstream address 'http://not a host:8000'"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_bind_address_is_an_error() {
+        let result = new_server(
+            "listen:\n  port: 8080\n  bind: not-an-ip\nupstream:\n  host: 127.0.0.1\n  port: 8000\n",
+        )
+        .await;
+        let err = result.err().expect("invalid bind address accepted");
+        assert!(
+            err.to_string()
+                .contains("invalid listen.bind address 'not-an-ip'"),
+            "{err}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod request_id_tests {
+    use super::serve;
+    use crate::proxy::server::ProxyServer;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    /// Upstream answering with the request id it was sent
+    async fn spawn_echo_upstream(header: &'static str) -> std::net::SocketAddr {
+        use http_body_util::Full;
+        use hyper::body::Bytes;
+        use hyper::server::conn::http1;
+        use hyper::service::service_fn;
+        use hyper_util::rt::TokioIo;
+        use std::convert::Infallible;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let service = service_fn(move |req: hyper::Request<_>| async move {
+                    let id = req.headers().get(header).cloned();
+                    let body = id.map(|id| Bytes::copy_from_slice(id.as_bytes()));
+                    Ok::<_, Infallible>(hyper::Response::new(Full::new(body.unwrap_or_default())))
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+        addr
+    }
+
+    /// Log output captured on the current thread
+    #[derive(Clone, Default)]
+    struct Logs(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generated_id_is_forwarded_echoed_and_logged() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let upstream = spawn_echo_upstream("x-request-id").await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: {}\nrequest_id: {{}}\n",
+            upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let response = reqwest::get(format!("http://{addr}/orders")).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let id = response.headers()["x-request-id"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(uuid::Uuid::parse_str(&id).is_ok(), "{id}");
+        assert_eq!(response.text().await.unwrap(), id);
+
+        let logs = String::from_utf8(logs.0.lock().clone()).unwrap();
+        let line = logs
+            .lines()
+            .find(|line| line.contains("Completed request: GET /orders -> 200"))
+            .unwrap_or_else(|| panic!("no access log line in {logs}"));
+        assert!(line.contains(&format!("request_id={id}")), "{line}");
+    }
+
+    #[tokio::test]
+    async fn test_incoming_id_reaches_copy_behaviors() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config: crate::config::Config = serde_yaml::from_str(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 9
+request_id:
+  header: X-Correlation-Id
+rules:
+  - id: mock
+    match: {}
+    fault:
+      error:
+        probability: 1.0
+        status: 200
+        body: '{"id": "${ID}"}'
+        behaviors:
+          copy:
+            - from: {headers: X-Correlation-Id}
+              into: "${ID}"
+              using: {method: regex, selector: ".+"}
+"#,
+        )
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let response = reqwest::Client::new()
+            .get(format!("http://{addr}/"))
+            .header("X-Correlation-Id", "abc-123")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["x-correlation-id"], "abc-123");
+        assert_eq!(response.text().await.unwrap(), r#"{"id": "abc-123"}"#);
+    }
+}
+
+#[cfg(test)]
+mod admin_port_tests {
+    use super::{serve, serve_admin, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_recordings_are_exported_and_cleared_on_admin_port() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: {}\nrecording:\n  mode: proxyOnce\n",
+            upstream.port()
+        ))
+        .unwrap();
+        let server = Arc::new(ProxyServer::new(config).await.unwrap());
+        let admin = serve_admin(&server).await;
+        let addr = serve(server).await;
+        let client = reqwest::Client::new();
+
+        let body = reqwest::get(format!("http://{addr}/users"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "ok");
+
+        let exported: serde_json::Value = reqwest::get(format!("http://{admin}/__rift/recordings"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(
+            exported["stubs"][0]["predicates"][0]["and"]["path"]["equals"],
+            "/users"
+        );
+
+        // The proxy listener forwards its own /_rift/recordings upstream
+        let response = client
+            .delete(format!("http://{addr}/_rift/recordings"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+
+        let cleared: serde_json::Value = client
+            .delete(format!("http://{admin}/__rift/recordings"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        // Both proxied requests were recorded
+        assert_eq!(cleared["cleared"], 2);
+
+        let metrics = reqwest::get(format!("http://{admin}/metrics"))
+            .await
+            .unwrap();
+        assert_eq!(metrics.status(), 200);
+        let missing = reqwest::get(format!("http://{admin}/users")).await.unwrap();
+        assert_eq!(missing.status(), 404);
+    }
+}
+
+#[cfg(test)]
+mod recording_persistence_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+    use crate::recording::{ProxyMode, RecordedResponse, RecordingStore, RequestSignature};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_recordings_load_through_fallback_backend() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let dir = tempfile::tempdir().unwrap();
+        let fallback = dir.path().join("recordings.json");
+        let saved = RecordingStore::new(ProxyMode::ProxyOnce);
+        saved.record(
+            RequestSignature::new("GET", "/cached", None, &[]),
+            RecordedResponse {
+                status: 200,
+                headers: HashMap::new(),
+                body: b"replayed".to_vec(),
+                latency_ms: None,
+                timestamp_secs: 0,
+            },
+        );
+        saved.save_to_file(&fallback).unwrap();
+
+        // The primary backend can't be read (its path is a directory), so
+        // recordings come from the fallback
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {}
+recording:
+  mode: proxyOnce
+  persistence:
+    backend: file
+    path: "{}"
+    fallback:
+      backend: file
+      path: "{}"
+"#,
+            upstream.port(),
+            dir.path().display(),
+            fallback.display()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let body = reqwest::get(format!("http://{addr}/cached"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "replayed");
+    }
+}
+
+#[cfg(test)]
+mod health_check_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_failing_upstream_leaves_its_group() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let healthy = spawn_upstream().await;
+        // Nothing listens on the closed port, so every probe fails
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstreams:
+  - name: down
+    url: "http://{closed}"
+    health_check:
+      path: /health
+      unhealthy_threshold: 1
+  - name: up
+    url: "http://{healthy}"
+    health_check:
+      path: /health
+upstream_groups:
+  - name: pool
+    upstreams: [down, up]
+routing:
+  - name: all
+    match:
+      path_prefix: /
+    upstream: pool
+"#
+        ))
+        .unwrap();
+        let server = Arc::new(ProxyServer::new(config).await.unwrap());
+        let _checks = server.spawn_health_checks();
+        let addr = serve(server).await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        for _ in 0..4 {
+            let response = reqwest::get(format!("http://{addr}/")).await.unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(response.text().await.unwrap(), "ok");
+        }
+    }
+}
+
+#[cfg(test)]
+mod response_cache_tests {
+    use super::serve;
+    use crate::proxy::server::ProxyServer;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Upstream answering with how many requests it has seen, marking
+    /// responses under `/private` as `no-store`
+    async fn spawn_counting_upstream() -> std::net::SocketAddr {
+        use http_body_util::Full;
+        use hyper::body::Bytes;
+        use hyper::server::conn::http1;
+        use hyper::service::service_fn;
+        use hyper_util::rt::TokioIo;
+        use std::convert::Infallible;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let count = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let count = Arc::clone(&count);
+                let service = service_fn(move |req: hyper::Request<hyper::body::Incoming>| {
+                    let seen = count.fetch_add(1, Ordering::SeqCst) + 1;
+                    let mut response =
+                        hyper::Response::new(Full::new(Bytes::from(seen.to_string())));
+                    if req.uri().path().starts_with("/private") {
+                        response
+                            .headers_mut()
+                            .insert("cache-control", "no-store".parse().unwrap());
+                    }
+                    async move { Ok::<_, Infallible>(response) }
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_get_responses_are_cached() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_counting_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: {}\nresponse_cache: {{}}\n",
+            upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+        let client = reqwest::Client::new();
+        let get = |path: &str| {
+            let request = client.get(format!("http://{addr}{path}"));
+            async move {
+                let response = request.send().await.unwrap();
+                let cached = response.headers().contains_key("x-rift-cached");
+                (response.text().await.unwrap(), cached)
+            }
+        };
+
+        assert_eq!(get("/items").await, ("1".to_string(), false));
+        assert_eq!(get("/items").await, ("1".to_string(), true));
+        assert_eq!(get("/items?page=2").await, ("2".to_string(), false));
+
+        // no-store responses always go to the upstream
+        assert_eq!(get("/private").await, ("3".to_string(), false));
+        assert_eq!(get("/private").await, ("4".to_string(), false));
+
+        let posted = client
+            .post(format!("http://{addr}/items"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(posted.text().await.unwrap(), "5");
+    }
+}
+
+#[cfg(test)]
+mod mtls_tests {
+    use super::serve;
+    use crate::proxy::server::ProxyServer;
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+    use rustls::server::WebPkiClientVerifier;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    /// PEM certificate and key issued for `localhost`
+    struct Issued {
+        cert: String,
+        key: String,
+    }
+
+    fn issue(ca: &rcgen::Certificate, ca_key: &KeyPair) -> Issued {
+        let params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+        let key = KeyPair::generate().unwrap();
+        let cert = params.signed_by(&key, ca, ca_key).unwrap();
+        Issued {
+            cert: cert.pem(),
+            key: key.serialize_pem(),
+        }
+    }
+
+    fn der_certs(pem: &str) -> Vec<CertificateDer<'static>> {
+        rustls_pemfile::certs(&mut pem.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn der_key(pem: &str) -> PrivateKeyDer<'static> {
+        rustls_pemfile::private_key(&mut pem.as_bytes())
+            .unwrap()
+            .unwrap()
+    }
+
+    /// HTTPS upstream answering "ok" only to clients with a certificate
+    /// issued by `ca`
+    async fn spawn_mtls_upstream(ca: &str, server: &Issued) -> u16 {
+        use http_body_util::Full;
+        use hyper::body::Bytes;
+        use hyper::server::conn::http1;
+        use hyper::service::service_fn;
+        use hyper_util::rt::TokioIo;
+        use std::convert::Infallible;
+
+        let mut roots = rustls::RootCertStore::empty();
+        for cert in der_certs(ca) {
+            roots.add(cert).unwrap();
+        }
+        let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+            .build()
+            .unwrap();
+        let tls = rustls::ServerConfig::builder()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(der_certs(&server.cert), der_key(&server.key))
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(tls));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let Ok(stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let service = service_fn(|_req: hyper::Request<hyper::body::Incoming>| async {
+                        Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from("ok"))))
+                    });
+                    let _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+        port
+    }
+
+    async fn proxy(port: u16, dir: &Path, client_tls: &str) -> std::net::SocketAddr {
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            "listen:\n  port: 8080\nupstream:\n  host: localhost\n  port: {port}\n  \
+             protocol: https\n  ca_path: {}\n{client_tls}",
+            dir.join("ca.pem").display()
+        ))
+        .unwrap();
+        config.validate().unwrap();
+        serve(ProxyServer::new(config).await.unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate_is_presented_to_upstream() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_key = KeyPair::generate().unwrap();
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let server = issue(&ca, &ca_key);
+        let client = issue(&ca, &ca_key);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ca.pem"), ca.pem()).unwrap();
+        std::fs::write(dir.path().join("client.pem"), &client.cert).unwrap();
+        std::fs::write(dir.path().join("client-key.pem"), &client.key).unwrap();
+        let port = spawn_mtls_upstream(&ca.pem(), &server).await;
+
+        let with_cert = proxy(
+            port,
+            dir.path(),
+            &format!(
+                "  client_cert_path: {}\n  client_key_path: {}\n",
+                dir.path().join("client.pem").display(),
+                dir.path().join("client-key.pem").display()
+            ),
+        )
+        .await;
+        let response = reqwest::get(format!("http://{with_cert}/")).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "ok");
+
+        // The upstream refuses the handshake without a client certificate
+        let without_cert = proxy(port, dir.path(), "").await;
+        let response = reqwest::get(format!("http://{without_cert}/"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 502);
+    }
+}
+
+#[cfg(test)]
+mod tls_skip_verify_tests {
+    use super::serve;
+    use crate::proxy::server::ProxyServer;
+    use std::sync::Arc;
+
+    /// HTTPS upstream with a self-signed certificate, answering "ok"
+    async fn spawn_self_signed_upstream() -> u16 {
+        use http_body_util::Full;
+        use hyper::body::Bytes;
+        use hyper::server::conn::http1;
+        use hyper::service::service_fn;
+        use hyper_util::rt::TokioIo;
+        use std::convert::Infallible;
+
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let key =
+            rustls::pki_types::PrivateKeyDer::try_from(cert.key_pair.serialize_der()).unwrap();
+        let tls = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.cert.der().clone()], key)
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(tls));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let Ok(stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let service = service_fn(|_req: hyper::Request<hyper::body::Incoming>| async {
+                        Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from("ok"))))
+                    });
+                    let _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+        port
+    }
+
+    async fn status(addr: std::net::SocketAddr, path: &str) -> u16 {
+        let response = reqwest::get(format!("http://{addr}{path}")).await.unwrap();
+        response.status().as_u16()
+    }
+
+    #[tokio::test]
+    async fn test_sidecar_upstream_skip_verify() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let port = spawn_self_signed_upstream().await;
+        let proxy = |skip: bool| async move {
+            let config: crate::config::Config = serde_yaml::from_str(&format!(
+                "listen:\n  port: 8080\nupstream:\n  host: localhost\n  port: {port}\n  \
+                 protocol: https\n  tls_skip_verify: {skip}\n"
+            ))
+            .unwrap();
+            serve(ProxyServer::new(config).await.unwrap()).await
+        };
+
+        assert_eq!(status(proxy(true).await, "/").await, 200);
+        assert_eq!(status(proxy(false).await, "/").await, 502);
+    }
+
+    #[tokio::test]
+    async fn test_skip_verify_applies_only_to_its_upstream() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let port = spawn_self_signed_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstreams:
+  - name: dev
+    url: "https://localhost:{port}"
+    tls_skip_verify: true
+  - name: strict
+    url: "https://localhost:{port}"
+routing:
+  - name: dev
+    match:
+      path_prefix: /dev
+    upstream: dev
+  - name: strict
+    match:
+      path_prefix: /strict
+    upstream: strict
+"#
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        assert_eq!(status(addr, "/dev").await, 200);
+        assert_eq!(status(addr, "/strict").await, 502);
+    }
+}
+
+#[cfg(test)]
+mod grpc_fault_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Send a unary call with the given content type over a raw connection,
+    /// returning the whole response
+    async fn call(addr: std::net::SocketAddr, content_type: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "POST /inventory.Stock/Reserve HTTP/1.1\r\nHost: localhost\r\n\
+             Connection: close\r\nTE: trailers\r\nContent-Type: {content_type}\r\n\
+             Content-Length: 5\r\n\r\n\0\0\0\0\0"
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_grpc_request_gets_status_trailers() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {}
+rules:
+  - id: stock-unavailable
+    match:
+      path:
+        prefix: /inventory.Stock/
+    fault:
+      error:
+        probability: 1.0
+        status: 503
+        grpc:
+          status_code: 14
+          message: stock service down
+"#,
+            upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let response = call(addr, "application/grpc").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        // Chunked body: no data, then the trailers
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        assert_eq!(
+            body,
+            "0\r\ngrpc-status: 14\r\ngrpc-message: stock service down\r\n\r\n"
+        );
+
+        // Plain HTTP clients still get the HTTP error
+        let response = call(addr, "application/json").await;
+        assert!(
+            response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+            "{response}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod rate_limit_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+
+    #[tokio::test]
+    async fn test_rate_limit_rejects_excess_requests() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {}
+rules:
+  - id: limited
+    match:
+      path:
+        prefix: /api
+    fault:
+      rate_limit:
+        requests: 2
+        per_secs: 60
+        key: {{ header: x-api-key }}
+"#,
+            upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+        let client = reqwest::Client::new();
+        let get = |key: &'static str| {
+            client
+                .get(format!("http://{addr}/api/items"))
+                .header("x-api-key", key)
+                .send()
+        };
+
+        for _ in 0..2 {
+            let response = get("alpha").await.unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(response.text().await.unwrap(), "ok");
+        }
+        let response = get("alpha").await.unwrap();
+        assert_eq!(response.status(), 429);
+        assert_eq!(response.headers()["retry-after"], "30");
+        assert_eq!(response.headers()["x-rift-fault"], "rate_limit");
+
+        // Another key is counted separately
+        assert_eq!(get("beta").await.unwrap().status(), 200);
+    }
+}
+
+#[cfg(test)]
+mod mock_response_tests {
+    use super::serve;
+    use crate::proxy::server::ProxyServer;
+
+    #[tokio::test]
+    async fn test_mock_response_rendered_from_request() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        // Nothing listens upstream: the mock never forwards
+        let config: crate::config::Config = serde_yaml::from_str(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 9
+rules:
+  - id: order-mock
+    match:
+      path:
+        prefix: /orders
+    fault:
+      mock:
+        status: 201
+        headers:
+          X-Order-Source: mock
+        body: '{"path": "${path}", "qty": "${query.qty}", "trace": "${header.x-trace}", "id": "${body:$.order.id}", "missing": "${body:$.nope}"}'
+"#,
+        )
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{addr}/orders/new?qty=3"))
+            .header("x-trace", "t-42")
+            .body(r#"{"order": {"id": 7}}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+        assert_eq!(response.headers()["x-order-source"], "mock");
+        assert_eq!(response.headers()["x-rift-fault"], "mock");
+        assert_eq!(
+            response.text().await.unwrap(),
+            r#"{"path": "/orders/new", "qty": "3", "trace": "t-42", "id": "7", "missing": ""}"#
+        );
+    }
+}
+
+#[cfg(test)]
+mod response_sequence_tests {
+    use super::serve;
+    use crate::proxy::server::ProxyServer;
+
+    #[tokio::test]
+    async fn test_sequential_requests_get_configured_responses() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config: crate::config::Config = serde_yaml::from_str(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 9
+rules:
+  - id: recovering
+    match:
+      path:
+        exact: /status
+    fault:
+      responses:
+        - status: 503
+          body: warming up
+        - status: 503
+          body: still warming up
+        - status: 200
+          body: ready
+"#,
+        )
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let mut seen = Vec::new();
+        for _ in 0..3 {
+            let response = reqwest::get(format!("http://{addr}/status")).await.unwrap();
+            seen.push((response.status().as_u16(), response.text().await.unwrap()));
+        }
+        assert_eq!(
+            seen,
+            [
+                (503, "warming up".to_string()),
+                (503, "still warming up".to_string()),
+                (200, "ready".to_string()),
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod path_template_tests {
+    use super::serve;
+    use crate::proxy::server::ProxyServer;
+
+    #[tokio::test]
+    async fn test_path_template_captures_feed_response() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config: crate::config::Config = serde_yaml::from_str(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: 9
+rules:
+  - id: order-lookup
+    match:
+      path:
+        template: '/users/{id:\d+}/orders/{orderId}'
+    fault:
+      mock:
+        body: '{"user": "${pathParams.id}", "order": "${pathParams.orderId}"}'
+  - id: fallback
+    match:
+      path:
+        prefix: /users
+    fault:
+      error:
+        probability: 1.0
+        status: 404
+"#,
+        )
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let response = reqwest::get(format!("http://{addr}/users/42/orders/o-9"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.text().await.unwrap(),
+            r#"{"user": "42", "order": "o-9"}"#
+        );
+
+        // A non-numeric id or an extra segment falls through to the next rule
+        for path in ["/users/bob/orders/o-9", "/users/42/orders/o-9/items"] {
+            let response = reqwest::get(format!("http://{addr}{path}")).await.unwrap();
+            assert_eq!(response.status(), 404, "{path}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod dry_run_match_tests {
+    use super::serve_admin;
+    use crate::proxy::server::ProxyServer;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_match_endpoint_reports_rule_route_and_fault() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config: crate::config::Config = serde_yaml::from_str(
+            r#"
+listen:
+  port: 8080
+upstreams:
+  - name: orders
+    url: "http://127.0.0.1:9"
+  - name: users
+    url: "http://127.0.0.1:9"
+routing:
+  - name: orders-route
+    match:
+      path_prefix: /orders
+    upstream: orders
+  - name: users-route
+    match:
+      path_prefix: /users
+    upstream: users
+rules:
+  - id: users-only
+    upstream: users
+    match:
+      path:
+        prefix: /
+    fault:
+      latency:
+        probability: 1.0
+        min_ms: 50
+        max_ms: 50
+  - id: big-order
+    match:
+      methods: [POST]
+      path:
+        prefix: /orders
+      query:
+        - name: region
+          value: eu
+      body:
+        contains: '"qty":100'
+    fault:
+      error:
+        probability: 1.0
+        status: 503
+"#,
+        )
+        .unwrap();
+        let server = Arc::new(ProxyServer::new(config).await.unwrap());
+        let admin = serve_admin(&server).await;
+        let client = reqwest::Client::new();
+        let dry_run = |description: serde_json::Value| {
+            let request = client
+                .post(format!("http://{admin}/__rift/match"))
+                .json(&description);
+            async move {
+                let response = request.send().await.unwrap();
+                assert_eq!(response.status(), 200);
+                response.json::<serde_json::Value>().await.unwrap()
+            }
+        };
+
+        let result = dry_run(serde_json::json!({
+            "method": "POST",
+            "path": "/orders/new",
+            "query": {"region": "eu"},
+            "body": {"qty":100},
+        }))
+        .await;
+        assert_eq!(result["matched"], "big-order");
+        assert_eq!(result["route"]["name"], "orders-route");
+        assert_eq!(result["route"]["upstream"], "orders");
+        assert_eq!(result["fault"]["type"], "error");
+        assert_eq!(result["fault"]["status"], 503);
+
+        // The upstream-scoped rule only applies once routing picks its upstream
+        let result = dry_run(serde_json::json!({"path": "/users/7"})).await;
+        assert_eq!(result["matched"], "users-only");
+        assert_eq!(result["fault"]["type"], "latency");
+        assert_eq!(result["fault"]["min_ms"], 50);
+        assert_eq!(result["fault"]["max_ms"], 50);
+        assert_eq!(result["fault"]["probability"], 1.0);
+
+        let result = dry_run(serde_json::json!({"method": "POST", "path": "/orders/new"})).await;
+        assert!(result["matched"].is_null());
+        assert!(result["fault"].is_null());
+        assert_eq!(result["route"]["name"], "orders-route");
+    }
+
+    #[tokio::test]
+    async fn test_match_endpoint_limits_body_size() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config: crate::config::Config = serde_yaml::from_str(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 9\nmax_body_bytes: 64\n",
+        )
+        .unwrap();
+        let server = Arc::new(ProxyServer::new(config).await.unwrap());
+        let admin = serve_admin(&server).await;
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{admin}/__rift/match"))
+            .json(&serde_json::json!({"path": "/", "body": "x".repeat(100)}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 413);
+    }
+}
+
+#[cfg(test)]
+mod access_rule_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+
+    async fn proxy(upstream_port: u16, rules: &str) -> std::net::SocketAddr {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: {upstream_port}\nrules:\n{rules}"
+        ))
+        .unwrap();
+        serve(ProxyServer::new(config).await.unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn test_ip_denylist_rule_returns_forbidden() {
+        let upstream = spawn_upstream().await;
+        let addr = proxy(
+            upstream.port(),
+            r#"
+  - id: loopback-blocked
+    action:
+      deny:
+        body: blocked
+        headers:
+          X-Blocked-By: rift
+    match:
+      ip:
+        cidrs: ["127.0.0.0/8"]
+      path:
+        prefix: /private
+"#,
+        )
+        .await;
+
+        let response = reqwest::get(format!("http://{addr}/private/data"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403);
+        assert_eq!(response.headers()["x-rift-rule-id"], "loopback-blocked");
+        assert_eq!(response.headers()["x-blocked-by"], "rift");
+        assert_eq!(response.text().await.unwrap(), "blocked");
+
+        let response = reqwest::get(format!("http://{addr}/public")).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_allow_rule_bypasses_later_fault() {
+        let upstream = spawn_upstream().await;
+        let addr = proxy(
+            upstream.port(),
+            r#"
+  - id: everything-fails
+    match: {}
+    fault:
+      error:
+        probability: 1.0
+        status: 503
+  - id: health-exempt
+    action: allow
+    match:
+      path:
+        exact: /health
+"#,
+        )
+        .await;
+
+        // Listed after the fault rule, the allow rule still decides first
+        let response = reqwest::get(format!("http://{addr}/health")).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "ok");
+
+        let response = reqwest::get(format!("http://{addr}/orders")).await.unwrap();
+        assert_eq!(response.status(), 503);
+    }
+}
+
+#[cfg(test)]
+mod rule_toggle_tests {
+    use super::{serve, serve_admin, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_admin_toggles_rule_at_runtime() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {}
+rules:
+  - id: outage
+    match: {{}}
+    fault:
+      error:
+        probability: 1.0
+        status: 503
+"#,
+            upstream.port()
+        ))
+        .unwrap();
+        let server = Arc::new(ProxyServer::new(config).await.unwrap());
+        let admin = serve_admin(&server).await;
+        let addr = serve(server).await;
+        let client = reqwest::Client::new();
+        let status = |path: &str| {
+            let request = client.get(format!("http://{addr}{path}"));
+            async move { request.send().await.unwrap().status() }
+        };
+        let toggle = |path: &str| {
+            let request = client.post(format!("http://{admin}/__rift/rules/{path}"));
+            async move { request.send().await.unwrap() }
+        };
+
+        assert_eq!(status("/orders").await, 503);
+
+        let response = toggle("outage/disable").await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["enabled"], false);
+        assert_eq!(status("/orders").await, 200);
+
+        assert_eq!(toggle("outage/enable").await.status(), 200);
+        assert_eq!(status("/orders").await, 503);
+
+        assert_eq!(toggle("missing/disable").await.status(), 404);
+        let wrong_method = client
+            .get(format!("http://{admin}/__rift/rules/outage/disable"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(wrong_method.status(), 405);
+
+        // The proxy listener doesn't serve toggles: the request meets the
+        // enabled rule like any other
+        let response = client
+            .post(format!("http://{addr}/_rift/rules/outage/disable"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 503);
+        assert_eq!(status("/orders").await, 503);
+    }
+
+    #[tokio::test]
+    async fn test_admin_toggles_script_rule() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {}
+script_rules:
+  - id: scripted-outage
+    script: |
+      fn should_inject(request, flow_store) {{
+        #{{ inject: true, fault: "error", status: 503, body: "Injected" }}
+      }}
+"#,
+            upstream.port()
+        ))
+        .unwrap();
+        let server = Arc::new(ProxyServer::new(config).await.unwrap());
+        let admin = serve_admin(&server).await;
+        let addr = serve(server).await;
+        let client = reqwest::Client::new();
+        let status = || {
+            let request = client.get(format!("http://{addr}/orders"));
+            async move { request.send().await.unwrap().status() }
+        };
+        let toggle = |action: &str| {
+            let url = format!("http://{admin}/__rift/rules/scripted-outage/{action}");
+            let request = client.post(url);
+            async move { request.send().await.unwrap().status() }
+        };
+
+        assert_eq!(status().await, 503);
+        assert_eq!(toggle("disable").await, 200);
+        assert_eq!(status().await, 200);
+        assert_eq!(toggle("enable").await, 200);
+        assert_eq!(status().await, 503);
+    }
+}
+
+#[cfg(test)]
+mod response_header_tests {
+    use super::serve;
+    use crate::proxy::server::ProxyServer;
+
+    /// Upstream answering with its own CORS and cache headers
+    async fn spawn_upstream_with_headers() -> std::net::SocketAddr {
+        use http_body_util::Full;
+        use hyper::body::Bytes;
+        use hyper::server::conn::http1;
+        use hyper::service::service_fn;
+        use hyper_util::rt::TokioIo;
+        use std::convert::Infallible;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let service = service_fn(|_req| async {
+                    let response = hyper::Response::builder()
+                        .header("access-control-allow-origin", "https://app.example")
+                        .header("cache-control", "max-age=60")
+                        .body(Full::new(Bytes::from("ok")))
+                        .unwrap();
+                    Ok::<_, Infallible>(response)
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_response_headers_on_proxied_and_faulted_responses() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream_with_headers().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {}
+response_headers:
+  add:
+    Access-Control-Allow-Origin: "*"
+    X-Content-Type-Options: nosniff
+  set:
+    Cache-Control: no-store
+rules:
+  - id: broken
+    match:
+      path:
+        prefix: /broken
+    fault:
+      error:
+        probability: 1.0
+        status: 500
+"#,
+            upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        // `add` keeps the upstream's value, `set` replaces it
+        let response = reqwest::get(format!("http://{addr}/ok")).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://app.example"
+        );
+        assert_eq!(headers["x-content-type-options"], "nosniff");
+        assert_eq!(headers["cache-control"], "no-store");
+        assert_eq!(headers.get_all("cache-control").iter().count(), 1);
+
+        let response = reqwest::get(format!("http://{addr}/broken")).await.unwrap();
+        assert_eq!(response.status(), 500);
+        let headers = response.headers();
+        assert_eq!(headers["access-control-allow-origin"], "*");
+        assert_eq!(headers["x-content-type-options"], "nosniff");
+        assert_eq!(headers["cache-control"], "no-store");
+    }
+
+    #[tokio::test]
+    async fn test_route_response_headers_override_global() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream_with_headers().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstreams:
+  - name: app
+    url: "http://{upstream}"
+routing:
+  - name: public
+    match:
+      path_prefix: /public
+    upstream: app
+    response_headers:
+      set:
+        Access-Control-Allow-Origin: "*"
+      add:
+        Cache-Control: no-store
+  - name: default
+    match:
+      path_prefix: /
+    upstream: app
+response_headers:
+  add:
+    Access-Control-Allow-Origin: https://fallback.example
+  set:
+    Cache-Control: private
+"#
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let response = reqwest::get(format!("http://{addr}/public/page"))
+            .await
+            .unwrap();
+        let headers = response.headers();
+        assert_eq!(headers["access-control-allow-origin"], "*");
+        // The route's `add` replaces the global `set`, so the upstream's value stays
+        assert_eq!(headers["cache-control"], "max-age=60");
+
+        let response = reqwest::get(format!("http://{addr}/other")).await.unwrap();
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://app.example"
+        );
+        assert_eq!(headers["cache-control"], "private");
+    }
+
+    #[test]
+    fn test_invalid_response_header_is_rejected() {
+        let config: crate::config::Config = serde_yaml::from_str(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 9\n\
+             response_headers:\n  set:\n    \"Bad Header\": x\n",
+        )
+        .unwrap();
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("response_headers"), "{error}");
+        assert!(error.contains("Bad Header"), "{error}");
+    }
+}
+
+#[cfg(test)]
+mod cors_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+
+    async fn proxy(upstream_port: u16) -> std::net::SocketAddr {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {upstream_port}
+cors:
+  allowed_origins: ["https://app.example"]
+  allowed_methods: [GET, POST]
+  allowed_headers: [Content-Type, Authorization]
+  max_age_secs: 600
+  allow_credentials: true
+"#
+        ))
+        .unwrap();
+        serve(ProxyServer::new(config).await.unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn test_preflight_is_answered_without_upstream() {
+        // Nothing listens on port 9, so only Rift can answer
+        let addr = proxy(9).await;
+        let client = reqwest::Client::new();
+        let preflight = |origin: &str, method: &str| {
+            client
+                .request(
+                    reqwest::Method::OPTIONS,
+                    format!("http://{addr}/api/orders"),
+                )
+                .header("Origin", origin)
+                .header("Access-Control-Request-Method", method)
+                .header("Access-Control-Request-Headers", "content-type")
+                .send()
+        };
+
+        let response = preflight("https://app.example", "POST").await.unwrap();
+        assert_eq!(response.status(), 204);
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://app.example"
+        );
+        assert_eq!(headers["access-control-allow-methods"], "GET, POST");
+        assert_eq!(
+            headers["access-control-allow-headers"],
+            "Content-Type, Authorization"
+        );
+        assert_eq!(headers["access-control-max-age"], "600");
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+        assert_eq!(headers["vary"], "Origin");
+
+        for (origin, method) in [
+            ("https://evil.example", "POST"),
+            ("https://app.example", "DELETE"),
+        ] {
+            let response = preflight(origin, method).await.unwrap();
+            assert_eq!(response.status(), 403, "{origin} {method}");
+            assert!(!response
+                .headers()
+                .contains_key("access-control-allow-origin"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_simple_request_gets_allow_origin() {
+        let upstream = spawn_upstream().await;
+        let addr = proxy(upstream.port()).await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .get(format!("http://{addr}/api/orders"))
+            .header("Origin", "https://app.example")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://app.example"
+        );
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+        assert_eq!(response.text().await.unwrap(), "ok");
+
+        let response = client
+            .get(format!("http://{addr}/api/orders"))
+            .header("Origin", "https://evil.example")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(!response
+            .headers()
+            .contains_key("access-control-allow-origin"));
+    }
+}
+
+#[cfg(test)]
+mod payload_matcher_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+
+    #[tokio::test]
+    async fn test_large_json_bodies_trigger_fault() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {}
+rules:
+  - id: large-json
+    match:
+      methods: [POST]
+      bodySize:
+        gt: 1024
+      contentType: application/json
+    fault:
+      error:
+        probability: 1.0
+        status: 413
+"#,
+            upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+        let client = reqwest::Client::new();
+        let post = |content_type: &'static str, size: usize| {
+            client
+                .post(format!("http://{addr}/upload"))
+                .header("Content-Type", content_type)
+                .body(format!(r#"{{"data":"{}"}}"#, "x".repeat(size)))
+                .send()
+        };
+
+        let response = post("application/json; charset=utf-8", 2048).await.unwrap();
+        assert_eq!(response.status(), 413);
+        assert_eq!(response.headers()["x-rift-rule-id"], "large-json");
+
+        let response = post("application/json", 100).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let response = post("text/plain", 2048).await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+}
+
+#[cfg(test)]
+mod script_rule_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+
+    #[tokio::test]
+    async fn test_inactive_script_rules_are_skipped() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let fail = r#"
+      fn should_inject(request, flow_store) {
+        #{ inject: true, fault: "error", status: 503, body: "Injected" }
+      }"#;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {port}
+script_rules:
+  - id: disabled
+    enabled: false
+    script: |{fail}
+    match:
+      path:
+        prefix: /disabled
+  - id: scheduled
+    active_window: {{ from: "2999-01-01T00:00:00Z", to: "2999-01-02T00:00:00Z" }}
+    script: |{fail}
+    match:
+      path:
+        prefix: /scheduled
+  - id: active
+    script: |{fail}
+    match:
+      path:
+        prefix: /active
+"#,
+            port = upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        for (path, expected) in [("/disabled", 200), ("/scheduled", 200), ("/active", 503)] {
+            let response = reqwest::get(format!("http://{addr}{path}")).await.unwrap();
+            assert_eq!(response.status(), expected, "{path}");
+        }
+    }
+}
+
+#[cfg(all(test, feature = "lua"))]
+mod lua_script_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+
+    /// Lua version of the Rhai `progressive-failure` example: the first two
+    /// attempts of a flow fail, later ones reach the upstream
+    #[tokio::test]
+    async fn test_lua_progressive_failure() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {port}
+script_engine:
+  engine: lua
+flow_state:
+  backend: inmemory
+  ttl_seconds: 300
+script_rules:
+  - id: progressive-failure
+    script: |
+      function should_inject(request, flow_store)
+        local flow_id = request.headers["x-flow-id"]
+        local attempts = flow_store:increment(flow_id, "attempts")
+        if attempts <= 2 then
+          return {{ inject = true, fault = "error", status = 503, body = "Retry" }}
+        end
+        return {{ inject = false }}
+      end
+    match:
+      methods: ["POST"]
+      path:
+        prefix: /api
+"#,
+            port = upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let client = reqwest::Client::new();
+        let attempt = |flow: &'static str| {
+            client
+                .post(format!("http://{addr}/api/orders"))
+                .header("x-flow-id", flow)
+                .send()
+        };
+        for expected in [503, 503, 200, 200] {
+            let response = attempt("flow-a").await.unwrap();
+            assert_eq!(response.status(), expected);
+        }
+        let response = attempt("flow-a").await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+
+        // Each flow counts its own attempts
+        let response = attempt("flow-b").await.unwrap();
+        assert_eq!(response.status(), 503);
+        assert_eq!(response.text().await.unwrap(), "Retry");
+    }
+
+    #[tokio::test]
+    async fn test_lua_on_response_rewrites_upstream_response() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {port}
+script_engine:
+  engine: lua
+script_rules:
+  - id: rewrite-response
+    script: |
+      function should_inject(request, flow_store)
+        return {{ inject = false }}
+      end
+
+      function on_response(request, response, flow_store)
+        response.status = 201
+        response.headers["x-rewritten"] = "yes"
+        response.body = response.body .. " (rewritten)"
+        return response
+      end
+    match:
+      path:
+        prefix: /api
+"#,
+            port = upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let response = reqwest::get(format!("http://{addr}/api/orders"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+        assert_eq!(response.headers()["x-rewritten"], "yes");
+        assert_eq!(response.text().await.unwrap(), "ok (rewritten)");
+    }
+}
+
+#[cfg(all(test, feature = "javascript"))]
+mod js_script_tests {
+    use super::{serve, spawn_upstream};
+    use crate::proxy::server::ProxyServer;
+
+    #[tokio::test]
+    async fn test_javascript_script_rule() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let upstream = spawn_upstream().await;
+        let config: crate::config::Config = serde_yaml::from_str(&format!(
+            r#"
+listen:
+  port: 8080
+upstream:
+  host: 127.0.0.1
+  port: {port}
+script_engine:
+  engine: javascript
+flow_state:
+  backend: inmemory
+  ttl_seconds: 300
+script_rules:
+  - id: js-first-call-fails
+    script: |
+      function should_inject(request, flow_store) {{
+        const calls = flow_store.increment(request.headers["x-flow-id"], "calls");
+        if (calls === 1) {{
+          return {{ inject: true, fault: "error", status: 429, body: "slow down" }};
+        }}
+        return {{ inject: false }};
+      }}
+    match:
+      path:
+        prefix: /api
+"#,
+            port = upstream.port()
+        ))
+        .unwrap();
+        let addr = serve(ProxyServer::new(config).await.unwrap()).await;
+
+        let client = reqwest::Client::new();
+        let call = || {
+            client
+                .get(format!("http://{addr}/api/items"))
+                .header("x-flow-id", "js-flow")
+                .send()
+        };
+        let response = call().await.unwrap();
+        assert_eq!(response.status(), 429);
+        assert_eq!(response.text().await.unwrap(), "slow down");
+
+        let response = call().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "ok");
+    }
+}
@@ -1,10 +1,12 @@
 //! TLS utilities for the proxy server.
 //!
-//! This module provides TLS-related functionality including certificate loading
-//! and a no-op certificate verifier for development/testing.
+//! This module provides TLS-related functionality including certificate loading,
+//! upstream client configuration and a no-op certificate verifier for
+//! development/testing.
 
+use crate::config::UpstreamTlsConfig;
 use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
-use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
 use rustls::DigitallySignedStruct;
 use std::sync::Arc;
 use tokio_rustls::TlsAcceptor;
@@ -56,39 +58,94 @@ impl ServerCertVerifier for NoVerifier {
     }
 }
 
-/// Create TLS acceptor from certificate and key files.
-pub fn create_tls_acceptor(cert_path: &str, key_path: &str) -> Result<TlsAcceptor, anyhow::Error> {
-    // Load certificate chain
-    let cert_file = std::fs::File::open(cert_path)
-        .map_err(|e| anyhow::anyhow!("Failed to open certificate file '{cert_path}': {e}"))?;
-    let mut cert_reader = std::io::BufReader::new(cert_file);
-    let certs: Vec<CertificateDer> = rustls_pemfile::certs(&mut cert_reader)
+/// ALPN protocol ID of HTTP/2
+pub const ALPN_H2: &[u8] = b"h2";
+
+/// Load a PEM certificate chain, failing if the file has none
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, anyhow::Error> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to open certificate file '{path}': {e}"))?;
+    let mut reader = std::io::BufReader::new(file);
+    let certs: Vec<CertificateDer> = rustls_pemfile::certs(&mut reader)
         .collect::<Result<_, _>>()
-        .map_err(|e| anyhow::anyhow!("Failed to parse certificate file: {e}"))?;
+        .map_err(|e| anyhow::anyhow!("Failed to parse certificate file '{path}': {e}"))?;
 
     if certs.is_empty() {
-        anyhow::bail!("No certificates found in certificate file: {cert_path}");
+        anyhow::bail!("No certificates found in certificate file: {path}");
     }
+    Ok(certs)
+}
 
-    // Load private key
-    let key_file = std::fs::File::open(key_path)
-        .map_err(|e| anyhow::anyhow!("Failed to open private key file '{key_path}': {e}"))?;
-    let mut key_reader = std::io::BufReader::new(key_file);
+/// Load a PEM private key (PKCS8, RSA or EC)
+fn load_private_key(path: &str) -> Result<PrivateKeyDer<'static>, anyhow::Error> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to open private key file '{path}': {e}"))?;
+    let mut reader = std::io::BufReader::new(file);
+    rustls_pemfile::private_key(&mut reader)
+        .map_err(|e| anyhow::anyhow!("Failed to parse private key file '{path}': {e}"))?
+        .ok_or_else(|| anyhow::anyhow!("No private key found in key file: {path}"))
+}
 
-    // Try reading as PKCS8, RSA, or EC private key
-    let key = rustls_pemfile::private_key(&mut key_reader)
-        .map_err(|e| anyhow::anyhow!("Failed to parse private key file: {e}"))?
-        .ok_or_else(|| anyhow::anyhow!("No private key found in key file: {key_path}"))?;
+/// Create TLS acceptor from certificate and key files.
+///
+/// The acceptor offers HTTP/2 and HTTP/1.1 through ALPN.
+pub fn create_tls_acceptor(cert_path: &str, key_path: &str) -> Result<TlsAcceptor, anyhow::Error> {
+    let certs = load_certs(cert_path)?;
+    let key = load_private_key(key_path)?;
 
     // Build TLS server configuration
-    let config = rustls::ServerConfig::builder()
+    let mut config = rustls::ServerConfig::builder()
         .with_no_client_auth()
         .with_single_cert(certs, key)
         .map_err(|e| anyhow::anyhow!("Failed to build TLS configuration: {e}"))?;
+    config.alpn_protocols = vec![ALPN_H2.to_vec(), b"http/1.1".to_vec()];
 
     Ok(TlsAcceptor::from(Arc::new(config)))
 }
 
+/// Create the TLS client configuration for connections to an upstream.
+///
+/// The upstream's certificate is checked against `ca_path` if set, or the
+/// system roots otherwise, unless `skip_verify` is set. The client
+/// certificate is presented when the upstream asks for one.
+pub fn create_client_tls_config(
+    tls: &UpstreamTlsConfig,
+    skip_verify: bool,
+) -> Result<rustls::ClientConfig, anyhow::Error> {
+    let builder = rustls::ClientConfig::builder();
+    let builder = if skip_verify {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerifier))
+    } else {
+        let mut roots = rustls::RootCertStore::empty();
+        match &tls.ca_path {
+            Some(ca_path) => {
+                for cert in load_certs(ca_path)? {
+                    roots.add(cert).map_err(|e| {
+                        anyhow::anyhow!("Invalid CA certificate in '{ca_path}': {e}")
+                    })?;
+                }
+            }
+            None => {
+                let native = rustls_native_certs::load_native_certs();
+                roots.add_parsable_certificates(native.certs);
+                if roots.is_empty() {
+                    anyhow::bail!("No native root certificates found");
+                }
+            }
+        }
+        builder.with_root_certificates(roots)
+    };
+
+    match (&tls.client_cert_path, &tls.client_key_path) {
+        (Some(cert_path), Some(key_path)) => builder
+            .with_client_auth_cert(load_certs(cert_path)?, load_private_key(key_path)?)
+            .map_err(|e| anyhow::anyhow!("Invalid client certificate or key: {e}")),
+        _ => Ok(builder.with_no_client_auth()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
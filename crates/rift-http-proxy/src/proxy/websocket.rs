@@ -0,0 +1,216 @@
+//! WebSocket pass-through.
+//!
+//! The opening handshake is an ordinary HTTP request, so it goes through
+//! rule matching like any other: a rule can fail or delay it before the
+//! upgrade happens. When the upstream accepts (`101 Switching Protocols`),
+//! both connections are taken over from hyper and bytes are copied between
+//! them until either side closes. Frames are not inspected.
+
+use hyper::body::Incoming;
+use hyper::header::{CONNECTION, UPGRADE};
+use hyper::http::request::Parts;
+use hyper::upgrade::OnUpgrade;
+use hyper::{HeaderMap, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::borrow::Cow;
+use tracing::{debug, warn};
+
+/// Whether the headers carry a WebSocket opening handshake
+pub fn is_upgrade_request(headers: &HeaderMap) -> bool {
+    let has_token = |name, token: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|v| v.trim().eq_ignore_ascii_case(token))
+    };
+    has_token(CONNECTION, "upgrade") && has_token(UPGRADE, "websocket")
+}
+
+/// The client side of the upgrade, if this request is a WebSocket handshake
+pub fn client_upgrade(parts: &Parts) -> Option<OnUpgrade> {
+    if !is_upgrade_request(&parts.headers) {
+        return None;
+    }
+    parts.extensions.get::<OnUpgrade>().cloned()
+}
+
+/// Map a `ws://` or `wss://` upstream to the scheme its handshake is sent
+/// over; other URLs are returned unchanged.
+pub fn http_upstream(upstream_uri: &str) -> Cow<'_, str> {
+    if let Some(rest) = upstream_uri.strip_prefix("ws://") {
+        Cow::Owned(format!("http://{rest}"))
+    } else if let Some(rest) = upstream_uri.strip_prefix("wss://") {
+        Cow::Owned(format!("https://{rest}"))
+    } else {
+        Cow::Borrowed(upstream_uri)
+    }
+}
+
+/// Connect the client and upstream connections once the upstream has
+/// accepted the upgrade.
+///
+/// Responses other than `101` are left alone and sent to the client as-is.
+pub fn splice(client: OnUpgrade, upstream_response: &mut Response<Incoming>) {
+    if upstream_response.status() != StatusCode::SWITCHING_PROTOCOLS {
+        return;
+    }
+    let upstream = hyper::upgrade::on(upstream_response);
+    tokio::spawn(async move {
+        let (client, upstream) = match tokio::try_join!(client, upstream) {
+            Ok(upgraded) => upgraded,
+            Err(e) => {
+                warn!("WebSocket upgrade failed: {}", e);
+                return;
+            }
+        };
+        let (mut client, mut upstream) = (TokioIo::new(client), TokioIo::new(upstream));
+        match tokio::io::copy_bidirectional(&mut client, &mut upstream).await {
+            Ok((sent, received)) => {
+                debug!(
+                    "WebSocket closed: {} bytes to upstream, {} bytes to client",
+                    sent, received
+                );
+            }
+            Err(e) => debug!("WebSocket connection ended: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::forwarding::forward_request_streaming;
+    use http_body_util::combinators::BoxBody;
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use std::convert::Infallible;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Upstream that accepts any handshake and then echoes raw bytes
+    async fn spawn_echo_upstream() -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            read_head(&mut stream).await;
+            stream
+                .write_all(
+                    b"HTTP/1.1 101 Switching Protocols\r\n\
+                      Upgrade: websocket\r\nConnection: Upgrade\r\n\r\n",
+                )
+                .await
+                .unwrap();
+            let mut buf = [0u8; 1024];
+            loop {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => stream.write_all(&buf[..n]).await.unwrap(),
+                }
+            }
+        });
+        addr
+    }
+
+    /// Proxy serving one connection through the regular forwarding path
+    async fn spawn_proxy(upstream: String) -> std::net::SocketAddr {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config: crate::config::Config = serde_yaml::from_str(
+            "listen:\n  port: 8080\nupstream:\n  host: 127.0.0.1\n  port: 8000\n",
+        )
+        .unwrap();
+        let client = crate::proxy::client::create_http_client(&config).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let service = service_fn(move |req: hyper::Request<Incoming>| {
+                let client = client.clone();
+                let upstream = upstream.clone();
+                async move {
+                    Ok::<_, Infallible>(
+                        forward_request_streaming(
+                            &client,
+                            req.map(BoxBody::new),
+                            &upstream,
+                            Default::default(),
+                        )
+                        .await,
+                    )
+                }
+            });
+            http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades()
+                .await
+                .unwrap();
+        });
+        addr
+    }
+
+    /// Read an HTTP head, returning it as text
+    async fn read_head(stream: &mut TcpStream) -> String {
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8];
+            stream.read_exact(&mut byte).await.unwrap();
+            head.push(byte[0]);
+        }
+        String::from_utf8(head).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_websocket_echo_through_proxy() {
+        let upstream = spawn_echo_upstream().await;
+        let proxy = spawn_proxy(format!("ws://{upstream}")).await;
+
+        let mut client = TcpStream::connect(proxy).await.unwrap();
+        client
+            .write_all(
+                b"GET /live HTTP/1.1\r\nHost: localhost\r\n\
+                  Connection: Upgrade\r\nUpgrade: websocket\r\n\
+                  Sec-WebSocket-Version: 13\r\n\
+                  Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let head = read_head(&mut client).await;
+        assert!(
+            head.starts_with("HTTP/1.1 101"),
+            "unexpected response: {head}"
+        );
+
+        // Bytes flow both ways over the spliced connection
+        for message in [&b"\x81\x05hello"[..], b"\x81\x05again"] {
+            client.write_all(message).await.unwrap();
+            let mut echoed = vec![0u8; message.len()];
+            client.read_exact(&mut echoed).await.unwrap();
+            assert_eq!(echoed, message);
+        }
+    }
+
+    #[test]
+    fn test_is_upgrade_request() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONNECTION, "keep-alive, Upgrade".parse().unwrap());
+        headers.insert(UPGRADE, "WebSocket".parse().unwrap());
+        assert!(is_upgrade_request(&headers));
+
+        headers.insert(UPGRADE, "h2c".parse().unwrap());
+        assert!(!is_upgrade_request(&headers));
+
+        headers.remove(CONNECTION);
+        headers.insert(UPGRADE, "websocket".parse().unwrap());
+        assert!(!is_upgrade_request(&headers));
+    }
+
+    #[test]
+    fn test_http_upstream() {
+        assert_eq!(http_upstream("ws://backend:8080"), "http://backend:8080");
+        assert_eq!(http_upstream("wss://backend"), "https://backend");
+        assert_eq!(http_upstream("http://backend"), "http://backend");
+    }
+}
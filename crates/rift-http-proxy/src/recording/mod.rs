@@ -8,22 +8,32 @@
 //! Features:
 //! - `addWaitBehavior`: Capture actual latency in recorded responses
 //! - `predicateGenerators`: Auto-generate stubs from recorded requests
-//! - File-based persistence for recordings
+//! - File and Redis persistence for recordings, with optional fallback chains
 //!
 //! # Module Structure
 //!
 //! - `mode` - Proxy recording mode enum
 //! - `types` - Response and signature types
 //! - `store` - Recording store implementation
+//! - `persistence` - Save/load backends for recordings
 //! - `stub_generator` - Mountebank stub generation
 
 mod mode;
+mod persistence;
 mod store;
 mod stub_generator;
 mod types;
 
 // Re-export main types
 pub use mode::ProxyMode;
+#[cfg(feature = "redis-backend")]
+#[allow(unused_imports)]
+pub use persistence::RedisRecordingBackend;
+#[allow(unused_imports)]
+pub use persistence::{
+    backend_from_config, FallbackRecordingBackend, FileRecordingBackend, RecordingBackend,
+    RecordingSnapshot,
+};
 pub use store::RecordingStore;
 #[allow(unused_imports)]
 pub use stub_generator::generate_stub;
@@ -0,0 +1,308 @@
+//! Persistence backends for recorded responses.
+//!
+//! A backend saves and loads a snapshot of the recording store. Backends can
+//! be chained with [`FallbackRecordingBackend`] so that, for example, Redis is
+//! tried first and a local file is used when Redis is unavailable.
+
+use super::types::{RecordedResponse, RequestSignature};
+use crate::config::RecordingPersistence;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+/// Serializable snapshot of a recording store
+pub type RecordingSnapshot = Vec<(RequestSignature, Vec<RecordedResponse>)>;
+
+/// Storage backend for recording snapshots
+pub trait RecordingBackend: Send + Sync {
+    /// Human-readable backend description, used in logs
+    fn name(&self) -> String;
+
+    /// Persist a snapshot, replacing any previously saved one
+    fn save(&self, recordings: &RecordingSnapshot) -> io::Result<()>;
+
+    /// Load the saved snapshot (empty if nothing has been saved yet)
+    fn load(&self) -> io::Result<RecordingSnapshot>;
+}
+
+fn to_json(recordings: &RecordingSnapshot) -> io::Result<String> {
+    serde_json::to_string_pretty(recordings)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+fn from_json(json: &str) -> io::Result<RecordingSnapshot> {
+    serde_json::from_str(json)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+/// File-based backend storing recordings as JSON
+pub struct FileRecordingBackend {
+    path: PathBuf,
+}
+
+impl FileRecordingBackend {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl RecordingBackend for FileRecordingBackend {
+    fn name(&self) -> String {
+        format!("file:{}", self.path.display())
+    }
+
+    fn save(&self, recordings: &RecordingSnapshot) -> io::Result<()> {
+        fs::write(&self.path, to_json(recordings)?)
+    }
+
+    fn load(&self) -> io::Result<RecordingSnapshot> {
+        if !self.path.exists() {
+            debug!(
+                "Recording file {:?} does not exist, starting fresh",
+                self.path
+            );
+            return Ok(Vec::new());
+        }
+        from_json(&fs::read_to_string(&self.path)?)
+    }
+}
+
+/// Redis-based backend storing recordings as a single JSON value
+#[cfg(feature = "redis-backend")]
+pub struct RedisRecordingBackend {
+    client: redis::Client,
+    key: String,
+}
+
+#[cfg(feature = "redis-backend")]
+impl RedisRecordingBackend {
+    /// Default Redis key for the recording snapshot
+    pub const DEFAULT_KEY: &'static str = "rift:recordings";
+
+    pub fn new(url: &str) -> io::Result<Self> {
+        let client = redis::Client::open(url).map_err(io::Error::other)?;
+        Ok(Self {
+            client,
+            key: Self::DEFAULT_KEY.to_string(),
+        })
+    }
+}
+
+#[cfg(feature = "redis-backend")]
+impl RecordingBackend for RedisRecordingBackend {
+    fn name(&self) -> String {
+        format!("redis:{}", self.client.get_connection_info().addr)
+    }
+
+    fn save(&self, recordings: &RecordingSnapshot) -> io::Result<()> {
+        use redis::Commands;
+        let mut conn = self.client.get_connection().map_err(io::Error::other)?;
+        conn.set::<_, _, ()>(&self.key, to_json(recordings)?)
+            .map_err(io::Error::other)
+    }
+
+    fn load(&self) -> io::Result<RecordingSnapshot> {
+        use redis::Commands;
+        let mut conn = self.client.get_connection().map_err(io::Error::other)?;
+        let json: Option<String> = conn.get(&self.key).map_err(io::Error::other)?;
+        json.map_or_else(|| Ok(Vec::new()), |json| from_json(&json))
+    }
+}
+
+/// Backend that tries a primary backend and falls back to a secondary on failure
+pub struct FallbackRecordingBackend {
+    primary: Box<dyn RecordingBackend>,
+    secondary: Box<dyn RecordingBackend>,
+}
+
+impl FallbackRecordingBackend {
+    pub fn new(primary: Box<dyn RecordingBackend>, secondary: Box<dyn RecordingBackend>) -> Self {
+        Self { primary, secondary }
+    }
+}
+
+impl RecordingBackend for FallbackRecordingBackend {
+    fn name(&self) -> String {
+        format!("{} -> {}", self.primary.name(), self.secondary.name())
+    }
+
+    fn save(&self, recordings: &RecordingSnapshot) -> io::Result<()> {
+        self.primary.save(recordings).or_else(|e| {
+            warn!(
+                "Saving recordings to {} failed ({}), falling back to {}",
+                self.primary.name(),
+                e,
+                self.secondary.name()
+            );
+            self.secondary.save(recordings)
+        })
+    }
+
+    fn load(&self) -> io::Result<RecordingSnapshot> {
+        self.primary.load().or_else(|e| {
+            warn!(
+                "Loading recordings from {} failed ({}), falling back to {}",
+                self.primary.name(),
+                e,
+                self.secondary.name()
+            );
+            self.secondary.load()
+        })
+    }
+}
+
+/// Build a backend (including any fallback chain) from persistence configuration
+pub fn backend_from_config(config: &RecordingPersistence) -> io::Result<Box<dyn RecordingBackend>> {
+    let backend: Box<dyn RecordingBackend> = match config.backend.as_str() {
+        "file" => {
+            let path = config.path.as_deref().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "file persistence requires 'path'",
+                )
+            })?;
+            Box::new(FileRecordingBackend::new(path))
+        }
+        #[cfg(feature = "redis-backend")]
+        "redis" => {
+            let url = config.redis_url.as_deref().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "redis persistence requires 'redisUrl'",
+                )
+            })?;
+            Box::new(RedisRecordingBackend::new(url)?)
+        }
+        other => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unsupported recording persistence backend: {other}"),
+            ))
+        }
+    };
+
+    match &config.fallback {
+        Some(fallback) => Ok(Box::new(FallbackRecordingBackend::new(
+            backend,
+            backend_from_config(fallback)?,
+        ))),
+        None => Ok(backend),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Mock Redis backend that is always unreachable
+    struct UnreachableRedis {
+        attempts: Arc<AtomicUsize>,
+    }
+
+    impl RecordingBackend for UnreachableRedis {
+        fn name(&self) -> String {
+            "redis:mock".to_string()
+        }
+
+        fn save(&self, _recordings: &RecordingSnapshot) -> io::Result<()> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                "connection refused",
+            ))
+        }
+
+        fn load(&self) -> io::Result<RecordingSnapshot> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                "connection refused",
+            ))
+        }
+    }
+
+    fn snapshot() -> RecordingSnapshot {
+        vec![(
+            RequestSignature::new("GET", "/users", None, &[]),
+            vec![RecordedResponse {
+                status: 200,
+                headers: HashMap::new(),
+                body: b"[]".to_vec(),
+                latency_ms: Some(5),
+                timestamp_secs: 0,
+            }],
+        )]
+    }
+
+    #[test]
+    fn test_fallback_to_file_when_redis_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recordings.json");
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let backend = FallbackRecordingBackend::new(
+            Box::new(UnreachableRedis {
+                attempts: attempts.clone(),
+            }),
+            Box::new(FileRecordingBackend::new(&path)),
+        );
+
+        backend.save(&snapshot()).unwrap();
+        assert!(path.exists());
+
+        let loaded = backend.load().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].0.path, "/users");
+        assert_eq!(loaded[0].1[0].body, b"[]");
+        // Primary was attempted for both save and load
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_fallback_skipped_when_primary_succeeds() {
+        let dir = tempfile::tempdir().unwrap();
+        let primary_path = dir.path().join("primary.json");
+        let secondary_path = dir.path().join("secondary.json");
+        let backend = FallbackRecordingBackend::new(
+            Box::new(FileRecordingBackend::new(&primary_path)),
+            Box::new(FileRecordingBackend::new(&secondary_path)),
+        );
+
+        backend.save(&snapshot()).unwrap();
+        assert!(primary_path.exists());
+        assert!(!secondary_path.exists());
+    }
+
+    #[cfg(feature = "redis-backend")]
+    #[test]
+    fn test_backend_from_config_with_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recordings.json");
+        let yaml = format!(
+            r#"
+backend: redis
+redisUrl: "redis://127.0.0.1:1"
+fallback:
+  backend: file
+  path: "{}"
+"#,
+            path.display()
+        );
+        let config: RecordingPersistence = serde_yaml::from_str(&yaml).unwrap();
+        let backend = backend_from_config(&config).unwrap();
+
+        // Nothing listens on port 1, so the file fallback must be used
+        backend.save(&snapshot()).unwrap();
+        assert!(path.exists());
+        assert_eq!(backend.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_backend_from_config_requires_path() {
+        let config: RecordingPersistence = serde_yaml::from_str("backend: file").unwrap();
+        assert!(backend_from_config(&config).is_err());
+    }
+}
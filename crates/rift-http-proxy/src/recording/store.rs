@@ -1,13 +1,13 @@
 //! Recording store for proxy responses.
 
 use super::mode::ProxyMode;
+use super::persistence::{FileRecordingBackend, RecordingBackend, RecordingSnapshot};
 use super::stub_generator::generate_stub;
 use super::types::{RecordedResponse, RequestSignature};
 use parking_lot::RwLock;
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
-use tracing::{debug, info};
+use tracing::info;
 
 /// Recording store for proxy responses
 pub struct RecordingStore {
@@ -96,39 +96,43 @@ impl RecordingStore {
     /// Save recordings to file (JSON format)
     // Public API for persistence
     pub fn save_to_file(&self, path: &Path) -> Result<(), std::io::Error> {
-        let data = self.responses.read();
-        let serializable: Vec<_> = data
-            .iter()
-            .map(|(sig, responses)| (sig.clone(), responses.clone()))
-            .collect();
-
-        let json = serde_json::to_string_pretty(&serializable)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
-
-        fs::write(path, json)?;
-        info!("Saved {} recordings to {:?}", data.len(), path);
-        Ok(())
+        self.save_to_backend(&FileRecordingBackend::new(path))
+            .map(|_| ())
     }
 
     /// Load recordings from file (JSON format)
     // Public API for persistence
     pub fn load_from_file(&self, path: &Path) -> Result<usize, std::io::Error> {
-        if !path.exists() {
-            debug!("Recording file {:?} does not exist, starting fresh", path);
-            return Ok(0);
-        }
+        self.load_from_backend(&FileRecordingBackend::new(path))
+    }
 
-        let json = fs::read_to_string(path)?;
-        let data: Vec<(RequestSignature, Vec<RecordedResponse>)> = serde_json::from_str(&json)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+    /// Save recordings to a persistence backend, returning the number saved
+    pub fn save_to_backend(&self, backend: &dyn RecordingBackend) -> Result<usize, std::io::Error> {
+        let snapshot: RecordingSnapshot = self
+            .responses
+            .read()
+            .iter()
+            .map(|(sig, responses)| (sig.clone(), responses.clone()))
+            .collect();
 
+        backend.save(&snapshot)?;
+        info!("Saved {} recordings to {}", snapshot.len(), backend.name());
+        Ok(snapshot.len())
+    }
+
+    /// Load recordings from a persistence backend, returning the number loaded
+    pub fn load_from_backend(
+        &self,
+        backend: &dyn RecordingBackend,
+    ) -> Result<usize, std::io::Error> {
+        let data = backend.load()?;
         let count = data.len();
         let mut store = self.responses.write();
         for (sig, responses) in data {
             store.insert(sig, responses);
         }
 
-        info!("Loaded {} recordings from {:?}", count, path);
+        info!("Loaded {} recordings from {}", count, backend.name());
         Ok(count)
     }
 
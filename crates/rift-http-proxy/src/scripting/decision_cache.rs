@@ -16,6 +16,9 @@ pub struct DecisionCacheConfig {
     pub max_size: usize,
     /// TTL for cache entries in seconds (0 = no expiration)
     pub ttl_seconds: u64,
+    /// Interval between background sweeps of expired entries
+    /// (None = min(ttl / 2, 60s))
+    pub cleanup_interval: Option<Duration>,
 }
 
 impl Default for DecisionCacheConfig {
@@ -24,6 +27,39 @@ impl Default for DecisionCacheConfig {
             enabled: true,
             max_size: 10000,
             ttl_seconds: 300, // 5 minutes
+            cleanup_interval: None,
+        }
+    }
+}
+
+/// A request field that contributes to a script rule's cache key.
+///
+/// Parsed from `cache_key_fields` entries: `method`, `path`, `body`,
+/// `header:<name>` or `query:<name>`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CacheKeyField {
+    Method,
+    Path,
+    Body,
+    Header(String),
+    Query(String),
+}
+
+impl std::str::FromStr for CacheKeyField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("header", name)) if !name.is_empty() => {
+                Ok(CacheKeyField::Header(name.to_lowercase()))
+            }
+            Some(("query", name)) if !name.is_empty() => Ok(CacheKeyField::Query(name.to_string())),
+            None if s == "method" => Ok(CacheKeyField::Method),
+            None if s == "path" => Ok(CacheKeyField::Path),
+            None if s == "body" => Ok(CacheKeyField::Body),
+            _ => Err(format!(
+                "Invalid cache key field '{s}' (expected method, path, body, header:<name> or query:<name>)"
+            )),
         }
     }
 }
@@ -35,7 +71,7 @@ pub struct CacheKey {
     method: String,
     /// Request path
     path: String,
-    /// Sorted header keys and values (for deterministic hashing)
+    /// Sorted header (or selected field) keys and values (for deterministic hashing)
     headers: Vec<(String, String)>,
     /// Body hash (to avoid storing large bodies)
     body_hash: u64,
@@ -67,6 +103,52 @@ impl CacheKey {
         }
     }
 
+    /// Create a cache key from only the declared fields of a request.
+    ///
+    /// Fields that aren't declared are left out of the key, so requests that
+    /// differ only in those fields share a cached decision. The body is hashed
+    /// only when `body` is declared.
+    pub fn from_fields(
+        fields: &[CacheKeyField],
+        method: &str,
+        path: &str,
+        headers: &HashMap<String, String>,
+        query: &HashMap<String, String>,
+        body: &serde_json::Value,
+        rule_id: String,
+    ) -> Self {
+        let mut key = Self {
+            method: String::new(),
+            path: String::new(),
+            headers: Vec::new(),
+            body_hash: 0,
+            rule_id,
+        };
+
+        for field in fields {
+            match field {
+                CacheKeyField::Method => key.method = method.to_string(),
+                CacheKeyField::Path => key.path = path.to_string(),
+                CacheKeyField::Body => key.body_hash = Self::hash_json(body),
+                CacheKeyField::Header(name) => {
+                    let value = headers
+                        .iter()
+                        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                        .map(|(_, v)| v.clone())
+                        .unwrap_or_default();
+                    key.headers.push((format!("header:{name}"), value));
+                }
+                CacheKeyField::Query(name) => {
+                    let value = query.get(name).cloned().unwrap_or_default();
+                    key.headers.push((format!("query:{name}"), value));
+                }
+            }
+        }
+        key.headers.sort();
+
+        key
+    }
+
     /// Hash a JSON value for cache key generation
     fn hash_json(value: &serde_json::Value) -> u64 {
         let mut hasher = std::collections::hash_map::DefaultHasher::new();
@@ -171,6 +253,11 @@ impl DecisionCache {
         }
     }
 
+    /// Whether caching is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
     /// Get a decision from cache if available and not expired
     pub fn get(&self, key: &CacheKey) -> Option<FaultDecision> {
         if !self.config.enabled {
@@ -307,6 +394,57 @@ impl DecisionCache {
     pub fn size(&self) -> usize {
         self.state.read().unwrap().entries.len()
     }
+
+    /// Interval for the background cleanup task, or None if entries never expire
+    pub fn cleanup_interval(&self) -> Option<Duration> {
+        if !self.config.enabled || self.config.ttl_seconds == 0 {
+            return None;
+        }
+        let interval = self.config.cleanup_interval.unwrap_or_else(|| {
+            (Duration::from_secs(self.config.ttl_seconds) / 2).min(Duration::from_secs(60))
+        });
+        Some(interval.max(Duration::from_millis(1)))
+    }
+
+    /// Spawn a background task that periodically calls `cleanup_expired`.
+    ///
+    /// The task stops when the returned guard is dropped or the cache itself
+    /// is dropped. Returns None when entries never expire.
+    pub fn spawn_cleanup_task(self: &Arc<Self>) -> Option<CleanupTask> {
+        let interval = self.cleanup_interval()?;
+        let cache = Arc::downgrade(self);
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match cache.upgrade() {
+                    Some(cache) => cache.cleanup_expired(),
+                    None => break,
+                }
+            }
+        });
+        debug!(
+            "Decision cache cleanup task started (interval: {:?})",
+            interval
+        );
+
+        Some(CleanupTask { handle })
+    }
+}
+
+/// Guard for the decision cache cleanup task; aborts the task when dropped
+pub struct CleanupTask {
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for CleanupTask {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
 }
 
 #[cfg(test)]
@@ -374,12 +512,101 @@ mod tests {
         assert_eq!(key1, key2);
     }
 
+    #[test]
+    fn test_cache_key_field_parsing() {
+        assert_eq!("method".parse(), Ok(CacheKeyField::Method));
+        assert_eq!("body".parse(), Ok(CacheKeyField::Body));
+        assert_eq!(
+            "header:X-Flow-Id".parse(),
+            Ok(CacheKeyField::Header("x-flow-id".to_string()))
+        );
+        assert_eq!(
+            "query:page".parse(),
+            Ok(CacheKeyField::Query("page".to_string()))
+        );
+        assert!("header:".parse::<CacheKeyField>().is_err());
+        assert!("cookie:session".parse::<CacheKeyField>().is_err());
+    }
+
+    #[test]
+    fn test_cache_key_from_fields_ignores_undeclared() {
+        let fields: Vec<CacheKeyField> = ["method", "path", "header:x-flow-id"]
+            .iter()
+            .map(|f| f.parse().unwrap())
+            .collect();
+        let query = HashMap::new();
+
+        let headers1: HashMap<String, String> = [
+            ("x-flow-id".to_string(), "flow-1".to_string()),
+            ("x-request-id".to_string(), "a".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let headers2: HashMap<String, String> = [
+            ("x-flow-id".to_string(), "flow-1".to_string()),
+            ("x-request-id".to_string(), "b".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        let key = |headers: &HashMap<String, String>, body: serde_json::Value| {
+            CacheKey::from_fields(
+                &fields,
+                "POST",
+                "/api/orders",
+                headers,
+                &query,
+                &body,
+                "rule1".to_string(),
+            )
+        };
+
+        // Undeclared headers and body don't affect the key
+        assert_eq!(
+            key(&headers1, json!({"payload": 1})),
+            key(&headers2, json!({"payload": 2}))
+        );
+
+        // Declared header does
+        let other_flow: HashMap<String, String> = [("x-flow-id".to_string(), "flow-2".to_string())]
+            .into_iter()
+            .collect();
+        assert_ne!(key(&headers1, json!({})), key(&other_flow, json!({})));
+    }
+
+    #[test]
+    fn test_cache_key_from_fields_with_body() {
+        let fields = vec![CacheKeyField::Path, CacheKeyField::Body];
+        let empty = HashMap::new();
+
+        let key1 = CacheKey::from_fields(
+            &fields,
+            "POST",
+            "/api",
+            &empty,
+            &empty,
+            &json!({"a": 1}),
+            "rule1".to_string(),
+        );
+        let key2 = CacheKey::from_fields(
+            &fields,
+            "POST",
+            "/api",
+            &empty,
+            &empty,
+            &json!({"a": 2}),
+            "rule1".to_string(),
+        );
+        assert_ne!(key1, key2);
+    }
+
     #[test]
     fn test_cache_basic_operations() {
         let config = DecisionCacheConfig {
             enabled: true,
             max_size: 100,
             ttl_seconds: 0, // No expiration for this test
+            cleanup_interval: None,
         };
 
         let cache = DecisionCache::new(config);
@@ -425,6 +652,7 @@ mod tests {
             enabled: true,
             max_size: 100,
             ttl_seconds: 1, // 1 second TTL
+            cleanup_interval: None,
         };
 
         let cache = DecisionCache::new(config);
@@ -460,6 +688,7 @@ mod tests {
             enabled: true,
             max_size: 3,
             ttl_seconds: 0,
+            cleanup_interval: None,
         };
 
         let cache = DecisionCache::new(config);
@@ -533,6 +762,7 @@ mod tests {
             enabled: false,
             max_size: 100,
             ttl_seconds: 0,
+            cleanup_interval: None,
         };
 
         let cache = DecisionCache::new(config);
@@ -611,6 +841,7 @@ mod tests {
             enabled: true,
             max_size: 100,
             ttl_seconds: 1,
+            cleanup_interval: None,
         };
 
         let cache = DecisionCache::new(config);
@@ -640,4 +871,51 @@ mod tests {
         let metrics = cache.metrics();
         assert_eq!(metrics.expirations, 5);
     }
+
+    #[test]
+    fn test_cleanup_interval_defaults() {
+        let cache = DecisionCache::new(DecisionCacheConfig::default());
+        assert_eq!(cache.cleanup_interval(), Some(Duration::from_secs(60)));
+
+        let cache = DecisionCache::new(DecisionCacheConfig {
+            ttl_seconds: 10,
+            ..Default::default()
+        });
+        assert_eq!(cache.cleanup_interval(), Some(Duration::from_secs(5)));
+
+        let cache = DecisionCache::new(DecisionCacheConfig {
+            ttl_seconds: 0,
+            ..Default::default()
+        });
+        assert_eq!(cache.cleanup_interval(), None);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_task_reclaims_expired_entries() {
+        let cache = Arc::new(DecisionCache::new(DecisionCacheConfig {
+            enabled: true,
+            max_size: 100,
+            ttl_seconds: 1,
+            cleanup_interval: Some(Duration::from_millis(50)),
+        }));
+        let _task = cache.spawn_cleanup_task().unwrap();
+
+        for i in 0..3 {
+            let key = CacheKey::new(
+                "GET".to_string(),
+                format!("/api/test{i}"),
+                vec![],
+                &json!({}),
+                format!("rule{i}"),
+            );
+            cache.insert(key, FaultDecision::None).unwrap();
+        }
+        assert_eq!(cache.size(), 3);
+
+        // No get() calls: only the background task can reclaim these
+        tokio::time::sleep(Duration::from_millis(1300)).await;
+
+        assert_eq!(cache.size(), 0);
+        assert_eq!(cache.metrics().expirations, 3);
+    }
 }
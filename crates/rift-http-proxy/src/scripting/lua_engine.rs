@@ -1,8 +1,11 @@
 use crate::extensions::flow_state::FlowStore;
-use crate::scripting::{FaultDecision, ScriptRequest};
+use crate::scripting::{
+    FaultDecision, ScriptContext, ScriptRequest, ScriptResponse, RESPONSE_HOOK,
+};
 use anyhow::{anyhow, Result};
 use mlua::prelude::*;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Lua script engine for fault injection
@@ -83,6 +86,30 @@ use std::sync::Arc;
 ///     return { inject = false }
 /// end
 /// ```
+///
+/// ## Context Object
+///
+/// `should_inject` may take a third `ctx` argument with a clock and random
+/// source controlled by the proxy (see [`ScriptContext`]):
+/// - `ctx:now_ms()` - Milliseconds since the Unix epoch
+/// - `ctx:rand()` - Random float in `[0, 1)`, reproducible under `seed`
+///
+/// ## Response Hook
+///
+/// A script may also define `on_response(request, response, flow_store)`,
+/// which runs once the upstream has answered a request the script let
+/// through. `response` is a table of `status`, `headers` and `body`;
+/// returning it (changed or not) replaces the response, returning `nil`
+/// leaves it untouched.
+///
+/// ```lua
+/// function on_response(request, response, flow_store)
+///     if response.status == 500 then
+///         response.status = 503
+///     end
+///     return response
+/// end
+/// ```
 #[derive(Debug, Clone)]
 pub struct LuaEngine {
     script: String,
@@ -231,7 +258,7 @@ impl LuaEngine {
             .get("flow_store")
             .map_err(|e| anyhow!("Failed to get flow_store: {e}"))?;
         let result: LuaTable = should_inject
-            .call((request_arg, flow_store_arg))
+            .call((request_arg, flow_store_arg, ScriptContext::default()))
             .map_err(|e| anyhow!("Failed to call should_inject: {e}"))?;
 
         // Parse result table
@@ -332,6 +359,7 @@ pub fn execute_lua_bytecode(
     request: &ScriptRequest,
     flow_store: Arc<dyn FlowStore>,
     rule_id: &str,
+    context: &ScriptContext,
 ) -> Result<FaultDecision> {
     // Create request table
     let request_table = lua
@@ -420,13 +448,133 @@ pub fn execute_lua_bytecode(
         .get("flow_store")
         .map_err(|e| anyhow!("Failed to get flow_store: {e}"))?;
     let result: LuaTable = should_inject
-        .call((request_arg, flow_store_arg))
+        .call((request_arg, flow_store_arg, context.clone()))
         .map_err(|e| anyhow!("Failed to call should_inject: {e}"))?;
 
     // Parse result table with rule_id parameter
     parse_fault_decision_lua(lua, result, rule_id)
 }
 
+/// Run a script's `on_response` hook with a reusable Lua state (for script pool).
+///
+/// Returns `None` if the hook leaves the response unchanged.
+pub fn execute_lua_on_response(
+    lua: &Lua,
+    bytecode: &[u8],
+    request: &ScriptRequest,
+    response: &ScriptResponse,
+    flow_store: Arc<dyn FlowStore>,
+) -> Result<Option<ScriptResponse>> {
+    let globals = lua.globals();
+    // The state is shared by every script on this worker; drop a hook left
+    // behind by another one
+    globals
+        .set(RESPONSE_HOOK, LuaNil)
+        .map_err(|e| anyhow!("Failed to reset {RESPONSE_HOOK}: {e}"))?;
+    lua.load(bytecode)
+        .exec()
+        .map_err(|e| anyhow!("Failed to execute bytecode: {e}"))?;
+    let hook: LuaFunction = globals
+        .get(RESPONSE_HOOK)
+        .map_err(|e| anyhow!("Failed to get {RESPONSE_HOOK} function: {e}"))?;
+
+    let to_anyhow = |e: LuaError| anyhow!("Failed to build {RESPONSE_HOOK} arguments: {e}");
+    let request_table = lua.create_table().map_err(to_anyhow)?;
+    request_table
+        .set("method", request.method.as_str())
+        .map_err(to_anyhow)?;
+    request_table
+        .set("path", request.path.as_str())
+        .map_err(to_anyhow)?;
+    request_table
+        .set("headers", string_table(lua, &request.headers)?)
+        .map_err(to_anyhow)?;
+    request_table
+        .set("query", string_table(lua, &request.query)?)
+        .map_err(to_anyhow)?;
+    request_table
+        .set("pathParams", string_table(lua, &request.path_params)?)
+        .map_err(to_anyhow)?;
+    request_table
+        .set("body", json_to_lua(lua, &request.body).map_err(to_anyhow)?)
+        .map_err(to_anyhow)?;
+
+    let response_table = lua.create_table().map_err(to_anyhow)?;
+    response_table
+        .set("status", response.status)
+        .map_err(to_anyhow)?;
+    response_table
+        .set("headers", string_table(lua, &response.headers)?)
+        .map_err(to_anyhow)?;
+    response_table
+        .set("body", json_to_lua(lua, &response.body).map_err(to_anyhow)?)
+        .map_err(to_anyhow)?;
+
+    let flow_store_ud = lua
+        .create_userdata(LuaFlowStore::new(flow_store))
+        .map_err(to_anyhow)?;
+    let result: LuaValue = hook
+        .call((request_table, response_table, flow_store_ud))
+        .map_err(|e| anyhow!("Failed to call {RESPONSE_HOOK}: {e}"))?;
+
+    let table = match result {
+        LuaValue::Nil => return Ok(None),
+        LuaValue::Table(table) => table,
+        _ => {
+            return Err(anyhow!(
+                "{RESPONSE_HOOK} must return the response table or nil"
+            ))
+        }
+    };
+    let status: Option<i64> = table
+        .get("status")
+        .map_err(|_| anyhow!("response status must be an integer"))?;
+    let headers: Option<HashMap<String, String>> = table
+        .get("headers")
+        .map_err(|_| anyhow!("response headers must be a table of strings"))?;
+    let body: LuaValue = table
+        .get("body")
+        .map_err(|e| anyhow!("Failed to read response body: {e}"))?;
+    let body = match body {
+        LuaValue::Nil => None,
+        body => Some(lua_to_json(lua, body).map_err(|e| anyhow!("Invalid response body: {e}"))?),
+    };
+    response.updated(status, headers, body).map(Some)
+}
+
+fn string_table(lua: &Lua, values: &HashMap<String, String>) -> Result<LuaTable> {
+    let table = lua
+        .create_table()
+        .map_err(|e| anyhow!("Failed to create table: {e}"))?;
+    for (k, v) in values {
+        table
+            .set(k.as_str(), v.as_str())
+            .map_err(|e| anyhow!("Failed to set {k}: {e}"))?;
+    }
+    Ok(table)
+}
+
+/// Whether the script's `should_inject` takes the optional `ctx` argument
+pub fn takes_context(script: &str) -> bool {
+    let lua = Lua::new();
+    if lua.load(script).exec().is_err() {
+        return false;
+    }
+    // LuaJIT's debug.getinfo has no `nparams`; jit.util reports the arity
+    lua.load("return require('jit.util').funcinfo(should_inject).params")
+        .eval::<u32>()
+        .is_ok_and(|nparams| nparams >= 3)
+}
+
+/// Whether `script` defines an `on_response` hook.
+///
+/// Runs the script's top level in a fresh state, the same way each
+/// execution does before calling into it.
+pub fn defines_response_hook(script: &str) -> bool {
+    let lua = Lua::new();
+    lua.load(script).exec().is_ok() && lua.globals().get::<LuaFunction>(RESPONSE_HOOK).is_ok()
+}
+
 /// Public function to execute Lua script with a reusable Lua state (for script pool)
 /// This eliminates the expensive thread spawning and runtime creation overhead
 pub fn execute_lua_with_state(
@@ -523,7 +671,7 @@ pub fn execute_lua_with_state(
         .get("flow_store")
         .map_err(|e| anyhow!("Failed to get flow_store: {e}"))?;
     let result: LuaTable = should_inject
-        .call((request_arg, flow_store_arg))
+        .call((request_arg, flow_store_arg, ScriptContext::default()))
         .map_err(|e| anyhow!("Failed to call should_inject: {e}"))?;
 
     // Parse result table with rule_id parameter
@@ -701,6 +849,13 @@ impl LuaUserData for LuaFlowStore {
     }
 }
 
+impl LuaUserData for ScriptContext {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method_mut("now_ms", |_lua, this, ()| Ok(this.now_ms()));
+        methods.add_method_mut("rand", |_lua, this, ()| Ok(this.rand()));
+    }
+}
+
 /// Convert JSON Value to Lua value
 fn json_to_lua(lua: &Lua, value: &Value) -> LuaResult<LuaValue> {
     match value {
@@ -1345,8 +1500,15 @@ end
             path_params: HashMap::new(),
         };
 
-        let result =
-            execute_lua_bytecode(&lua, &bytecode, &request, store, "bytecode-rule").unwrap();
+        let result = execute_lua_bytecode(
+            &lua,
+            &bytecode,
+            &request,
+            store,
+            "bytecode-rule",
+            &ScriptContext::default(),
+        )
+        .unwrap();
 
         match result {
             FaultDecision::Error {
@@ -1403,16 +1565,38 @@ end
         };
 
         // First two executions should pass
-        let result1 =
-            execute_lua_bytecode(&lua, &bytecode, &request, Arc::clone(&store), "rule-1").unwrap();
+        let result1 = execute_lua_bytecode(
+            &lua,
+            &bytecode,
+            &request,
+            Arc::clone(&store),
+            "rule-1",
+            &ScriptContext::default(),
+        )
+        .unwrap();
         assert!(matches!(result1, FaultDecision::None));
 
-        let result2 =
-            execute_lua_bytecode(&lua, &bytecode, &request, Arc::clone(&store), "rule-1").unwrap();
+        let result2 = execute_lua_bytecode(
+            &lua,
+            &bytecode,
+            &request,
+            Arc::clone(&store),
+            "rule-1",
+            &ScriptContext::default(),
+        )
+        .unwrap();
         assert!(matches!(result2, FaultDecision::None));
 
         // Third execution should trigger rate limit
-        let result3 = execute_lua_bytecode(&lua, &bytecode, &request, store, "rule-1").unwrap();
+        let result3 = execute_lua_bytecode(
+            &lua,
+            &bytecode,
+            &request,
+            store,
+            "rule-1",
+            &ScriptContext::default(),
+        )
+        .unwrap();
         assert!(matches!(result3, FaultDecision::Error { status: 429, .. }));
     }
 
@@ -1507,4 +1691,122 @@ end
             _ => panic!("Expected error fault decision with path params"),
         }
     }
+
+    #[tokio::test]
+    async fn test_lua_on_response_rewrites_response() {
+        let script = r#"
+function should_inject(request, flow_store)
+    return { inject = false }
+end
+
+function on_response(request, response, flow_store)
+    if response.status == 500 then
+        response.status = 503
+        response.headers["retry-after"] = "1"
+        response.body = { error = "unavailable", path = request.path }
+    end
+    return response
+end
+"#;
+        assert!(defines_response_hook(script));
+        let bytecode = compile_to_bytecode(script).unwrap();
+        let store: Arc<dyn FlowStore> = Arc::new(InMemoryFlowStore::new(300));
+        let request = ScriptRequest {
+            method: "GET".to_string(),
+            path: "/orders".to_string(),
+            headers: HashMap::new(),
+            body: json!(null),
+            query: HashMap::new(),
+            path_params: HashMap::new(),
+        };
+        let response = ScriptResponse {
+            status: 500,
+            headers: HashMap::new(),
+            body: json!("boom"),
+        };
+
+        let lua = Lua::new();
+        let updated = execute_lua_on_response(&lua, &bytecode, &request, &response, store)
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.status, 503);
+        assert_eq!(updated.headers.get("retry-after").unwrap(), "1");
+        assert_eq!(
+            updated.body,
+            json!({"error": "unavailable", "path": "/orders"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lua_on_response_nil_keeps_response() {
+        let script = r#"
+function should_inject(request, flow_store)
+    return { inject = false }
+end
+
+function on_response(request, response, flow_store)
+    return nil
+end
+"#;
+        let bytecode = compile_to_bytecode(script).unwrap();
+        let store: Arc<dyn FlowStore> = Arc::new(InMemoryFlowStore::new(300));
+        let request = ScriptRequest {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            headers: HashMap::new(),
+            body: json!(null),
+            query: HashMap::new(),
+            path_params: HashMap::new(),
+        };
+        let response = ScriptResponse {
+            status: 200,
+            headers: HashMap::new(),
+            body: json!(null),
+        };
+
+        let lua = Lua::new();
+        let updated = execute_lua_on_response(&lua, &bytecode, &request, &response, store).unwrap();
+        assert!(updated.is_none());
+    }
+
+    #[test]
+    fn test_lua_script_without_hook() {
+        assert!(!defines_response_hook(
+            "function should_inject(request, flow_store) return { inject = false } end"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_lua_context() {
+        use crate::extensions::fault::FaultRng;
+
+        let script = r#"
+function should_inject(request, flow_store, ctx)
+    return { inject = true, fault = "latency", duration_ms = ctx:now_ms() + math.floor(ctx:rand()) }
+end
+"#;
+        assert!(takes_context(script));
+        assert!(!takes_context(
+            "function should_inject(request, flow_store) return { inject = false } end"
+        ));
+
+        let bytecode = compile_to_bytecode(script).unwrap();
+        let store: Arc<dyn FlowStore> = Arc::new(InMemoryFlowStore::new(300));
+        let request = ScriptRequest {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            headers: HashMap::new(),
+            body: json!(null),
+            query: HashMap::new(),
+            path_params: HashMap::new(),
+        };
+        let context = ScriptContext::new(Arc::new(FaultRng::new(Some(1)))).with_pinned_clock(1234);
+
+        let lua = Lua::new();
+        match execute_lua_bytecode(&lua, &bytecode, &request, store, "ctx-rule", &context).unwrap()
+        {
+            FaultDecision::Latency { duration_ms, .. } => assert_eq!(duration_ms, 1234),
+            other => panic!("Expected latency decision, got {other:?}"),
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use crate::extensions::fault::FaultRng;
 use crate::extensions::flow_state::FlowStore;
 use anyhow::{anyhow, Result};
 use rhai::Dynamic;
@@ -11,16 +12,19 @@ pub use rhai_engine::RhaiEngine;
 
 // Script pool for optimized execution
 mod script_pool;
-pub use script_pool::{CompiledScript, ScriptPool, ScriptPoolConfig};
+pub use script_pool::{CompiledScript, ScriptPool, ScriptPoolConfig, ScriptPoolError};
+
+/// Optional script function run on the upstream response
+pub const RESPONSE_HOOK: &str = "on_response";
 
 // Decision cache for memoization
 mod decision_cache;
-pub use decision_cache::{CacheKey, DecisionCache, DecisionCacheConfig};
+pub use decision_cache::{CacheKey, CacheKeyField, DecisionCache, DecisionCacheConfig};
 
 #[cfg(feature = "lua")]
 mod lua_engine;
 #[cfg(feature = "lua")]
-pub use lua_engine::{compile_to_bytecode, LuaEngine};
+pub use lua_engine::{compile_to_bytecode, defines_response_hook, takes_context, LuaEngine};
 
 #[cfg(feature = "javascript")]
 mod js_engine;
@@ -144,6 +148,82 @@ pub struct ScriptRequest {
     pub path_params: HashMap<String, String>,
 }
 
+/// Upstream response passed to a script's `on_response` hook
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    /// Parsed JSON, the body as a string if it is not JSON, or null if it
+    /// is not UTF-8
+    pub body: Value,
+}
+
+impl ScriptResponse {
+    /// Take `status`, `headers` and `body` from a hook's result, keeping the
+    /// original value of any field it leaves out
+    fn updated(
+        &self,
+        status: Option<i64>,
+        headers: Option<HashMap<String, String>>,
+        body: Option<Value>,
+    ) -> Result<Self> {
+        let status = match status {
+            Some(status) => u16::try_from(status)
+                .ok()
+                .filter(|s| (100..=599).contains(s))
+                .ok_or_else(|| anyhow!("on_response returned invalid status {status}"))?,
+            None => self.status,
+        };
+        Ok(Self {
+            status,
+            headers: headers.unwrap_or_else(|| self.headers.clone()),
+            body: body.unwrap_or_else(|| self.body.clone()),
+        })
+    }
+}
+
+/// Clock and random source passed to scripts as the optional `ctx`
+/// argument of `should_inject(request, flow_store, ctx)`.
+///
+/// `ctx.rand()` draws from the proxy's fault RNG, so a configured `seed`
+/// makes it reproducible. Decisions from scripts that take `ctx` are never
+/// cached, since the same request may legitimately get a different answer.
+#[derive(Clone, Default)]
+pub struct ScriptContext {
+    rng: Arc<FaultRng>,
+    pinned_now_ms: Option<i64>,
+}
+
+impl ScriptContext {
+    pub fn new(rng: Arc<FaultRng>) -> Self {
+        Self {
+            rng,
+            pinned_now_ms: None,
+        }
+    }
+
+    /// Report `now_ms` as the time instead of the system clock
+    pub fn with_pinned_clock(mut self, now_ms: i64) -> Self {
+        self.pinned_now_ms = Some(now_ms);
+        self
+    }
+
+    /// Milliseconds since the Unix epoch
+    pub fn now_ms(&mut self) -> i64 {
+        self.pinned_now_ms.unwrap_or_else(|| {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            now.as_millis() as i64
+        })
+    }
+
+    /// Random float in `[0, 1)`
+    pub fn rand(&mut self) -> f64 {
+        self.rng.next_f64()
+    }
+}
+
 /// Wrapper for FlowStore that can be used in scripts (both Rhai and Lua)
 /// Uses direct synchronous calls since FlowStore is no longer async
 #[derive(Clone)]
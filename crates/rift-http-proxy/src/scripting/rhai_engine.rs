@@ -4,7 +4,9 @@ use rhai::{Dynamic, Engine, Map, Scope, AST};
 use serde_json::Value;
 use std::sync::Arc;
 
-use super::{FaultDecision, ScriptFlowStore, ScriptRequest};
+use super::{
+    FaultDecision, ScriptContext, ScriptFlowStore, ScriptRequest, ScriptResponse, RESPONSE_HOOK,
+};
 
 /// Helper function to check if a year is a leap year
 fn is_leap_year(year: u64) -> bool {
@@ -44,6 +46,19 @@ fn create_request_map(request: &ScriptRequest) -> Map {
     request_map
 }
 
+/// Create a Rhai Map from a ScriptResponse
+fn create_response_map(response: &ScriptResponse) -> Map {
+    let mut response_map = Map::new();
+    response_map.insert("status".into(), Dynamic::from(response.status as i64));
+    let mut headers_map = Map::new();
+    for (k, v) in &response.headers {
+        headers_map.insert(k.clone().into(), Dynamic::from(v.clone()));
+    }
+    response_map.insert("headers".into(), Dynamic::from(headers_map));
+    response_map.insert("body".into(), json_to_dynamic(response.body.clone()));
+    response_map
+}
+
 /// Rhai script engine for fault injection
 ///
 /// # Script Interface
@@ -122,6 +137,37 @@ fn create_request_map(request: &ScriptRequest) -> Map {
 ///     #{ inject: false }
 /// }
 /// ```
+///
+/// ## Context Object
+///
+/// `should_inject` may take a third `ctx` parameter offering a clock and
+/// random source the proxy controls (see [`ScriptContext`]):
+/// - `ctx.now_ms()` - Milliseconds since the Unix epoch
+/// - `ctx.rand()` - Random float in `[0, 1)`, reproducible under `seed`
+///
+/// ```rhai
+/// fn should_inject(request, flow_store, ctx) {
+///     if ctx.rand() < 0.1 {
+///         return #{ inject: true, fault: "error", status: 503 };
+///     }
+///     #{ inject: false }
+/// }
+/// ```
+///
+/// ## Response Hook
+///
+/// A script may also define `on_response(request, response, flow_store)`.
+/// It runs once the upstream has answered a request the script let through
+/// (no fault, or latency), with `response` a map of `status`, `headers` and
+/// `body`. Returning the map, changed or not, replaces the response;
+/// returning `()` leaves it untouched.
+///
+/// ```rhai
+/// fn on_response(request, response, flow_store) {
+///     response.body.password = "***";
+///     response
+/// }
+/// ```
 #[derive(Clone)]
 
 pub struct RhaiEngine {
@@ -165,6 +211,11 @@ impl RhaiEngine {
             .register_fn("increment", ScriptFlowStore::increment)
             .register_fn("set_ttl", ScriptFlowStore::set_ttl);
 
+        engine
+            .register_type::<ScriptContext>()
+            .register_fn("now_ms", ScriptContext::now_ms)
+            .register_fn("rand", ScriptContext::rand);
+
         // Register helper function for RFC 1123 timestamps
         engine.register_fn("timestamp_header", || -> String {
             // Generate RFC 1123 formatted timestamp for HTTP Date header
@@ -254,14 +305,14 @@ impl RhaiEngine {
             .map_err(|e| anyhow!("Script execution error: {e}"))?;
 
         // Now call the should_inject function with request and flow_store arguments
-        let result: Dynamic = engine
-            .call_fn(
-                &mut scope,
-                self.ast.as_ref(),
-                "should_inject",
-                (request_map, flow_store_wrapper),
-            )
-            .map_err(|e| anyhow!("Failed to call should_inject function: {e}"))?;
+        let result = call_should_inject(
+            &engine,
+            &mut scope,
+            &self.ast,
+            request_map,
+            flow_store_wrapper,
+            ScriptContext::default(),
+        )?;
 
         // Parse result
         self.parse_fault_decision(result)
@@ -361,6 +412,7 @@ pub fn execute_rhai_with_engine(
     request: &ScriptRequest,
     flow_store: Arc<dyn FlowStore>,
     rule_id: &str,
+    context: &ScriptContext,
 ) -> Result<FaultDecision> {
     let mut scope = Scope::new();
 
@@ -376,17 +428,112 @@ pub fn execute_rhai_with_engine(
         .map_err(|e| anyhow!("Script execution error: {e}"))?;
 
     // Now call the should_inject function with request and flow_store arguments
+    let result = call_should_inject(
+        engine,
+        &mut scope,
+        ast,
+        request_map,
+        flow_store_wrapper,
+        context.clone(),
+    )?;
+
+    // Parse result
+    parse_fault_decision_with_rule_id(result, rule_id)
+}
+
+/// Call `should_inject`, passing `ctx` only to scripts that declare it
+fn call_should_inject(
+    engine: &Engine,
+    scope: &mut Scope,
+    ast: &AST,
+    request_map: Map,
+    flow_store: ScriptFlowStore,
+    context: ScriptContext,
+) -> Result<Dynamic> {
+    let result = if takes_context(ast) {
+        engine.call_fn(
+            scope,
+            ast,
+            "should_inject",
+            (request_map, flow_store, context),
+        )
+    } else {
+        engine.call_fn(scope, ast, "should_inject", (request_map, flow_store))
+    };
+    result.map_err(|e| anyhow!("Failed to call should_inject function: {e}"))
+}
+
+/// Whether `should_inject` takes the optional `ctx` argument
+pub fn takes_context(ast: &AST) -> bool {
+    ast.iter_functions()
+        .any(|f| f.name == "should_inject" && f.params.len() == 3)
+}
+
+/// Run a script's `on_response` hook with a reusable engine (for script pool).
+///
+/// Returns `None` if the hook leaves the response unchanged.
+pub fn execute_rhai_on_response(
+    engine: &Engine,
+    ast: &Arc<AST>,
+    request: &ScriptRequest,
+    response: &ScriptResponse,
+    flow_store: Arc<dyn FlowStore>,
+) -> Result<Option<ScriptResponse>> {
+    let mut scope = Scope::new();
+    engine
+        .run_ast_with_scope(&mut scope, ast)
+        .map_err(|e| anyhow!("Script execution error: {e}"))?;
+
     let result: Dynamic = engine
         .call_fn(
             &mut scope,
             ast,
-            "should_inject",
-            (request_map, flow_store_wrapper),
+            RESPONSE_HOOK,
+            (
+                create_request_map(request),
+                create_response_map(response),
+                ScriptFlowStore::new(flow_store),
+            ),
         )
-        .map_err(|e| anyhow!("Failed to call should_inject function: {e}"))?;
+        .map_err(|e| anyhow!("Failed to call {RESPONSE_HOOK} function: {e}"))?;
 
-    // Parse result
-    parse_fault_decision_with_rule_id(result, rule_id)
+    if result.is_unit() {
+        return Ok(None);
+    }
+    let map = result
+        .try_cast::<Map>()
+        .ok_or_else(|| anyhow!("{RESPONSE_HOOK} must return the response map or ()"))?;
+
+    let status = match map.get("status") {
+        Some(status) => Some(
+            status
+                .as_int()
+                .map_err(|_| anyhow!("response status must be an integer"))?,
+        ),
+        None => None,
+    };
+    let headers = match map.get("headers") {
+        Some(headers) => {
+            let headers = headers
+                .clone()
+                .try_cast::<Map>()
+                .ok_or_else(|| anyhow!("response headers must be a map"))?;
+            Some(
+                headers
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            )
+        }
+        None => None,
+    };
+    let body = map.get("body").cloned().map(dynamic_to_json);
+    response.updated(status, headers, body).map(Some)
+}
+
+/// Whether a compiled script defines an `on_response` hook
+pub fn defines_response_hook(ast: &AST) -> bool {
+    ast.iter_functions().any(|f| f.name == RESPONSE_HOOK)
 }
 
 /// Helper to parse fault decision with a given rule_id
@@ -773,6 +920,7 @@ mod tests {
                 &request,
                 Arc::clone(&store),
                 "cache-test",
+                &ScriptContext::default(),
             )
             .unwrap();
 
@@ -791,4 +939,169 @@ mod tests {
             "AST should be same Arc instance"
         );
     }
+
+    fn hook_fixture() -> (ScriptRequest, ScriptResponse) {
+        let request = ScriptRequest {
+            method: "GET".to_string(),
+            path: "/users/1".to_string(),
+            headers: HashMap::new(),
+            body: json!(null),
+            query: HashMap::new(),
+            path_params: HashMap::new(),
+        };
+        let response = ScriptResponse {
+            status: 200,
+            headers: HashMap::from([("content-type".to_string(), "application/json".to_string())]),
+            body: json!({"name": "alice", "password": "hunter2"}),
+        };
+        (request, response)
+    }
+
+    #[test]
+    fn test_on_response_rewrites_response() {
+        let script = r#"
+            fn should_inject(request, flow_store) {
+                #{ inject: false }
+            }
+
+            fn on_response(request, response, flow_store) {
+                response.body.password = "***";
+                response.headers["x-redacted"] = "true";
+                if request.path == "/users/1" {
+                    response.status = 203;
+                }
+                response
+            }
+        "#;
+        let engine = RhaiEngine::new(script, "hook-rule".to_string()).unwrap();
+        assert!(defines_response_hook(engine.ast()));
+
+        let (request, response) = hook_fixture();
+        let store: Arc<dyn FlowStore> = Arc::new(InMemoryFlowStore::new(300));
+        let updated = execute_rhai_on_response(
+            &RhaiEngine::create_engine(),
+            engine.ast(),
+            &request,
+            &response,
+            store,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(updated.status, 203);
+        assert_eq!(updated.body, json!({"name": "alice", "password": "***"}));
+        assert_eq!(updated.headers.get("x-redacted").unwrap(), "true");
+        assert_eq!(
+            updated.headers.get("content-type").unwrap(),
+            "application/json"
+        );
+    }
+
+    #[test]
+    fn test_on_response_unit_keeps_response() {
+        let script = r#"
+            fn should_inject(request, flow_store) { #{ inject: false } }
+            fn on_response(request, response, flow_store) { }
+        "#;
+        let engine = RhaiEngine::new(script, "hook-rule".to_string()).unwrap();
+        let (request, response) = hook_fixture();
+        let store: Arc<dyn FlowStore> = Arc::new(InMemoryFlowStore::new(300));
+        let updated = execute_rhai_on_response(
+            &RhaiEngine::create_engine(),
+            engine.ast(),
+            &request,
+            &response,
+            store,
+        )
+        .unwrap();
+        assert!(updated.is_none());
+    }
+
+    #[test]
+    fn test_on_response_rejects_invalid_status() {
+        let script = r#"
+            fn should_inject(request, flow_store) { #{ inject: false } }
+            fn on_response(request, response, flow_store) { #{ status: 42 } }
+        "#;
+        let engine = RhaiEngine::new(script, "hook-rule".to_string()).unwrap();
+        let (request, response) = hook_fixture();
+        let store: Arc<dyn FlowStore> = Arc::new(InMemoryFlowStore::new(300));
+        let result = execute_rhai_on_response(
+            &RhaiEngine::create_engine(),
+            engine.ast(),
+            &request,
+            &response,
+            store,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_script_without_hook() {
+        let script = "fn should_inject(request, flow_store) { #{ inject: false } }";
+        let engine = RhaiEngine::new(script, "no-hook".to_string()).unwrap();
+        assert!(!defines_response_hook(engine.ast()));
+    }
+
+    #[test]
+    fn test_context_is_reproducible_under_seed() {
+        use crate::extensions::fault::FaultRng;
+
+        let script = r#"
+            fn should_inject(request, flow_store, ctx) {
+                let roll = ctx.rand();
+                if roll < 0.5 {
+                    return #{ inject: true, fault: "latency", duration_ms: ctx.now_ms() };
+                }
+                #{ inject: false }
+            }
+        "#;
+        let engine = RhaiEngine::new(script, "seeded".to_string()).unwrap();
+        assert!(takes_context(engine.ast()));
+        let reusable = RhaiEngine::create_engine();
+        let store: Arc<dyn FlowStore> = Arc::new(InMemoryFlowStore::new(300));
+        let request = ScriptRequest {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            headers: HashMap::new(),
+            body: json!(null),
+            query: HashMap::new(),
+            path_params: HashMap::new(),
+        };
+
+        let run = |seed: u64| -> Vec<Option<u64>> {
+            let context = ScriptContext::new(Arc::new(FaultRng::new(Some(seed))))
+                .with_pinned_clock(1_700_000_000_000);
+            (0..32)
+                .map(|_| {
+                    match execute_rhai_with_engine(
+                        &reusable,
+                        engine.ast(),
+                        &request,
+                        Arc::clone(&store),
+                        "seeded",
+                        &context,
+                    )
+                    .unwrap()
+                    {
+                        FaultDecision::Latency { duration_ms, .. } => Some(duration_ms),
+                        FaultDecision::None => None,
+                        other => panic!("unexpected decision {other:?}"),
+                    }
+                })
+                .collect()
+        };
+
+        let first = run(42);
+        assert_eq!(first, run(42));
+        assert_ne!(first, run(43));
+        assert!(first.contains(&Some(1_700_000_000_000)) && first.contains(&None));
+    }
+
+    #[test]
+    fn test_two_argument_script_does_not_take_context() {
+        let script = "fn should_inject(request, flow_store) { #{ inject: false } }";
+        let engine = RhaiEngine::new(script, "plain".to_string()).unwrap();
+        assert!(!takes_context(engine.ast()));
+    }
 }
@@ -1,5 +1,5 @@
 use crate::extensions::flow_state::FlowStore;
-use crate::scripting::{FaultDecision, ScriptRequest};
+use crate::scripting::{FaultDecision, ScriptContext, ScriptRequest, ScriptResponse};
 use anyhow::{anyhow, Result};
 use crossbeam::channel::{bounded, Receiver, Sender};
 use rhai::AST;
@@ -23,6 +23,10 @@ pub struct ScriptPoolConfig {
     pub queue_size: usize,
     /// Timeout in milliseconds for script execution
     pub timeout_ms: u64,
+    /// Inject a 503 when a script times out or the queue is full, instead
+    /// of forwarding the request as if the script had passed (see
+    /// [`ScriptPool::unavailable_decision`])
+    pub fail_closed: bool,
 }
 
 impl Default for ScriptPoolConfig {
@@ -33,6 +37,7 @@ impl Default for ScriptPoolConfig {
             workers,
             queue_size: 1000,
             timeout_ms: 5000,
+            fail_closed: false,
         }
     }
 }
@@ -49,6 +54,10 @@ pub enum CompiledScript {
     Lua {
         bytecode: Arc<Vec<u8>>,
         rule_id: String,
+        /// Whether the script defines `on_response`, checked at compile time
+        response_hook: bool,
+        /// Whether `should_inject` takes `ctx`, checked at compile time
+        takes_context: bool,
     },
     #[cfg(feature = "javascript")]
     JavaScript {
@@ -58,13 +67,92 @@ pub enum CompiledScript {
     },
 }
 
+impl CompiledScript {
+    /// ID of the rule the script belongs to
+    pub fn rule_id(&self) -> &str {
+        match self {
+            CompiledScript::Rhai { rule_id, .. } => rule_id,
+            #[cfg(feature = "lua")]
+            CompiledScript::Lua { rule_id, .. } => rule_id,
+            #[cfg(feature = "javascript")]
+            CompiledScript::JavaScript { rule_id, .. } => rule_id,
+        }
+    }
+
+    /// Whether the script's `should_inject` takes the `ctx` argument, making
+    /// its decisions unsuitable for caching
+    pub fn takes_context(&self) -> bool {
+        match self {
+            CompiledScript::Rhai { ast, .. } => crate::scripting::rhai_engine::takes_context(ast),
+            #[cfg(feature = "lua")]
+            CompiledScript::Lua { takes_context, .. } => *takes_context,
+            #[cfg(feature = "javascript")]
+            CompiledScript::JavaScript { .. } => false,
+        }
+    }
+
+    /// Whether the script defines an `on_response` hook
+    pub fn has_response_hook(&self) -> bool {
+        match self {
+            CompiledScript::Rhai { ast, .. } => {
+                crate::scripting::rhai_engine::defines_response_hook(ast)
+            }
+            #[cfg(feature = "lua")]
+            CompiledScript::Lua { response_hook, .. } => *response_hook,
+            #[cfg(feature = "javascript")]
+            CompiledScript::JavaScript { .. } => false,
+        }
+    }
+}
+
 /// A task submitted to the script pool for execution
 pub struct ScriptTask {
     pub engine: CompiledScript,
     pub request: ScriptRequest,
     pub flow_store: Arc<dyn FlowStore>,
+    pub context: ScriptContext,
     pub timeout: Duration,
-    pub result_tx: oneshot::Sender<Result<FaultDecision>>,
+    pub job: ScriptJob,
+}
+
+impl ScriptTask {
+    /// Whether the caller stopped waiting before a worker picked the task up
+    fn is_abandoned(&self) -> bool {
+        match &self.job {
+            ScriptJob::Decide(result_tx) => result_tx.is_closed(),
+            ScriptJob::OnResponse { result_tx, .. } => result_tx.is_closed(),
+        }
+    }
+}
+
+/// What a task runs, and where its result goes
+pub enum ScriptJob {
+    /// Call `should_inject` to decide on a fault
+    Decide(oneshot::Sender<Result<FaultDecision>>),
+    /// Call `on_response` with the upstream response
+    OnResponse {
+        response: ScriptResponse,
+        result_tx: oneshot::Sender<Result<Option<ScriptResponse>>>,
+    },
+}
+
+/// Why the pool could not produce a script's result
+#[derive(Debug, thiserror::Error)]
+pub enum ScriptPoolError {
+    #[error("script execution timed out")]
+    TimedOut,
+    #[error("script pool queue full")]
+    QueueFull,
+}
+
+impl ScriptPoolError {
+    /// Label for the script error metric
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScriptPoolError::TimedOut => "timeout",
+            ScriptPoolError::QueueFull => "queue_full",
+        }
+    }
 }
 
 /// Script worker thread
@@ -81,10 +169,16 @@ impl ScriptWorker {
                 debug!("Script worker {} started", worker_id);
 
                 // Create reusable engine instances per worker with custom functions
-                let rhai_engine = crate::scripting::rhai_engine::RhaiEngine::create_engine();
+                let mut rhai_engine = crate::scripting::rhai_engine::RhaiEngine::create_engine();
 
                 #[cfg(feature = "lua")]
                 let lua = Lua::new();
+                // Compiled traces never call the instruction hook that
+                // enforces the timeout, so scripts run interpreted
+                #[cfg(feature = "lua")]
+                if let Err(e) = lua.load("jit.off()").exec() {
+                    warn!("Failed to disable the Lua JIT: {}", e);
+                }
 
                 loop {
                     // Check for shutdown signal (non-blocking)
@@ -96,43 +190,91 @@ impl ScriptWorker {
                     // Wait for work with timeout to allow shutdown checks
                     match work_rx.recv_timeout(Duration::from_millis(100)) {
                         Ok(task) => {
+                            if task.is_abandoned() {
+                                debug!("Skipping script task abandoned while queued");
+                                continue;
+                            }
                             let start = Instant::now();
-
-                            let result = match &task.engine {
-                                CompiledScript::Rhai { ast, rule_id } => Self::execute_rhai(
-                                    &rhai_engine,
-                                    ast,
-                                    &task.request,
-                                    task.flow_store.clone(),
-                                    rule_id,
-                                ),
-                                #[cfg(feature = "lua")]
-                                CompiledScript::Lua { bytecode, rule_id } => Self::execute_lua(
-                                    &lua,
-                                    bytecode,
-                                    &task.request,
-                                    task.flow_store.clone(),
-                                    rule_id,
-                                ),
-                                #[cfg(feature = "javascript")]
-                                CompiledScript::JavaScript { bytecode, rule_id } => {
-                                    Self::execute_javascript(
-                                        bytecode,
-                                        &task.request,
-                                        task.flow_store.clone(),
-                                        rule_id,
-                                    )
+                            let deadline = start + task.timeout;
+                            Self::arm_deadline(&mut rhai_engine, deadline);
+                            #[cfg(feature = "lua")]
+                            Self::arm_lua_deadline(&lua, deadline);
+
+                            match task.job {
+                                ScriptJob::Decide(result_tx) => {
+                                    let result = match &task.engine {
+                                        CompiledScript::Rhai { ast, rule_id } => {
+                                            Self::execute_rhai(
+                                                &rhai_engine,
+                                                ast,
+                                                &task.request,
+                                                task.flow_store.clone(),
+                                                rule_id,
+                                                &task.context,
+                                            )
+                                        }
+                                        #[cfg(feature = "lua")]
+                                        CompiledScript::Lua {
+                                            bytecode, rule_id, ..
+                                        } => Self::execute_lua(
+                                            &lua,
+                                            bytecode,
+                                            &task.request,
+                                            task.flow_store.clone(),
+                                            rule_id,
+                                            &task.context,
+                                        ),
+                                        #[cfg(feature = "javascript")]
+                                        CompiledScript::JavaScript { bytecode, rule_id } => {
+                                            Self::execute_javascript(
+                                                bytecode,
+                                                &task.request,
+                                                task.flow_store.clone(),
+                                                rule_id,
+                                            )
+                                        }
+                                    };
+                                    // Send result back (ignore if receiver dropped)
+                                    let _ = result_tx.send(Self::check_deadline(result, deadline));
+                                }
+                                ScriptJob::OnResponse {
+                                    response,
+                                    result_tx,
+                                } => {
+                                    let result = match &task.engine {
+                                        CompiledScript::Rhai { ast, .. } => {
+                                            crate::scripting::rhai_engine::execute_rhai_on_response(
+                                                &rhai_engine,
+                                                ast,
+                                                &task.request,
+                                                &response,
+                                                task.flow_store.clone(),
+                                            )
+                                        }
+                                        #[cfg(feature = "lua")]
+                                        CompiledScript::Lua { bytecode, .. } => {
+                                            crate::scripting::lua_engine::execute_lua_on_response(
+                                                &lua,
+                                                bytecode,
+                                                &task.request,
+                                                &response,
+                                                task.flow_store.clone(),
+                                            )
+                                        }
+                                        #[cfg(feature = "javascript")]
+                                        CompiledScript::JavaScript { .. } => Err(anyhow!(
+                                            "on_response is not supported for JavaScript scripts"
+                                        )),
+                                    };
+                                    let _ = result_tx.send(Self::check_deadline(result, deadline));
                                 }
-                            };
+                            }
 
-                            let duration = start.elapsed();
                             debug!(
                                 "Script execution completed in {:?} for worker {}",
-                                duration, worker_id
+                                start.elapsed(),
+                                worker_id
                             );
-
-                            // Send result back (ignore if receiver dropped)
-                            let _ = task.result_tx.send(result);
                         }
                         Err(crossbeam::channel::RecvTimeoutError::Timeout) => {
                             // Normal timeout, check for shutdown and continue
@@ -155,17 +297,53 @@ impl ScriptWorker {
         }
     }
 
+    /// Make Rhai abort scripts still running at `deadline`
+    fn arm_deadline(engine: &mut rhai::Engine, deadline: Instant) {
+        engine.on_progress(move |operations| {
+            // Reading the clock on every operation would dominate simple scripts
+            if operations % 1024 == 0 && Instant::now() >= deadline {
+                Some("script execution timed out".into())
+            } else {
+                None
+            }
+        });
+    }
+
+    /// Make Lua abort scripts still running at `deadline`
+    #[cfg(feature = "lua")]
+    fn arm_lua_deadline(lua: &Lua, deadline: Instant) {
+        lua.set_hook(
+            mlua::HookTriggers::new().every_nth_instruction(1024),
+            move |_, _| {
+                if Instant::now() >= deadline {
+                    Err(mlua::Error::runtime("script execution timed out"))
+                } else {
+                    Ok(mlua::VmState::Continue)
+                }
+            },
+        );
+    }
+
+    /// Report a script aborted at its deadline as a timeout
+    fn check_deadline<T>(result: Result<T>, deadline: Instant) -> Result<T> {
+        match result {
+            Err(_) if Instant::now() >= deadline => Err(ScriptPoolError::TimedOut.into()),
+            result => result,
+        }
+    }
+
     fn execute_rhai(
         engine: &rhai::Engine,
         ast: &Arc<AST>,
         request: &ScriptRequest,
         flow_store: Arc<dyn FlowStore>,
         rule_id: &str,
+        context: &ScriptContext,
     ) -> Result<FaultDecision> {
         // Import necessary types from rhai_engine module
         use crate::scripting::rhai_engine::execute_rhai_with_engine;
 
-        execute_rhai_with_engine(engine, ast, request, flow_store, rule_id)
+        execute_rhai_with_engine(engine, ast, request, flow_store, rule_id, context)
     }
 
     #[cfg(feature = "lua")]
@@ -175,11 +353,19 @@ impl ScriptWorker {
         request: &ScriptRequest,
         flow_store: Arc<dyn FlowStore>,
         rule_id: &str,
+        context: &ScriptContext,
     ) -> Result<FaultDecision> {
         // Import necessary types from lua_engine module
         use crate::scripting::lua_engine::execute_lua_bytecode;
 
-        execute_lua_bytecode(lua, bytecode.as_slice(), request, flow_store, rule_id)
+        execute_lua_bytecode(
+            lua,
+            bytecode.as_slice(),
+            request,
+            flow_store,
+            rule_id,
+            context,
+        )
     }
 
     #[cfg(feature = "javascript")]
@@ -219,7 +405,10 @@ pub struct ScriptPool {
 
 impl ScriptPool {
     /// Create a new script pool with the given configuration
-    pub fn new(config: ScriptPoolConfig) -> Result<Self> {
+    pub fn new(mut config: ScriptPoolConfig) -> Result<Self> {
+        if config.workers == 0 {
+            config.workers = ScriptPoolConfig::default().workers;
+        }
         info!(
             "Creating script pool with {} workers, queue size {}",
             config.workers, config.queue_size
@@ -252,28 +441,89 @@ impl ScriptPool {
         engine: CompiledScript,
         request: ScriptRequest,
         flow_store: Arc<dyn FlowStore>,
+        context: ScriptContext,
     ) -> Result<FaultDecision> {
         let (result_tx, result_rx) = oneshot::channel();
+        self.submit(
+            engine,
+            request,
+            flow_store,
+            context,
+            ScriptJob::Decide(result_tx),
+            result_rx,
+        )
+        .await
+    }
+
+    /// Decision for a script that timed out or could not be queued: no
+    /// fault, or a 503 when the pool fails closed
+    pub fn unavailable_decision(&self, rule_id: &str, error: &ScriptPoolError) -> FaultDecision {
+        if !self.config.fail_closed {
+            return FaultDecision::None;
+        }
+        FaultDecision::Error {
+            status: 503,
+            body: format!("Script for rule '{rule_id}' did not complete: {error}"),
+            rule_id: rule_id.to_string(),
+            headers: Default::default(),
+        }
+    }
+
+    /// Run a script's `on_response` hook on the upstream response.
+    ///
+    /// Returns `None` if the hook leaves the response unchanged.
+    pub async fn execute_on_response(
+        &self,
+        engine: CompiledScript,
+        request: ScriptRequest,
+        response: ScriptResponse,
+        flow_store: Arc<dyn FlowStore>,
+    ) -> Result<Option<ScriptResponse>> {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.submit(
+            engine,
+            request,
+            flow_store,
+            ScriptContext::default(),
+            ScriptJob::OnResponse {
+                response,
+                result_tx,
+            },
+            result_rx,
+        )
+        .await
+    }
+
+    async fn submit<T>(
+        &self,
+        engine: CompiledScript,
+        request: ScriptRequest,
+        flow_store: Arc<dyn FlowStore>,
+        context: ScriptContext,
+        job: ScriptJob,
+        result_rx: oneshot::Receiver<Result<T>>,
+    ) -> Result<T> {
         let timeout = Duration::from_millis(self.config.timeout_ms);
 
         let task = ScriptTask {
             engine,
             request,
             flow_store,
+            context,
             timeout,
-            result_tx,
+            job,
         };
 
         // Track queue depth
         self.queue_depth.fetch_add(1, Ordering::Relaxed);
 
-        // Try to send task to queue
+        // Try to send task to queue; a full queue rejects rather than waits
         self.work_tx.try_send(task).map_err(|e| {
             self.queue_depth.fetch_sub(1, Ordering::Relaxed);
             match e {
                 crossbeam::channel::TrySendError::Full(_) => {
                     warn!("Script pool queue is full");
-                    anyhow!("Script pool queue full")
+                    anyhow::Error::new(ScriptPoolError::QueueFull)
                 }
                 crossbeam::channel::TrySendError::Disconnected(_) => {
                     error!("Script pool is shut down");
@@ -285,17 +535,17 @@ impl ScriptPool {
         // Track active tasks
         self.active_tasks.fetch_add(1, Ordering::Relaxed);
 
-        // Wait for result with timeout
-        let result = tokio::time::timeout(timeout, result_rx)
-            .await
-            .map_err(|_| anyhow!("Script execution timed out"))?
-            .map_err(|_| anyhow!("Script execution cancelled"))?;
+        // Wait for result with timeout. Workers abort scripts at the same
+        // deadline, so a timed-out task does not keep its worker busy.
+        let result = tokio::time::timeout(timeout, result_rx).await;
 
         // Update metrics
         self.queue_depth.fetch_sub(1, Ordering::Relaxed);
         self.active_tasks.fetch_sub(1, Ordering::Relaxed);
 
         result
+            .map_err(|_| anyhow::Error::new(ScriptPoolError::TimedOut))?
+            .map_err(|_| anyhow!("Script execution cancelled"))?
     }
 
     /// Get current queue depth (for metrics)
@@ -350,6 +600,7 @@ mod tests {
             workers: 2,
             queue_size: 10,
             timeout_ms: 5000,
+            fail_closed: false,
         };
 
         let pool = ScriptPool::new(config.clone()).unwrap();
@@ -364,6 +615,7 @@ mod tests {
             workers: 2,
             queue_size: 10,
             timeout_ms: 5000,
+            fail_closed: false,
         };
 
         let mut pool = ScriptPool::new(config).unwrap();
@@ -394,6 +646,7 @@ mod tests {
             workers: 8,
             queue_size: 500,
             timeout_ms: 10000,
+            fail_closed: false,
         };
 
         assert_eq!(config.workers, 8);
@@ -407,6 +660,7 @@ mod tests {
             workers: 4,
             queue_size: 200,
             timeout_ms: 3000,
+            fail_closed: false,
         };
 
         let cloned = config.clone();
@@ -421,6 +675,7 @@ mod tests {
             workers: 4,
             queue_size: 100,
             timeout_ms: 5000,
+            fail_closed: false,
         };
 
         let debug_str = format!("{config:?}");
@@ -435,6 +690,7 @@ mod tests {
             workers: 1,
             queue_size: 10,
             timeout_ms: 5000,
+            fail_closed: false,
         };
 
         let pool = ScriptPool::new(config).unwrap();
@@ -447,6 +703,7 @@ mod tests {
             workers: 16,
             queue_size: 100,
             timeout_ms: 5000,
+            fail_closed: false,
         };
 
         let pool = ScriptPool::new(config).unwrap();
@@ -459,6 +716,7 @@ mod tests {
             workers: 2,
             queue_size: 1,
             timeout_ms: 5000,
+            fail_closed: false,
         };
 
         let pool = ScriptPool::new(config).unwrap();
@@ -471,6 +729,7 @@ mod tests {
             workers: 4,
             queue_size: 50,
             timeout_ms: 5000,
+            fail_closed: false,
         };
 
         let pool = ScriptPool::new(config).unwrap();
@@ -487,6 +746,7 @@ mod tests {
             workers: 2,
             queue_size: 10,
             timeout_ms: 5000,
+            fail_closed: false,
         };
 
         let mut pool = ScriptPool::new(config).unwrap();
@@ -501,6 +761,7 @@ mod tests {
             workers: 2,
             queue_size: 10,
             timeout_ms: 5000,
+            fail_closed: false,
         };
 
         // Pool should be dropped gracefully
@@ -549,10 +810,14 @@ mod tests {
         let compiled = CompiledScript::Lua {
             bytecode: Arc::new(vec![1, 2, 3, 4]),
             rule_id: "lua-rule".to_string(),
+            response_hook: false,
+            takes_context: false,
         };
 
         match compiled {
-            CompiledScript::Lua { rule_id, bytecode } => {
+            CompiledScript::Lua {
+                rule_id, bytecode, ..
+            } => {
                 assert_eq!(rule_id, "lua-rule");
                 assert_eq!(bytecode.len(), 4);
             }
@@ -597,6 +862,7 @@ mod tests {
             workers: 2,
             queue_size: 10,
             timeout_ms: 100, // Very short timeout
+            fail_closed: false,
         };
 
         let pool = ScriptPool::new(config).unwrap();
@@ -614,6 +880,7 @@ mod tests {
             workers: 2,
             queue_size: 10,
             timeout_ms: 5000,
+            fail_closed: false,
         };
 
         let pool = ScriptPool::new(config).unwrap();
@@ -646,7 +913,183 @@ mod tests {
 
         let flow_store: Arc<dyn crate::extensions::flow_state::FlowStore> = Arc::new(NoOpFlowStore);
 
-        let result = pool.execute(compiled, request, flow_store).await;
+        let result = pool
+            .execute(compiled, request, flow_store, Default::default())
+            .await;
         assert!(result.is_ok());
     }
+
+    #[cfg(feature = "javascript")]
+    #[tokio::test]
+    async fn test_pool_javascript_times_out() {
+        use crate::extensions::flow_state::NoOpFlowStore;
+        use crate::scripting::compile_js_to_bytecode;
+        use std::collections::HashMap;
+
+        let pool = ScriptPool::new(ScriptPoolConfig {
+            workers: 1,
+            queue_size: 10,
+            timeout_ms: 50,
+            fail_closed: false,
+        })
+        .unwrap();
+
+        // Busy for well past the pool timeout, but finite so the worker can
+        // be joined when the pool is dropped
+        let bytecode = compile_js_to_bytecode(
+            r#"
+function should_inject(request, flow_store) {
+    const until = Date.now() + 500;
+    while (Date.now() < until) {}
+    return { inject: false };
+}
+"#,
+        )
+        .unwrap();
+        let compiled = CompiledScript::JavaScript {
+            bytecode: Arc::new(bytecode),
+            rule_id: "slow-js".to_string(),
+        };
+        let request = ScriptRequest {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            headers: HashMap::new(),
+            body: serde_json::json!(null),
+            query: HashMap::new(),
+            path_params: HashMap::new(),
+        };
+
+        let start = Instant::now();
+        let result = pool
+            .execute(
+                compiled,
+                request,
+                Arc::new(NoOpFlowStore),
+                Default::default(),
+            )
+            .await;
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<ScriptPoolError>(),
+            Some(ScriptPoolError::TimedOut)
+        ));
+        assert!(start.elapsed() < Duration::from_millis(400));
+    }
+
+    fn runaway_test_request() -> ScriptRequest {
+        ScriptRequest {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            headers: std::collections::HashMap::new(),
+            body: serde_json::json!(null),
+            query: std::collections::HashMap::new(),
+            path_params: std::collections::HashMap::new(),
+        }
+    }
+
+    fn runaway_test_pool(fail_closed: bool) -> ScriptPool {
+        ScriptPool::new(ScriptPoolConfig {
+            workers: 1,
+            queue_size: 10,
+            timeout_ms: 100,
+            fail_closed,
+        })
+        .unwrap()
+    }
+
+    fn assert_timed_out(result: Result<FaultDecision>) {
+        match result {
+            Err(e) => assert!(
+                matches!(
+                    e.downcast_ref::<ScriptPoolError>(),
+                    Some(ScriptPoolError::TimedOut)
+                ),
+                "unexpected error: {e}"
+            ),
+            Ok(_) => panic!("Expected the script to time out"),
+        }
+    }
+
+    fn runaway_rhai_script(rule_id: &str) -> CompiledScript {
+        let ast = rhai::Engine::new()
+            .compile("fn should_inject(request, flow_store) { loop {} }")
+            .unwrap();
+        CompiledScript::Rhai {
+            ast: Arc::new(ast),
+            rule_id: rule_id.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runaway_rhai_script_times_out() {
+        use crate::extensions::flow_state::NoOpFlowStore;
+
+        let pool = runaway_test_pool(false);
+        for _ in 0..2 {
+            // The second run only completes in time if the worker was freed
+            let start = Instant::now();
+            let result = pool
+                .execute(
+                    runaway_rhai_script("runaway-rhai"),
+                    runaway_test_request(),
+                    Arc::new(NoOpFlowStore),
+                    Default::default(),
+                )
+                .await;
+            assert_timed_out(result);
+            assert!(start.elapsed() < Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn test_unavailable_decision() {
+        let decision = runaway_test_pool(false)
+            .unavailable_decision("runaway-open", &ScriptPoolError::TimedOut);
+        assert!(matches!(decision, FaultDecision::None));
+
+        let decision = runaway_test_pool(true)
+            .unavailable_decision("runaway-closed", &ScriptPoolError::QueueFull);
+        match decision {
+            FaultDecision::Error {
+                status, rule_id, ..
+            } => {
+                assert_eq!(status, 503);
+                assert_eq!(rule_id, "runaway-closed");
+            }
+            _ => panic!("Expected a 503 when failing closed"),
+        }
+    }
+
+    #[cfg(feature = "lua")]
+    #[tokio::test]
+    async fn test_runaway_lua_script_times_out() {
+        use crate::extensions::flow_state::NoOpFlowStore;
+        use crate::scripting::compile_to_bytecode;
+
+        let pool = runaway_test_pool(false);
+        let bytecode = Arc::new(
+            compile_to_bytecode(
+                "function should_inject(request, flow_store)\n  while true do end\nend",
+            )
+            .unwrap(),
+        );
+        for _ in 0..2 {
+            let start = Instant::now();
+            let compiled = CompiledScript::Lua {
+                bytecode: Arc::clone(&bytecode),
+                rule_id: "runaway-lua".to_string(),
+                response_hook: false,
+                takes_context: false,
+            };
+            let result = pool
+                .execute(
+                    compiled,
+                    runaway_test_request(),
+                    Arc::new(NoOpFlowStore),
+                    Default::default(),
+                )
+                .await;
+            assert_timed_out(result);
+            assert!(start.elapsed() < Duration::from_millis(1000));
+        }
+    }
 }
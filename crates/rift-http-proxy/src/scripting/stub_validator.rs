@@ -268,6 +268,7 @@ mod tests {
                         engine: engine.to_string(),
                         code: code.to_string(),
                     }),
+                    conditional: false,
                 },
             }],
             scenario_name: None,
@@ -363,6 +364,7 @@ mod tests {
                             code: r#"fn should_inject(request, flow_store) { #{ inject: false } }"#
                                 .to_string(),
                         }),
+                        conditional: false,
                     },
                 }],
                 scenario_name: None,
@@ -378,6 +380,7 @@ mod tests {
                             code: r#"fn should_inject(request, flow_store) { #{ inject: "#
                                 .to_string(), // Invalid
                         }),
+                        conditional: false,
                     },
                 }],
                 scenario_name: None,
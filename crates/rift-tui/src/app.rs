@@ -1928,10 +1928,10 @@ impl App {
                     KeyCode::Up | KeyCode::Char('k') => {
                         self.help_scroll = self.help_scroll.saturating_sub(1);
                     }
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        if self.help_scroll < self.help_max_scroll {
-                            self.help_scroll += 1;
-                        }
+                    KeyCode::Down | KeyCode::Char('j')
+                        if self.help_scroll < self.help_max_scroll =>
+                    {
+                        self.help_scroll += 1;
                     }
                     KeyCode::PageUp => {
                         self.help_scroll = self.help_scroll.saturating_sub(10);
@@ -2240,10 +2240,8 @@ impl App {
             },
             KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
                 match self.input_state.focus_field {
-                    0 => {
-                        if c.is_ascii_digit() {
-                            self.input_state.port.push(c);
-                        }
+                    0 if c.is_ascii_digit() => {
+                        self.input_state.port.push(c);
                     }
                     1 => {
                         self.input_state.name.push(c);
@@ -2314,10 +2312,8 @@ impl App {
                     0 => {
                         self.input_state.target_url.push(c);
                     }
-                    1 => {
-                        if c.is_ascii_digit() {
-                            self.input_state.port.push(c);
-                        }
+                    1 if c.is_ascii_digit() => {
+                        self.input_state.port.push(c);
                     }
                     2 => {
                         self.input_state.name.push(c);
@@ -2359,32 +2355,24 @@ impl App {
                     FileAction::ExportToFolder => self.export_to_folder(&path).await,
                 }
             }
-            KeyCode::Left => {
-                if self.input_state.cursor_pos > 0 {
-                    self.input_state.cursor_pos -= 1;
-                }
+            KeyCode::Left if self.input_state.cursor_pos > 0 => {
+                self.input_state.cursor_pos -= 1;
             }
-            KeyCode::Right => {
-                if self.input_state.cursor_pos < self.input_state.file_path.len() {
-                    self.input_state.cursor_pos += 1;
-                }
+            KeyCode::Right if self.input_state.cursor_pos < self.input_state.file_path.len() => {
+                self.input_state.cursor_pos += 1;
             }
             KeyCode::Home => self.input_state.cursor_pos = 0,
             KeyCode::End => self.input_state.cursor_pos = self.input_state.file_path.len(),
-            KeyCode::Backspace => {
-                if self.input_state.cursor_pos > 0 {
-                    self.input_state.cursor_pos -= 1;
-                    self.input_state
-                        .file_path
-                        .remove(self.input_state.cursor_pos);
-                }
+            KeyCode::Backspace if self.input_state.cursor_pos > 0 => {
+                self.input_state.cursor_pos -= 1;
+                self.input_state
+                    .file_path
+                    .remove(self.input_state.cursor_pos);
             }
-            KeyCode::Delete => {
-                if self.input_state.cursor_pos < self.input_state.file_path.len() {
-                    self.input_state
-                        .file_path
-                        .remove(self.input_state.cursor_pos);
-                }
+            KeyCode::Delete if self.input_state.cursor_pos < self.input_state.file_path.len() => {
+                self.input_state
+                    .file_path
+                    .remove(self.input_state.cursor_pos);
             }
             KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
                 self.input_state